@@ -0,0 +1,138 @@
+// cosmic-connect-applet/src/applet_config.rs
+//! Popup and panel preferences kept between sessions.
+//!
+//! Stored in ~/.config/cosmic-connect/applet under [Popup] and [Panel].
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// Icon shown in the panel unless another one is configured
+pub const DEFAULT_PANEL_ICON: &str = "phone-symbolic";
+
+/// What the panel button shows next to its icon
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PanelStyle {
+    /// The icon alone; only pairing requests are flagged
+    IconOnly,
+    /// The icon and the number of connected devices
+    #[default]
+    IconCount,
+    /// The icon and the battery of the connected device, or the count
+    /// when several are connected
+    Battery,
+}
+
+impl PanelStyle {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PanelStyle::IconOnly => "icon",
+            PanelStyle::IconCount => "count",
+            PanelStyle::Battery => "battery",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "icon" => Some(PanelStyle::IconOnly),
+            "count" => Some(PanelStyle::IconCount),
+            "battery" => Some(PanelStyle::Battery),
+            _ => None,
+        }
+    }
+}
+
+/// How the applet looks in the panel
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PanelConfig {
+    pub style: PanelStyle,
+    /// Icon name from the icon theme, None for the default icon
+    pub icon: Option<String>,
+}
+
+impl PanelConfig {
+    /// Configured icon, falling back to the default icon
+    pub fn icon_name(&self) -> &str {
+        self.icon.as_deref().unwrap_or(DEFAULT_PANEL_ICON)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AppletConfig {
+    /// Device cards show only name, battery and status until expanded
+    pub compact: bool,
+    pub panel: PanelConfig,
+}
+
+impl AppletConfig {
+    /// Load config from file
+    pub fn load() -> io::Result<Self> {
+        let path = Self::get_config_path();
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        Ok(Self::parse(&fs::read_to_string(&path)?))
+    }
+
+    fn parse(content: &str) -> Self {
+        let mut config = Self::default();
+        for line in content.lines() {
+            if let Some((key, value)) = line.trim().split_once('=') {
+                let value = value.trim();
+                match key.trim() {
+                    "compact" => config.compact = value.parse().unwrap_or(false),
+                    "style" => config.panel.style = PanelStyle::parse(value).unwrap_or_default(),
+                    "icon" if !value.is_empty() => config.panel.icon = Some(value.to_string()),
+                    _ => {}
+                }
+            }
+        }
+        config
+    }
+
+    /// Save config to file
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::get_config_path();
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = fs::File::create(&path)?;
+        writeln!(file, "[Popup]")?;
+        writeln!(file, "compact={}", self.compact)?;
+        writeln!(file)?;
+        writeln!(file, "[Panel]")?;
+        writeln!(file, "style={}", self.panel.style.as_str())?;
+        writeln!(file, "icon={}", self.panel.icon.as_deref().unwrap_or_default())?;
+
+        Ok(())
+    }
+
+    fn get_config_path() -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+        PathBuf::from(format!("{}/.config/cosmic-connect/applet", home))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_panel_config_parse_and_fallback() {
+        let config = AppletConfig::parse("[Popup]\ncompact=true\n\n[Panel]\nstyle=battery\nicon=smartphone-symbolic\n");
+        assert!(config.compact);
+        assert_eq!(config.panel.style, PanelStyle::Battery);
+        assert_eq!(config.panel.icon_name(), "smartphone-symbolic");
+
+        // Older files have no [Panel]; unknown styles and a blank icon fall back
+        let config = AppletConfig::parse("[Popup]\ncompact=false\n");
+        assert_eq!(config.panel, PanelConfig::default());
+        let config = AppletConfig::parse("[Panel]\nstyle=sparkles\nicon=\n");
+        assert_eq!(config.panel.style, PanelStyle::IconCount);
+        assert_eq!(config.panel.icon_name(), DEFAULT_PANEL_ICON);
+    }
+}
@@ -1,83 +1,199 @@
 // cosmic-connect-applet/src/backend.rs
 //! Backend interface using D-Bus client to communicate with kdeconnect-service
 
-use anyhow::Result;
-use kdeconnect_dbus_client::{KdeConnectClient, ServiceEvent};
+use cosmic_connect_common::notification_text;
+use cosmic_connect_common::plugin_config::{DevicePluginsConfig, PingPluginConfig};
+use kdeconnect_dbus_client::{KdeConnectClient, KdeConnectError, ServiceEvent};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use std::collections::HashMap;
 use futures::StreamExt;
+use tracing::{debug, info, warn};
 
+use crate::device_aliases;
+use crate::device_data;
+use crate::messages::Message;
 use crate::models::Device;
 
+type Result<T, E = KdeConnectError> = std::result::Result<T, E>;
+
 lazy_static::lazy_static! {
     static ref CLIENT: Arc<Mutex<Option<Arc<KdeConnectClient>>>> = Arc::new(Mutex::new(None));
     static ref DEVICE_CACHE: Arc<Mutex<HashMap<String, Device>>> = Arc::new(Mutex::new(HashMap::new()));
+    /// Flips to true once `initialize` has connected
+    static ref CLIENT_READY: tokio::sync::watch::Sender<bool> = tokio::sync::watch::channel(false).0;
 }
 
-/// Initialize the D-Bus client connection
-pub async fn initialize() -> Result<()> {
-    eprintln!("=== Initializing D-Bus Client ===");
-    
-    let client = KdeConnectClient::new().await?;
-    
+/// Longest a caller waits for the service to answer a connection attempt
+const CONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// The shared client, connecting to the service first if needed. The lock
+/// is held while connecting, so concurrent callers wait for one attempt
+/// instead of racing their own; after a failure the next caller retries.
+async fn connected_client() -> Result<Arc<KdeConnectClient>> {
     let mut client_guard = CLIENT.lock().await;
-    *client_guard = Some(Arc::new(client));
-    
-    eprintln!("✓ D-Bus client connected to kdeconnect-service");
-    Ok(())
+    if let Some(client) = client_guard.as_ref() {
+        return Ok(client.clone());
+    }
+
+    debug!("Initializing D-Bus client");
+    let client = tokio::time::timeout(CONNECT_TIMEOUT, KdeConnectClient::new()).await
+        .map_err(|_| KdeConnectError::ServiceUnavailable)??;
+    let client = Arc::new(client);
+    *client_guard = Some(client.clone());
+    CLIENT_READY.send_replace(true);
+
+    info!("D-Bus client connected to kdeconnect-service");
+    Ok(client)
+}
+
+/// Connect to the service if no call has yet. Requests connect on their
+/// own too, so this only surfaces a failure early.
+pub async fn initialize() -> Result<()> {
+    connected_client().await.map(|_| ())
+}
+
+/// Wait until `initialize` has connected, without polling
+pub async fn wait_until_ready() {
+    let mut ready = CLIENT_READY.subscribe();
+    let _ = ready.wait_for(|ready| *ready).await;
 }
 
 /// Fetch all devices from the service
 pub async fn fetch_devices() -> Vec<Device> {
-    let client_guard = CLIENT.lock().await;
-    
-    let Some(client) = client_guard.as_ref() else {
-        eprintln!("⚠️  D-Bus client not initialized");
-        return vec![];
+    let client = match connected_client().await {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("D-Bus client unavailable: {}", e);
+            return vec![];
+        }
     };
     
     match client.list_devices().await {
         Ok(dbus_devices) => {
             let mut cache = DEVICE_CACHE.lock().await;
             let devices: Vec<Device> = dbus_devices.into_iter().map(|d| {
-                let device = Device {
-                    id: d.id.clone(),
-                    name: d.name.clone(),
-                    device_type: "phone".to_string(),
-                    is_paired: d.is_paired,
-                    is_reachable: d.is_reachable,
-                    battery_level: None,
-                    is_charging: None,
-                    network_type: None,
-                    signal_strength: None,
-                    pairing_requests: 0,
-                    has_battery: false,
-                    has_ping: true,
-                    has_sms: true,
-                    has_contacts: false,
-                    has_clipboard: true,
-                    has_findmyphone: true,
-                    has_share: true,
-                    has_sftp: false,
-                    has_mpris: false,
-                    has_remote_keyboard: false,
-                    has_presenter: false,
-                    has_lockdevice: false,
-                    has_virtualmonitor: false,
-                };
-                cache.insert(d.id.clone(), device.clone());
+                let device = device_from_dbus(d);
+                cache.insert(device.id.clone(), device.clone());
                 device
             }).collect();
             devices
         }
         Err(e) => {
-            eprintln!("✗ Failed to fetch devices: {:?}", e);
+            warn!("Failed to fetch devices: {:?}", e);
             vec![]
         }
     }
 }
 
+/// Fetch a single device from the service. `UnknownDevice` means the
+/// service no longer knows it; other errors say nothing about the device.
+pub async fn fetch_device(device_id: String) -> Result<Device> {
+    let client = connected_client().await?;
+    let device = device_from_dbus(client.get_device(&device_id).await?);
+    DEVICE_CACHE.lock().await.insert(device.id.clone(), device.clone());
+    Ok(device)
+}
+
+/// Fetch the service health snapshot for the troubleshooting panel
+pub async fn fetch_diagnostics() -> Result<kdeconnect_dbus_client::Diagnostics, String> {
+    let client = connected_client().await.map_err(|e| e.to_string())?;
+    
+    client.diagnostics().await
+        .map_err(|e| kdeconnect_dbus_client::error_detail(&e))
+}
+
+/// Fetch the service's developer packet log from `since_ms` onwards
+pub async fn fetch_packet_log(since_ms: i64) -> Result<Vec<kdeconnect_dbus_client::LoggedPacket>, String> {
+    let client = connected_client().await.map_err(|e| e.to_string())?;
+    
+    client.packet_log(since_ms).await
+        .map_err(|e| kdeconnect_dbus_client::error_detail(&e))
+}
+
+/// Apply a name announced by a device to a device map, returning whether
+/// the device was known
+pub fn rename_device(devices: &mut HashMap<String, Device>, device_id: &str, name: &str) -> bool {
+    match devices.get_mut(device_id) {
+        Some(device) => {
+            device.name = name.to_string();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Apply a pairing announcement to a device map so the popup shows the
+/// device's name and type at once. An unpaired device is marked as asking
+/// to pair; one not seen before is added as reachable. Returns whether the
+/// device was already paired.
+pub fn note_pairing_request(devices: &mut HashMap<String, Device>, device_id: &str, name: &str, device_type: &str) -> bool {
+    let device = devices.entry(device_id.to_string()).or_insert_with(|| {
+        device_from_dbus(kdeconnect_dbus_client::Device {
+            id: device_id.to_string(),
+            name: name.to_string(),
+            device_type: device_type.to_string(),
+            is_paired: false,
+            is_reachable: true,
+            pending_packets: 0,
+            connection_type: String::new(),
+            enabled: true,
+        })
+    });
+    device.name = name.to_string();
+    device.device_type = device_type.to_string();
+    if !device.is_paired {
+        device.pairing_requests = device.pairing_requests.max(1);
+    }
+    device.is_paired
+}
+
+/// Record a device's new name in the cache
+pub async fn apply_rename(device_id: &str, name: &str) {
+    rename_device(&mut *DEVICE_CACHE.lock().await, device_id, name);
+}
+
+/// Turn syncing with a device on or off and return its updated state
+pub async fn set_device_enabled(device_id: String, enabled: bool) -> Result<Device> {
+    let client = connected_client().await?;
+    
+    let device = device_from_dbus(client.set_device_enabled(&device_id, enabled).await?);
+    DEVICE_CACHE.lock().await.insert(device.id.clone(), device.clone());
+    Ok(device)
+}
+
+/// Applet model of a service device, named by its alias when one is set
+fn device_from_dbus(d: kdeconnect_dbus_client::Device) -> Device {
+    Device {
+        name: device_aliases::alias_for(&d.id).unwrap_or(d.name),
+        id: d.id,
+        device_type: Some(d.device_type).filter(|t| !t.is_empty()).unwrap_or_else(|| "phone".to_string()),
+        is_paired: d.is_paired,
+        is_reachable: d.is_reachable,
+        battery_level: None,
+        is_charging: None,
+        network_type: None,
+        connection_type: Some(d.connection_type).filter(|t| !t.is_empty()),
+        enabled: d.enabled,
+        signal_strength: None,
+        pairing_requests: 0,
+        pending_packets: d.pending_packets,
+        has_battery: false,
+        has_ping: true,
+        has_sms: true,
+        has_contacts: false,
+        has_clipboard: true,
+        has_findmyphone: true,
+        has_share: true,
+        has_sftp: false,
+        has_mpris: false,
+        has_remote_keyboard: false,
+        has_presenter: false,
+        has_lockdevice: false,
+        has_virtualmonitor: false,
+    }
+}
+
 /// Update device in cache
 #[allow(dead_code)]
 pub async fn update_device(device_id: String, device: Device) {
@@ -94,62 +210,87 @@ pub async fn remove_device(device_id: &str) {
 
 /// Pair with a device
 pub async fn pair_device(device_id: String) -> Result<()> {
-    let client_guard = CLIENT.lock().await;
-    
-    let Some(client) = client_guard.as_ref() else {
-        return Err(anyhow::anyhow!("D-Bus client not initialized"));
-    };
+    let client = connected_client().await?;
     
-    client.pair_device(&device_id).await
+    Ok(client.pair_device(&device_id).await?)
 }
 
-/// Unpair from a device
-pub async fn unpair_device(device_id: String) -> Result<()> {
-    let client_guard = CLIENT.lock().await;
-    
-    let Some(client) = client_guard.as_ref() else {
-        return Err(anyhow::anyhow!("D-Bus client not initialized"));
-    };
+/// Unpair from a device, deleting its data on this computer unless
+/// `keep_data` is set. Failing to delete some of it doesn't fail the unpair.
+pub async fn unpair_device(device_id: String, keep_data: bool) -> Result<()> {
+    let client = connected_client().await?;
     
-    client.unpair_device(&device_id).await
+    client.unpair_device(&device_id).await?;
+
+    if !keep_data {
+        let failures = tokio::task::spawn_blocking(move || device_data::remove(&device_id))
+            .await
+            .unwrap_or_default();
+        for (what, e) in failures {
+            warn!("Failed to remove {}: {}", what, e);
+        }
+    }
+    Ok(())
 }
 
-/// Send a ping to a device
-pub async fn ping_device(device_id: String) -> Result<()> {
-    let client_guard = CLIENT.lock().await;
-    
-    let Some(client) = client_guard.as_ref() else {
-        return Err(anyhow::anyhow!("D-Bus client not initialized"));
-    };
+/// Fail with `PluginDisabled` if `plugin` (e.g. "clipboard") is switched
+/// off for the device in its plugin settings. Every send path checks this
+/// first, so nothing goes out for a plugin the user turned off.
+pub fn permission_guard(device_id: &str, plugin: &str) -> Result<()> {
+    let config = DevicePluginsConfig::load(device_id).unwrap_or_else(|e| {
+        warn!("Failed to load plugin settings for {}: {}", device_id, e);
+        DevicePluginsConfig::default()
+    });
+    check_permission(&config, plugin)
+}
+
+fn check_permission(config: &DevicePluginsConfig, plugin: &str) -> Result<()> {
+    if config.allows(plugin) {
+        Ok(())
+    } else {
+        Err(KdeConnectError::PluginDisabled(plugin.to_string()))
+    }
+}
+
+/// Send a ping to a device, using the configured ping text when no message is given
+pub async fn ping_device(device_id: String, message: Option<String>) -> Result<()> {
+    permission_guard(&device_id, "ping")?;
+    let message = message.unwrap_or_else(|| {
+        PingPluginConfig::load(&device_id).unwrap_or_default().message
+    });
+
+    let client = connected_client().await?;
     
-    client.send_ping(&device_id, "Ping from COSMIC!").await
+    Ok(client.send_ping(&device_id, &message).await?)
 }
 
 /// Send files to a device
 pub async fn send_files(device_id: String, files: Vec<String>) -> Result<()> {
-    let client_guard = CLIENT.lock().await;
-    
-    let Some(client) = client_guard.as_ref() else {
-        return Err(anyhow::anyhow!("D-Bus client not initialized"));
-    };
+    permission_guard(&device_id, "share")?;
+    let client = connected_client().await?;
     
-    client.send_files(&device_id, files).await
+    Ok(client.send_files(&device_id, files).await?)
 }
 
 /// Send clipboard content to a device
 pub async fn send_clipboard(device_id: String, content: String) -> Result<()> {
-    let client_guard = CLIENT.lock().await;
+    permission_guard(&device_id, "clipboard")?;
+    let client = connected_client().await?;
     
-    let Some(client) = client_guard.as_ref() else {
-        return Err(anyhow::anyhow!("D-Bus client not initialized"));
-    };
+    Ok(client.send_clipboard(&device_id, &content).await?)
+}
+
+/// Share text with a device through its share plugin
+pub async fn send_text(device_id: String, text: String) -> Result<()> {
+    permission_guard(&device_id, "share")?;
+    let client = connected_client().await?;
     
-    client.send_clipboard(&device_id, &content).await
+    Ok(client.send_text(&device_id, &text).await?)
 }
 
 /// Browse device filesystem (via SFTP)
 pub async fn browse_device_filesystem(_device_id: String) -> Result<()> {
-    eprintln!("⚠️  Browse filesystem not yet implemented via D-Bus");
+    warn!("Browse filesystem not yet implemented via D-Bus");
     Ok(())
 }
 
@@ -160,106 +301,349 @@ pub async fn accept_pairing(device_id: String) -> Result<()> {
 
 /// Reject a pairing request
 pub async fn reject_pairing(device_id: String) -> Result<()> {
-    unpair_device(device_id).await
+    unpair_device(device_id, true).await
 }
 
 /// Ring a device (findmyphone)
 pub async fn ring_device(device_id: String) -> Result<()> {
-    let client_guard = CLIENT.lock().await;
+    permission_guard(&device_id, "findmyphone")?;
+    let client = connected_client().await?;
     
-    let Some(client) = client_guard.as_ref() else {
-        return Err(anyhow::anyhow!("D-Bus client not initialized"));
-    };
+    Ok(client.ring_device(&device_id).await?)
+}
+
+/// Send a custom packet; the service rejects unknown types and bodies
+/// that aren't JSON objects
+pub async fn send_raw_packet(device_id: String, packet_type: String, body_json: String) -> Result<(), String> {
+    let client = connected_client().await.map_err(|e| e.to_string())?;
     
-    client.ring_device(&device_id).await
+    client.send_raw_packet(&device_id, &packet_type, &body_json).await
+        .map_err(|e| kdeconnect_dbus_client::error_detail(&e))
 }
 
 /// Request SMS conversations from a device
 #[allow(dead_code)]
 pub async fn request_conversations(device_id: String) -> Result<()> {
-    let client_guard = CLIENT.lock().await;
-    
-    let Some(client) = client_guard.as_ref() else {
-        return Err(anyhow::anyhow!("D-Bus client not initialized"));
-    };
+    permission_guard(&device_id, "sms")?;
+    let client = connected_client().await?;
     
-    client.request_conversations(&device_id).await
+    Ok(client.request_conversations(&device_id).await?)
 }
 
 /// Request a specific SMS conversation thread
 #[allow(dead_code)]
 pub async fn request_conversation(device_id: String, thread_id: i64) -> Result<()> {
-    let client_guard = CLIENT.lock().await;
-    
-    let Some(client) = client_guard.as_ref() else {
-        return Err(anyhow::anyhow!("D-Bus client not initialized"));
-    };
+    permission_guard(&device_id, "sms")?;
+    let client = connected_client().await?;
     
-    client.request_conversation(&device_id, thread_id).await
+    Ok(client.request_conversation(&device_id, thread_id).await?)
 }
 
 /// Send an SMS message
 #[allow(dead_code)]
 pub async fn send_sms(device_id: String, phone_number: String, message: String) -> Result<()> {
-    let client_guard = CLIENT.lock().await;
+    permission_guard(&device_id, "sms")?;
+    let client = connected_client().await?;
     
-    let Some(client) = client_guard.as_ref() else {
-        return Err(anyhow::anyhow!("D-Bus client not initialized"));
-    };
+    crate::plugins::sms::validate_sms(&phone_number, &message)
+        .map_err(KdeConnectError::InvalidArgs)?;
     
-    client.send_sms(&device_id, &phone_number, &message).await
+    Ok(client.send_sms(&device_id, &phone_number, &message).await?)
 }
 
-/// Create a stream of service events
-#[allow(dead_code)]
-pub async fn event_stream() -> futures::stream::BoxStream<'static, ServiceEvent> {
-    use tokio::sync::mpsc;
-    use tokio::time::{sleep, Duration};
-    
-    let (tx, rx) = mpsc::channel::<ServiceEvent>(100);
-    
-    tokio::spawn(async move {
-        let mut attempts = 0;
+/// Name to show for `number` from a device: the synced contact's name, or
+/// the number itself. Reads the contacts on disk, so it works without an
+/// SMS window open.
+pub fn resolve_contact_name(device_id: &str, number: &str) -> String {
+    let contacts = crate::plugins::sms::load_contacts(device_id).unwrap_or_else(|e| {
+        warn!("Failed to load contacts for {}: {:?}", device_id, e);
+        HashMap::new()
+    });
+    crate::plugins::sms::find_contact_name(&contacts, number)
+        .map_or_else(|| number.to_string(), str::to_string)
+}
+
+/// Announce a text message received on a device with a desktop
+/// notification, titled with the sender's contact name
+pub async fn show_sms_notification(device_id: String, address: String, body: String) {
+    let sender = resolve_contact_name(&device_id, &address);
+    let result = async {
+        let connection = zbus::Connection::session().await?;
+        // Bodies are escaped only for servers that render markup
+        let markup = connection.call_method(
+            Some("org.freedesktop.Notifications"),
+            "/org/freedesktop/Notifications",
+            Some("org.freedesktop.Notifications"),
+            "GetCapabilities",
+            &(),
+        ).await
+            .and_then(|reply| reply.body().deserialize::<Vec<String>>())
+            .map_or(true, |capabilities| capabilities.iter().any(|c| c == "body-markup"));
+
+        let summary = notification_text::summary(&sender);
+        let body = notification_text::body(&body, markup);
+        connection.call_method(
+            Some("org.freedesktop.Notifications"),
+            "/org/freedesktop/Notifications",
+            Some("org.freedesktop.Notifications"),
+            "Notify",
+            &(
+                "KDE Connect",
+                0u32,
+                "mail-unread-symbolic",
+                summary.as_str(),
+                body.as_str(),
+                Vec::<&str>::new(),
+                HashMap::<&str, zbus::zvariant::Value<'_>>::new(),
+                -1i32,
+            ),
+        ).await
+    };
+
+    if let Err(e) = result.await {
+        warn!("Failed to show SMS notification: {}", e);
+    }
+}
+
+/// Item of the service event stream
+#[derive(Debug, Clone)]
+pub enum ServiceUpdate {
+    /// Subscribed to the service's signals
+    Listening,
+    /// Subscribing failed, e.g. while the service is starting; retrying
+    NotReady(String),
+    Event(ServiceEvent),
+}
+
+/// Create a stream of service events.
+///
+/// Awaits the D-Bus signal stream directly, so it only wakes up when the
+/// service emits something. Failed subscriptions are retried with backoff.
+pub fn event_stream() -> futures::stream::BoxStream<'static, ServiceUpdate> {
+    async_stream::stream! {
+        // Keep trying until the service is there, e.g. when it starts
+        // after the applet
+        let mut failures = 0;
         let client = loop {
-            let client_guard = CLIENT.lock().await;
-            if let Some(client) = client_guard.clone() {
-                drop(client_guard);
-                break client;
+            match connected_client().await {
+                Ok(client) => break client,
+                Err(e) => {
+                    let delay = kdeconnect_dbus_client::resubscribe_delay(failures);
+                    warn!("Can't connect to the service: {}, retrying in {:?}", e, delay);
+                    failures += 1;
+                    yield ServiceUpdate::NotReady(e.to_string());
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        };
+
+        debug!("Event stream: D-Bus client ready");
+
+        let mut failures = 0;
+        loop {
+            let mut events = match client.listen_for_events().await {
+                Ok(events) => events,
+                Err(e) => {
+                    let delay = kdeconnect_dbus_client::resubscribe_delay(failures);
+                    warn!("{:#}, retrying in {:?}", e, delay);
+                    failures += 1;
+                    yield ServiceUpdate::NotReady(e.to_string());
+                    tokio::time::sleep(delay).await;
+                    continue;
+                }
+            };
+            if failures > 0 {
+                info!("Subscribed to service signals after {} failed attempts", failures);
+                failures = 0;
             }
-            drop(client_guard);
-            
-            attempts += 1;
-            if attempts > 20 {
-                eprintln!("⚠️  Timeout waiting for D-Bus client initialization");
+            yield ServiceUpdate::Listening;
+
+            while let Some(event) = events.next().await {
+                yield ServiceUpdate::Event(event);
+            }
+
+            warn!("Event stream ended, resubscribing in 1s");
+            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+        }
+    }
+    .boxed()
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait Login1Manager {
+    #[zbus(signal)]
+    fn prepare_for_sleep(&self, start: bool) -> zbus::Result<()>;
+}
+
+/// Yield once each time the system resumes from suspend, using logind's
+/// PrepareForSleep signal. Ends immediately on systems without logind.
+pub fn resume_events() -> futures::stream::BoxStream<'static, ()> {
+    async_stream::stream! {
+        let signals = async {
+            let connection = zbus::Connection::system().await?;
+            Login1ManagerProxy::new(&connection).await?.receive_prepare_for_sleep().await
+        };
+        let mut signals = match signals.await {
+            Ok(signals) => signals,
+            Err(e) => {
+                info!("logind not available, not watching for resume: {}", e);
                 return;
             }
-            
-            eprintln!("⏳ Waiting for D-Bus client initialization... (attempt {})", attempts);
-            sleep(Duration::from_millis(100)).await;
         };
-        
-        eprintln!("✓ Event stream: D-Bus client ready");
-        
-        let mut stream = client.listen_for_events().await;
-        
-        while let Some(event) = stream.next().await {
-            if tx.send(event).await.is_err() {
-                eprintln!("Event receiver dropped, stopping event listener");
-                break;
+
+        while let Some(signal) = signals.next().await {
+            match signal.args() {
+                // start is true before sleeping and false after waking
+                Ok(args) if !args.start => yield (),
+                Ok(_) => debug!("System is going to sleep"),
+                Err(e) => warn!("Failed to parse PrepareForSleep signal: {:?}", e),
             }
         }
-        
-        eprintln!("Event stream ended");
-    });
+    }
+    .boxed()
+}
+
+/// Reset the service's device links after a resume; devices reappear as
+/// they reconnect
+pub async fn reset_connections() -> Result<u32, String> {
+    let client = connected_client().await.map_err(|e| e.to_string())?;
     
-    futures::stream::unfold(rx, |mut rx| async move {
-        match rx.recv().await {
-            Some(event) => Some((event, rx)),
-            None => {
-                loop {
-                    tokio::time::sleep(tokio::time::Duration::from_secs(3600)).await;
-                }
+    client.reset_connections().await
+        .map_err(|e| kdeconnect_dbus_client::error_detail(&e))
+}
+
+/// Applet messages for service events: connects and disconnects refresh
+/// that device, and pairing signals carry its name and type so the popup
+/// can show them before the refresh. SMS batches are handled by the SMS
+/// window; the applet only counts and announces unread messages and
+/// watches for new conversations, so they never refresh the device list.
+pub fn service_messages(
+    events: impl futures::Stream<Item = ServiceUpdate> + Send + 'static,
+) -> impl futures::Stream<Item = Message> + Send + 'static {
+    events.filter_map(|update| async move {
+        let event = match update {
+            ServiceUpdate::Listening => return Some(Message::ServiceReady),
+            ServiceUpdate::NotReady(reason) => return Some(Message::ServiceNotReady(reason)),
+            ServiceUpdate::Event(event) => event,
+        };
+        match event {
+            ServiceEvent::DeviceConnected(device_id, _)
+            | ServiceEvent::DeviceDisconnected(device_id) => Some(Message::RefreshDevice(device_id)),
+            ServiceEvent::DevicePaired(device_id, device) => {
+                let device = device_from_dbus(device);
+                Some(Message::PairingRequestReceived(device_id, device.name, device.device_type))
+            }
+            // An alias set on this computer wins over the phone's own name
+            ServiceEvent::DeviceRenamed(device_id, _) if device_aliases::alias_for(&device_id).is_some() => None,
+            ServiceEvent::DeviceRenamed(device_id, name) => Some(Message::DeviceRenamed(device_id, name)),
+            // Unattributed batches can't be counted or opened on a device
+            ServiceEvent::SmsMessagesReceived(device_id, json) => {
+                (!device_id.is_empty()).then_some(Message::SmsReceived(device_id, json))
             }
         }
-    }).boxed()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_service_messages_quiet_without_events() {
+        let mut messages = Box::pin(service_messages(futures::stream::pending()));
+        let quiet = tokio::time::timeout(Duration::from_millis(200), messages.next()).await;
+        assert!(quiet.is_err(), "no message expected while the service is silent");
+
+        let events = futures::stream::iter(vec![
+            ServiceUpdate::Event(ServiceEvent::SmsMessagesReceived(String::new(), "{}".to_string())),
+            ServiceUpdate::Event(ServiceEvent::DeviceDisconnected("phone".to_string())),
+        ]);
+        let messages: Vec<Message> = service_messages(events).collect().await;
+        assert_eq!(messages.len(), 1);
+        assert!(matches!(&messages[0], Message::RefreshDevice(id) if id == "phone"));
+    }
+
+    #[test]
+    fn test_disabled_plugin_blocks_send() {
+        let mut config = DevicePluginsConfig::default();
+        assert!(check_permission(&config, "clipboard").is_ok());
+        assert_eq!(
+            check_permission(&config, "remotecommands"),
+            Err(KdeConnectError::PluginDisabled("remotecommands".to_string())),
+            "off unless switched on"
+        );
+
+        config.set_enabled("clipboard", false);
+        let error = check_permission(&config, "clipboard").unwrap_err();
+        assert_eq!(error, KdeConnectError::PluginDisabled("clipboard".to_string()));
+        assert_eq!(error.to_string(), "The clipboard plugin is disabled for this device");
+        assert!(check_permission(&config, "ping").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_device_rename_propagates() {
+        let events = futures::stream::iter(vec![
+            ServiceUpdate::Event(ServiceEvent::DeviceRenamed("phone".to_string(), "Pixel 9".to_string())),
+        ]);
+        let messages: Vec<Message> = service_messages(events).collect().await;
+        assert!(matches!(
+            messages.as_slice(),
+            [Message::DeviceRenamed(id, name)] if id == "phone" && name == "Pixel 9"
+        ));
+
+        let mut cache = HashMap::new();
+        cache.insert("phone".to_string(), device_from_dbus(kdeconnect_dbus_client::Device {
+            id: "phone".to_string(),
+            name: "Old name".to_string(),
+            device_type: "phone".to_string(),
+            is_paired: true,
+            is_reachable: true,
+            pending_packets: 0,
+            connection_type: String::new(),
+            enabled: true,
+        }));
+        assert!(rename_device(&mut cache, "phone", "Pixel 9"));
+        assert_eq!(cache["phone"].name, "Pixel 9");
+        assert!(!rename_device(&mut cache, "tablet", "Tab"));
+    }
+
+    #[tokio::test]
+    async fn test_pairing_signal_carries_name_and_type() {
+        let device = |device_type: &str, is_paired| kdeconnect_dbus_client::Device {
+            id: "tab".to_string(),
+            name: "Galaxy Tab".to_string(),
+            device_type: device_type.to_string(),
+            is_paired,
+            is_reachable: true,
+            pending_packets: 0,
+            connection_type: String::new(),
+            enabled: true,
+        };
+        let events = futures::stream::iter(vec![
+            ServiceUpdate::Event(ServiceEvent::DevicePaired("tab".to_string(), device("tablet", false))),
+        ]);
+        let messages: Vec<Message> = service_messages(events).collect().await;
+        assert!(matches!(
+            messages.as_slice(),
+            [Message::PairingRequestReceived(id, name, kind)] if id == "tab" && name == "Galaxy Tab" && kind == "tablet"
+        ));
+
+        // An unknown device shows up asking to pair; a paired one only
+        // takes the new name and type
+        let mut devices = HashMap::new();
+        assert!(!note_pairing_request(&mut devices, "tab", "Galaxy Tab", "tablet"));
+        assert_eq!(devices["tab"].device_type, "tablet");
+        assert_eq!(devices["tab"].pairing_requests, 1);
+
+        devices.insert("tab".to_string(), device_from_dbus(device("", true)));
+        assert_eq!(devices["tab"].device_type, "phone", "blank types fall back to phone");
+        assert!(note_pairing_request(&mut devices, "tab", "Tab S9", "tablet"));
+        assert_eq!((devices["tab"].name.as_str(), devices["tab"].device_type.as_str()), ("Tab S9", "tablet"));
+        assert_eq!(devices["tab"].pairing_requests, 0);
+    }
 }
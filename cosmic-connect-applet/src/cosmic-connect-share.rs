@@ -0,0 +1,205 @@
+// cosmic-connect-applet/src/cosmic-connect-share.rs
+//! Share text or files with a device from scripts or a keyboard shortcut.
+//!
+//! Usage: cosmic-connect-share [--device <id> | --device-name <name>] [--text <text>] [FILE...]
+//!
+//! Without --text or files, the text is read from stdin, so the current
+//! selection can be shared with e.g. `wl-paste --primary | cosmic-connect-share
+//! --device-name pixel`. Without a device, the paired devices are listed.
+
+use cosmic_connect_applet::backend;
+use cosmic_connect_applet::models::Device;
+use std::io::{IsTerminal, Read};
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage: cosmic-connect-share [--device <id> | --device-name <name>] [--text <text>] [FILE...]";
+
+#[derive(Debug, Default, PartialEq)]
+struct Args {
+    device: Option<String>,
+    device_name: Option<String>,
+    text: Option<String>,
+    files: Vec<String>,
+    help: bool,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) if args.help => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("cosmic-connect-share: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--device" => parsed.device = Some(args.next().ok_or("--device needs a device id")?),
+            "--device-name" => parsed.device_name = Some(args.next().ok_or("--device-name needs a name")?),
+            "--text" => parsed.text = Some(args.next().ok_or("--text needs the text to send")?),
+            "-h" | "--help" => parsed.help = true,
+            "--" => parsed.files.extend(args.by_ref()),
+            option if option.starts_with('-') && option != "-" => {
+                return Err(format!("Unknown option: {}", option));
+            }
+            _ => parsed.files.push(arg),
+        }
+    }
+    if parsed.device.is_some() && parsed.device_name.is_some() {
+        return Err("Use either --device or --device-name, not both".to_string());
+    }
+    Ok(parsed)
+}
+
+async fn run(args: Args) -> Result<(), String> {
+    backend::initialize().await
+        .map_err(|e| format!("Can't reach kdeconnect-service: {}", e))?;
+    let mut devices: Vec<Device> = backend::fetch_devices().await
+        .into_iter()
+        .filter(|d| d.is_paired)
+        .collect();
+    devices.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let device = match (&args.device, &args.device_name) {
+        (Some(id), _) => devices.iter()
+            .find(|d| &d.id == id)
+            .ok_or_else(|| format!("No paired device with id {}", id))?,
+        (None, Some(name)) => find_by_name(&devices, |d| d.name.as_str(), name)?,
+        (None, None) => {
+            list_devices(&devices);
+            if args.text.is_some() || !args.files.is_empty() {
+                return Err("Choose a device with --device or --device-name".to_string());
+            }
+            return Ok(());
+        }
+    };
+
+    // The service resolves paths itself, so hand it absolute ones
+    let files = args.files.iter()
+        .map(|file| {
+            std::fs::canonicalize(file)
+                .map(|path| path.to_string_lossy().into_owned())
+                .map_err(|e| format!("{}: {}", file, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let text = match args.text {
+        Some(text) => Some(text),
+        None if files.is_empty() => Some(read_stdin()?),
+        None => None,
+    };
+
+    if let Some(text) = text {
+        backend::send_text(device.id.clone(), text).await
+            .map_err(|e| format!("Failed to send text to {}: {}", device.name, e))?;
+    }
+    if !files.is_empty() {
+        let count = files.len();
+        backend::send_files(device.id.clone(), files).await
+            .map_err(|e| format!("Failed to send files to {}: {}", device.name, e))?;
+        println!("Sending {} file(s) to {}", count, device.name);
+    }
+    Ok(())
+}
+
+fn list_devices(devices: &[Device]) {
+    if devices.is_empty() {
+        println!("No paired devices");
+        return;
+    }
+    for device in devices {
+        let status = if device.is_reachable { "connected" } else { "offline" };
+        println!("{}\t{}\t({})", device.id, device.name, status);
+    }
+}
+
+/// Text piped in, without the newline `echo` adds
+fn read_stdin() -> Result<String, String> {
+    let mut stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return Err("Nothing to send: pass --text or files, or pipe text in".to_string());
+    }
+    let mut text = String::new();
+    stdin.read_to_string(&mut text).map_err(|e| format!("Failed to read stdin: {}", e))?;
+    if let Some(stripped) = text.strip_suffix('\n') {
+        text.truncate(stripped.len());
+    }
+    if text.is_empty() {
+        return Err("Nothing to send: stdin was empty".to_string());
+    }
+    Ok(text)
+}
+
+/// Find the item `query` names, ignoring case: an exact name first, then
+/// names containing it, then names holding its letters in order ("px7"
+/// finds "Pixel 7"). Several matches at the same step is an error.
+fn find_by_name<'a, T>(items: &'a [T], name: impl Fn(&T) -> &str, query: &str) -> Result<&'a T, String> {
+    let query = query.to_lowercase();
+    let steps: [&dyn Fn(&str) -> bool; 3] = [
+        &|candidate: &str| candidate == query,
+        &|candidate: &str| candidate.contains(&query),
+        &|candidate: &str| has_letters_in_order(candidate, &query),
+    ];
+
+    for matches in steps {
+        let found: Vec<&T> = items.iter()
+            .filter(|item| matches(&name(item).to_lowercase()))
+            .collect();
+        match found.as_slice() {
+            [] => continue,
+            [item] => return Ok(item),
+            _ => {
+                let names: Vec<&str> = found.iter().map(|item| name(item)).collect();
+                return Err(format!("\"{}\" matches several devices: {}", query, names.join(", ")));
+            }
+        }
+    }
+    Err(format!("No paired device matches \"{}\"", query))
+}
+
+fn has_letters_in_order(candidate: &str, query: &str) -> bool {
+    let mut letters = candidate.chars();
+    query.chars()
+        .filter(|c| !c.is_whitespace())
+        .all(|c| letters.any(|l| l == c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args_and_device_name_matching() {
+        let args = parse_args(["--device-name", "pixel", "a.txt", "--", "--b.txt"].map(String::from)).unwrap();
+        assert_eq!(args.device_name.as_deref(), Some("pixel"));
+        assert_eq!(args.files, ["a.txt", "--b.txt"]);
+        assert!(parse_args(["--device".to_string()]).is_err());
+        assert!(parse_args(["--device", "a", "--device-name", "b"].map(String::from)).is_err());
+        assert!(parse_args(["--verbose".to_string()]).is_err());
+
+        let names = ["Pixel 7", "Pixel 7 Pro", "Galaxy S22"];
+        let find = |query| find_by_name(&names, |n| *n, query).copied();
+        assert_eq!(find("pixel 7"), Ok("Pixel 7"));
+        assert_eq!(find("pro"), Ok("Pixel 7 Pro"));
+        assert_eq!(find("gs22"), Ok("Galaxy S22"));
+        assert!(find("pixel").unwrap_err().contains("several"));
+        assert!(find("iphone").is_err());
+    }
+}
@@ -1,19 +1,46 @@
 // cosmic-connect-applet/src/cosmic-connect-sms.rs
 //! Binary entry point for the SMS window application.
+//!
+//! Usage: cosmic-connect-sms <device_id[,device_id...] | --all> [device_name] [--thread thread_id]
+//!
+//! Several comma-separated ids, or --all for every paired device, open a
+//! unified inbox across devices. --thread opens that conversation, in the
+//! window already open for the devices if there is one.
+
+use cosmic_connect_applet::plugins::sms::instance;
+use tracing::{info, warn};
 
 fn main() -> cosmic::iced::Result {
+    cosmic_connect_applet::logging::init();
+
     // Setup signal handlers for graceful shutdown
     setup_signal_handlers();
     
-    let args: Vec<String> = std::env::args().collect();
+    let mut args: Vec<String> = std::env::args().collect();
+    let thread_id = args.iter()
+        .position(|arg| arg == "--thread")
+        .and_then(|i| args.drain(i..(i + 2).min(args.len())).nth(1));
     
     let device_id = args.get(1).cloned().unwrap_or_else(|| "unknown".to_string());
     let device_name = args.get(2).cloned().unwrap_or_else(|| "Unknown Device".to_string());
     
-    eprintln!("=== KDE Connect SMS Window ===");
-    eprintln!("Device: {} ({})", device_name, device_id);
+    // Only one window per device: raise the existing one instead
+    let runtime = tokio::runtime::Runtime::new().expect("failed to start tokio runtime");
+    let _instance = match runtime.block_on(instance::claim(&device_id, thread_id.as_deref())) {
+        Ok(Some(connection)) => Some(connection),
+        Ok(None) => {
+            info!("SMS window for {} is already open, raised it", device_id);
+            return Ok(());
+        }
+        Err(e) => {
+            warn!("Single-instance check failed, opening anyway: {}", e);
+            None
+        }
+    };
+    
+    info!("Starting SMS window for {} ({})", device_name, device_id);
     
-    cosmic_connect_applet::plugins::sms::run(device_id, device_name)
+    cosmic_connect_applet::plugins::sms::run(device_id, device_name, thread_id)
 }
 
 fn setup_signal_handlers() {
@@ -23,11 +50,11 @@ fn setup_signal_handlers() {
     
     ctrlc::set_handler(move || {
         if SHUTDOWN_REQUESTED.swap(true, Ordering::SeqCst) {
-            eprintln!("Force shutdown");
+            info!("Force shutdown");
             std::process::exit(1);
         }
         
-        eprintln!("Graceful shutdown requested...");
+        info!("Graceful shutdown requested");
         std::process::exit(0);
     })
     .ok(); // Ignore error if already set
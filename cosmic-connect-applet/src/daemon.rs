@@ -0,0 +1,173 @@
+// cosmic-connect-applet/src/daemon.rs
+//! Detecting, starting and autostarting kdeconnect-service.
+//!
+//! The applet and the SMS window share one service over D-Bus, so the
+//! applet never runs its own connection to the phones. When the service
+//! isn't running the popup says so and offers to start it: through the
+//! systemd user unit when it is installed, otherwise by spawning the binary.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::{debug, info};
+
+/// systemd user unit installed by `just install-systemd`
+pub const UNIT: &str = "kdeconnect.service";
+
+/// Service binary, looked up in ~/.local/bin first and then on PATH
+pub const BINARY: &str = "kdeconnect-service";
+
+/// How long a started service gets to claim its bus name
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// State of the shared service as seen from the applet
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum DaemonStatus {
+    #[default]
+    Checking,
+    Running { version: String },
+    NotRunning,
+    Starting,
+    /// Running, but speaking a different D-Bus API revision. `None` if it
+    /// is too old to report one.
+    Incompatible { version: Option<String>, api_version: Option<u32> },
+    /// The session bus itself couldn't be reached
+    Unreachable(String),
+}
+
+impl DaemonStatus {
+    /// Classify what the service reported about itself
+    pub fn from_version(version: Option<(String, u32)>) -> Self {
+        match version {
+            Some((version, api)) if api == kdeconnect_dbus_client::API_VERSION => Self::Running { version },
+            Some((version, api)) => Self::Incompatible { version: Some(version), api_version: Some(api) },
+            None => Self::Incompatible { version: None, api_version: None },
+        }
+    }
+}
+
+/// Look up the service on the session bus
+pub async fn status() -> DaemonStatus {
+    match kdeconnect_dbus_client::service_running().await {
+        Ok(true) => {}
+        Ok(false) => return DaemonStatus::NotRunning,
+        Err(e) => return DaemonStatus::Unreachable(e.to_string()),
+    }
+
+    match kdeconnect_dbus_client::service_version().await {
+        Ok(version) => DaemonStatus::from_version(version),
+        Err(e) => DaemonStatus::Unreachable(kdeconnect_dbus_client::error_detail(&e)),
+    }
+}
+
+/// Start the service and wait for it to claim its bus name
+pub async fn start() -> Result<(), String> {
+    if unit_installed().await {
+        info!("Starting {}", UNIT);
+        systemctl(&["start", UNIT]).await?;
+    } else {
+        let binary = binary_path();
+        info!("Spawning {}", binary.display());
+        // Not waited on; tokio reaps the process if it exits before the applet
+        Command::new(&binary)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Couldn't run {}: {}", binary.display(), e))?;
+    }
+
+    let deadline = tokio::time::Instant::now() + STARTUP_TIMEOUT;
+    while tokio::time::Instant::now() < deadline {
+        if matches!(kdeconnect_dbus_client::service_running().await, Ok(true)) {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_millis(250)).await;
+    }
+    Err(format!("The service didn't start within {} seconds", STARTUP_TIMEOUT.as_secs()))
+}
+
+/// Start the service at login from now on: enables the systemd unit when it
+/// is installed, otherwise adds an XDG autostart entry
+pub async fn enable_autostart() -> Result<(), String> {
+    if unit_installed().await {
+        return systemctl(&["enable", UNIT]).await;
+    }
+
+    let path = autostart_path();
+    let entry = format!(
+        "[Desktop Entry]\nType=Application\nName=KDE Connect Service\nExec={}\nNoDisplay=true\nX-GNOME-Autostart-enabled=true\n",
+        binary_path().display()
+    );
+    tokio::task::spawn_blocking(move || {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, entry)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| format!("Couldn't write autostart entry: {}", e))
+}
+
+async fn unit_installed() -> bool {
+    let installed = Command::new("systemctl")
+        .args(["--user", "cat", UNIT])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+        .is_ok_and(|status| status.success());
+    debug!("{} installed: {}", UNIT, installed);
+    installed
+}
+
+async fn systemctl(args: &[&str]) -> Result<(), String> {
+    let output = Command::new("systemctl")
+        .arg("--user")
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| format!("Couldn't run systemctl: {}", e))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+fn binary_path() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+    let local = PathBuf::from(format!("{}/.local/bin/{}", home, BINARY));
+    if local.exists() { local } else { PathBuf::from(BINARY) }
+}
+
+fn autostart_path() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+    PathBuf::from(format!("{}/.config/autostart/{}.desktop", home, BINARY))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_from_version() {
+        let api = kdeconnect_dbus_client::API_VERSION;
+        assert_eq!(
+            DaemonStatus::from_version(Some(("0.1.0".to_string(), api))),
+            DaemonStatus::Running { version: "0.1.0".to_string() }
+        );
+        assert_eq!(
+            DaemonStatus::from_version(Some(("0.2.0".to_string(), api + 1))),
+            DaemonStatus::Incompatible { version: Some("0.2.0".to_string()), api_version: Some(api + 1) }
+        );
+        assert_eq!(
+            DaemonStatus::from_version(None),
+            DaemonStatus::Incompatible { version: None, api_version: None }
+        );
+    }
+}
@@ -0,0 +1,116 @@
+// cosmic-connect-applet/src/device_aliases.rs
+//! Names chosen on this computer for paired devices.
+//!
+//! Stored in ~/.config/cosmic-connect/device_aliases under [Aliases] as
+//! device_id=name. An alias replaces the name the device announces
+//! everywhere it is shown, including after the phone is renamed.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// Longest alias kept, in characters
+pub const MAX_ALIAS_CHARS: usize = 64;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceAliases {
+    aliases: BTreeMap<String, String>,
+}
+
+impl DeviceAliases {
+    /// Load aliases from file
+    pub fn load() -> io::Result<Self> {
+        let path = Self::get_config_path();
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let mut aliases = BTreeMap::new();
+        for line in fs::read_to_string(&path)?.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+                continue;
+            }
+            if let Some((device_id, alias)) = line.split_once('=') {
+                aliases.insert(device_id.trim().to_string(), alias.trim().to_string());
+            }
+        }
+
+        Ok(Self { aliases })
+    }
+
+    /// Save aliases to file
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::get_config_path();
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = fs::File::create(&path)?;
+        writeln!(file, "[Aliases]")?;
+        for (device_id, alias) in &self.aliases {
+            writeln!(file, "{}={}", device_id, alias)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn get(&self, device_id: &str) -> Option<&str> {
+        self.aliases.get(device_id).map(String::as_str)
+    }
+
+    /// Set a device's alias; a blank one removes it. Line breaks become
+    /// spaces and the alias is cut to MAX_ALIAS_CHARS. Returns whether
+    /// anything changed.
+    pub fn set(&mut self, device_id: &str, alias: &str) -> bool {
+        let alias: String = alias
+            .split(['\n', '\r'])
+            .collect::<Vec<_>>()
+            .join(" ")
+            .trim()
+            .chars()
+            .take(MAX_ALIAS_CHARS)
+            .collect();
+
+        if alias.is_empty() {
+            return self.aliases.remove(device_id).is_some();
+        }
+        self.aliases.insert(device_id.to_string(), alias.clone()) != Some(alias)
+    }
+
+    fn get_config_path() -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+        PathBuf::from(format!("{}/.config/cosmic-connect/device_aliases", home))
+    }
+}
+
+/// The alias for a device, if one is set
+pub fn alias_for(device_id: &str) -> Option<String> {
+    DeviceAliases::load().ok()?.get(device_id).map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alias_set_and_clear() {
+        let mut aliases = DeviceAliases::default();
+        assert!(aliases.set("phone", "  Work phone "));
+        assert_eq!(aliases.get("phone"), Some("Work phone"));
+        assert!(!aliases.set("phone", "Work phone"), "unchanged");
+
+        assert!(aliases.set("phone", "Line\nbreak"));
+        assert_eq!(aliases.get("phone"), Some("Line break"));
+
+        aliases.set("tablet", &"x".repeat(100));
+        assert_eq!(aliases.get("tablet").map(|a| a.chars().count()), Some(MAX_ALIAS_CHARS));
+
+        assert!(aliases.set("phone", "   "));
+        assert_eq!(aliases.get("phone"), None);
+        assert!(!aliases.set("phone", ""));
+    }
+}
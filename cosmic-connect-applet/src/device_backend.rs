@@ -0,0 +1,159 @@
+// cosmic-connect-applet/src/device_backend.rs
+//! Device requests the applet makes of kdeconnect-service.
+//!
+//! `KdeConnectApplet` makes them through a `DeviceBackend` rather than
+//! calling `backend` directly, so its update logic can be tested against
+//! `MockDevices`. Calling a method only builds the request; the work
+//! happens when the returned future runs.
+
+use futures::future::{BoxFuture, FutureExt};
+use kdeconnect_dbus_client::KdeConnectError;
+
+use crate::backend;
+use crate::models::Device;
+
+type Result<T, E = KdeConnectError> = std::result::Result<T, E>;
+
+pub trait DeviceBackend: Send + Sync {
+    fn fetch_devices(&self) -> BoxFuture<'static, Vec<Device>>;
+
+    /// `UnknownDevice` if the service no longer knows the device
+    fn fetch_device(&self, device_id: String) -> BoxFuture<'static, Result<Device>>;
+
+    /// Ping with `message`, or the configured text when None
+    fn ping_device(&self, device_id: String, message: Option<String>) -> BoxFuture<'static, Result<()>>;
+
+    fn ring_device(&self, device_id: String) -> BoxFuture<'static, Result<()>>;
+
+    fn pair_device(&self, device_id: String) -> BoxFuture<'static, Result<()>>;
+
+    fn unpair_device(&self, device_id: String, keep_data: bool) -> BoxFuture<'static, Result<()>>;
+
+    fn accept_pairing(&self, device_id: String) -> BoxFuture<'static, Result<()>>;
+
+    fn reject_pairing(&self, device_id: String) -> BoxFuture<'static, Result<()>>;
+
+    fn set_device_enabled(&self, device_id: String, enabled: bool) -> BoxFuture<'static, Result<Device>>;
+}
+
+/// Devices reached through kdeconnect-service
+pub struct DbusDevices;
+
+impl DeviceBackend for DbusDevices {
+    fn fetch_devices(&self) -> BoxFuture<'static, Vec<Device>> {
+        backend::fetch_devices().boxed()
+    }
+
+    fn fetch_device(&self, device_id: String) -> BoxFuture<'static, Result<Device>> {
+        backend::fetch_device(device_id).boxed()
+    }
+
+    fn ping_device(&self, device_id: String, message: Option<String>) -> BoxFuture<'static, Result<()>> {
+        backend::ping_device(device_id, message).boxed()
+    }
+
+    fn ring_device(&self, device_id: String) -> BoxFuture<'static, Result<()>> {
+        backend::ring_device(device_id).boxed()
+    }
+
+    fn pair_device(&self, device_id: String) -> BoxFuture<'static, Result<()>> {
+        backend::pair_device(device_id).boxed()
+    }
+
+    fn unpair_device(&self, device_id: String, keep_data: bool) -> BoxFuture<'static, Result<()>> {
+        backend::unpair_device(device_id, keep_data).boxed()
+    }
+
+    fn accept_pairing(&self, device_id: String) -> BoxFuture<'static, Result<()>> {
+        backend::accept_pairing(device_id).boxed()
+    }
+
+    fn reject_pairing(&self, device_id: String) -> BoxFuture<'static, Result<()>> {
+        backend::reject_pairing(device_id).boxed()
+    }
+
+    fn set_device_enabled(&self, device_id: String, enabled: bool) -> BoxFuture<'static, Result<Device>> {
+        backend::set_device_enabled(device_id, enabled).boxed()
+    }
+}
+
+#[cfg(test)]
+pub mod mock {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// A request as the applet made it
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum Request {
+        FetchDevices,
+        FetchDevice(String),
+        Ping(String, Option<String>),
+        Ring(String),
+        Pair(String),
+        /// device_id, keep_data
+        Unpair(String, bool),
+        AcceptPairing(String),
+        RejectPairing(String),
+        SetEnabled(String, bool),
+    }
+
+    /// Records requests instead of sending them. No devices are known, and
+    /// everything else succeeds.
+    #[derive(Default)]
+    pub struct MockDevices {
+        requests: Mutex<Vec<Request>>,
+    }
+
+    impl MockDevices {
+        /// Requests made since the last call, oldest first
+        pub fn take(&self) -> Vec<Request> {
+            std::mem::take(&mut *self.requests.lock().unwrap())
+        }
+
+        fn record(&self, request: Request) -> BoxFuture<'static, Result<()>> {
+            self.requests.lock().unwrap().push(request);
+            async { Ok(()) }.boxed()
+        }
+    }
+
+    impl DeviceBackend for MockDevices {
+        fn fetch_devices(&self) -> BoxFuture<'static, Vec<Device>> {
+            self.requests.lock().unwrap().push(Request::FetchDevices);
+            async { Vec::new() }.boxed()
+        }
+
+        fn fetch_device(&self, device_id: String) -> BoxFuture<'static, Result<Device>> {
+            self.requests.lock().unwrap().push(Request::FetchDevice(device_id.clone()));
+            async { Err(KdeConnectError::UnknownDevice(device_id)) }.boxed()
+        }
+
+        fn ping_device(&self, device_id: String, message: Option<String>) -> BoxFuture<'static, Result<()>> {
+            self.record(Request::Ping(device_id, message))
+        }
+
+        fn ring_device(&self, device_id: String) -> BoxFuture<'static, Result<()>> {
+            self.record(Request::Ring(device_id))
+        }
+
+        fn pair_device(&self, device_id: String) -> BoxFuture<'static, Result<()>> {
+            self.record(Request::Pair(device_id))
+        }
+
+        fn unpair_device(&self, device_id: String, keep_data: bool) -> BoxFuture<'static, Result<()>> {
+            self.record(Request::Unpair(device_id, keep_data))
+        }
+
+        fn accept_pairing(&self, device_id: String) -> BoxFuture<'static, Result<()>> {
+            self.record(Request::AcceptPairing(device_id))
+        }
+
+        fn reject_pairing(&self, device_id: String) -> BoxFuture<'static, Result<()>> {
+            self.record(Request::RejectPairing(device_id))
+        }
+
+        fn set_device_enabled(&self, device_id: String, enabled: bool) -> BoxFuture<'static, Result<Device>> {
+            self.requests.lock().unwrap().push(Request::SetEnabled(device_id, enabled));
+            async { Err(KdeConnectError::NotInitialized) }.boxed()
+        }
+    }
+}
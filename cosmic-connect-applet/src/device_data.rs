@@ -0,0 +1,75 @@
+// cosmic-connect-applet/src/device_data.rs
+//! Per-device data left on this computer, removed when a device is unpaired.
+//!
+//! Covers plugin settings under ~/.config/kdeconnect/{device_id}, SMS state
+//! under ~/.config/cosmic-connect/{device_id}, synced contacts under
+//! ~/.local/share/kdeconnect/{device_id} and the device's entry in the
+//! trusted devices list.
+
+use cosmic_connect_common::trusted_devices::TrustedDevicesConfig;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use tracing::{debug, warn};
+
+/// Directories holding data for `device_id`. Empty when the id isn't a single
+/// plain path component, so a hostile id can't point outside these folders.
+pub fn data_dirs(device_id: &str) -> Vec<PathBuf> {
+    if device_id.is_empty()
+        || device_id == "."
+        || device_id == ".."
+        || device_id.contains(['/', '\\', '\0'])
+    {
+        return Vec::new();
+    }
+
+    let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+    vec![
+        PathBuf::from(format!("{}/.config/kdeconnect/{}", home, device_id)),
+        PathBuf::from(format!("{}/.config/cosmic-connect/{}", home, device_id)),
+        PathBuf::from(format!("{}/.local/share/kdeconnect/{}", home, device_id)),
+    ]
+}
+
+/// Delete everything stored for a device. Keeps going past failures (e.g. a
+/// locked file) and returns them as (what, error) pairs for logging.
+pub fn remove(device_id: &str) -> Vec<(String, io::Error)> {
+    let mut failures = Vec::new();
+
+    for dir in data_dirs(device_id) {
+        match fs::remove_dir_all(&dir) {
+            Ok(()) => debug!("Removed {}", dir.display()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => failures.push((dir.display().to_string(), e)),
+        }
+    }
+
+    match TrustedDevicesConfig::load() {
+        Ok(mut trusted) => {
+            if trusted.remove(device_id) {
+                if let Err(e) = trusted.save() {
+                    failures.push(("trusted devices".to_string(), e));
+                }
+            }
+        }
+        Err(e) => warn!("Failed to load trusted devices: {}", e),
+    }
+
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_dirs_reject_unsafe_ids() {
+        let dirs = data_dirs("a1b2c3");
+        assert_eq!(dirs.len(), 3);
+        assert!(dirs.iter().all(|d| d.ends_with("a1b2c3")));
+
+        for id in ["", ".", "..", "../..", "a/b", "a\\b"] {
+            assert!(data_dirs(id).is_empty(), "{:?} should be rejected", id);
+        }
+    }
+}
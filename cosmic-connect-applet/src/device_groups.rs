@@ -0,0 +1,179 @@
+// cosmic-connect-applet/src/device_groups.rs
+//! Groups for organizing many paired devices in the popup.
+//!
+//! Stored in ~/.config/cosmic-connect/device_groups under [Groups] as
+//! name=device_id,device_id in display order. A device is in at most one
+//! group; devices in none are listed under "Ungrouped". Without groups the
+//! popup keeps its flat list.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// Heading for devices that are in no group
+pub const UNGROUPED: &str = "Ungrouped";
+
+/// Longest group name kept, in characters
+pub const MAX_GROUP_NAME_CHARS: usize = 32;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceGroups {
+    /// Group names with their device ids, in display order
+    groups: Vec<(String, Vec<String>)>,
+}
+
+impl DeviceGroups {
+    /// Load groups from file
+    pub fn load() -> io::Result<Self> {
+        let path = Self::get_config_path();
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        Ok(Self::parse(&fs::read_to_string(&path)?))
+    }
+
+    fn parse(content: &str) -> Self {
+        let mut groups = Self::default();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+                continue;
+            }
+            if let Some((name, device_ids)) = line.split_once('=') {
+                for device_id in device_ids.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+                    groups.assign(device_id, Some(name));
+                }
+            }
+        }
+        groups
+    }
+
+    /// Save groups to file
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::get_config_path();
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = fs::File::create(&path)?;
+        writeln!(file, "[Groups]")?;
+        for (name, device_ids) in &self.groups {
+            writeln!(file, "{}={}", name, device_ids.join(","))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Group names in display order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.groups.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// The group a device is in
+    pub fn group_of(&self, device_id: &str) -> Option<&str> {
+        self.groups.iter()
+            .find(|(_, device_ids)| device_ids.iter().any(|id| id == device_id))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Move a device into `group`, creating it at the end if needed, or out
+    /// of every group with None. Groups left empty are removed. Line breaks
+    /// and '=' are dropped from the name, which is cut to
+    /// MAX_GROUP_NAME_CHARS; a blank name, or "Ungrouped", means no group.
+    /// Returns whether anything changed.
+    pub fn assign(&mut self, device_id: &str, group: Option<&str>) -> bool {
+        let name: Option<String> = group
+            .map(|name| {
+                name.chars()
+                    .filter(|c| !c.is_control() && *c != '=')
+                    .collect::<String>()
+                    .trim()
+                    .chars()
+                    .take(MAX_GROUP_NAME_CHARS)
+                    .collect::<String>()
+            })
+            .filter(|name| !name.is_empty() && name != UNGROUPED);
+        if self.group_of(device_id) == name.as_deref() {
+            return false;
+        }
+
+        for (_, device_ids) in &mut self.groups {
+            device_ids.retain(|id| id != device_id);
+        }
+        self.groups.retain(|(_, device_ids)| !device_ids.is_empty());
+
+        if let Some(name) = name {
+            match self.groups.iter_mut().find(|(existing, _)| *existing == name) {
+                Some((_, device_ids)) => device_ids.push(device_id.to_string()),
+                None => self.groups.push((name, vec![device_id.to_string()])),
+            }
+        }
+        true
+    }
+
+    /// Split `items` into sections by group, in group order, with
+    /// ungrouped items last under None. Items keep their order within a
+    /// section and empty sections are left out.
+    pub fn sections<'a, T>(&'a self, items: Vec<T>, id: impl Fn(&T) -> &str) -> Vec<(Option<&'a str>, Vec<T>)> {
+        let mut sections: Vec<(Option<&str>, Vec<T>)> = self.names()
+            .map(|name| (Some(name), Vec::new()))
+            .chain(std::iter::once((None, Vec::new())))
+            .collect();
+        for item in items {
+            let group = self.group_of(id(&item));
+            if let Some((_, section)) = sections.iter_mut().find(|(name, _)| *name == group) {
+                section.push(item);
+            }
+        }
+        sections.retain(|(_, section)| !section.is_empty());
+        sections
+    }
+
+    fn get_config_path() -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+        PathBuf::from(format!("{}/.config/cosmic-connect/device_groups", home))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_device_groups_assign_and_sections() {
+        let mut groups = DeviceGroups::parse("[Groups]\nFamily=kid,partner\nWork=laptop\n");
+        assert_eq!(groups.names().collect::<Vec<_>>(), ["Family", "Work"]);
+        assert_eq!(groups.group_of("partner"), Some("Family"));
+
+        let devices = ["kid", "laptop", "mine", "partner"];
+        let sections = groups.sections(devices.to_vec(), |d| d);
+        assert_eq!(sections, [
+            (Some("Family"), vec!["kid", "partner"]),
+            (Some("Work"), vec!["laptop"]),
+            (None, vec!["mine"]),
+        ]);
+
+        // Moving the last device out of a group removes the group
+        assert!(groups.assign("laptop", Some(" Family ")));
+        assert!(!groups.assign("laptop", Some("Family")), "unchanged");
+        assert_eq!(groups.names().collect::<Vec<_>>(), ["Family"]);
+
+        assert!(groups.assign("mine", Some("Me=\nmyself")));
+        assert_eq!(groups.group_of("mine"), Some("Memyself"));
+        assert!(groups.assign("mine", Some(UNGROUPED)));
+        assert_eq!(groups.group_of("mine"), None);
+        assert!(!groups.assign("mine", Some("  ")));
+
+        for device in ["kid", "partner", "laptop"] {
+            groups.assign(device, None);
+        }
+        assert!(groups.is_empty());
+    }
+}
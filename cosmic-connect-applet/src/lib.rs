@@ -4,13 +4,18 @@
 //! This library provides shared modules for the KDE Connect applet,
 //! settings window, and SMS window binaries.
 
+pub mod applet_config;
 pub mod backend;
+pub mod daemon;
+pub mod device_aliases;
+pub mod device_backend;
+pub mod device_groups;
+pub mod device_data;
+pub mod logging;
 pub mod messages;
 pub mod models;
-pub mod notifications;
+pub mod onboarding;
 pub mod plugins;
 pub mod portal;
+pub mod settings_backup;
 pub mod ui;
-
-// Re-export commonly used types
-pub use notifications::{PairingNotification, start_notification_listener};
\ No newline at end of file
@@ -0,0 +1,13 @@
+// cosmic-connect-applet/src/logging.rs
+//! Logging setup shared by the applet, settings, and SMS binaries.
+
+/// Install a `tracing` subscriber honoring `RUST_LOG`, defaulting to `info`
+/// so per-message traces (logged at `debug`) stay out of the journal.
+pub fn init() {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info"))
+        )
+        .init();
+}
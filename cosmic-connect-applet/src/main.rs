@@ -1,28 +1,80 @@
 // cosmic-connect-applet/src/main.rs
 
+mod applet_config;
 mod backend;
+mod daemon;
+mod device_aliases;
+mod device_backend;
+mod device_groups;
+mod device_data;
+mod logging;
 mod messages;
 mod models;
-mod notifications;
+mod onboarding;
 mod plugins;
 mod portal;
 mod ui;
 
 use messages::Message;
-use models::Device;
+use models::{ClipboardHistory, Device};
 
 use cosmic::app::Core;
 use cosmic::iced::window::Id as SurfaceId;
 use cosmic::iced::{Limits, Subscription};
 use cosmic::iced_winit::commands::popup::{destroy_popup, get_popup};
 use cosmic::{widget, Element, Task};
-use std::collections::HashMap;
+use cosmic_connect_common::plugin_config;
+use kdeconnect_dbus_client::KdeConnectError;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tracing::{debug, error, info, warn};
+
+/// How long "Sent to <device>" stays under the clipboard button
+const CLIPBOARD_CONFIRMATION: std::time::Duration = std::time::Duration::from_secs(4);
+
+/// Taps on "Find my phone" within this long of the last ring are ignored,
+/// so they don't stack rings on the phone
+const RING_COOLDOWN: std::time::Duration = std::time::Duration::from_secs(5);
 
 pub struct KdeConnectApplet {
     core: Core,
+    /// Where device requests go
+    device_backend: Arc<dyn device_backend::DeviceBackend>,
     popup: Option<SurfaceId>,
     devices: HashMap<String, Device>,
     expanded_device: Option<String>,
+    drop_target: Option<String>,
+    ping_message: String,
+    /// Device whose unpair confirmation is showing
+    confirm_unpair: Option<String>,
+    onboarding: ui::popup::Onboarding,
+    /// Dense device list, persisted in the applet config
+    compact: bool,
+    /// Panel icon and label, read from the applet config
+    panel: applet_config::PanelConfig,
+    /// Groups the device list is split into; empty for a flat list
+    groups: device_groups::DeviceGroups,
+    /// Headings of collapsed groups
+    collapsed_groups: HashSet<String>,
+    /// Conversations seen per device, for opening the SMS window on new ones
+    new_threads: plugins::sms::auto_open::NewThreadWatcher,
+    /// Unread text messages per device, shown on the panel icon
+    unread_sms: plugins::sms::unread::UnreadSms,
+    /// When each device was last rung
+    last_ring: HashMap<String, std::time::Instant>,
+    /// New group name typed on the expanded card
+    group_input: String,
+    /// Clipboard items shared this session, for re-sending
+    clipboard_history: ClipboardHistory,
+    /// Device whose "Clipboard history" section is expanded
+    clipboard_device: Option<String>,
+    /// Confirmation, warning or error from the last clipboard share, by device
+    clipboard_notice: Option<(String, String)>,
+    /// Set while the service's signals can't be subscribed to
+    service_error: Option<String>,
+    daemon_status: daemon::DaemonStatus,
+    /// Why starting the service or enabling autostart failed
+    daemon_error: Option<String>,
 }
 
 impl cosmic::Application for KdeConnectApplet {
@@ -37,18 +89,11 @@ impl cosmic::Application for KdeConnectApplet {
     fn init(core: Core, _flags: Self::Flags) -> (Self, Task<cosmic::Action<Self::Message>>) {
         tokio::spawn(async {
             if let Err(e) = backend::initialize().await {
-                eprintln!("Backend init failed: {:?}", e);
+                error!("Backend init failed: {:?}", e);
             }
         });
 
-        let app = KdeConnectApplet {
-            core,
-            popup: None,
-            devices: HashMap::new(),
-            expanded_device: None,
-        };
-
-        (app, Task::none())
+        (Self::new(core, Arc::new(device_backend::DbusDevices)), check_daemon())
     }
 
     fn on_close_requested(&self, id: SurfaceId) -> Option<Message> {
@@ -79,7 +124,7 @@ impl cosmic::Application for KdeConnectApplet {
 
                     Task::batch(vec![
                         get_popup(popup_settings),
-                        Task::perform(backend::fetch_devices(), |devices| {
+                        Task::perform(self.device_backend.fetch_devices(), |devices| {
                             cosmic::Action::App(Message::DevicesUpdated(devices))
                         }),
                     ])
@@ -91,7 +136,7 @@ impl cosmic::Application for KdeConnectApplet {
                 }
             }
             Message::RefreshDevices => {
-                return Task::perform(backend::fetch_devices(), |devices| {
+                return Task::perform(self.device_backend.fetch_devices(), |devices| {
                     cosmic::Action::App(Message::DevicesUpdated(devices))
                 });
             }
@@ -100,9 +145,118 @@ impl cosmic::Application for KdeConnectApplet {
                 for device in devices {
                     self.devices.insert(device.id.clone(), device);
                 }
+                self.finish_onboarding_once_paired();
+            }
+            Message::RefreshDevice(device_id) => {
+                let id = device_id.clone();
+                return Task::perform(self.device_backend.fetch_device(id), move |device| {
+                    cosmic::Action::App(Message::DeviceUpdated(device_id, device))
+                });
+            }
+            Message::DeviceUpdated(device_id, device) => {
+                match device {
+                    Ok(device) => {
+                        self.devices.insert(device_id, device);
+                    }
+                    Err(KdeConnectError::UnknownDevice(_)) => {
+                        self.devices.remove(&device_id);
+                    }
+                    // A failed refresh says nothing about the device, so
+                    // keep showing what was last known
+                    Err(e) => warn!("Failed to refresh {}: {}", device_id, e),
+                }
+                self.finish_onboarding_once_paired();
+            }
+            Message::DeviceRenamed(device_id, name) => {
+                info!("Device {} is now called {}", device_id, name);
+                backend::rename_device(&mut self.devices, &device_id, &name);
+                return Task::perform(
+                    async move { backend::apply_rename(&device_id, &name).await },
+                    |_| cosmic::Action::None,
+                );
+            }
+            Message::ServiceReady => {
+                let check = check_daemon();
+                // Signals may have been missed while not subscribed
+                if self.service_error.take().is_some() {
+                    return Task::batch([
+                        check,
+                        Task::perform(self.device_backend.fetch_devices(), |devices| {
+                            cosmic::Action::App(Message::DevicesUpdated(devices))
+                        }),
+                    ]);
+                }
+                return check;
+            }
+            Message::Resumed => {
+                info!("Resumed from suspend, resetting device connections");
+                return Task::perform(backend::reset_connections(), |result| {
+                    cosmic::Action::App(Message::ConnectionsReset(result))
+                });
+            }
+            Message::ConnectionsReset(result) => {
+                match result {
+                    Ok(count) => debug!("Reset {} device connection(s)", count),
+                    Err(e) => warn!("Failed to reset connections after resume: {}", e),
+                }
+                return Task::perform(self.device_backend.fetch_devices(), |devices| {
+                    cosmic::Action::App(Message::DevicesUpdated(devices))
+                });
+            }
+            Message::ServiceNotReady(reason) => {
+                self.service_error = Some(reason);
+                return check_daemon();
+            }
+            Message::CheckDaemon => {
+                return check_daemon();
+            }
+            Message::DaemonStatusChecked(status) => {
+                // A check finishing mid-start would flash "not running"
+                if self.daemon_status == daemon::DaemonStatus::Starting
+                    && status == daemon::DaemonStatus::NotRunning
+                {
+                    return Task::none();
+                }
+                if status != self.daemon_status {
+                    info!("Service status: {:?}", status);
+                }
+                self.daemon_status = status;
+            }
+            Message::StartDaemon(autostart) => {
+                self.daemon_status = daemon::DaemonStatus::Starting;
+                self.daemon_error = None;
+                return Task::perform(
+                    async move {
+                        if autostart {
+                            daemon::enable_autostart().await?;
+                        }
+                        daemon::start().await
+                    },
+                    |result| cosmic::Action::App(Message::DaemonStarted(result)),
+                );
+            }
+            Message::DaemonStarted(result) => {
+                if let Err(e) = result {
+                    warn!("Failed to start the service: {}", e);
+                    self.daemon_error = Some(e);
+                }
+                self.daemon_status = daemon::DaemonStatus::Checking;
+                return check_daemon();
+            }
+            Message::SetDeviceEnabled(device_id, enabled) => {
+                let id = device_id.clone();
+                return Task::perform(self.device_backend.set_device_enabled(id, enabled), move |result| {
+                    match result {
+                        Ok(device) => cosmic::Action::App(Message::DeviceUpdated(device_id, Ok(device))),
+                        Err(e) => {
+                            warn!("Failed to set {} enabled={}: {:?}", device_id, enabled, e);
+                            cosmic::Action::App(Message::RefreshDevice(device_id))
+                        }
+                    }
+                });
             }
             Message::DelayedRefresh => {
-                return Task::perform(backend::fetch_devices(), |devices| {
+                return Task::perform(self.device_backend.fetch_devices(), |devices| {
                     cosmic::Action::App(Message::DevicesUpdated(devices))
                 });
             }
@@ -112,41 +266,92 @@ impl cosmic::Application for KdeConnectApplet {
                 } else {
                     self.expanded_device = Some(device_id.clone());
                 }
+                self.ping_message.clear();
+                self.group_input.clear();
             }
-            Message::SendSMS(ref device_id) => {
-                // Look up device name for the window title
-                let device_name = self.devices
-                    .get(device_id)
-                    .map(|d| d.name.clone())
-                    .unwrap_or_else(|| "Unknown Device".to_string());
-                let id = device_id.clone();
-
-                eprintln!("[APPLET] Launching SMS window for device={} name={}", id, device_name);
-
-                // Spawn in a thread so the process::Command doesn't block the executor
-                std::thread::spawn(move || {
-                    match std::process::Command::new("cosmic-connect-sms")
-                        .arg(&id)
-                        .arg(&device_name)
-                        .spawn()
-                    {
-                        Ok(_) => eprintln!("[APPLET] cosmic-connect-sms launched OK"),
-                        Err(e) => eprintln!("[APPLET] Failed to launch cosmic-connect-sms: {:?}", e),
+            Message::ToggleCompactView => {
+                self.compact = !self.compact;
+                let config = applet_config::AppletConfig {
+                    compact: self.compact,
+                    panel: self.panel.clone(),
+                };
+                if let Err(e) = config.save() {
+                    warn!("Failed to save applet config: {}", e);
+                }
+            }
+            Message::ToggleGroupCollapsed(title) => {
+                if !self.collapsed_groups.remove(&title) {
+                    self.collapsed_groups.insert(title);
+                }
+            }
+            Message::GroupNameChanged(name) => {
+                self.group_input = name;
+            }
+            Message::AssignGroup(device_id, group) => {
+                self.group_input.clear();
+                if self.groups.assign(&device_id, group.as_deref()) {
+                    if let Err(e) = self.groups.save() {
+                        warn!("Failed to save device groups: {}", e);
                     }
+                }
+            }
+            Message::SendSMS(device_id) => {
+                self.unread_sms.clear(&device_id);
+                self.launch_sms_window(device_id, None);
+            }
+            Message::SmsReceived(device_id, json) => {
+                let (messages, _) = plugins::sms::dbus::parse_sms_messages(&json);
+                let notifications = Task::batch(self.unread_sms.observe(&device_id, &messages).into_iter().map(|message| {
+                    Task::perform(
+                        backend::show_sms_notification(device_id.clone(), message.address.clone(), message.body.clone()),
+                        |_| cosmic::Action::None,
+                    )
+                }));
+
+                let auto_open = plugins::sms::auto_open::AutoOpenConfig::load().unwrap_or_else(|e| {
+                    warn!("Failed to load SMS auto-open setting: {}", e);
+                    Default::default()
                 });
+                if !auto_open.enabled {
+                    return notifications;
+                }
+                if let Some(thread_id) = self.new_threads.observe(&device_id, &messages) {
+                    info!("New conversation {} on {}, opening it", thread_id, device_id);
+                    self.unread_sms.clear(&device_id);
+                    self.launch_sms_window(device_id, Some(thread_id));
+                }
+                return notifications;
             }
-            Message::PingDevice(ref device_id) => {
-                let id = device_id.clone();
+            Message::PingDevice(device_id) => {
+                let ping = self.device_backend.ping_device(device_id.clone(), None);
                 return Task::perform(
-                    async move { backend::ping_device(id).await.ok(); },
-                    |_| cosmic::Action::App(Message::RefreshDevices),
+                    async move { ping.await.ok(); },
+                    move |_| cosmic::Action::App(Message::RefreshDevice(device_id)),
                 );
             }
-            Message::RingDevice(ref device_id) => {
-                let id = device_id.clone();
+            Message::PingMessageChanged(text) => {
+                self.ping_message = text;
+            }
+            Message::SendCustomPing(device_id) => {
+                let text = std::mem::take(&mut self.ping_message);
+                let message = Some(text).filter(|t| !t.trim().is_empty());
+                let ping = self.device_backend.ping_device(device_id.clone(), message);
                 return Task::perform(
-                    async move { backend::ring_device(id).await.ok(); },
-                    |_| cosmic::Action::App(Message::RefreshDevices),
+                    async move { ping.await.ok(); },
+                    move |_| cosmic::Action::App(Message::RefreshDevice(device_id)),
+                );
+            }
+            Message::RingDevice(device_id) => {
+                let now = std::time::Instant::now();
+                if self.last_ring.get(&device_id).is_some_and(|last| now.duration_since(*last) < RING_COOLDOWN) {
+                    debug!("Rang {} moments ago, not ringing again", device_id);
+                    return Task::none();
+                }
+                self.last_ring.insert(device_id.clone(), now);
+                let ring = self.device_backend.ring_device(device_id.clone());
+                return Task::perform(
+                    async move { ring.await.ok(); },
+                    move |_| cosmic::Action::App(Message::RefreshDevice(device_id)),
                 );
             }
             Message::BrowseDevice(ref device_id) => {
@@ -157,19 +362,30 @@ impl cosmic::Application for KdeConnectApplet {
                 );
             }
             Message::PairDevice(ref device_id) => {
-                let id = device_id.clone();
+                let pair = self.device_backend.pair_device(device_id.clone());
                 return Task::perform(
-                    async move { backend::pair_device(id).await.ok(); },
+                    async move { pair.await.ok(); },
                     |_| cosmic::Action::App(Message::RefreshDevices),
                 );
             }
-            Message::UnpairDevice(ref device_id) => {
-                let id = device_id.clone();
+            Message::UnpairDevice(device_id) => {
+                self.confirm_unpair = Some(device_id);
+            }
+            Message::ConfirmUnpair(device_id, keep_data) => {
+                self.confirm_unpair = None;
+                let unpair = self.device_backend.unpair_device(device_id, keep_data);
                 return Task::perform(
-                    async move { backend::unpair_device(id).await.ok(); },
+                    async move {
+                        if let Err(e) = unpair.await {
+                            warn!("Failed to unpair device: {:?}", e);
+                        }
+                    },
                     |_| cosmic::Action::App(Message::RefreshDevices),
                 );
             }
+            Message::CancelUnpair => {
+                self.confirm_unpair = None;
+            }
             Message::SendFiles(ref device_id) => {
                 let id = device_id.clone();
                 return Task::perform(
@@ -182,66 +398,198 @@ impl cosmic::Application for KdeConnectApplet {
                     |_| cosmic::Action::App(Message::RefreshDevices),
                 );
             }
-            Message::ShareClipboard(ref device_id) => {
+            Message::FilesDropped(device_id, paths) => {
+                self.drop_target = None;
+                let files: Vec<String> = paths.iter()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .collect();
+                if files.is_empty() {
+                    return Task::none();
+                }
+                info!("Sending {} dropped file(s) to {}", files.len(), device_id);
+                return Task::perform(
+                    async move { backend::send_files(device_id, files).await.ok(); },
+                    |_| cosmic::Action::App(Message::RefreshDevices),
+                );
+            }
+            Message::DropTargetChanged(target) => {
+                self.drop_target = target;
+            }
+            Message::ShareClipboard(device_id) => {
                 let id = device_id.clone();
+                self.clipboard_notice = None;
                 return Task::perform(
                     async move {
-                        if let Ok(content) = portal::read_clipboard().await {
-                            backend::send_clipboard(id, content).await.ok();
-                        }
+                        let mut content = portal::read_clipboard().await.map_err(|e| e.to_string())?;
+                        let config = plugin_config::ClipboardPluginConfig::load(&id).unwrap_or_default();
+                        let warning = plugin_config::truncate_clipboard_text(&mut content, config.max_text_bytes())
+                            .then(|| format!("Clipboard is over {} KB, only the start was sent", config.max_text_kb));
+                        backend::send_clipboard(id, content.clone()).await.map_err(|e| e.to_string())?;
+                        Ok((content, warning))
                     },
-                    |_| cosmic::Action::App(Message::RefreshDevices),
+                    move |result| cosmic::Action::App(Message::ClipboardShared(device_id, result)),
                 );
             }
-            Message::AcceptPairing(ref device_id) => {
+            Message::ClipboardShared(device_id, result) => {
+                match result {
+                    Ok((content, Some(warning))) => {
+                        self.clipboard_history.push(content);
+                        self.clipboard_notice = Some((device_id.clone(), warning));
+                    }
+                    Ok((content, None)) => {
+                        self.clipboard_history.push(content);
+                        // Phones don't acknowledge clipboard packets, so the
+                        // confirmation is optimistic once the service has it
+                        let device = self.devices.get(&device_id);
+                        let name = device.map_or(device_id.as_str(), |d| d.name.as_str());
+                        let confirmation = if device.is_some_and(|d| !d.is_reachable) {
+                            format!("Queued for {}", name)
+                        } else {
+                            format!("Sent to {}", name)
+                        };
+                        self.clipboard_notice = Some((device_id.clone(), confirmation.clone()));
+                        let id = device_id.clone();
+                        let refresh = Task::perform(self.device_backend.fetch_device(device_id.clone()), move |device| {
+                            cosmic::Action::App(Message::DeviceUpdated(device_id, device))
+                        });
+                        let expire = Task::perform(tokio::time::sleep(CLIPBOARD_CONFIRMATION), move |_| {
+                            cosmic::Action::App(Message::ClipboardNoticeExpired(id, confirmation))
+                        });
+                        return Task::batch([refresh, expire]);
+                    }
+                    Err(e) => {
+                        warn!("Failed to share clipboard with {}: {}", device_id, e);
+                        self.clipboard_notice = Some((device_id.clone(), format!("Couldn't share clipboard: {}", e)));
+                    }
+                }
+                return Task::perform(self.device_backend.fetch_device(device_id.clone()), move |device| {
+                    cosmic::Action::App(Message::DeviceUpdated(device_id, device))
+                });
+            }
+            Message::ClipboardNoticeExpired(device_id, confirmation) => {
+                if self.clipboard_notice.as_ref().is_some_and(|(id, notice)| *id == device_id && *notice == confirmation) {
+                    self.clipboard_notice = None;
+                }
+            }
+            Message::ToggleClipboardHistory(device_id) => {
+                if self.clipboard_device.as_ref() == Some(&device_id) {
+                    self.clipboard_device = None;
+                } else {
+                    self.clipboard_device = Some(device_id);
+                }
+            }
+            Message::ResendClipboard(device_id, index) => {
+                let Some(content) = self.clipboard_history.get(index).cloned() else {
+                    return Task::none();
+                };
                 let id = device_id.clone();
                 return Task::perform(
-                    async move { backend::accept_pairing(id).await.ok(); },
+                    async move { backend::send_clipboard(id, content).await.ok(); },
+                    move |_| cosmic::Action::App(Message::RefreshDevice(device_id)),
+                );
+            }
+            Message::ClearClipboardHistory => {
+                self.clipboard_history.clear();
+            }
+            Message::AcceptPairing(ref device_id) => {
+                let accept = self.device_backend.accept_pairing(device_id.clone());
+                return Task::perform(
+                    async move { accept.await.ok(); },
                     |_| cosmic::Action::App(Message::RefreshDevices),
                 );
             }
             Message::RejectPairing(ref device_id) => {
-                let id = device_id.clone();
+                let reject = self.device_backend.reject_pairing(device_id.clone());
                 return Task::perform(
-                    async move { backend::reject_pairing(id).await.ok(); },
+                    async move { reject.await.ok(); },
                     |_| cosmic::Action::App(Message::RefreshDevices),
                 );
             }
             Message::PairingRequestReceived(device_id, device_name, device_type) => {
-                eprintln!("Pairing request: {} ({}) [{}]", device_name, device_id, device_type);
+                info!("Pairing request: {} ({}) [{}]", device_name, device_id, device_type);
+
+                // Shown straight away; the refresh brings the rest of the
+                // device's state
+                backend::note_pairing_request(&mut self.devices, &device_id, &device_name, &device_type);
+                return cosmic::task::message(cosmic::Action::App(Message::RefreshDevice(device_id)));
             }
             Message::MprisReceived(device_id, mpris_data) => {
-                eprintln!("MPRIS from {}: {:?}", device_id, mpris_data);
+                debug!("MPRIS from {}: {:?}", device_id, mpris_data);
             }
             Message::OpenSettings => {
                 std::process::Command::new("cosmic-connect-settings").spawn().ok();
             }
+            Message::DismissOnboarding => {
+                self.dismiss_onboarding();
+            }
             Message::RemoteInput(ref device_id) => {
-                eprintln!("Remote input: {}", device_id);
+                debug!("Remote input not yet implemented: {}", device_id);
             }
             Message::LockDevice(ref device_id) => {
-                eprintln!("Lock device: {}", device_id);
+                debug!("Lock device not yet implemented: {}", device_id);
             }
             Message::PresenterMode(ref device_id) => {
-                eprintln!("Presenter mode: {}", device_id);
+                debug!("Presenter mode not yet implemented: {}", device_id);
             }
             Message::UseAsMonitor(ref device_id) => {
-                eprintln!("Use as monitor: {}", device_id);
+                debug!("Use as monitor not yet implemented: {}", device_id);
             }
             Message::ShareText(ref device_id) => {
-                eprintln!("Share text: {}", device_id);
+                debug!("Share text not yet implemented: {}", device_id);
             }
             Message::ShareUrl(ref device_id) => {
-                eprintln!("Share URL: {}", device_id);
+                debug!("Share URL not yet implemented: {}", device_id);
             }
         }
         Task::none()
     }
 
     fn view(&self) -> Element<'_, Self::Message> {
-        self.core
-            .applet
-            .icon_button("phone-symbolic")
+        let badge = ui::badge::PanelBadge {
+            unread_sms: self.unread_sms.total(),
+            ..ui::badge::panel_badge(&self.devices)
+        };
+        let icon = self.panel.icon_name();
+        let unread = badge.unread_label();
+        let label = badge.label(self.panel.style);
+        if label.is_none() && unread.is_none() {
+            return self.core
+                .applet
+                .icon_button(icon)
+                .on_press(Message::TogglePopup)
+                .into();
+        }
+
+        let (icon_size, _) = self.core.applet.suggested_size(true);
+        let (pad_major, pad_minor) = self.core.applet.suggested_padding(true);
+        let content = widget::row()
+            .spacing(2)
+            .align_y(cosmic::iced::Alignment::Center)
+            .push(widget::icon::from_name(icon).size(icon_size))
+            .push_maybe(label.map(|label| {
+                widget::container(widget::text(label).size(10).font(cosmic::font::bold()))
+                    .padding([0, 3])
+                    .class(if badge.attention {
+                        cosmic::theme::Container::Primary
+                    } else {
+                        cosmic::theme::Container::Card
+                    })
+            }))
+            .push_maybe(unread.map(|unread| {
+                widget::container(
+                    widget::row()
+                        .spacing(1)
+                        .align_y(cosmic::iced::Alignment::Center)
+                        .push(widget::icon::from_name("mail-unread-symbolic").size(10))
+                        .push(widget::text(unread).size(10).font(cosmic::font::bold()))
+                )
+                .padding([0, 3])
+                .class(cosmic::theme::Container::Primary)
+            }));
+
+        widget::button::custom(content)
+            .padding([pad_minor, pad_major])
+            .class(cosmic::theme::Button::AppletIcon)
             .on_press(Message::TogglePopup)
             .into()
     }
@@ -258,6 +606,26 @@ impl cosmic::Application for KdeConnectApplet {
             &self.devices,
             self.expanded_device.as_ref(),
             None,
+            self.drop_target.as_ref(),
+            &self.ping_message,
+            self.confirm_unpair.as_ref(),
+            &self.onboarding,
+            self.compact,
+            self.service_error.as_deref(),
+            ui::popup::Daemon {
+                status: &self.daemon_status,
+                error: self.daemon_error.as_deref(),
+            },
+            ui::popup::ClipboardMenu {
+                open_for: self.clipboard_device.as_ref(),
+                history: &self.clipboard_history,
+                notice: self.clipboard_notice.as_ref().map(|(id, notice)| (id, notice.as_str())),
+            },
+            ui::popup::Groups {
+                config: &self.groups,
+                collapsed: &self.collapsed_groups,
+                input: &self.group_input,
+            },
         )
     }
 
@@ -266,12 +634,200 @@ impl cosmic::Application for KdeConnectApplet {
     }
 
     fn subscription(&self) -> Subscription<Self::Message> {
-        cosmic::iced::time::every(std::time::Duration::from_secs(10))
+        use futures::StreamExt;
+
+        // Load the device list once connected, then follow service signals.
+        // Nothing is emitted while the service is idle.
+        let events = futures::stream::once(backend::wait_until_ready())
             .map(|_| Message::RefreshDevices)
+            .chain(backend::service_messages(backend::event_stream()));
+
+        // Links to phones die silently during suspend; drop them on resume
+        // instead of waiting for the next poll
+        let resumes = backend::resume_events().map(|_| Message::Resumed);
+
+        Subscription::batch([
+            Subscription::run_with_id("kdeconnect-events", events),
+            Subscription::run_with_id("logind-resume", resumes),
+        ])
+    }
+}
+
+impl KdeConnectApplet {
+    /// Applet state with device requests going through `device_backend`
+    fn new(core: Core, device_backend: Arc<dyn device_backend::DeviceBackend>) -> Self {
+        let config = applet_config::AppletConfig::load().unwrap_or_else(|e| {
+            warn!("Failed to load applet config: {}", e);
+            applet_config::AppletConfig::default()
+        });
+
+        KdeConnectApplet {
+            core,
+            device_backend,
+            popup: None,
+            devices: HashMap::new(),
+            expanded_device: None,
+            drop_target: None,
+            ping_message: String::new(),
+            confirm_unpair: None,
+            compact: config.compact,
+            panel: config.panel,
+            groups: device_groups::DeviceGroups::load().unwrap_or_else(|e| {
+                warn!("Failed to load device groups: {}", e);
+                device_groups::DeviceGroups::default()
+            }),
+            collapsed_groups: HashSet::new(),
+            new_threads: Default::default(),
+            unread_sms: Default::default(),
+            last_ring: HashMap::new(),
+            group_input: String::new(),
+            onboarding: ui::popup::Onboarding {
+                dismissed: onboarding::OnboardingState::load().unwrap_or_else(|e| {
+                    warn!("Failed to load onboarding state: {}", e);
+                    onboarding::OnboardingState::default()
+                }).dismissed,
+            },
+            clipboard_history: ClipboardHistory::default(),
+            clipboard_device: None,
+            clipboard_notice: None,
+            service_error: None,
+            daemon_status: daemon::DaemonStatus::Checking,
+            daemon_error: None,
+        }
+    }
+
+    /// Open the SMS window for a device, on `thread_id` if given
+    fn launch_sms_window(&self, device_id: String, thread_id: Option<String>) {
+        // Look up device name for the window title
+        let device_name = self.devices
+            .get(&device_id)
+            .map(|d| d.name.clone())
+            .unwrap_or_else(|| "Unknown Device".to_string());
+
+        info!("Launching SMS window for device={} name={}", device_id, device_name);
+
+        // Spawn in a thread so the process::Command doesn't block the executor
+        std::thread::spawn(move || {
+            let mut command = std::process::Command::new("cosmic-connect-sms");
+            command.arg(&device_id).arg(&device_name);
+            if let Some(thread_id) = thread_id {
+                command.arg("--thread").arg(thread_id);
+            }
+            match command.spawn() {
+                Ok(_) => debug!("cosmic-connect-sms launched"),
+                Err(e) => warn!("Failed to launch cosmic-connect-sms: {:?}", e),
+            }
+        });
+    }
+
+    fn dismiss_onboarding(&mut self) {
+        self.onboarding.dismissed = true;
+        if let Err(e) = (onboarding::OnboardingState { dismissed: true }).save() {
+            warn!("Failed to save onboarding state: {}", e);
+        }
+    }
+
+    /// The guide is only for getting the first device paired
+    fn finish_onboarding_once_paired(&mut self) {
+        if !self.onboarding.dismissed && self.devices.values().any(|d| d.is_paired) {
+            self.dismiss_onboarding();
+        }
     }
 }
 
+/// Look up the shared service on the session bus
+fn check_daemon() -> Task<cosmic::Action<Message>> {
+    Task::perform(daemon::status(), |status| {
+        cosmic::Action::App(Message::DaemonStatusChecked(status))
+    })
+}
+
 fn main() -> cosmic::iced::Result {
+    logging::init();
     ctrlc::set_handler(move || std::process::exit(0)).ok();
     cosmic::applet::run::<KdeConnectApplet>(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmic::Application;
+    use device_backend::mock::{MockDevices, Request};
+
+    #[test]
+    fn test_device_actions_against_mock() {
+        let backend = Arc::new(MockDevices::default());
+        let mut applet = KdeConnectApplet::new(Core::default(), backend.clone());
+        let phone = || "phone".to_string();
+
+        let _ = applet.update(Message::PingDevice(phone()));
+        assert_eq!(backend.take(), [Request::Ping(phone(), None)]);
+
+        // A blank custom ping falls back to the configured text
+        let _ = applet.update(Message::PingMessageChanged("  ".to_string()));
+        let _ = applet.update(Message::SendCustomPing(phone()));
+        let _ = applet.update(Message::PingMessageChanged("Dinner".to_string()));
+        let _ = applet.update(Message::SendCustomPing(phone()));
+        assert!(applet.ping_message.is_empty());
+        assert_eq!(backend.take(), [
+            Request::Ping(phone(), None),
+            Request::Ping(phone(), Some("Dinner".to_string())),
+        ]);
+
+        // Unpairing asks first
+        let _ = applet.update(Message::UnpairDevice(phone()));
+        assert_eq!(applet.confirm_unpair, Some(phone()));
+        assert!(backend.take().is_empty());
+        let _ = applet.update(Message::ConfirmUnpair(phone(), true));
+        assert_eq!(applet.confirm_unpair, None);
+        assert_eq!(backend.take(), [Request::Unpair(phone(), true)]);
+
+        let _ = applet.update(Message::AcceptPairing("tablet".to_string()));
+        let _ = applet.update(Message::RejectPairing("laptop".to_string()));
+        let _ = applet.update(Message::RefreshDevices);
+        assert_eq!(backend.take(), [
+            Request::AcceptPairing("tablet".to_string()),
+            Request::RejectPairing("laptop".to_string()),
+            Request::FetchDevices,
+        ]);
+    }
+
+    #[test]
+    fn test_repeated_ring_taps_ring_once() {
+        let backend = Arc::new(MockDevices::default());
+        let mut applet = KdeConnectApplet::new(Core::default(), backend.clone());
+
+        let _ = applet.update(Message::RingDevice("phone".to_string()));
+        let _ = applet.update(Message::RingDevice("phone".to_string()));
+        let _ = applet.update(Message::RingDevice("tablet".to_string()));
+        assert_eq!(backend.take(), [
+            Request::Ring("phone".to_string()),
+            Request::Ring("tablet".to_string()),
+        ]);
+
+        // Once the cooldown has passed the phone rings again
+        applet.last_ring.insert("phone".to_string(), std::time::Instant::now() - RING_COOLDOWN);
+        let _ = applet.update(Message::RingDevice("phone".to_string()));
+        assert_eq!(backend.take(), [Request::Ring("phone".to_string())]);
+    }
+
+    #[test]
+    fn test_clipboard_share_confirmation_expires() {
+        let backend = Arc::new(MockDevices::default());
+        let mut applet = KdeConnectApplet::new(Core::default(), backend.clone());
+        let phone = || "phone".to_string();
+        let sent = || Message::ClipboardNoticeExpired(phone(), "Sent to phone".to_string());
+
+        let _ = applet.update(Message::ClipboardShared(phone(), Ok(("Hello".to_string(), None))));
+        assert_eq!(applet.clipboard_notice, Some((phone(), "Sent to phone".to_string())));
+        assert_eq!(backend.take(), [Request::FetchDevice(phone())]);
+        let _ = applet.update(sent());
+        assert_eq!(applet.clipboard_notice, None);
+
+        // Warnings stay, and an old confirmation expiring doesn't clear them
+        let warning = "Clipboard is over 1 KB, only the start was sent".to_string();
+        let _ = applet.update(Message::ClipboardShared(phone(), Ok(("Hello".to_string(), Some(warning.clone())))));
+        let _ = applet.update(sent());
+        assert_eq!(applet.clipboard_notice, Some((phone(), warning)));
+    }
+}
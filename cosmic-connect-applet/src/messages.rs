@@ -1,7 +1,10 @@
 // cosmic-connect-applet/src/messages.rs
 // #[allow(dead_code)] = Placeholder for code that will be used once features are fully integrated
 
+use crate::daemon::DaemonStatus;
 use crate::models::Device;
+use kdeconnect_dbus_client::KdeConnectError;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -9,20 +12,52 @@ pub enum Message {
     PopupClosed(cosmic::iced::window::Id),
     RefreshDevices,
     DevicesUpdated(Vec<Device>),
+    RefreshDevice(String),
+    /// device_id, and the device or why it couldn't be fetched
+    DeviceUpdated(String, Result<Device, KdeConnectError>),
+    DeviceRenamed(String, String), // device_id, name announced by the device
+    ServiceReady,
+    ServiceNotReady(String), // why subscribing to the service failed
+    CheckDaemon,
+    DaemonStatusChecked(DaemonStatus),
+    StartDaemon(bool), // also start it at login
+    DaemonStarted(Result<(), String>),
+    Resumed, // the system woke from suspend
+    ConnectionsReset(Result<u32, String>),
+    SetDeviceEnabled(String, bool),
     ToggleDeviceMenu(String),
+    ToggleCompactView,
+    ToggleGroupCollapsed(String), // group heading
+    GroupNameChanged(String),
+    AssignGroup(String, Option<String>), // device_id, group; None leaves every group
     
     // Device actions
     PingDevice(String),
+    PingMessageChanged(String),
+    SendCustomPing(String),
     PairDevice(String),
-    UnpairDevice(String),
+    UnpairDevice(String), // asks for confirmation first
+    ConfirmUnpair(String, bool), // device_id, keep the device's data
+    CancelUnpair,
     RingDevice(String),
     BrowseDevice(String),
     SendFiles(String),
+    FilesDropped(String, Vec<PathBuf>), // device_id, dropped files
+    DropTargetChanged(Option<String>),  // device card currently under a drag
     SendSMS(String),
+    SmsReceived(String, String), // device_id, JSON batch, for the unread badge and new conversations
     ShareClipboard(String),
+    ClipboardShared(String, Result<(String, Option<String>), String>), // device_id, Ok((text sent, warning))
+    ClipboardNoticeExpired(String, String), // device_id, confirmation to clear if still shown
+    ToggleClipboardHistory(String),
+    ResendClipboard(String, usize), // device_id, index into the history
+    ClearClipboardHistory,
     ShareText(String),
     ShareUrl(String),
     
+    // First-run guide
+    DismissOnboarding,
+    
     // Advanced features
     RemoteInput(String),
     LockDevice(String),
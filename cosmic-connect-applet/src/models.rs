@@ -29,10 +29,15 @@ pub struct Device {
     pub has_lockdevice: bool,
     pub has_virtualmonitor: bool,
     pub pairing_requests: i32,
+    /// Sends buffered by the service until the device reconnects
+    pub pending_packets: u32,
     // Connectivity information
     pub signal_strength: Option<i32>,  // 0-4 bars, or -1 for no signal
     #[allow(dead_code)]
     pub network_type: Option<String>,  // "5G", "4G", "3G", "2G", etc.
+    pub connection_type: Option<String>,  // "lan" or "bluetooth", None if unknown
+    // Syncing is on; a disabled device stays paired but exchanges nothing
+    pub enabled: bool,
 }
 
 impl Device {
@@ -63,6 +68,14 @@ impl Device {
         }
     }
 
+    pub fn connection_icon(&self) -> Option<&'static str> {
+        match self.connection_type.as_deref() {
+            Some("lan") => Some("network-wireless-symbolic"),
+            Some("bluetooth") => Some("bluetooth-active-symbolic"),
+            _ => None,
+        }
+    }
+
     pub fn signal_icon(&self) -> Option<&'static str> {
         self.signal_strength.map(|strength| {
             match strength {
@@ -76,4 +89,68 @@ impl Device {
             }
         })
     }
-}
\ No newline at end of file
+}
+
+/// Most clipboard items remembered for re-sending
+pub const CLIPBOARD_HISTORY_LEN: usize = 20;
+
+/// Clipboard contents shared with phones, newest first. Kept in memory
+/// only so clipboard contents never reach the disk.
+#[derive(Debug, Clone, Default)]
+pub struct ClipboardHistory {
+    items: std::collections::VecDeque<String>,
+}
+
+impl ClipboardHistory {
+    /// Record a captured item, skipping empty text and repeats of the
+    /// newest item
+    pub fn push(&mut self, content: String) {
+        if content.trim().is_empty() || self.items.front() == Some(&content) {
+            return;
+        }
+        self.items.push_front(content);
+        self.items.truncate(CLIPBOARD_HISTORY_LEN);
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = &String> {
+        self.items.iter()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&String> {
+        self.items.get(index)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clipboard_history_dedup_and_cap() {
+        let mut history = ClipboardHistory::default();
+        history.push("a".to_string());
+        history.push("a".to_string());
+        history.push("  ".to_string());
+        history.push("b".to_string());
+        history.push("a".to_string());
+        let items: Vec<&str> = history.items().map(String::as_str).collect();
+        assert_eq!(items, ["a", "b", "a"]);
+
+        for i in 0..30 {
+            history.push(i.to_string());
+        }
+        assert_eq!(history.items().count(), CLIPBOARD_HISTORY_LEN);
+        assert_eq!(history.get(0).map(String::as_str), Some("29"));
+
+        history.clear();
+        assert!(history.is_empty());
+    }
+}
@@ -0,0 +1,56 @@
+// cosmic-connect-applet/src/onboarding.rs
+//! Whether the first-run guide in the popup has been dismissed.
+//!
+//! Stored in ~/.config/cosmic-connect/onboarding. Set once the first device
+//! is paired or the user closes the guide, so it doesn't come back.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OnboardingState {
+    pub dismissed: bool,
+}
+
+impl OnboardingState {
+    /// Load state from file
+    pub fn load() -> io::Result<Self> {
+        let path = Self::get_config_path();
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let mut state = Self::default();
+        for line in fs::read_to_string(&path)?.lines() {
+            if let Some((key, value)) = line.trim().split_once('=') {
+                if key.trim() == "dismissed" {
+                    state.dismissed = value.trim().parse().unwrap_or(false);
+                }
+            }
+        }
+
+        Ok(state)
+    }
+
+    /// Save state to file
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::get_config_path();
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = fs::File::create(&path)?;
+        writeln!(file, "[General]")?;
+        writeln!(file, "dismissed={}", self.dismissed)?;
+
+        Ok(())
+    }
+
+    fn get_config_path() -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+        PathBuf::from(format!("{}/.config/cosmic-connect/onboarding", home))
+    }
+}
@@ -1,18 +1,96 @@
 // cosmic-connect-applet/src/plugins/sms/app.rs
 use cosmic::{
     app::Core,
-    iced::{Length, Subscription},
+    iced::{keyboard, widget::text_editor, Length, Subscription},
     iced_futures::futures::StreamExt,
     widget, Application, ApplicationExt, Element, Task, Action,
 };
 use async_stream::stream;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tracing::{debug, error, info, warn};
 
+use super::appearance::{BubbleColor, SmsAppearanceConfig};
+use super::backend::{DbusSms, SmsBackend};
+use super::cache::ConversationCache;
 use super::dbus;
-use super::models::{Conversation, Message, ProtocolEvent};
+use super::instance;
+use super::layout::SmsLayoutConfig;
+use super::models::{ContactsState, Conversation, Message, MessageEvent, ProtocolEvent, SendStatus};
+use super::retention::{self, RetentionConfig};
+use super::storage::{self, MutedThreads, QuickRepliesConfig, SendConfirmConfig, SessionState, ThreadStateConfig};
+use super::undo::InputHistory;
 use super::utils;
 use super::views;
 
+/// Number of messages requested from the phone and revealed per page.
+pub const MESSAGE_PAGE_SIZE: usize = 50;
+
+/// Number of messages requested per page when catching up on a thread
+/// that was cached; most reopened threads need only the first page.
+pub const SYNC_PAGE_SIZE: usize = 10;
+
+/// Device argument that aggregates every paired device into one inbox
+pub const ALL_DEVICES_ARG: &str = "--all";
+
+/// Storage key for window state in unified-inbox mode
+const UNIFIED_STORAGE_ID: &str = "unified";
+
+/// How long a manual sync waits for the phone's conversation list
+const SYNC_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// How often relative timestamps ("2 min ago") are redrawn
+const TIMESTAMP_REFRESH: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Distance from the bottom of the message list, in pixels, that still
+/// counts as being at the latest message
+const AT_BOTTOM_SLACK: f32 = 24.0;
+
+/// Thread key used in unified mode, where thread ids from different phones
+/// could otherwise collide
+fn thread_key(device_id: &str, thread_id: &str) -> String {
+    format!("{}/{}", device_id, thread_id)
+}
+
+/// Tag parsed messages and conversations with the device they came from
+fn tag_source(messages: &mut [Message], conversations: &mut [Conversation], source: &str, unified: bool) {
+    for conv in conversations.iter_mut() {
+        conv.device_id = source.to_string();
+        if unified {
+            conv.thread_id = thread_key(source, &conv.thread_id);
+        }
+    }
+    if unified {
+        for msg in messages.iter_mut() {
+            msg.thread_id = thread_key(source, &msg.thread_id);
+        }
+    }
+}
+
+/// Alt+Up/Alt+Down move between conversations while a thread is open
+fn thread_navigation_key(key: keyboard::Key, modifiers: keyboard::Modifiers) -> Option<SmsMessage> {
+    use keyboard::key::Named;
+
+    if !modifiers.alt() {
+        return None;
+    }
+    match key {
+        keyboard::Key::Named(Named::ArrowUp) => Some(SmsMessage::SelectAdjacentThread(-1)),
+        keyboard::Key::Named(Named::ArrowDown) => Some(SmsMessage::SelectAdjacentThread(1)),
+        _ => None,
+    }
+}
+
+/// Ctrl+Z/Ctrl+Shift+Z undo and redo edits to the composer
+fn composer_history_key(key: keyboard::Key, modifiers: keyboard::Modifiers) -> Option<SmsMessage> {
+    match key {
+        keyboard::Key::Character(c) if modifiers.command() && c.eq_ignore_ascii_case("z") => {
+            Some(if modifiers.shift() { SmsMessage::RedoInput } else { SmsMessage::UndoInput })
+        }
+        _ => None,
+    }
+}
+
 #[allow(dead_code)]
 pub fn run(device_id: String, device_name: String) -> cosmic::iced::Result {
     cosmic::app::run::<SmsWindow>(
@@ -25,43 +103,230 @@ pub fn run(device_id: String, device_name: String) -> cosmic::iced::Result {
 pub enum SmsMessage {
     #[allow(dead_code)]
     LoadConversations,
+    /// Request every conversation again, with progress in the list header
+    SyncConversations,
+    /// A sync request failed or timed out (sync generation, reason)
+    SyncFailed(u64, String),
     #[allow(dead_code)]
     ConversationsLoaded(Vec<Conversation>),
     #[allow(dead_code)]
-    ContactsLoaded(HashMap<String, String>),
+    ContactsLoaded(Result<HashMap<String, String>, String>),
+    /// Read the synced contacts again after a failure
+    RetryContacts,
+    /// Devices included in this window, as (id, name)
+    DevicesResolved(Vec<(String, String)>),
     SelectThread(String),
+    /// Move to the conversation this many places away in the list
+    SelectAdjacentThread(isize),
     UpdateInput(String),
+    UndoInput,
+    RedoInput,
+    /// Fill in (or send, if so configured) a canned reply by index
+    UseQuickReply(usize),
+    /// The phone or service rejected a message (optimistic id, reason)
+    SendFailed(String, String),
+    /// A device in this window connected again
+    DeviceReconnected(String),
+    /// A device in this window announced a new name (id, name)
+    DeviceRenamed(String, String),
+    /// Re-send failed messages in order, one at a time
+    ResendFailed,
+    ResendFinished(String, Result<(), String>),
+    DismissResend,
+    /// Show or hide the bubble appearance settings
+    ToggleAppearance,
+    SetSentColor(usize),
+    SetReceivedColor(usize),
+    SetCornerRadius(u16),
+    ResetAppearance,
+    /// The window is now this many pixels wide
+    WindowResized(f32),
+    /// The divider between the list and the thread was grabbed
+    StartListResize,
+    /// The pointer moved to this x position while resizing the list
+    ListResized(f32),
+    EndListResize,
+    /// Close the thread and show the list, in the single-panel layout
+    BackToList,
     UpdateSearch(String),
     SendMessage,
+    /// Answer the long-message confirmation: send it, or go back to editing
+    ConfirmSend(bool),
+    /// "Don't ask again" was ticked or unticked in the confirmation
+    DontAskAgain(bool),
     RefreshThread,
-    #[allow(dead_code)]
+    /// Persist session state, stop the event subscription and exit
     CloseWindow,
+    /// Show another device argument (an id, a comma-separated list or
+    /// --all) in this window, restarting the event subscription
+    SwitchDevice(String),
+    /// Another launch for the same devices asked this window to come
+    /// forward, showing a thread if it was launched for one
+    Activate(Option<String>),
+    /// Redraw so relative timestamps stay current; changes no state
+    ClockTick,
+    /// Reload the retention limits and prune history past them
+    PruneHistory,
     ProtocolEventReceived(ProtocolEvent),
     OpenNewChatDialog,
     CloseNewChatDialog,
     UpdateNewChatPhone(String),
     SelectContactForNewChat(String, String),
     CreateNewChat,
+    /// Open or close the context actions for a conversation (right-click)
+    ToggleThreadMenu(String),
+    PinThread(String),
+    /// Silence or unsilence notifications for a conversation
+    ToggleMuteThread(String),
+    ArchiveThread(String),
+    /// Ask before deleting a conversation
+    RequestDeleteThread(String),
+    CancelDeleteThread,
+    DeleteThread(String),
+    ToggleArchivedSection,
+    /// Reveal (and if needed request) the next page of older messages
+    LoadOlderMessages,
+    /// Distance of the message list's viewport from the top and from the
+    /// bottom of its content, in pixels
+    MessagesScrolled(f32, f32),
+    /// Scroll the message list back to the newest message
+    JumpToLatest,
+    /// Absolute scroll offset and viewport height of the conversation list
+    ConversationListScrolled(f32, f32),
+    /// Open or close the context actions for a message bubble (right-click)
+    ToggleMessageMenu(String),
+    CopyMessage(String),
+    /// Show a message's text in a selectable field, or back as plain text
+    SelectMessageText(String),
+    /// Cursor and selection changes in the selectable field; edits are dropped
+    SelectionAction(text_editor::Action),
+    /// Thread header actions for the conversation's phone number
+    CopyPhoneNumber(String),
+    CallPhoneNumber(String),
+    /// Save the thread's number to the device's contacts (thread id)
+    AddToContacts(String),
+    /// Open a link from a message body with the default handler
+    OpenLink(String),
+    DeleteMessage(String),
+    ForwardMessage(String),
+}
+
+/// Catching up on a thread's messages newer than those already known,
+/// newest page first
+#[derive(Debug)]
+struct CatchUp {
+    thread_id: String,
+    /// Date of the newest message known when the thread opened
+    since: i64,
+    /// Messages of the thread received for the page asked for last
+    received: usize,
+    oldest: Option<i64>,
 }
 
 pub struct SmsWindow {
     core: Core,
+    /// Where requests to phones go
+    backend: Arc<dyn SmsBackend>,
+    /// Device argument the window shows: an id, a comma-separated list or
+    /// --all. The event subscription is keyed on it.
+    device_arg: String,
+    /// Device for single-device mode, or the storage key in unified mode
     pub device_id: String,
-    #[allow(dead_code)]
+    /// Devices whose conversations are shown (one unless unified)
+    pub device_ids: Vec<String>,
+    pub device_names: HashMap<String, String>,
+    /// Conversations from several devices are aggregated into one inbox
+    pub unified: bool,
+    /// Name of the device in single-device mode, shown in the title
     pub device_name: String,
     pub conversations: Vec<Conversation>,
+    /// A manual sync is waiting for the phone's conversation list
+    pub is_loading: bool,
+    /// Bumped per sync so a stale timeout doesn't end a newer one
+    sync_generation: u64,
+    pub sync_error: Option<String>,
     pub contacts: HashMap<String, String>,
+    pub contacts_state: ContactsState,
     pub selected_thread: Option<String>,
     pub messages: Vec<Message>,
+    /// Messages from the cache by thread key, shown when a thread opens
+    /// until the phone answers
+    cached_messages: HashMap<String, Vec<Message>>,
     pub message_input: String,
     pub search_query: String,
+    /// The user is typing a search; keyboard navigation leaves focus there
+    search_active: bool,
     pub show_new_chat_dialog: bool,
     pub new_chat_phone_input: String,
+    pub thread_state: ThreadStateConfig,
+    pub muted: MutedThreads,
+    pub quick_replies: QuickRepliesConfig,
+    pub thread_menu: Option<String>,
+    /// Conversation whose delete confirmation is showing
+    pub confirm_delete_thread: Option<String>,
+    pub show_archived: bool,
+    /// Scroll offset and viewport height of the conversation list, used
+    /// to build only the visible rows
+    pub conversation_scroll: (f32, f32),
+    /// Number of newest messages rendered in the thread view
+    pub message_window: usize,
+    pub loading_older: bool,
+    /// The message list is scrolled to the newest message
+    pub messages_at_bottom: bool,
+    /// First message received while scrolled up; the "Unread messages"
+    /// divider goes above it until the list is back at the bottom
+    pub unread_divider: Option<String>,
+    /// Messages received since scrolling up, shown on the jump button
+    pub new_below: usize,
+    pub message_menu: Option<String>,
+    /// Message whose text is shown selectable, with its text
+    pub selecting: Option<(String, text_editor::Content)>,
+    /// Message ids removed locally, so a re-sync doesn't bring them back
+    pub deleted_messages: HashSet<String>,
+    /// Unsent text for threads other than the selected one
+    pub drafts: HashMap<String, String>,
+    /// Composer undo steps per thread, kept for this session only
+    input_history: HashMap<String, InputHistory>,
+    /// Thread just switched away from with unsent text; an undo right
+    /// after the switch goes back to it
+    switched_from: Option<String>,
+    /// Thread to reopen once the phone has sent the conversation list
+    restore_thread: Option<String>,
+    /// Why the last message couldn't be sent, shown above the composer
+    pub send_error: Option<String>,
+    /// When to ask before sending a many-segment message
+    pub send_confirm: SendConfirmConfig,
+    /// Limits on the conversations and messages kept
+    pub retention: RetentionConfig,
+    /// Segments of the message waiting for confirmation
+    pub confirm_send: Option<usize>,
+    /// "Don't ask again" is ticked in the confirmation
+    pub dont_ask_again: bool,
+    /// Failed outgoing messages across all threads, oldest first
+    pub failed_sends: Vec<Message>,
+    /// A device reconnected while messages were failed; shows the resend bar
+    pub resend_offered: bool,
+    pub resending: bool,
+    pub appearance: SmsAppearanceConfig,
+    pub show_appearance: bool,
+    pub layout: SmsLayoutConfig,
+    pub window_width: f32,
+    /// The list/thread divider is being dragged
+    pub resizing_list: bool,
+    /// The service's events stopped; shown as a banner over dimmed data
+    /// until they resume
+    pub service_lost: bool,
+    catch_up: Option<CatchUp>,
+    /// Devices that answered a ranged request with more than a page, so
+    /// they ignore ranges and threads are refreshed in full instead
+    unranged_devices: HashSet<String>,
+    /// Set on close so the subscription is dropped before exiting
+    closing: bool,
 }
 
 impl Application for SmsWindow {
     type Executor = cosmic::executor::Default;
-    type Flags = (String, String);
+    type Flags = (String, String, Option<String>);
     type Message = SmsMessage;
     const APP_ID: &'static str = "com.system76.CosmicConnectSms";
 
@@ -69,64 +334,152 @@ impl Application for SmsWindow {
     fn core_mut(&mut self) -> &mut Core { &mut self.core }
 
     fn init(core: Core, flags: Self::Flags) -> (Self, Task<Action<Self::Message>>) {
-        let (device_id, device_name) = flags;
-        eprintln!("[SMS-APP] init() device_id={}", device_id);
-
-        let mut app = Self {
-            core,
-            device_id: device_id.clone(),
-            device_name: device_name.clone(),
-            conversations: Vec::new(),
-            contacts: HashMap::new(),
-            selected_thread: None,
-            messages: Vec::new(),
-            message_input: String::new(),
-            search_query: String::new(),
-            show_new_chat_dialog: false,
-            new_chat_phone_input: String::new(),
-        };
-
-        let title = format!("SMS - {}", device_name);
-        let title_task = app.set_window_title(title, app.core.main_window_id().unwrap());
+        let mut app = Self::new(core, flags, Arc::new(DbusSms));
+        let task = app.start();
+        (app, task)
+    }
 
-        (app, title_task)
+    fn on_close_requested(&self, _id: cosmic::iced::window::Id) -> Option<Self::Message> {
+        Some(SmsMessage::CloseWindow)
     }
 
     fn subscription(&self) -> Subscription<Self::Message> {
+        // Dropping the subscription cancels the stream and its D-Bus listener
+        if self.closing {
+            return Subscription::none();
+        }
+
         let device_id = self.device_id.clone();
+        let unified = self.unified;
+        let configured_ids = self.device_ids.clone();
 
-        Subscription::run_with_id(
-            format!("sms-{}", device_id),
+        let navigation = keyboard::on_key_press(thread_navigation_key);
+        let editing = keyboard::on_key_press(composer_history_key);
+
+        // Keyed on the device argument rather than the resolved ids, which
+        // change once --all is discovered. A new key drops the old stream,
+        // and with it the D-Bus listener, before the new one starts.
+        let events = Subscription::run_with_id(
+            format!("sms-{}", self.device_arg),
             stream! {
-                eprintln!("[SMS-SUB] stream started for device={}", device_id);
+                debug!("stream started for device={}", device_id);
 
-                if let Err(e) = dbus::initialize().await {
-                    eprintln!("[SMS-SUB] init FAILED: {:?}", e);
-                    std::future::pending::<()>().await;
-                    return;
+                // Wait for the service rather than leaving the window idle
+                let mut failures = 0;
+                while let Err(e) = dbus::initialize().await {
+                    let delay = kdeconnect_dbus_client::resubscribe_delay(failures);
+                    error!("D-Bus init failed: {:?}, retrying in {:?}", e, delay);
+                    failures += 1;
+                    tokio::time::sleep(delay).await;
                 }
 
-                eprintln!("[SMS-SUB] D-Bus init OK, requesting conversations");
+                debug!("D-Bus init OK, requesting conversations");
                 tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
-                dbus::fetch_conversations(&device_id).await;
+
+                let devices = if configured_ids.is_empty() {
+                    dbus::paired_devices().await
+                } else {
+                    dbus::device_names(&configured_ids).await
+                };
+                let device_ids: Vec<String> = devices.iter().map(|(id, _)| id.clone()).collect();
+                yield SmsMessage::DevicesResolved(devices);
+
+                for id in &device_ids {
+                    dbus::fetch_conversations(id).await;
+                }
 
                 let Some(client) = dbus::get_client().await else {
-                    eprintln!("[SMS-SUB] no client, stream idle");
+                    warn!("No D-Bus client, event stream idle");
                     std::future::pending::<()>().await;
                     return;
                 };
 
-                eprintln!("[SMS-SUB] entering event loop");
+                debug!("entering event loop");
 
+                let mut failures = 0;
+                // The service went away and hasn't been listened to since
+                let mut lost = false;
                 loop {
-                    eprintln!("[SMS-SUB] subscribing to events");
-                    let mut event_stream = client.listen_for_events().await;
+                    debug!("subscribing to events");
+                    // Subscribing can succeed with no service on the bus
+                    let running = matches!(kdeconnect_dbus_client::service_running().await, Ok(true));
+                    let event_stream = match client.listen_for_events().await {
+                        Ok(stream) if running => stream,
+                        result => {
+                            let delay = kdeconnect_dbus_client::resubscribe_delay(failures);
+                            match result {
+                                Err(e) => warn!("{:#}, retrying in {:?}", e, delay),
+                                Ok(_) => warn!("KDE Connect service is not running, retrying in {:?}", delay),
+                            }
+                            failures += 1;
+                            if !lost {
+                                lost = true;
+                                yield SmsMessage::ProtocolEventReceived(ProtocolEvent::Disconnected);
+                            }
+                            tokio::time::sleep(delay).await;
+                            continue;
+                        }
+                    };
+                    failures = 0;
+
+                    let service_loss = match client.listen_for_service_loss().await {
+                        Ok(stream) => stream.boxed(),
+                        Err(e) => {
+                            warn!("Can't watch for the service stopping: {:#}", e);
+                            futures::stream::pending().boxed()
+                        }
+                    };
 
-                    while let Some(event) = event_stream.next().await {
+                    if lost {
+                        lost = false;
+                        info!("Service is back, requesting conversations again");
+                        yield SmsMessage::ProtocolEventReceived(ProtocolEvent::Reconnected);
+                        for id in &device_ids {
+                            dbus::fetch_conversations(id).await;
+                        }
+                    }
+
+                    // None once the service leaves the bus
+                    let mut event_stream = futures::stream::select(
+                        event_stream.map(Some),
+                        service_loss.map(|()| None),
+                    );
+                    while let Some(Some(event)) = event_stream.next().await {
                         use kdeconnect_dbus_client::ServiceEvent;
-                        if let ServiceEvent::SmsMessagesReceived(json) = event {
-                            eprintln!("[SMS-SUB] SmsMessagesReceived len={}", json.len());
-                            let (messages, conversations) = dbus::parse_sms_messages(&json);
+                        if let ServiceEvent::DeviceConnected(id, _) = &event {
+                            if device_ids.contains(id) {
+                                yield SmsMessage::DeviceReconnected(id.clone());
+                            }
+                            continue;
+                        }
+                        if let ServiceEvent::DeviceRenamed(id, name) = &event {
+                            if device_ids.contains(id) && crate::device_aliases::alias_for(id).is_none() {
+                                yield SmsMessage::DeviceRenamed(id.clone(), name.clone());
+                            }
+                            continue;
+                        }
+                        if let ServiceEvent::SmsMessagesReceived(source, json) = event {
+                            debug!("SmsMessagesReceived from={} len={}", source, json.len());
+
+                            // Ignore batches known to come from a device not in this window
+                            if !source.is_empty() && !device_ids.contains(&source) {
+                                continue;
+                            }
+                            // The service can't always tell which device sent a
+                            // batch. A single-device window takes it as its own;
+                            // the unified inbox keeps it unattributed rather than
+                            // guess, so replies can't go out from the wrong phone.
+                            let source = if source.is_empty() && !unified {
+                                device_ids.first().cloned().unwrap_or_default()
+                            } else {
+                                source
+                            };
+
+                            let (mut messages, mut conversations) = dbus::parse_sms_messages(&json);
+                            if !source.is_empty() {
+                                ConversationCache::update(&source, |cache| cache.record(&messages, &conversations));
+                            }
+                            tag_source(&mut messages, &mut conversations, &source, unified);
                             for msg in messages {
                                 yield SmsMessage::ProtocolEventReceived(
                                     ProtocolEvent::MessageReceived(msg)
@@ -138,85 +491,325 @@ impl Application for SmsWindow {
                         }
                     }
 
-                    eprintln!("[SMS-SUB] stream ended, reconnecting in 1s...");
+                    warn!("Lost the service's event stream, reconnecting in 1s");
+                    lost = true;
+                    yield SmsMessage::ProtocolEventReceived(ProtocolEvent::Disconnected);
                     tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
                 }
             }
-        )
+        );
+
+        let activations = Subscription::run_with_id(
+            "sms-activations",
+            instance::activations().map(SmsMessage::Activate),
+        );
+
+        let clock = Subscription::run_with_id(
+            "sms-clock",
+            stream! {
+                loop {
+                    tokio::time::sleep(TIMESTAMP_REFRESH).await;
+                    yield SmsMessage::ClockTick;
+                }
+            },
+        );
+
+        let resizes = cosmic::iced::window::resize_events()
+            .map(|(_, size)| SmsMessage::WindowResized(size.width));
+
+        let pruning = Subscription::run_with_id(
+            "sms-pruning",
+            stream! {
+                loop {
+                    tokio::time::sleep(retention::PRUNE_INTERVAL).await;
+                    yield SmsMessage::PruneHistory;
+                }
+            },
+        );
+
+        Subscription::batch([events, navigation, editing, activations, clock, resizes, pruning])
     }
 
     fn update(&mut self, message: Self::Message) -> Task<Action<Self::Message>> {
         match message {
             SmsMessage::LoadConversations => {
-                let device_id = self.device_id.clone();
+                let fetches: Vec<_> = self.device_ids.iter()
+                    .map(|device_id| self.backend.fetch_conversations(device_id.clone()))
+                    .collect();
                 return cosmic::task::future(async move {
-                    dbus::fetch_conversations(&device_id).await;
+                    for fetch in fetches {
+                        fetch.await;
+                    }
                     Action::App(SmsMessage::RefreshThread)
                 });
             }
+            SmsMessage::SyncConversations => {
+                if self.is_loading { return Task::none(); }
+                if self.device_ids.is_empty() {
+                    self.sync_error = Some("No device to sync with".to_string());
+                    return Task::none();
+                }
+                self.is_loading = true;
+                self.sync_error = None;
+                self.sync_generation += 1;
+                let generation = self.sync_generation;
+                let requests: Vec<_> = self.device_ids.iter()
+                    .map(|device_id| self.backend.request_conversations(device_id.clone()))
+                    .collect();
+                return Task::batch([
+                    cosmic::task::future(async move {
+                        for request in requests {
+                            if let Err(e) = request.await {
+                                return Action::App(SmsMessage::SyncFailed(generation, e));
+                            }
+                        }
+                        Action::None
+                    }),
+                    cosmic::task::future(async move {
+                        tokio::time::sleep(SYNC_TIMEOUT).await;
+                        Action::App(SmsMessage::SyncFailed(
+                            generation,
+                            format!("The phone didn't answer within {} seconds", SYNC_TIMEOUT.as_secs()),
+                        ))
+                    }),
+                ]);
+            }
+            SmsMessage::SyncFailed(generation, reason) => {
+                if self.is_loading && generation == self.sync_generation {
+                    warn!("SMS sync failed: {}", reason);
+                    self.is_loading = false;
+                    self.sync_error = Some(reason);
+                }
+            }
+            SmsMessage::DevicesResolved(devices) => {
+                debug!("DevicesResolved: {}", devices.len());
+                self.device_ids = devices.iter().map(|(id, _)| id.clone()).collect();
+                self.device_names = devices.into_iter().collect();
+                return self.load_contacts();
+            }
             SmsMessage::ConversationsLoaded(conversations) => {
-                eprintln!("[SMS-APP] ConversationsLoaded: {}", conversations.len());
+                debug!("ConversationsLoaded: {}", conversations.len());
                 self.conversations = conversations;
                 self.update_conversation_names();
+                self.apply_thread_state();
             }
-            SmsMessage::ContactsLoaded(contacts) => {
+            SmsMessage::ContactsLoaded(Ok(contacts)) => {
+                debug!("ContactsLoaded: {}", contacts.len());
                 self.contacts = contacts;
+                self.contacts_state = ContactsState::Loaded;
                 self.update_conversation_names();
             }
+            SmsMessage::ContactsLoaded(Err(e)) => {
+                warn!("Failed to load contacts: {}", e);
+                self.contacts_state = ContactsState::Error(e);
+            }
+            SmsMessage::RetryContacts => {
+                return self.load_contacts();
+            }
             SmsMessage::SelectThread(thread_id) => {
-                eprintln!("[SMS-APP] SelectThread: {}", thread_id);
-                self.selected_thread = Some(thread_id.clone());
-                self.messages.clear();
-                let device_id = self.device_id.clone();
-                return cosmic::task::future(async move {
-                    dbus::request_conversation_messages(&device_id, &thread_id).await;
-                    Action::App(SmsMessage::RefreshThread)
-                });
+                // Picking a conversation ends any search typing
+                self.search_active = false;
+                return Task::batch([self.open_thread(thread_id), self.focus_composer()]);
+            }
+            SmsMessage::SelectAdjacentThread(offset) => {
+                if self.show_new_chat_dialog {
+                    return Task::none();
+                }
+                if let Some(thread_id) = self.adjacent_thread(offset) {
+                    // Arrowing through search results keeps focus in the search field
+                    return Task::batch([self.open_thread(thread_id), self.focus_composer()]);
+                }
             }
             SmsMessage::UpdateInput(input) => {
-                self.message_input = input;
+                self.edit_input(input);
+                self.send_error = None;
+            }
+            SmsMessage::UndoInput => {
+                if self.show_new_chat_dialog || self.show_appearance {
+                    return Task::none();
+                }
+                if let Some(thread_id) = self.switched_from.take() {
+                    return Task::batch([self.open_thread(thread_id), self.focus_composer()]);
+                }
+                let Some(thread_id) = &self.selected_thread else { return Task::none(); };
+                if let Some(text) = self.input_history.get_mut(thread_id).and_then(|h| h.undo(&self.message_input)) {
+                    self.message_input = text;
+                }
+            }
+            SmsMessage::RedoInput => {
+                if self.show_new_chat_dialog || self.show_appearance {
+                    return Task::none();
+                }
+                let Some(thread_id) = &self.selected_thread else { return Task::none(); };
+                if let Some(text) = self.input_history.get_mut(thread_id).and_then(|h| h.redo(&self.message_input)) {
+                    self.message_input = text;
+                }
+            }
+            SmsMessage::SendFailed(message_id, error) => {
+                warn!("SMS not sent: {}", error);
+                // The bubble stays, marked failed, so it can be re-sent later
+                if let Some(msg) = self.messages.iter_mut().find(|m| m.id == message_id) {
+                    msg.status = SendStatus::Failed(error.clone());
+                    self.failed_sends.push(msg.clone());
+                }
+                self.send_error = Some(error);
+            }
+            SmsMessage::DeviceRenamed(device_id, name) => {
+                debug!("DeviceRenamed: {} -> {}", device_id, name);
+                self.device_names.insert(device_id.clone(), name.clone());
+                if !self.unified && device_id == self.device_id {
+                    self.device_name = name;
+                    if let Some(id) = self.core.main_window_id() {
+                        return self.set_window_title(self.window_title(), id);
+                    }
+                }
+            }
+            SmsMessage::DeviceReconnected(device_id) => {
+                debug!("DeviceReconnected: {}", device_id);
+                if self.failed_sends.iter().any(|m| self.route(&m.thread_id).0 == device_id) {
+                    self.resend_offered = true;
+                }
+            }
+            SmsMessage::ResendFailed => {
+                self.resending = true;
+                self.send_error = None;
+                return self.resend_next();
+            }
+            SmsMessage::ResendFinished(message_id, result) => {
+                match result {
+                    Ok(()) => {
+                        // Stays "sending" until the phone's copy replaces it
+                        self.failed_sends.retain(|m| m.id != message_id);
+                        if self.failed_sends.is_empty() {
+                            self.resending = false;
+                            self.resend_offered = false;
+                        } else if self.resending {
+                            return self.resend_next();
+                        }
+                    }
+                    Err(e) => {
+                        // Failing again means the device still can't send; stop here
+                        warn!("Resend of {} failed: {}", message_id, e);
+                        self.set_send_status(&message_id, SendStatus::Failed(e.clone()));
+                        self.resending = false;
+                        self.send_error = Some(e);
+                    }
+                }
+            }
+            SmsMessage::DismissResend => {
+                self.resending = false;
+                self.resend_offered = false;
+            }
+            SmsMessage::ToggleAppearance => {
+                self.show_appearance = !self.show_appearance;
+            }
+            SmsMessage::SetSentColor(index) => {
+                self.appearance.sent_color = BubbleColor::ALL.get(index).copied().unwrap_or_default();
+                self.save_appearance();
+            }
+            SmsMessage::SetReceivedColor(index) => {
+                self.appearance.received_color = BubbleColor::ALL.get(index).copied().unwrap_or_default();
+                self.save_appearance();
+            }
+            SmsMessage::SetCornerRadius(radius) => {
+                self.appearance.corner_radius = Some(radius);
+                self.save_appearance();
+            }
+            SmsMessage::ResetAppearance => {
+                self.appearance = SmsAppearanceConfig::default();
+                self.save_appearance();
+            }
+            SmsMessage::WindowResized(width) => {
+                self.window_width = width;
+            }
+            SmsMessage::StartListResize => {
+                self.resizing_list = true;
+            }
+            SmsMessage::ListResized(x) => {
+                if self.resizing_list {
+                    self.layout.list_width = x;
+                    self.layout.list_width = self.layout.list_width_in(self.window_width);
+                }
+            }
+            SmsMessage::EndListResize => {
+                if self.resizing_list {
+                    self.resizing_list = false;
+                    if let Err(e) = self.layout.save() {
+                        warn!("Failed to save SMS layout: {:?}", e);
+                    }
+                }
+            }
+            SmsMessage::BackToList => {
+                self.stash_draft();
+                self.selected_thread = None;
+                self.messages.clear();
+                self.send_error = None;
+                self.confirm_send = None;
+                self.catch_up = None;
+                self.clear_unread_divider();
             }
             SmsMessage::UpdateSearch(query) => {
                 self.search_query = query;
+                self.search_active = true;
+            }
+            SmsMessage::UseQuickReply(index) => {
+                let Some(reply) = self.quick_replies.replies().get(index) else { return Task::none(); };
+                self.edit_input(reply.clone());
+                if self.quick_replies.send_immediately {
+                    return self.update(SmsMessage::SendMessage);
+                }
             }
             SmsMessage::SendMessage => {
-                if self.message_input.trim().is_empty() { return Task::none(); }
-                let Some(thread_id) = self.selected_thread.clone() else { return Task::none(); };
-                let Some(conv) = self.conversations.iter().find(|c| c.thread_id == thread_id) else { return Task::none(); };
-
-                let device_id = self.device_id.clone();
-                let phone = conv.phone_number.clone();
-                let text = self.message_input.clone();
-                let now = utils::now_millis();
-
-                self.messages.push(Message {
-                    id: format!("sending_{}", now),
-                    thread_id: thread_id.clone(),
-                    body: text.clone(),
-                    address: phone.clone(),
-                    date: now,
-                    type_: 2,
-                    read: true,
-                });
-                self.messages.sort_by_key(|m| m.date);
-                self.message_input.clear();
-
-                return cosmic::task::future(async move {
-                    dbus::send_sms(&device_id, &phone, &text).await;
-                    Action::App(SmsMessage::RefreshThread)
-                });
+                return self.send_message(false);
+            }
+            SmsMessage::ConfirmSend(send) => {
+                if self.confirm_send.take().is_none() {
+                    return Task::none();
+                }
+                if !send {
+                    return self.focus_composer();
+                }
+                if self.dont_ask_again {
+                    self.send_confirm.enabled = false;
+                    if let Err(e) = self.send_confirm.save() {
+                        warn!("Failed to save send confirmation settings: {:?}", e);
+                    }
+                }
+                return self.send_message(true);
+            }
+            SmsMessage::DontAskAgain(checked) => {
+                self.dont_ask_again = checked;
             }
             SmsMessage::RefreshThread => {}
             SmsMessage::ProtocolEventReceived(event) => {
-                eprintln!("[SMS-APP] ProtocolEventReceived: {:?}", std::mem::discriminant(&event));
+                debug!("ProtocolEventReceived: {:?}", std::mem::discriminant(&event));
+                let batch_end = matches!(event, ProtocolEvent::ConversationsReceived(_));
                 self.handle_protocol_event(event);
+                let catch_up = if batch_end { self.continue_catch_up() } else { Task::none() };
+
+                // Reopen the thread from the last session once it is known
+                if self.selected_thread.is_none() {
+                    if let Some(thread_id) = self.restore_thread.take_if(|t| {
+                        self.conversations.iter().any(|c| c.thread_id == *t)
+                    }) {
+                        return Task::batch([
+                            catch_up,
+                            cosmic::task::message(Action::App(SmsMessage::SelectThread(thread_id))),
+                        ]);
+                    }
+                }
+                return catch_up;
             }
             SmsMessage::OpenNewChatDialog => {
+                self.stash_draft();
                 self.show_new_chat_dialog = true;
+                return widget::text_input::focus(views::NEW_CHAT_PHONE_INPUT.clone());
             }
             SmsMessage::CloseNewChatDialog => {
                 self.show_new_chat_dialog = false;
                 self.new_chat_phone_input.clear();
+                self.restore_draft();
+                return self.focus_composer();
             }
             SmsMessage::UpdateNewChatPhone(phone) => {
                 self.new_chat_phone_input = phone;
@@ -228,26 +821,265 @@ impl Application for SmsWindow {
                 let phone = self.new_chat_phone_input.trim().to_string();
                 if !phone.is_empty() {
                     let thread_id = format!("new_{}", utils::now_millis());
+                    let (device_id, _) = self.route(&thread_id);
                     self.conversations.insert(0, Conversation {
                         thread_id: thread_id.clone(),
+                        device_id,
                         phone_number: phone,
                         contact_name: String::new(),
                         last_message: String::new(),
+                        last_message_sent: false,
+                        last_message_read: false,
                         timestamp: utils::now_millis(),
                         unread: false,
+                        pinned: false,
+                        archived: false,
                     });
                     self.show_new_chat_dialog = false;
                     self.new_chat_phone_input.clear();
                     return cosmic::task::message(Action::App(SmsMessage::SelectThread(thread_id)));
                 }
             }
-            SmsMessage::CloseWindow => std::process::exit(0),
+            SmsMessage::ToggleThreadMenu(thread_id) => {
+                self.confirm_delete_thread = None;
+                if self.thread_menu.as_ref() == Some(&thread_id) {
+                    self.thread_menu = None;
+                } else {
+                    self.thread_menu = Some(thread_id);
+                }
+            }
+            SmsMessage::PinThread(thread_id) => {
+                let pinned = self.thread_state.toggle_pinned(&thread_id);
+                debug!("PinThread: {} pinned={}", thread_id, pinned);
+                self.thread_menu = None;
+                self.apply_thread_state();
+                self.save_thread_state();
+            }
+            SmsMessage::ToggleMuteThread(thread_id) => {
+                let (device_id, raw_thread_id) = self.route(&thread_id);
+                let muted = self.muted.toggle(&device_id, &raw_thread_id);
+                debug!("ToggleMuteThread: {} muted={}", thread_id, muted);
+                self.thread_menu = None;
+                if let Err(e) = self.muted.save() {
+                    warn!("Failed to save muted threads: {:?}", e);
+                }
+            }
+            SmsMessage::ArchiveThread(thread_id) => {
+                let archived = self.thread_state.toggle_archived(&thread_id);
+                debug!("ArchiveThread: {} archived={}", thread_id, archived);
+                self.thread_menu = None;
+                self.apply_thread_state();
+                self.save_thread_state();
+            }
+            SmsMessage::RequestDeleteThread(thread_id) => {
+                self.confirm_delete_thread = Some(thread_id);
+            }
+            SmsMessage::CancelDeleteThread => {
+                self.confirm_delete_thread = None;
+            }
+            SmsMessage::DeleteThread(thread_id) => {
+                // The SMS protocol has no thread deletion, so the conversation
+                // is only hidden on this desktop until a new message arrives.
+                debug!("DeleteThread (local only): {}", thread_id);
+                self.confirm_delete_thread = None;
+                self.thread_menu = None;
+                self.delete_thread(&thread_id);
+            }
+            SmsMessage::ToggleArchivedSection => {
+                self.show_archived = !self.show_archived;
+            }
+            SmsMessage::LoadOlderMessages => {
+                if self.loading_older { return Task::none(); }
+                let Some(thread_id) = self.selected_thread.clone() else { return Task::none(); };
+
+                // Reveal already-loaded messages before asking the phone for more
+                let hidden = self.messages.len().saturating_sub(self.message_window);
+                self.message_window += MESSAGE_PAGE_SIZE;
+                if hidden >= MESSAGE_PAGE_SIZE {
+                    return Task::none();
+                }
+
+                let Some(oldest) = self.messages.first().map(|m| m.date) else { return Task::none(); };
+                self.loading_older = true;
+                let (device_id, thread_id) = self.route(&thread_id);
+                let request = self.backend.request_messages(device_id, thread_id, oldest, MESSAGE_PAGE_SIZE);
+                return cosmic::task::future(async move {
+                    request.await;
+                    Action::App(SmsMessage::RefreshThread)
+                });
+            }
+            SmsMessage::ConversationListScrolled(offset_y, height) => {
+                self.conversation_scroll = (offset_y, height);
+            }
+            SmsMessage::MessagesScrolled(from_top, from_bottom) => {
+                self.messages_at_bottom = from_bottom <= AT_BOTTOM_SLACK;
+                if self.messages_at_bottom {
+                    self.clear_unread_divider();
+                }
+                if from_top <= 0.0 && self.has_older_messages() {
+                    return cosmic::task::message(Action::App(SmsMessage::LoadOlderMessages));
+                }
+            }
+            SmsMessage::JumpToLatest => {
+                self.messages_at_bottom = true;
+                self.clear_unread_divider();
+                // The list is anchored to its end, so offsets count from the bottom
+                return cosmic::iced::widget::scrollable::snap_to(
+                    views::MESSAGE_LIST.clone(),
+                    cosmic::iced::widget::scrollable::RelativeOffset::START,
+                );
+            }
+            SmsMessage::ToggleMessageMenu(message_id) => {
+                if self.message_menu.as_ref() == Some(&message_id) {
+                    self.message_menu = None;
+                } else {
+                    self.message_menu = Some(message_id);
+                }
+            }
+            SmsMessage::CopyMessage(message_id) => {
+                self.message_menu = None;
+                let Some(body) = self.find_message_body(&message_id) else { return Task::none(); };
+                return cosmic::task::future(async move {
+                    if let Err(e) = crate::portal::write_clipboard(&body).await {
+                        warn!("Failed to copy message: {:?}", e);
+                    }
+                    Action::App(SmsMessage::RefreshThread)
+                });
+            }
+            SmsMessage::SelectMessageText(message_id) => {
+                self.message_menu = None;
+                if self.selecting.as_ref().is_some_and(|(id, _)| *id == message_id) {
+                    self.selecting = None;
+                } else if let Some(body) = self.find_message_body(&message_id) {
+                    self.selecting = Some((message_id, text_editor::Content::with_text(&body)));
+                }
+            }
+            SmsMessage::SelectionAction(action) => {
+                // Read-only: selecting and copying work, typing doesn't
+                if let Some((_, content)) = &mut self.selecting {
+                    if !action.is_edit() {
+                        content.perform(action);
+                    }
+                }
+            }
+            SmsMessage::CopyPhoneNumber(phone_number) => {
+                return cosmic::task::future(async move {
+                    if let Err(e) = crate::portal::write_clipboard(&phone_number).await {
+                        warn!("Failed to copy phone number: {:?}", e);
+                    }
+                    Action::None
+                });
+            }
+            SmsMessage::CallPhoneNumber(phone_number) => {
+                // The KDE Connect protocol can't start a call on the phone, so
+                // hand a tel: URI to whatever dialer is installed
+                let uri = format!("tel:{}", phone_number.replace(' ', ""));
+                debug!("CallPhoneNumber: {}", uri);
+                if let Err(e) = std::process::Command::new("xdg-open").arg(&uri).spawn() {
+                    warn!("Failed to open {}: {:?}", uri, e);
+                }
+            }
+            SmsMessage::AddToContacts(thread_id) => {
+                let Some(conv) = self.conversations.iter().find(|c| c.thread_id == thread_id) else {
+                    return Task::none();
+                };
+                let phone_number = conv.phone_number.clone();
+                let name = if conv.contact_name.is_empty() { phone_number.clone() } else { conv.contact_name.clone() };
+                let (device_id, _) = self.route(&thread_id);
+                match storage::save_contact(&device_id, &name, &phone_number) {
+                    Ok(path) => {
+                        info!("Saved contact {} to {}", name, path.display());
+                        self.contacts.insert(phone_number, name);
+                        self.update_conversation_names();
+                    }
+                    Err(e) => warn!("Failed to save contact for {}: {:?}", phone_number, e),
+                }
+            }
+            SmsMessage::OpenLink(url) => {
+                debug!("OpenLink: {}", url);
+                if let Err(e) = std::process::Command::new("xdg-open").arg(&url).spawn() {
+                    warn!("Failed to open link {}: {:?}", url, e);
+                }
+            }
+            SmsMessage::DeleteMessage(message_id) => {
+                // The SMS protocol has no message deletion, so this only hides
+                // the message on this desktop.
+                debug!("DeleteMessage (local only): {}", message_id);
+                self.message_menu = None;
+                if self.selecting.as_ref().is_some_and(|(id, _)| *id == message_id) {
+                    self.selecting = None;
+                }
+                self.messages.retain(|m| m.id != message_id);
+                self.failed_sends.retain(|m| m.id != message_id);
+                self.deleted_messages.insert(message_id);
+            }
+            SmsMessage::ForwardMessage(message_id) => {
+                self.message_menu = None;
+                let Some(body) = self.find_message_body(&message_id) else { return Task::none(); };
+                self.stash_draft();
+                self.message_input = body;
+                self.new_chat_phone_input.clear();
+                self.show_new_chat_dialog = true;
+                return widget::text_input::focus(views::NEW_CHAT_PHONE_INPUT.clone());
+            }
+            SmsMessage::SwitchDevice(device_arg) => {
+                if self.closing || device_arg == self.device_arg {
+                    return Task::none();
+                }
+                info!("SMS window switching from {} to {}", self.device_arg, device_arg);
+                self.save_session();
+
+                let device_name = self.device_names.get(&device_arg).cloned().unwrap_or_else(|| device_arg.clone());
+                let core = std::mem::take(&mut self.core);
+                *self = Self::new(core, (device_arg, device_name, None), self.backend.clone());
+                return self.start();
+            }
+            SmsMessage::Activate(thread_id) => {
+                let focus = match self.core.main_window_id() {
+                    Some(id) => cosmic::iced::window::gain_focus(id),
+                    None => Task::none(),
+                };
+                let Some(thread_id) = thread_id else {
+                    return focus;
+                };
+                if self.conversations.iter().any(|c| c.thread_id == thread_id) {
+                    return Task::batch([focus, cosmic::task::message(Action::App(SmsMessage::SelectThread(thread_id)))]);
+                }
+                // Opened once the phone sends the conversation
+                self.restore_thread = Some(thread_id);
+                return focus;
+            }
+            // The view is rebuilt after every message, which is all a tick
+            // is for; scroll position and selection are left alone
+            SmsMessage::ClockTick => {}
+            SmsMessage::PruneHistory => {
+                // Pick up limits changed in the settings window
+                self.retention = RetentionConfig::load().unwrap_or_else(|e| {
+                    warn!("Failed to load SMS retention limits: {:?}", e);
+                    self.retention.clone()
+                });
+                self.prune_history();
+            }
+            SmsMessage::CloseWindow => {
+                if self.closing { return Task::none(); }
+                info!("SMS window closing for device={}", self.device_id);
+                self.closing = true;
+                self.save_session();
+
+                return cosmic::task::future(async move {
+                    dbus::shutdown().await;
+                    Action::App(SmsMessage::RefreshThread)
+                })
+                .chain(cosmic::iced::exit());
+            }
         }
         Task::none()
     }
 
     fn view(&self) -> Element<'_, Self::Message> {
-        let content = if self.show_new_chat_dialog {
+        let content = if self.show_appearance {
+            views::view_appearance_dialog(self)
+        } else if self.show_new_chat_dialog {
             views::view_new_chat_dialog(self)
         } else {
             views::view_main(self)
@@ -260,10 +1092,161 @@ impl Application for SmsWindow {
 }
 
 impl SmsWindow {
+    /// Window state for `flags` (device argument, device name, thread to
+    /// open), with requests to phones going through `backend`
+    fn new(core: Core, flags: (String, String, Option<String>), backend: Arc<dyn SmsBackend>) -> Self {
+        let (device_arg, device_name, open_thread) = flags;
+        info!("SMS window init for device={}", device_arg);
+
+        // A comma-separated list or --all opens the unified inbox
+        let discover = device_arg == ALL_DEVICES_ARG;
+        let device_ids: Vec<String> = if discover {
+            Vec::new()
+        } else {
+            device_arg.split(',')
+                .map(|id| id.trim().to_string())
+                .filter(|id| !id.is_empty())
+                .collect()
+        };
+        let unified = discover || device_ids.len() > 1;
+        let device_id = if unified {
+            UNIFIED_STORAGE_ID.to_string()
+        } else {
+            device_ids.first().cloned().unwrap_or_else(|| device_arg.clone())
+        };
+
+        let thread_state = ThreadStateConfig::load(&device_id).unwrap_or_else(|e| {
+            warn!("Failed to load thread state: {:?}", e);
+            ThreadStateConfig::default()
+        });
+
+        let session = SessionState::load(&device_id).unwrap_or_else(|e| {
+            warn!("Failed to load session state: {:?}", e);
+            SessionState::default()
+        });
+
+        let muted = MutedThreads::load().unwrap_or_else(|e| {
+            warn!("Failed to load muted threads: {:?}", e);
+            MutedThreads::default()
+        });
+        let quick_replies = QuickRepliesConfig::load().unwrap_or_else(|e| {
+            warn!("Failed to load quick replies: {:?}", e);
+            QuickRepliesConfig::default()
+        });
+
+        let send_confirm = SendConfirmConfig::load().unwrap_or_else(|e| {
+            warn!("Failed to load send confirmation settings: {:?}", e);
+            SendConfirmConfig::default()
+        });
+
+        let retention = RetentionConfig::load().unwrap_or_else(|e| {
+            warn!("Failed to load SMS retention limits: {:?}", e);
+            RetentionConfig::default()
+        });
+
+        let appearance = SmsAppearanceConfig::load().unwrap_or_else(|e| {
+            warn!("Failed to load SMS appearance: {:?}", e);
+            SmsAppearanceConfig::default()
+        });
+
+        let layout = SmsLayoutConfig::load().unwrap_or_else(|e| {
+            warn!("Failed to load SMS layout: {:?}", e);
+            SmsLayoutConfig::default()
+        });
+
+        let mut app = Self {
+            core,
+            backend,
+            device_arg,
+            device_id: device_id.clone(),
+            device_ids,
+            device_names: HashMap::new(),
+            unified,
+            device_name,
+            conversations: Vec::new(),
+            is_loading: false,
+            sync_generation: 0,
+            sync_error: None,
+            contacts: HashMap::new(),
+            contacts_state: ContactsState::Loading,
+            selected_thread: None,
+            messages: Vec::new(),
+            cached_messages: HashMap::new(),
+            message_input: String::new(),
+            search_query: String::new(),
+            search_active: false,
+            show_new_chat_dialog: false,
+            new_chat_phone_input: String::new(),
+            thread_state,
+            muted,
+            quick_replies,
+            thread_menu: None,
+            confirm_delete_thread: None,
+            show_archived: false,
+            // Until the first scroll event, assume a tall window
+            conversation_scroll: (0.0, 1200.0),
+            message_window: MESSAGE_PAGE_SIZE,
+            loading_older: false,
+            messages_at_bottom: true,
+            unread_divider: None,
+            new_below: 0,
+            message_menu: None,
+            selecting: None,
+            deleted_messages: HashSet::new(),
+            drafts: session.drafts,
+            input_history: HashMap::new(),
+            switched_from: None,
+            // A thread asked for at launch wins over the last session's
+            restore_thread: open_thread.or(session.selected_thread),
+            send_error: None,
+            send_confirm,
+            retention,
+            confirm_send: None,
+            dont_ask_again: false,
+            failed_sends: Vec::new(),
+            resend_offered: false,
+            resending: false,
+            appearance,
+            show_appearance: false,
+            layout,
+            // Until the first resize event, assume the default window size
+            window_width: 1024.0,
+            resizing_list: false,
+            service_lost: false,
+            catch_up: None,
+            unranged_devices: HashSet::new(),
+            closing: false,
+        };
+
+        app.load_cache();
+        app
+    }
+
+    /// Title the window and reopen the last session's thread if it was
+    /// cached, after opening or switching devices
+    fn start(&mut self) -> Task<Action<SmsMessage>> {
+        let title_task = match self.core.main_window_id() {
+            Some(id) => self.set_window_title(self.window_title(), id),
+            None => Task::none(),
+        };
+
+        let restore_task = match self.restore_thread.take_if(|t| self.conversations.iter().any(|c| c.thread_id == *t)) {
+            Some(thread_id) => cosmic::task::message(Action::App(SmsMessage::SelectThread(thread_id))),
+            None => Task::none(),
+        };
+
+        Task::batch([title_task, restore_task])
+    }
+
     fn handle_protocol_event(&mut self, event: ProtocolEvent) {
         match event {
             ProtocolEvent::ConversationsReceived(conversations) => {
-                eprintln!("[SMS-APP] ConversationsReceived: {} conversations", conversations.len());
+                debug!("ConversationsReceived: {} conversations", conversations.len());
+
+                // Each batch from the phone ends with this event, so any
+                // pending older-page request or sync has been answered.
+                self.loading_older = false;
+                self.is_loading = false;
 
                 // Merge: preserve new_* threads, update/add real ones
                 let mut merged = self.conversations.clone();
@@ -291,6 +1274,7 @@ impl SmsWindow {
 
                 self.conversations = merged;
                 self.update_conversation_names();
+                self.apply_thread_state();
 
                 // If selected thread was new_*, update to real thread_id
                 if let Some(sel) = &self.selected_thread.clone() {
@@ -300,54 +1284,776 @@ impl SmsWindow {
                         }
                     }
                 }
+                // Phones send every conversation; the caches are pruned on
+                // the next PruneHistory
+                self.prune_loaded();
             }
             ProtocolEvent::MessageReceived(message) => {
-                eprintln!("[SMS-APP] MessageReceived thread={}", message.thread_id);
+                debug!("MessageReceived thread={}", message.thread_id);
+                if self.deleted_messages.contains(&message.id) {
+                    return;
+                }
                 let is_selected = self.selected_thread.as_deref() == Some(&message.thread_id);
+                if let Some(catch_up) = self.catch_up.as_mut().filter(|c| c.thread_id == message.thread_id) {
+                    catch_up.received += 1;
+                    catch_up.oldest = Some(catch_up.oldest.map_or(message.date, |oldest| oldest.min(message.date)));
+                }
 
                 if is_selected {
-                    let already_exists = self.messages.iter().any(|m| {
-                        m.id == message.id
-                        || (m.id.starts_with("sending_")
-                            && m.type_ == 2
-                            && message.type_ == 2
-                            && m.body == message.body
-                            && (m.date - message.date).abs() < 300_000)
-                    });
-
-                    if !already_exists {
-                        self.messages.push(message.clone());
-                    } else {
-                        if let Some(existing) = self.messages.iter_mut().find(|m| {
-                            m.id.starts_with("sending_")
-                            && m.type_ == 2
-                            && m.body == message.body
-                        }) {
-                            existing.id = message.id.clone();
-                            existing.thread_id = message.thread_id.clone();
-                            existing.date = message.date;
-                        }
+                    let arrived_below = !self.messages_at_bottom && utils::is_new_arrival(&self.messages, &message);
+                    if arrived_below {
+                        self.unread_divider.get_or_insert_with(|| message.id.clone());
+                        self.new_below += 1;
                     }
-                    self.messages.sort_by_key(|m| m.date);
+                    utils::merge_message(&mut self.messages, message.clone());
                 }
 
+                // An older message arriving late doesn't replace the preview
                 if let Some(conv) = self.conversations.iter_mut()
-                    .find(|c| c.thread_id == message.thread_id)
+                    .find(|c| c.thread_id == message.thread_id && c.timestamp <= message.date)
                 {
+                    conv.last_message_sent = message.is_sent();
+                    conv.last_message_read = message.read;
                     conv.last_message = message.body;
                     conv.timestamp = message.date;
                 }
                 self.conversations.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
             }
-            ProtocolEvent::Error(e) => eprintln!("[SMS-APP] error: {}", e),
+            ProtocolEvent::Disconnected => {
+                // Nothing pending will be answered by the lost stream
+                self.service_lost = true;
+                self.is_loading = false;
+                self.loading_older = false;
+                self.catch_up = None;
+            }
+            ProtocolEvent::Reconnected => {
+                self.service_lost = false;
+            }
+            ProtocolEvent::Error(e) => warn!("Protocol error: {}", e),
         }
     }
 
-    fn update_conversation_names(&mut self) {
+    /// Device and raw phone-side thread id for a conversation key. Replies
+    /// and history requests go to the device the conversation came from;
+    /// new chats use the first included device. The device is empty for
+    /// unified threads from batches the service couldn't attribute.
+    fn route(&self, thread_key: &str) -> (String, String) {
+        match thread_key.split_once('/') {
+            Some((device_id, thread_id)) if self.unified => (device_id.to_string(), thread_id.to_string()),
+            _ => (
+                self.device_ids.first().cloned().unwrap_or_else(|| self.device_id.clone()),
+                thread_key.to_string(),
+            ),
+        }
+    }
+
+    fn clear_unread_divider(&mut self) {
+        self.unread_divider = None;
+        self.new_below = 0;
+    }
+
+    /// Whether the thread view may have older messages to show, either
+    /// already loaded but hidden or still on the phone.
+    pub fn has_older_messages(&self) -> bool {
+        !self.messages.is_empty() && self.messages.len() >= self.message_window
+    }
+
+    /// State of a conversation's last message for the list glyph, counting
+    /// messages sent from this window that the phone hasn't confirmed
+    pub fn last_message_state(&self, conv: &Conversation) -> utils::LastMessageState {
+        let pending = self.failed_sends.iter()
+            .chain(self.messages.iter())
+            .filter(|m| m.thread_id == conv.thread_id && m.status != SendStatus::Synced && m.date >= conv.timestamp)
+            .max_by_key(|m| m.date)
+            .map(|m| &m.status);
+        utils::last_message_state(conv, pending)
+    }
+
+    /// The newest `message_window` messages, oldest first
+    pub fn visible_messages(&self) -> &[Message] {
+        let start = self.messages.len().saturating_sub(self.message_window);
+        &self.messages[start..]
+    }
+
+    fn find_message_body(&self, message_id: &str) -> Option<String> {
+        self.messages.iter()
+            .find(|m| m.id == message_id)
+            .map(|m| m.body.clone())
+    }
+
+    /// Send the composer's text to the selected thread. Messages with more
+    /// segments than configured wait for confirmation unless `confirmed`.
+    fn send_message(&mut self, confirmed: bool) -> Task<Action<SmsMessage>> {
+        if self.message_input.trim().is_empty() { return Task::none(); }
+        let Some(thread_id) = self.selected_thread.clone() else { return Task::none(); };
+        let Some(conv) = self.conversations.iter().find(|c| c.thread_id == thread_id) else { return Task::none(); };
+
+        let (device_id, _) = self.route(&thread_id);
+        if device_id.is_empty() {
+            self.send_error = Some("Can't tell which device this conversation is on. Reply from that device's own SMS window.".to_string());
+            return Task::none();
+        }
+        let phone = conv.phone_number.clone();
+        let text = self.message_input.clone();
+        let now = utils::now_millis();
+
+        if let Err(e) = utils::validate_sms(&phone, &text) {
+            self.send_error = Some(e);
+            return Task::none();
+        }
+        self.send_error = None;
+
+        let segments = utils::segment_info(&text).segments;
+        if !confirmed && self.send_confirm.should_confirm(segments) {
+            self.confirm_send = Some(segments);
+            self.dont_ask_again = false;
+            return Task::none();
+        }
+        self.confirm_send = None;
+
+        let optimistic_id = format!("{}{}", utils::OPTIMISTIC_ID_PREFIX, now);
+        self.messages.push(Message {
+            id: optimistic_id.clone(),
+            thread_id: thread_id.clone(),
+            body: text.clone(),
+            address: phone.clone(),
+            date: now,
+            type_: 2,
+            read: true,
+            status: SendStatus::Sending,
+            sub_id: None,
+            event: MessageEvent::Text,
+            subject: None,
+        });
+        utils::sort_messages(&mut self.messages);
+        self.edit_input(String::new());
+        self.drafts.remove(&thread_id);
+
+        let sent = self.backend.send_sms(device_id, phone, text);
+        let send = cosmic::task::future(async move {
+            match sent.await {
+                Ok(()) => Action::App(SmsMessage::RefreshThread),
+                Err(e) => Action::App(SmsMessage::SendFailed(optimistic_id, e)),
+            }
+        });
+        // Clicking Send takes focus from the composer; give it back
+        Task::batch([send, self.focus_composer()])
+    }
+
+    /// Show a conversation and ask the phone for its latest messages
+    fn open_thread(&mut self, thread_id: String) -> Task<Action<SmsMessage>> {
+        debug!("SelectThread: {}", thread_id);
+        self.confirm_send = None;
+        // A freshly created chat keeps the composed text (e.g. a forward)
+        if !thread_id.starts_with("new_") {
+            self.switched_from = self.selected_thread.clone()
+                .filter(|t| *t != thread_id && !self.message_input.trim().is_empty());
+            self.stash_draft();
+        }
+        self.selected_thread = Some(thread_id.clone());
+        if !thread_id.starts_with("new_") {
+            self.restore_draft();
+        }
+        self.messages.clear();
+        self.messages.extend(
+            self.failed_sends.iter().filter(|m| m.thread_id == thread_id).cloned()
+        );
+        if let Some(cached) = self.cached_messages.get(&thread_id) {
+            self.messages.extend(cached.iter().cloned());
+            utils::sort_messages(&mut self.messages);
+        }
+        self.message_window = MESSAGE_PAGE_SIZE;
+        self.loading_older = false;
+        self.messages_at_bottom = true;
+        self.clear_unread_divider();
+        // With messages cached, only ask for those newer than the newest
+        let (device_id, raw_thread_id) = self.route(&thread_id);
+        let since = utils::latest_synced(&self.messages)
+            .filter(|_| !self.unranged_devices.contains(&device_id));
+        self.catch_up = since.map(|since| CatchUp { thread_id, since, received: 0, oldest: None });
+        let request = match since {
+            Some(since) => self.backend.request_messages_since(device_id, raw_thread_id, since, SYNC_PAGE_SIZE),
+            None => self.backend.request_messages(device_id, raw_thread_id, utils::now_millis(), MESSAGE_PAGE_SIZE),
+        };
+        cosmic::task::future(async move {
+            request.await;
+            Action::App(SmsMessage::RefreshThread)
+        })
+    }
+
+    /// After a batch from the phone, ask for the page before the last one
+    /// caught up on until a page reaches the messages known before. A
+    /// phone that sent more than a page ignores ranges; its threads are
+    /// refreshed in full from then on.
+    fn continue_catch_up(&mut self) -> Task<Action<SmsMessage>> {
+        let Some(catch_up) = self.catch_up.take() else { return Task::none(); };
+        if self.selected_thread.as_deref() != Some(&catch_up.thread_id) {
+            return Task::none();
+        }
+        // The batch was about other threads; the page is still coming
+        if catch_up.received == 0 {
+            self.catch_up = Some(catch_up);
+            return Task::none();
+        }
+
+        let (device_id, thread_id) = self.route(&catch_up.thread_id);
+        if catch_up.received > SYNC_PAGE_SIZE {
+            info!("{} ignored a ranged request ({} messages), refreshing threads in full", device_id, catch_up.received);
+            self.unranged_devices.insert(device_id);
+            return Task::none();
+        }
+        let Some(oldest) = catch_up.oldest.filter(|oldest| catch_up.received == SYNC_PAGE_SIZE && *oldest > catch_up.since) else {
+            debug!("Caught up on thread {}", catch_up.thread_id);
+            return Task::none();
+        };
+
+        self.catch_up = Some(CatchUp { received: 0, oldest: None, ..catch_up });
+        let request = self.backend.request_messages(device_id, thread_id, oldest, SYNC_PAGE_SIZE);
+        cosmic::task::future(async move {
+            request.await;
+            Action::App(SmsMessage::RefreshThread)
+        })
+    }
+
+    /// Read the contacts synced from this window's devices
+    fn load_contacts(&mut self) -> Task<Action<SmsMessage>> {
+        self.contacts_state = ContactsState::Loading;
+        let device_ids = self.device_ids.clone();
+        cosmic::task::future(async move {
+            let mut contacts = HashMap::new();
+            for id in &device_ids {
+                match storage::load_contacts(id) {
+                    Ok(device_contacts) => contacts.extend(device_contacts),
+                    Err(e) => {
+                        let reason = format!("Couldn't read contacts from {}: {}", storage::contacts_dir(id).display(), e);
+                        return Action::App(SmsMessage::ContactsLoaded(Err(reason)));
+                    }
+                }
+            }
+            Action::App(SmsMessage::ContactsLoaded(Ok(contacts)))
+        })
+    }
+
+    /// Move keyboard focus to the composer, unless the user is typing in
+    /// the search field or a dialog is open
+    fn focus_composer(&self) -> Task<Action<SmsMessage>> {
+        if self.search_active || self.show_new_chat_dialog || self.show_appearance || self.selected_thread.is_none() {
+            return Task::none();
+        }
+        widget::text_input::focus(views::COMPOSER_INPUT.clone())
+    }
+
+    /// Re-apply persisted pin/archive flags. Incoming conversations from the
+    /// phone never carry these, so this must run after every merge.
+    fn apply_thread_state(&mut self) {
+        self.conversations.retain(|c| !self.thread_state.is_deleted(&c.thread_id, c.timestamp));
         for conv in &mut self.conversations {
-            if let Some(name) = self.contacts.get(&conv.phone_number) {
-                conv.contact_name = name.clone();
+            conv.pinned = self.thread_state.is_pinned(&conv.thread_id);
+            conv.archived = self.thread_state.is_archived(&conv.thread_id);
+        }
+    }
+
+    /// Replace the composer text, keeping an undo step if the edit is
+    /// significant
+    fn edit_input(&mut self, text: String) {
+        if let Some(thread_id) = &self.selected_thread {
+            self.input_history.entry(thread_id.clone()).or_default().record(&self.message_input, &text);
+        }
+        self.message_input = text;
+        self.switched_from = None;
+        // A confirmation was for the text as it was
+        self.confirm_send = None;
+    }
+
+    /// Move the composer text into the selected thread's draft
+    fn stash_draft(&mut self) {
+        let text = std::mem::take(&mut self.message_input);
+        let Some(thread_id) = self.selected_thread.clone() else { return; };
+        if text.trim().is_empty() {
+            self.drafts.remove(&thread_id);
+        } else {
+            self.drafts.insert(thread_id, text);
+        }
+    }
+
+    /// Load the selected thread's draft into the composer
+    fn restore_draft(&mut self) {
+        self.message_input = self.selected_thread.as_ref()
+            .and_then(|t| self.drafts.remove(t))
+            .unwrap_or_default();
+    }
+
+    fn save_session(&mut self) {
+        // Fresh chats have placeholder ids that won't exist next launch
+        if !self.show_new_chat_dialog {
+            self.stash_draft();
+        }
+        let persistent = |t: &String| !t.starts_with("new_");
+        let session = SessionState {
+            selected_thread: self.selected_thread.clone().filter(persistent),
+            drafts: self.drafts.iter()
+                .filter(|(t, _)| persistent(t))
+                .map(|(t, d)| (t.clone(), d.clone()))
+                .collect(),
+        };
+        if let Err(e) = session.save(&self.device_id) {
+            warn!("Failed to save session state: {:?}", e);
+        }
+    }
+
+    /// Send the oldest failed message again
+    fn resend_next(&mut self) -> Task<Action<SmsMessage>> {
+        let Some(msg) = self.failed_sends.first().cloned() else {
+            self.resending = false;
+            return Task::none();
+        };
+        // Re-date it so the phone's copy is recognised as its echo
+        let now = utils::now_millis();
+        for m in self.failed_sends.iter_mut().chain(self.messages.iter_mut()) {
+            if m.id == msg.id {
+                m.date = now;
+            }
+        }
+        utils::sort_messages(&mut self.messages);
+        self.set_send_status(&msg.id, SendStatus::Sending);
+
+        let (device_id, _) = self.route(&msg.thread_id);
+        let sent = self.backend.send_sms(device_id, msg.address.clone(), msg.body.clone());
+        cosmic::task::future(async move {
+            Action::App(SmsMessage::ResendFinished(msg.id, sent.await))
+        })
+    }
+
+    fn set_send_status(&mut self, message_id: &str, status: SendStatus) {
+        for m in self.failed_sends.iter_mut().chain(self.messages.iter_mut()) {
+            if m.id == message_id {
+                m.status = status.clone();
+            }
+        }
+    }
+
+    /// Conversations in list order: the main list (pinned first, newest
+    /// first) and the archived section, both filtered by the search query
+    pub fn sorted_conversations(&self) -> (Vec<&Conversation>, Vec<&Conversation>) {
+        let (mut archived, mut visible): (Vec<_>, Vec<_>) = self.conversations
+            .iter()
+            .filter(|c| self.matches_search(c))
+            .partition(|c| c.archived);
+
+        visible.sort_by(|a, b| b.pinned.cmp(&a.pinned).then(b.timestamp.cmp(&a.timestamp)));
+        archived.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        (visible, archived)
+    }
+
+    fn matches_search(&self, conv: &Conversation) -> bool {
+        if self.search_query.is_empty() {
+            return true;
+        }
+
+        let query = self.search_query.to_lowercase();
+        conv.contact_name.to_lowercase().contains(&query)
+            || conv.phone_number.contains(&self.search_query)
+            || utils::normalize_phone_number(&conv.phone_number)
+                .contains(&utils::normalize_phone_number(&self.search_query))
+    }
+
+    /// Thread `offset` places from the selected one in the list as shown,
+    /// including archived threads only while that section is expanded.
+    /// Stops at the ends rather than wrapping.
+    pub fn adjacent_thread(&self, offset: isize) -> Option<String> {
+        let (visible, archived) = self.sorted_conversations();
+        let mut order = visible;
+        if self.show_archived {
+            order.extend(archived);
+        }
+        let current = self.selected_thread.as_ref()
+            .and_then(|t| order.iter().position(|c| c.thread_id == *t));
+        utils::adjacent_index(order.len(), current, offset)
+            .map(|i| order[i].thread_id.clone())
+    }
+
+    fn window_title(&self) -> String {
+        if self.unified {
+            "SMS - All devices".to_string()
+        } else {
+            format!("SMS - {}", self.device_name)
+        }
+    }
+
+    /// Whether notifications are silenced for a thread key
+    pub fn is_thread_muted(&self, thread_id: &str) -> bool {
+        let (device_id, raw_thread_id) = self.route(thread_id);
+        self.muted.is_muted(&device_id, &raw_thread_id)
+    }
+
+    fn save_appearance(&self) {
+        if let Err(e) = self.appearance.save() {
+            warn!("Failed to save SMS appearance: {:?}", e);
+        }
+    }
+
+    /// Drop a conversation with its messages, draft, pin, archive and mute
+    /// state, and remember it so a re-sync doesn't bring it back
+    fn delete_thread(&mut self, thread_id: &str) {
+        let latest = self.conversations.iter()
+            .find(|c| c.thread_id == thread_id)
+            .map_or(0, |c| c.timestamp);
+        self.conversations.retain(|c| c.thread_id != thread_id);
+        self.failed_sends.retain(|m| m.thread_id != thread_id);
+        self.cached_messages.remove(thread_id);
+        self.drafts.remove(thread_id);
+        self.input_history.remove(thread_id);
+        if self.switched_from.as_deref() == Some(thread_id) {
+            self.switched_from = None;
+        }
+
+        if self.selected_thread.as_deref() == Some(thread_id) {
+            self.selected_thread = None;
+            self.messages.clear();
+            self.message_input.clear();
+            self.send_error = None;
+        }
+
+        self.thread_state.delete(thread_id, latest);
+        self.save_thread_state();
+
+        let (device_id, raw_thread_id) = self.route(thread_id);
+        ConversationCache::update(&device_id, |cache| cache.forget_thread(&raw_thread_id));
+        if self.muted.remove(&device_id, &raw_thread_id) {
+            if let Err(e) = self.muted.save() {
+                warn!("Failed to save muted threads: {:?}", e);
             }
         }
     }
+
+    fn save_thread_state(&self) {
+        if let Err(e) = self.thread_state.save(&self.device_id) {
+            warn!("Failed to save thread state: {:?}", e);
+        }
+    }
+
+    /// Show the cached conversations of the configured devices until the
+    /// phones answer. `--all` resolves its devices later and starts empty.
+    fn load_cache(&mut self) {
+        for device_id in self.device_ids.clone() {
+            let cache = ConversationCache::load(&device_id).unwrap_or_else(|e| {
+                warn!("Failed to read SMS cache for {}: {}", device_id, e);
+                ConversationCache::default()
+            });
+            let mut conversations = cache.conversations;
+            tag_source(&mut [], &mut conversations, &device_id, self.unified);
+            self.conversations.extend(conversations);
+            for (thread_id, mut messages) in cache.messages {
+                tag_source(&mut messages, &mut [], &device_id, self.unified);
+                let key = if self.unified { thread_key(&device_id, &thread_id) } else { thread_id };
+                self.cached_messages.insert(key, messages);
+            }
+        }
+        debug!("Loaded {} cached conversations", self.conversations.len());
+        self.apply_thread_state();
+        self.conversations.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        self.prune_history();
+    }
+
+    /// Thread never pruned: the open one, or the one about to reopen
+    fn kept_thread(&self) -> Option<&str> {
+        self.selected_thread.as_deref().or(self.restore_thread.as_deref())
+    }
+
+    /// Prune the conversations and cached messages in memory past the
+    /// retention limits. The open thread's messages are left whole.
+    fn prune_loaded(&mut self) {
+        if self.retention.is_unlimited() {
+            return;
+        }
+        let now = utils::now_millis();
+        let keep = self.kept_thread().map(str::to_string);
+        for thread_id in self.retention.prune_conversations(&mut self.conversations, keep.as_deref(), now) {
+            self.cached_messages.remove(&thread_id);
+        }
+        self.retention.prune_threads(&mut self.cached_messages, keep.as_deref(), now);
+    }
+
+    /// `prune_loaded`, and prune the on-disk caches of this window's devices
+    fn prune_history(&mut self) {
+        if self.retention.is_unlimited() {
+            return;
+        }
+        self.prune_loaded();
+        let now = utils::now_millis();
+        let keep = self.kept_thread().map(|thread| self.route(thread));
+        for device_id in &self.device_ids {
+            let keep = keep.as_ref()
+                .filter(|(keep_device, _)| keep_device == device_id)
+                .map(|(_, thread_id)| thread_id.as_str());
+            ConversationCache::update(device_id, |cache| cache.prune(&self.retention, keep, now));
+        }
+    }
+
+    fn update_conversation_names(&mut self) {
+        utils::apply_contact_names(&mut self.conversations, &self.contacts);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::backend::mock::{MockSms, Request};
+
+    const DEVICE: &str = "sms-window-test-device";
+
+    fn window(backend: &Arc<MockSms>) -> SmsWindow {
+        let mut window = SmsWindow::new(Core::default(), (DEVICE.to_string(), "Phone".to_string(), None), backend.clone());
+        window.cached_messages.clear();
+        window.drafts.clear();
+        window.retention = RetentionConfig::default();
+        window.conversations = ["1", "2"].iter()
+            .map(|thread_id| Conversation {
+                thread_id: thread_id.to_string(),
+                device_id: DEVICE.to_string(),
+                contact_name: String::new(),
+                phone_number: format!("555000000{}", thread_id),
+                last_message: String::new(),
+                last_message_sent: false,
+                last_message_read: false,
+                timestamp: 0,
+                unread: false,
+                pinned: false,
+                archived: false,
+            })
+            .collect();
+        window
+    }
+
+    fn received(window: &mut SmsWindow, id: &str, body: &str, type_: i32) {
+        let message = Message {
+            id: id.to_string(),
+            thread_id: "1".to_string(),
+            body: body.to_string(),
+            address: "5550000001".to_string(),
+            date: utils::now_millis(),
+            type_,
+            read: false,
+            status: SendStatus::Synced,
+            sub_id: None,
+            event: MessageEvent::Text,
+            subject: None,
+        };
+        let _ = window.update(SmsMessage::ProtocolEventReceived(ProtocolEvent::MessageReceived(message)));
+    }
+
+    #[test]
+    fn test_select_send_and_receive_against_mock() {
+        let backend = Arc::new(MockSms::default());
+        let mut window = window(&backend);
+
+        let _ = window.update(SmsMessage::SelectThread("1".to_string()));
+        assert_eq!(window.selected_thread.as_deref(), Some("1"));
+        assert_eq!(backend.take(), [Request::RequestMessages(DEVICE.to_string(), "1".to_string(), MESSAGE_PAGE_SIZE)]);
+
+        let _ = window.update(SmsMessage::UpdateInput("See you at 8".to_string()));
+        let _ = window.update(SmsMessage::SendMessage);
+        assert!(window.message_input.is_empty());
+        assert_eq!(window.messages.len(), 1);
+        assert_eq!(window.messages[0].status, SendStatus::Sending);
+        assert_eq!(backend.take(), [Request::SendSms(DEVICE.to_string(), "5550000001".to_string(), "See you at 8".to_string())]);
+
+        // The phone's copy replaces the optimistic bubble; a reply is added
+        received(&mut window, "100", "See you at 8", 2);
+        assert_eq!(window.messages.len(), 1);
+        assert_eq!(window.messages[0].id, "100");
+        received(&mut window, "101", "Great", 1);
+        assert_eq!(window.messages.len(), 2);
+        assert_eq!(window.conversations.iter().find(|c| c.thread_id == "1").unwrap().last_message, "Great");
+
+        // Blank input sends nothing
+        let _ = window.update(SmsMessage::UpdateInput("   ".to_string()));
+        let _ = window.update(SmsMessage::SendMessage);
+        assert!(backend.take().is_empty());
+    }
+
+    #[test]
+    fn test_unified_inbox_refuses_replies_to_unattributed_threads() {
+        let backend = Arc::new(MockSms::default());
+        let mut window = window(&backend);
+        window.unified = true;
+        let mut messages = Vec::new();
+        tag_source(&mut messages, &mut window.conversations, "", true);
+        assert_eq!(window.conversations[0].thread_id, "/1");
+
+        let _ = window.update(SmsMessage::SelectThread("/1".to_string()));
+        let _ = backend.take();
+        let _ = window.update(SmsMessage::UpdateInput("On my way".to_string()));
+        let _ = window.update(SmsMessage::SendMessage);
+        assert!(window.send_error.is_some());
+        assert_eq!(window.message_input, "On my way", "the text is kept");
+        assert!(backend.take().is_empty(), "nothing is sent from a guessed device");
+    }
+
+    #[test]
+    fn test_drafts_follow_thread_switches() {
+        let backend = Arc::new(MockSms::default());
+        let mut window = window(&backend);
+
+        let _ = window.update(SmsMessage::SelectThread("1".to_string()));
+        let _ = window.update(SmsMessage::UpdateInput("Half written".to_string()));
+        let _ = window.update(SmsMessage::SelectThread("2".to_string()));
+        assert!(window.message_input.is_empty());
+        assert_eq!(window.drafts.get("1").map(String::as_str), Some("Half written"));
+
+        let _ = window.update(SmsMessage::SelectThread("1".to_string()));
+        assert_eq!(window.message_input, "Half written");
+        assert!(!window.drafts.contains_key("1"));
+        assert_eq!(backend.take().len(), 3);
+    }
+
+    #[test]
+    fn test_narrow_window_goes_back_to_the_list() {
+        let backend = Arc::new(MockSms::default());
+        let mut window = window(&backend);
+
+        let _ = window.update(SmsMessage::WindowResized(480.0));
+        assert!(super::super::layout::is_single_panel(window.window_width));
+        let _ = window.update(SmsMessage::SelectThread("1".to_string()));
+        let _ = window.update(SmsMessage::UpdateInput("Half written".to_string()));
+        let _ = window.update(SmsMessage::BackToList);
+        assert_eq!(window.selected_thread, None);
+        assert!(window.messages.is_empty());
+        assert_eq!(window.drafts.get("1").map(String::as_str), Some("Half written"));
+
+        // The list only follows the pointer while the divider is held, and
+        // leaves the thread room
+        let _ = window.update(SmsMessage::WindowResized(1200.0));
+        let width = window.layout.list_width;
+        let _ = window.update(SmsMessage::ListResized(500.0));
+        assert_eq!(window.layout.list_width, width);
+        let _ = window.update(SmsMessage::StartListResize);
+        let _ = window.update(SmsMessage::ListResized(1100.0));
+        assert_eq!(window.layout.list_width, 1200.0 - super::super::layout::MIN_THREAD_WIDTH);
+    }
+
+    #[test]
+    fn test_reopened_thread_catches_up_from_newest_known() {
+        fn synced(thread_id: &str, date: i64) -> Message {
+            Message {
+                id: date.to_string(),
+                thread_id: thread_id.to_string(),
+                body: format!("Message {}", date),
+                address: "5550000001".to_string(),
+                date,
+                type_: 1,
+                read: true,
+                status: SendStatus::Synced,
+                sub_id: None,
+                event: MessageEvent::Text,
+                subject: None,
+            }
+        }
+        fn batch(window: &mut SmsWindow, thread_id: &str, dates: impl Iterator<Item = i64>) {
+            for date in dates {
+                let _ = window.update(SmsMessage::ProtocolEventReceived(ProtocolEvent::MessageReceived(synced(thread_id, date))));
+            }
+            let _ = window.update(SmsMessage::ProtocolEventReceived(ProtocolEvent::ConversationsReceived(Vec::new())));
+        }
+
+        let backend = Arc::new(MockSms::default());
+        let mut window = window(&backend);
+        window.cached_messages.insert("1".to_string(), (1000..1005).map(|date| synced("1", date)).collect());
+        window.cached_messages.insert("2".to_string(), vec![synced("2", 1000)]);
+
+        let _ = window.update(SmsMessage::SelectThread("1".to_string()));
+        assert_eq!(backend.take(), [Request::RequestMessagesSince(DEVICE.to_string(), "1".to_string(), 1004, SYNC_PAGE_SIZE)]);
+
+        // A full page of new messages: the next page may hold more
+        batch(&mut window, "1", 2000..2010);
+        assert_eq!(backend.take(), [Request::RequestMessages(DEVICE.to_string(), "1".to_string(), SYNC_PAGE_SIZE)]);
+
+        // This page reaches the cached messages, which merge without duplicates
+        batch(&mut window, "1", (1995..2000).chain(1000..1005));
+        assert!(backend.take().is_empty());
+        assert_eq!(window.messages.len(), 20);
+        assert_eq!(window.messages.first().map(|m| m.date), Some(1000));
+        assert_eq!(window.messages.last().map(|m| m.date), Some(2009));
+
+        // A phone that ignores ranges sends everything; refresh it in full
+        let _ = window.update(SmsMessage::SelectThread("2".to_string()));
+        backend.take();
+        batch(&mut window, "2", 0..30);
+        assert!(backend.take().is_empty());
+        let _ = window.update(SmsMessage::SelectThread("1".to_string()));
+        assert_eq!(backend.take(), [Request::RequestMessages(DEVICE.to_string(), "1".to_string(), MESSAGE_PAGE_SIZE)]);
+    }
+
+    #[test]
+    fn test_service_loss_marks_data_stale() {
+        let backend = Arc::new(MockSms::default());
+        let mut window = window(&backend);
+        let _ = window.update(SmsMessage::SyncConversations);
+        assert!(window.is_loading);
+
+        let _ = window.update(SmsMessage::ProtocolEventReceived(ProtocolEvent::Disconnected));
+        assert!(window.service_lost);
+        assert!(!window.is_loading, "the lost stream won't answer the sync");
+        assert_eq!(window.conversations.len(), 2, "data stays visible");
+
+        let _ = window.update(SmsMessage::ProtocolEventReceived(ProtocolEvent::Reconnected));
+        assert!(!window.service_lost);
+    }
+
+    #[test]
+    fn test_long_message_waits_for_confirmation() {
+        let backend = Arc::new(MockSms::default());
+        let mut window = window(&backend);
+        window.send_confirm = SendConfirmConfig { enabled: true, max_segments: 1 };
+        let _ = window.update(SmsMessage::SelectThread("1".to_string()));
+        backend.take();
+
+        let long = "a".repeat(200);
+        let _ = window.update(SmsMessage::UpdateInput(long.clone()));
+        let _ = window.update(SmsMessage::SendMessage);
+        assert_eq!(window.confirm_send, Some(2));
+        assert!(backend.take().is_empty());
+
+        // Cancelling keeps the text for editing
+        let _ = window.update(SmsMessage::ConfirmSend(false));
+        assert_eq!(window.confirm_send, None);
+        assert_eq!(window.message_input, long);
+
+        let _ = window.update(SmsMessage::SendMessage);
+        let _ = window.update(SmsMessage::ConfirmSend(true));
+        assert_eq!(backend.take(), [Request::SendSms(DEVICE.to_string(), "5550000001".to_string(), long)]);
+
+        // Short messages go straight out
+        let _ = window.update(SmsMessage::UpdateInput("Short".to_string()));
+        let _ = window.update(SmsMessage::SendMessage);
+        assert_eq!(window.confirm_send, None);
+        assert_eq!(backend.take().len(), 1);
+    }
+
+    #[test]
+    fn test_switch_device_starts_over() {
+        const OTHER: &str = "sms-window-test-other-device";
+        let backend = Arc::new(MockSms::default());
+        let mut window = window(&backend);
+
+        let _ = window.update(SmsMessage::SelectThread("1".to_string()));
+        let _ = window.update(SmsMessage::UpdateInput("Before switching".to_string()));
+        let _ = window.update(SmsMessage::SwitchDevice(OTHER.to_string()));
+        assert_eq!(window.device_id, OTHER);
+        assert_eq!(window.device_ids, [OTHER]);
+        assert_eq!(window.selected_thread, None);
+        assert!(window.message_input.is_empty());
+        assert!(!window.conversations.iter().any(|c| c.device_id == DEVICE));
+
+        // Switching to the same device changes nothing
+        let _ = window.update(SmsMessage::SwitchDevice(OTHER.to_string()));
+        assert_eq!(window.device_id, OTHER);
+
+        // The first device's session was saved on the way out
+        let _ = window.update(SmsMessage::SwitchDevice(DEVICE.to_string()));
+        assert_eq!(window.drafts.get("1").map(String::as_str), Some("Before switching"));
+
+        window.drafts.clear();
+        window.save_session();
+    }
 }
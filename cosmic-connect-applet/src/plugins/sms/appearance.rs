@@ -0,0 +1,239 @@
+// cosmic-connect-applet/src/plugins/sms/appearance.rs
+//! Message bubble colors and shape for the SMS window.
+//!
+//! Stored in ~/.config/cosmic-connect/sms.toml. Unset values fall back to
+//! the current COSMIC theme.
+
+use cosmic::iced::{Background, Border, Color};
+use cosmic::iced::widget::container;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// Largest selectable bubble corner radius
+pub const MAX_CORNER_RADIUS: u16 = 24;
+
+/// Bubble color choices from the COSMIC palette
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BubbleColor {
+    /// Theme default (primary container for sent, no fill for received)
+    #[default]
+    Default,
+    Accent,
+    Blue,
+    Green,
+    Orange,
+    Pink,
+    Purple,
+    Red,
+    Yellow,
+    WarmGrey,
+}
+
+impl BubbleColor {
+    pub const ALL: [BubbleColor; 10] = [
+        BubbleColor::Default,
+        BubbleColor::Accent,
+        BubbleColor::Blue,
+        BubbleColor::Green,
+        BubbleColor::Orange,
+        BubbleColor::Pink,
+        BubbleColor::Purple,
+        BubbleColor::Red,
+        BubbleColor::Yellow,
+        BubbleColor::WarmGrey,
+    ];
+
+    /// Dropdown labels, in the order of `ALL`
+    pub const LABELS: [&'static str; 10] = [
+        "Theme default", "Accent", "Blue", "Green", "Orange",
+        "Pink", "Purple", "Red", "Yellow", "Warm grey",
+    ];
+
+    fn key(self) -> &'static str {
+        match self {
+            BubbleColor::Default => "default",
+            BubbleColor::Accent => "accent",
+            BubbleColor::Blue => "blue",
+            BubbleColor::Green => "green",
+            BubbleColor::Orange => "orange",
+            BubbleColor::Pink => "pink",
+            BubbleColor::Purple => "purple",
+            BubbleColor::Red => "red",
+            BubbleColor::Yellow => "yellow",
+            BubbleColor::WarmGrey => "warm_grey",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.key() == key)
+    }
+
+    pub fn index(self) -> usize {
+        Self::ALL.iter().position(|c| *c == self).unwrap_or(0)
+    }
+
+    /// Resolve against the active theme; `None` for the theme default
+    fn color(self, theme: &cosmic::Theme) -> Option<Color> {
+        let cosmic = theme.cosmic();
+        let palette = cosmic.palette.as_ref();
+        let srgba = match self {
+            BubbleColor::Default => return None,
+            BubbleColor::Accent => cosmic.accent_color(),
+            BubbleColor::Blue => palette.accent_blue,
+            BubbleColor::Green => palette.accent_green,
+            BubbleColor::Orange => palette.accent_orange,
+            BubbleColor::Pink => palette.accent_pink,
+            BubbleColor::Purple => palette.accent_purple,
+            BubbleColor::Red => palette.accent_red,
+            BubbleColor::Yellow => palette.accent_yellow,
+            BubbleColor::WarmGrey => palette.accent_warm_grey,
+        };
+        Some(Color::from(srgba))
+    }
+}
+
+/// Appearance of message bubbles
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SmsAppearanceConfig {
+    pub sent_color: BubbleColor,
+    pub received_color: BubbleColor,
+    /// Corner radius in pixels, theme default when unset
+    pub corner_radius: Option<u16>,
+}
+
+impl SmsAppearanceConfig {
+    /// Load appearance from file
+    pub fn load() -> io::Result<Self> {
+        let config_path = Self::get_config_path();
+
+        if !config_path.exists() {
+            return Ok(Self::default());
+        }
+
+        Ok(Self::parse(&fs::read_to_string(&config_path)?))
+    }
+
+    fn parse(content: &str) -> Self {
+        let mut config = Self::default();
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some((key, value)) = line.split_once('=') {
+                let value = value.trim().trim_matches('"');
+                match key.trim() {
+                    "sent_color" => {
+                        config.sent_color = BubbleColor::from_key(value).unwrap_or_default();
+                    }
+                    "received_color" => {
+                        config.received_color = BubbleColor::from_key(value).unwrap_or_default();
+                    }
+                    "corner_radius" => {
+                        config.corner_radius = value.parse::<u16>().ok()
+                            .map(|r| r.min(MAX_CORNER_RADIUS));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        config
+    }
+
+    /// Save appearance to file
+    pub fn save(&self) -> io::Result<()> {
+        let config_path = Self::get_config_path();
+
+        // Ensure directory exists
+        if let Some(parent) = config_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = fs::File::create(&config_path)?;
+
+        writeln!(file, "sent_color = \"{}\"", self.sent_color.key())?;
+        writeln!(file, "received_color = \"{}\"", self.received_color.key())?;
+        if let Some(radius) = self.corner_radius {
+            writeln!(file, "corner_radius = {}", radius)?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether everything is left to the theme
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Container class for a sent or received bubble
+    pub fn bubble_class(&self, is_sent: bool) -> cosmic::theme::Container<'static> {
+        if self.is_default() {
+            return if is_sent {
+                cosmic::theme::Container::Primary
+            } else {
+                cosmic::theme::Container::Transparent
+            };
+        }
+
+        let choice = if is_sent { self.sent_color } else { self.received_color };
+        let radius = self.corner_radius;
+
+        cosmic::theme::Container::custom(move |theme| {
+            let cosmic = theme.cosmic();
+            let background = match choice.color(theme) {
+                Some(color) => Some(color),
+                None if is_sent => Some(Color::from(cosmic.primary.base)),
+                None => None,
+            };
+            let text_color = match choice {
+                BubbleColor::Default if is_sent => Some(Color::from(cosmic.primary.on)),
+                BubbleColor::Default => None,
+                _ => background.map(contrasting_text),
+            };
+            let radius = radius.map_or(cosmic.corner_radii.radius_s[0], f32::from);
+
+            container::Style {
+                text_color,
+                background: background.map(Background::Color),
+                border: Border {
+                    radius: radius.into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        })
+    }
+
+    /// Get the config file path
+    fn get_config_path() -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+        PathBuf::from(format!("{}/.config/cosmic-connect/sms.toml", home))
+    }
+}
+
+/// Black or white, whichever reads better on `background`
+fn contrasting_text(background: Color) -> Color {
+    let luminance = 0.2126 * background.r + 0.7152 * background.g + 0.0722 * background.b;
+    if luminance > 0.5 { Color::BLACK } else { Color::WHITE }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_appearance() {
+        let config = SmsAppearanceConfig::parse(
+            "# bubbles\nsent_color = \"green\"\nreceived_color = \"bogus\"\ncorner_radius = 99\n",
+        );
+        assert_eq!(config.sent_color, BubbleColor::Green);
+        assert_eq!(config.received_color, BubbleColor::Default);
+        assert_eq!(config.corner_radius, Some(MAX_CORNER_RADIUS));
+
+        assert!(SmsAppearanceConfig::parse("").is_default());
+    }
+}
@@ -0,0 +1,170 @@
+// cosmic-connect-applet/src/plugins/sms/auto_open.rs
+//! Opening the SMS window when a new conversation begins.
+//!
+//! Opt-in, stored in ~/.config/cosmic-connect/sms_auto_open. The applet
+//! watches SMS batches from the service; a received message in a thread it
+//! has not seen for that device opens `cosmic-connect-sms` on that thread.
+//! At most one window is opened per MIN_OPEN_INTERVAL_MS, so a burst of new
+//! threads (or a first sync) can't flood the desktop with windows.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use tracing::debug;
+
+use super::cache::ConversationCache;
+use super::models::Message;
+use super::utils::now_millis;
+
+/// Shortest time between two windows opened automatically, in ms
+pub const MIN_OPEN_INTERVAL_MS: i64 = 30_000;
+
+/// Messages older than this when they arrive don't start a conversation;
+/// they come from a sync, not from someone writing now
+pub const MAX_MESSAGE_AGE_MS: i64 = 5 * 60 * 1000;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AutoOpenConfig {
+    /// Open the SMS window on the first message of a new conversation
+    pub enabled: bool,
+}
+
+impl AutoOpenConfig {
+    /// Load config from file
+    pub fn load() -> io::Result<Self> {
+        let path = Self::get_config_path();
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        Ok(Self::parse(&fs::read_to_string(&path)?))
+    }
+
+    fn parse(content: &str) -> Self {
+        let mut config = Self::default();
+        for line in content.lines() {
+            if let Some((key, value)) = line.trim().split_once('=') {
+                if key.trim() == "openNewConversations" {
+                    config.enabled = value.trim().parse().unwrap_or(false);
+                }
+            }
+        }
+        config
+    }
+
+    /// Save config to file
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::get_config_path();
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = fs::File::create(&path)?;
+        writeln!(file, "[General]")?;
+        writeln!(file, "openNewConversations={}", self.enabled)?;
+
+        Ok(())
+    }
+
+    fn get_config_path() -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+        PathBuf::from(format!("{}/.config/cosmic-connect/sms_auto_open", home))
+    }
+}
+
+/// Threads known per device, to tell which conversations are new
+#[derive(Debug, Default)]
+pub struct NewThreadWatcher {
+    known: HashMap<String, HashSet<String>>,
+    /// When a window was last opened, in ms since the epoch
+    last_open: Option<i64>,
+}
+
+impl NewThreadWatcher {
+    /// Note a batch of messages from `device_id` and return the thread to
+    /// open, if any. A device's threads are first taken from its SMS cache;
+    /// with no cache the first batch only teaches which threads exist.
+    pub fn observe(&mut self, device_id: &str, messages: &[Message]) -> Option<String> {
+        if !self.known.contains_key(device_id) {
+            let cached: HashSet<String> = ConversationCache::load(device_id)
+                .map(|cache| cache.conversations.into_iter().map(|c| c.thread_id).collect())
+                .unwrap_or_default();
+            if cached.is_empty() {
+                self.known.insert(device_id.to_string(), messages.iter().map(|m| m.thread_id.clone()).collect());
+                return None;
+            }
+            self.known.insert(device_id.to_string(), cached);
+        }
+        self.observe_known(device_id, messages, now_millis())
+    }
+
+    /// `observe` for a device whose threads are known, at `now` (ms since
+    /// the epoch)
+    fn observe_known(&mut self, device_id: &str, messages: &[Message], now: i64) -> Option<String> {
+        let known = self.known.entry(device_id.to_string()).or_default();
+        let mut new_thread = None;
+        for message in messages {
+            let is_new = known.insert(message.thread_id.clone());
+            if is_new && new_thread.is_none() && message.type_ == 1 && now - message.date <= MAX_MESSAGE_AGE_MS {
+                new_thread = Some(message.thread_id.clone());
+            }
+        }
+
+        let thread_id = new_thread?;
+        if self.last_open.is_some_and(|last| now - last < MIN_OPEN_INTERVAL_MS) {
+            debug!("New conversation {} on {}, but a window was opened recently", thread_id, device_id);
+            return None;
+        }
+        self.last_open = Some(now);
+        Some(thread_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::sms::models::{MessageEvent, SendStatus};
+
+    fn message(thread_id: &str, type_: i32, date: i64) -> Message {
+        Message {
+            id: format!("{}-{}", thread_id, date),
+            thread_id: thread_id.to_string(),
+            body: "Hi".to_string(),
+            address: "+15550100".to_string(),
+            date,
+            type_,
+            read: false,
+            status: SendStatus::Synced,
+            sub_id: None,
+            event: MessageEvent::Text,
+            subject: None,
+        }
+    }
+
+    #[test]
+    fn test_new_thread_opens_once_per_interval() {
+        assert!(!AutoOpenConfig::default().enabled);
+        assert!(AutoOpenConfig::parse("[General]\nopenNewConversations=true\n").enabled);
+
+        let now = 1_700_000_000_000;
+        let mut watcher = NewThreadWatcher::default();
+        watcher.known.insert("phone".to_string(), ["1".to_string()].into());
+
+        // Known threads, sent messages and old history open nothing
+        assert_eq!(watcher.observe_known("phone", &[message("1", 1, now)], now), None);
+        assert_eq!(watcher.observe_known("phone", &[message("2", 2, now)], now), None);
+        assert_eq!(watcher.observe_known("phone", &[message("3", 1, now - MAX_MESSAGE_AGE_MS - 1)], now), None);
+
+        assert_eq!(watcher.observe_known("phone", &[message("4", 1, now)], now), Some("4".to_string()));
+        assert_eq!(watcher.observe_known("phone", &[message("4", 1, now + 1)], now + 1), None, "no longer new");
+
+        // A burst only opens one window until the interval has passed
+        let later = now + 1000;
+        assert_eq!(watcher.observe_known("phone", &[message("5", 1, later), message("6", 1, later)], later), None);
+        let later = now + MIN_OPEN_INTERVAL_MS;
+        assert_eq!(watcher.observe_known("phone", &[message("7", 1, later)], later), Some("7".to_string()));
+    }
+}
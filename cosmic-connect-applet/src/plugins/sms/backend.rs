@@ -0,0 +1,119 @@
+// cosmic-connect-applet/src/plugins/sms/backend.rs
+//! Requests the SMS window sends to phones.
+//!
+//! `SmsWindow` makes them through an `SmsBackend` rather than calling
+//! `dbus` directly, so its update logic can be tested against `MockSms`.
+//! Calling a method only builds the request; the work happens when the
+//! returned future runs.
+
+use futures::future::{BoxFuture, FutureExt};
+
+use super::dbus;
+
+pub trait SmsBackend: Send + Sync {
+    /// Ask a device for its conversation list
+    fn fetch_conversations(&self, device_id: String) -> BoxFuture<'static, ()>;
+
+    /// Ask a device to send every conversation again
+    fn request_conversations(&self, device_id: String) -> BoxFuture<'static, Result<(), String>>;
+
+    /// Ask for up to `count` messages of a thread older than `before`
+    fn request_messages(&self, device_id: String, thread_id: String, before: i64, count: usize) -> BoxFuture<'static, ()>;
+
+    /// Ask for the newest `count` messages of a thread, to catch up on
+    /// those after `after`
+    fn request_messages_since(&self, device_id: String, thread_id: String, after: i64, count: usize) -> BoxFuture<'static, ()>;
+
+    fn send_sms(&self, device_id: String, phone_number: String, body: String) -> BoxFuture<'static, Result<(), String>>;
+}
+
+/// Phones reached through kdeconnect-service
+pub struct DbusSms;
+
+impl SmsBackend for DbusSms {
+    fn fetch_conversations(&self, device_id: String) -> BoxFuture<'static, ()> {
+        async move { dbus::fetch_conversations(&device_id).await }.boxed()
+    }
+
+    fn request_conversations(&self, device_id: String) -> BoxFuture<'static, Result<(), String>> {
+        async move { dbus::request_conversations(&device_id).await }.boxed()
+    }
+
+    fn request_messages(&self, device_id: String, thread_id: String, before: i64, count: usize) -> BoxFuture<'static, ()> {
+        async move { dbus::request_conversation_messages_range(&device_id, &thread_id, before, count).await }.boxed()
+    }
+
+    fn request_messages_since(&self, device_id: String, thread_id: String, after: i64, count: usize) -> BoxFuture<'static, ()> {
+        async move { dbus::request_conversation_messages_since(&device_id, &thread_id, after, count).await }.boxed()
+    }
+
+    fn send_sms(&self, device_id: String, phone_number: String, body: String) -> BoxFuture<'static, Result<(), String>> {
+        async move { dbus::send_sms(&device_id, &phone_number, &body).await }.boxed()
+    }
+}
+
+#[cfg(test)]
+pub mod mock {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// A request as the window made it
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum Request {
+        FetchConversations(String),
+        RequestConversations(String),
+        /// device_id, thread_id, count
+        RequestMessages(String, String, usize),
+        /// device_id, thread_id, after, count
+        RequestMessagesSince(String, String, i64, usize),
+        /// device_id, phone number, body
+        SendSms(String, String, String),
+    }
+
+    /// Records requests instead of sending them; sends fail with
+    /// `send_error` when it is set
+    #[derive(Default)]
+    pub struct MockSms {
+        requests: Mutex<Vec<Request>>,
+        pub send_error: Option<String>,
+    }
+
+    impl MockSms {
+        /// Requests made since the last call, oldest first
+        pub fn take(&self) -> Vec<Request> {
+            std::mem::take(&mut *self.requests.lock().unwrap())
+        }
+
+        fn record(&self, request: Request) {
+            self.requests.lock().unwrap().push(request);
+        }
+    }
+
+    impl SmsBackend for MockSms {
+        fn fetch_conversations(&self, device_id: String) -> BoxFuture<'static, ()> {
+            self.record(Request::FetchConversations(device_id));
+            async {}.boxed()
+        }
+
+        fn request_conversations(&self, device_id: String) -> BoxFuture<'static, Result<(), String>> {
+            self.record(Request::RequestConversations(device_id));
+            async { Ok(()) }.boxed()
+        }
+
+        fn request_messages(&self, device_id: String, thread_id: String, _before: i64, count: usize) -> BoxFuture<'static, ()> {
+            self.record(Request::RequestMessages(device_id, thread_id, count));
+            async {}.boxed()
+        }
+
+        fn request_messages_since(&self, device_id: String, thread_id: String, after: i64, count: usize) -> BoxFuture<'static, ()> {
+            self.record(Request::RequestMessagesSince(device_id, thread_id, after, count));
+            async {}.boxed()
+        }
+
+        fn send_sms(&self, device_id: String, phone_number: String, body: String) -> BoxFuture<'static, Result<(), String>> {
+            self.record(Request::SendSms(device_id, phone_number, body));
+            let result = self.send_error.clone().map_or(Ok(()), Err);
+            async move { result }.boxed()
+        }
+    }
+}
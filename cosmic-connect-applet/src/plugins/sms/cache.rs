@@ -0,0 +1,269 @@
+// cosmic-connect-applet/src/plugins/sms/cache.rs
+//! Conversations and recent messages cached between SMS window launches.
+//!
+//! Stored per device in ~/.cache/kdeconnect/{device_id}/conversations.json
+//! with phone-side thread ids, so the window can show history before the
+//! phone answers. The phone's data always wins once it arrives. Files
+//! written by another cache version are ignored and replaced.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use tracing::{debug, warn};
+
+use super::models::{Conversation, Message, MessageEvent, SendStatus};
+use super::retention::RetentionConfig;
+
+/// Layout version written, and the only one read
+pub const CACHE_VERSION: u64 = 1;
+
+/// Newest messages kept per thread
+pub const MAX_CACHED_MESSAGES: usize = 50;
+
+#[derive(Debug, Clone, Default)]
+pub struct ConversationCache {
+    pub conversations: Vec<Conversation>,
+    /// Newest messages per thread, oldest first
+    pub messages: HashMap<String, Vec<Message>>,
+}
+
+impl ConversationCache {
+    /// Load a device's cache. A missing, unreadable or outdated file gives
+    /// an empty cache.
+    pub fn load(device_id: &str) -> io::Result<Self> {
+        let path = Self::get_cache_path(device_id);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(Self::parse(&fs::read_to_string(&path)?).unwrap_or_else(|reason| {
+            debug!("Ignoring SMS cache {}: {}", path.display(), reason);
+            Self::default()
+        }))
+    }
+
+    fn parse(content: &str) -> Result<Self, String> {
+        let value: serde_json::Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+        match value["version"].as_u64() {
+            Some(CACHE_VERSION) => {}
+            version => return Err(format!("cache version {:?}, expected {}", version, CACHE_VERSION)),
+        }
+
+        let conversations = value["conversations"].as_array()
+            .map(|entries| entries.iter().filter_map(conversation_from_json).collect())
+            .unwrap_or_default();
+        let messages = value["messages"].as_object()
+            .map(|threads| {
+                threads.iter()
+                    .map(|(thread_id, entries)| {
+                        let messages = entries.as_array()
+                            .map(|entries| entries.iter().filter_map(|e| message_from_json(thread_id, e)).collect())
+                            .unwrap_or_default();
+                        (thread_id.clone(), messages)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(Self { conversations, messages })
+    }
+
+    fn to_json(&self) -> String {
+        let conversations: Vec<serde_json::Value> = self.conversations.iter()
+            .map(|c| serde_json::json!({
+                "threadId": c.thread_id,
+                "phoneNumber": c.phone_number,
+                "lastMessage": c.last_message,
+                "lastMessageSent": c.last_message_sent,
+                "lastMessageRead": c.last_message_read,
+                "timestamp": c.timestamp,
+                "unread": c.unread,
+            }))
+            .collect();
+        let messages: serde_json::Map<String, serde_json::Value> = self.messages.iter()
+            .map(|(thread_id, messages)| {
+                let entries: Vec<serde_json::Value> = messages.iter()
+                    .map(|m| serde_json::json!({
+                        "id": m.id,
+                        "body": m.body,
+                        "address": m.address,
+                        "date": m.date,
+                        "type": m.type_,
+                        "read": m.read,
+                        "subId": m.sub_id,
+                        "mms": m.event == MessageEvent::Mms,
+                        "subject": m.subject,
+                    }))
+                    .collect();
+                (thread_id.clone(), serde_json::Value::from(entries))
+            })
+            .collect();
+        serde_json::json!({
+            "version": CACHE_VERSION,
+            "conversations": conversations,
+            "messages": messages,
+        }).to_string()
+    }
+
+    /// Save the cache, replacing the file in one step so a crash never
+    /// leaves half a file behind
+    pub fn save(&self, device_id: &str) -> io::Result<()> {
+        let path = Self::get_cache_path(device_id);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, self.to_json())?;
+        fs::rename(&temp, &path)
+    }
+
+    /// Fold a batch from the phone into the cache: conversations replace
+    /// the cached ones with the same thread, messages are added by id and
+    /// each thread keeps its newest `MAX_CACHED_MESSAGES`
+    pub fn record(&mut self, messages: &[Message], conversations: &[Conversation]) {
+        for incoming in conversations {
+            match self.conversations.iter_mut().find(|c| c.thread_id == incoming.thread_id) {
+                Some(existing) => *existing = incoming.clone(),
+                None => self.conversations.push(incoming.clone()),
+            }
+        }
+        for message in messages {
+            let thread = self.messages.entry(message.thread_id.clone()).or_default();
+            match thread.iter_mut().find(|m| m.id == message.id) {
+                Some(existing) => *existing = message.clone(),
+                None => thread.push(message.clone()),
+            }
+        }
+        for thread in self.messages.values_mut() {
+            thread.sort_by_key(|m| m.date);
+            let excess = thread.len().saturating_sub(MAX_CACHED_MESSAGES);
+            thread.drain(..excess);
+        }
+    }
+
+    /// Apply the retention limits, dropping pruned threads with their
+    /// messages
+    pub fn prune(&mut self, retention: &RetentionConfig, keep: Option<&str>, now: i64) {
+        for thread_id in retention.prune_conversations(&mut self.conversations, keep, now) {
+            self.messages.remove(&thread_id);
+        }
+        retention.prune_threads(&mut self.messages, keep, now);
+    }
+
+    /// Drop a deleted thread
+    pub fn forget_thread(&mut self, thread_id: &str) {
+        self.conversations.retain(|c| c.thread_id != thread_id);
+        self.messages.remove(thread_id);
+    }
+
+    /// Load, change and save a device's cache, logging failures; the cache
+    /// is only a head start, so nothing depends on it being written
+    pub fn update(device_id: &str, change: impl FnOnce(&mut Self)) {
+        let mut cache = Self::load(device_id).unwrap_or_else(|e| {
+            warn!("Failed to read SMS cache for {}: {}", device_id, e);
+            Self::default()
+        });
+        change(&mut cache);
+        if let Err(e) = cache.save(device_id) {
+            warn!("Failed to write SMS cache for {}: {}", device_id, e);
+        }
+    }
+
+    fn get_cache_path(device_id: &str) -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+        PathBuf::from(format!("{}/.cache/kdeconnect/{}/conversations.json", home, device_id))
+    }
+}
+
+fn conversation_from_json(entry: &serde_json::Value) -> Option<Conversation> {
+    Some(Conversation {
+        thread_id: entry["threadId"].as_str()?.to_string(),
+        device_id: String::new(),
+        contact_name: String::new(),
+        phone_number: entry["phoneNumber"].as_str()?.to_string(),
+        last_message: entry["lastMessage"].as_str().unwrap_or_default().to_string(),
+        last_message_sent: entry["lastMessageSent"].as_bool().unwrap_or(false),
+        last_message_read: entry["lastMessageRead"].as_bool().unwrap_or(false),
+        timestamp: entry["timestamp"].as_i64()?,
+        unread: entry["unread"].as_bool().unwrap_or(false),
+        pinned: false,
+        archived: false,
+    })
+}
+
+fn message_from_json(thread_id: &str, entry: &serde_json::Value) -> Option<Message> {
+    Some(Message {
+        id: entry["id"].as_str()?.to_string(),
+        thread_id: thread_id.to_string(),
+        body: entry["body"].as_str().unwrap_or_default().to_string(),
+        address: entry["address"].as_str().unwrap_or_default().to_string(),
+        date: entry["date"].as_i64()?,
+        type_: entry["type"].as_i64().and_then(|t| i32::try_from(t).ok())?,
+        read: entry["read"].as_bool().unwrap_or(true),
+        status: SendStatus::Synced,
+        sub_id: entry["subId"].as_i64().and_then(|id| i32::try_from(id).ok()),
+        event: if entry["mms"].as_bool().unwrap_or(false) { MessageEvent::Mms } else { MessageEvent::Text },
+        subject: entry["subject"].as_str().map(str::to_string),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: &str, thread_id: &str, date: i64) -> Message {
+        Message {
+            id: id.to_string(),
+            thread_id: thread_id.to_string(),
+            body: format!("message {}", id),
+            address: "5551234567".to_string(),
+            date,
+            type_: 1,
+            read: true,
+            status: SendStatus::Synced,
+            sub_id: None,
+            event: MessageEvent::Text,
+            subject: None,
+        }
+    }
+
+    #[test]
+    fn test_cache_round_trip_cap_and_versioning() {
+        let conversation = Conversation {
+            thread_id: "42".to_string(),
+            device_id: String::new(),
+            contact_name: String::new(),
+            phone_number: "5551234567".to_string(),
+            last_message: "message 0".to_string(),
+            last_message_sent: false,
+            last_message_read: false,
+            timestamp: 0,
+            unread: false,
+            pinned: false,
+            archived: false,
+        };
+        let messages: Vec<Message> = (0..60).rev().map(|i| message(&i.to_string(), "42", i)).collect();
+
+        let mut cache = ConversationCache::default();
+        cache.record(&messages, &[conversation]);
+        cache.record(&[message("59", "42", 59)], &[]);
+        assert_eq!(cache.messages["42"].len(), MAX_CACHED_MESSAGES);
+        assert_eq!(cache.messages["42"][0].id, "10");
+
+        let parsed = ConversationCache::parse(&cache.to_json()).unwrap();
+        assert_eq!(parsed.conversations.len(), 1);
+        assert_eq!(parsed.conversations[0].phone_number, "5551234567");
+        let last = parsed.messages["42"].last().unwrap();
+        assert_eq!((last.id.as_str(), last.date, last.body.as_str()), ("59", 59, "message 59"));
+
+        // Files from another version, or not JSON at all, are not used
+        assert!(ConversationCache::parse(r#"{"version":2,"conversations":[]}"#).is_err());
+        assert!(ConversationCache::parse(r#"{"conversations":[]}"#).is_err());
+        assert!(ConversationCache::parse("garbage").is_err());
+
+        // Entries missing required fields are skipped, not fatal
+        let partial = r#"{"version":1,"conversations":[{"threadId":"7"}],"messages":{"7":[{"id":"1"}]}}"#;
+        let parsed = ConversationCache::parse(partial).unwrap();
+        assert!(parsed.conversations.is_empty());
+        assert!(parsed.messages["7"].is_empty());
+    }
+}
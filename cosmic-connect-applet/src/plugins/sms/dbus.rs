@@ -1,82 +1,260 @@
 // cosmic-connect-applet/src/plugins/sms/dbus.rs
 use anyhow::Result;
-use kdeconnect_dbus_client::KdeConnectClient;
+use kdeconnect_dbus_client::{KdeConnectClient, KdeConnectError};
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
 
-use super::models::{Conversation, Message};
+use crate::device_aliases::alias_for;
+
+use super::models::{Conversation, Message, MessageEvent, SendStatus};
 
 lazy_static::lazy_static! {
     static ref SMS_CLIENT: Arc<Mutex<Option<Arc<KdeConnectClient>>>> = Arc::new(Mutex::new(None));
 }
 
+/// Longest a caller waits for the service to answer a connection attempt
+const CONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// The shared client, connecting first if needed. The lock is held while
+/// connecting, so requests made before the window's event stream started
+/// wait for the same attempt; after a failure the next caller retries.
+async fn connected_client() -> Result<Arc<KdeConnectClient>, KdeConnectError> {
+    let mut guard = SMS_CLIENT.lock().await;
+    if let Some(client) = guard.as_ref() {
+        return Ok(client.clone());
+    }
+
+    debug!("initialize()");
+    let client = tokio::time::timeout(CONNECT_TIMEOUT, KdeConnectClient::new()).await
+        .map_err(|_| KdeConnectError::ServiceUnavailable)??;
+    let client = Arc::new(client);
+    *guard = Some(client.clone());
+    info!("SMS D-Bus client connected");
+    Ok(client)
+}
+
+/// Connect to the service if no request has yet
 pub async fn initialize() -> Result<()> {
-    eprintln!("[SMS-DBUS] initialize()");
-    let client = KdeConnectClient::new().await?;
-    *SMS_CLIENT.lock().await = Some(Arc::new(client));
-    eprintln!("[SMS-DBUS] initialize() OK");
+    connected_client().await?;
     Ok(())
 }
 
-/// Wait up to 10s for the client to be ready, then return it.
+/// Drop the shared client so its D-Bus connection is closed
+pub async fn shutdown() {
+    debug!("shutdown()");
+    SMS_CLIENT.lock().await.take();
+}
+
+/// The shared client, or None (logged) if the service can't be reached
 pub async fn get_client() -> Option<Arc<KdeConnectClient>> {
-    for i in 0..100 {
-        {
-            let guard = SMS_CLIENT.lock().await;
-            if let Some(c) = guard.as_ref() {
-                eprintln!("[SMS-DBUS] get_client() ready after {}*100ms", i);
-                return Some(c.clone());
-            }
+    match connected_client().await {
+        Ok(client) => Some(client),
+        Err(e) => {
+            warn!("SMS D-Bus client unavailable: {}", e);
+            None
+        }
+    }
+}
+
+/// Paired devices to aggregate in the unified inbox, as (id, name)
+pub async fn paired_devices() -> Vec<(String, String)> {
+    let Some(client) = get_client().await else { return Vec::new(); };
+    match client.list_devices().await {
+        Ok(devices) => devices.into_iter()
+            .filter(|d| d.is_paired)
+            .map(|d| {
+                let name = alias_for(&d.id).unwrap_or(d.name);
+                (d.id, name)
+            })
+            .collect(),
+        Err(e) => {
+            warn!("list_devices failed: {:?}", e);
+            Vec::new()
         }
-        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
     }
-    eprintln!("[SMS-DBUS] get_client() TIMEOUT");
-    None
+}
+
+/// Resolve display names for the given device ids, falling back to the id
+pub async fn device_names(device_ids: &[String]) -> Vec<(String, String)> {
+    let Some(client) = get_client().await else {
+        return device_ids.iter().map(|id| (id.clone(), id.clone())).collect();
+    };
+    let mut names = Vec::with_capacity(device_ids.len());
+    for id in device_ids {
+        if let Some(alias) = alias_for(id) {
+            names.push((id.clone(), alias));
+            continue;
+        }
+        let name = match client.get_device(id).await {
+            Ok(d) => d.name,
+            Err(e) => {
+                debug!("get_device {} failed: {:?}", id, e);
+                id.clone()
+            }
+        };
+        names.push((id.clone(), name));
+    }
+    names
 }
 
 pub async fn fetch_conversations(device_id: &str) {
-    eprintln!("[SMS-DBUS] fetch_conversations() device={}", device_id);
-    let Some(client) = get_client().await else { return; };
-    match client.request_conversations(device_id).await {
-        Ok(_) => eprintln!("[SMS-DBUS] request_conversations sent OK"),
-        Err(e) => eprintln!("[SMS-DBUS] request_conversations FAILED: {:?}", e),
+    debug!("fetch_conversations() device={}", device_id);
+    match request_conversations(device_id).await {
+        Ok(()) => debug!("request_conversations sent OK"),
+        Err(e) => warn!("request_conversations failed: {}", e),
+    }
+}
+
+/// Ask the phone for its full conversation list. The answer arrives later
+/// as an SMS messages signal.
+pub async fn request_conversations(device_id: &str) -> Result<(), String> {
+    crate::backend::permission_guard(device_id, "sms").map_err(|e| e.to_string())?;
+    let client = connected_client().await.map_err(|e| e.to_string())?;
+    client.request_conversations(device_id).await
+        .map_err(|e| kdeconnect_dbus_client::error_detail(&e))
+}
+
+/// Why a thread id can't be requested from the phone
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ThreadIdError {
+    /// A `new_...` thread started on the desktop; the phone has no messages
+    /// for it until the first one is sent
+    Placeholder,
+    /// Not a placeholder and not a number
+    Invalid(std::num::ParseIntError),
+}
+
+/// Parse a thread id for a protocol request
+pub fn protocol_thread_id(thread_id: &str) -> Result<i64, ThreadIdError> {
+    if thread_id.starts_with("new_") {
+        return Err(ThreadIdError::Placeholder);
+    }
+    thread_id.parse::<i64>().map_err(ThreadIdError::Invalid)
+}
+
+/// Parse `thread_id`, logging why it can't be requested. Requesting a
+/// fallback thread would pull in some other conversation instead.
+fn requestable_thread_id(device_id: &str, thread_id: &str) -> Option<i64> {
+    match protocol_thread_id(thread_id) {
+        Ok(tid) => Some(tid),
+        Err(ThreadIdError::Placeholder) => {
+            debug!("Skipping request for thread {} on {}: no messages on the phone yet", thread_id, device_id);
+            None
+        }
+        Err(ThreadIdError::Invalid(e)) => {
+            warn!("Skipping request for thread {:?} on {}: invalid thread id ({})", thread_id, device_id, e);
+            None
+        }
     }
 }
 
 pub async fn request_conversation_messages(device_id: &str, thread_id: &str) {
-    eprintln!("[SMS-DBUS] request_conversation device={} thread={}", device_id, thread_id);
+    debug!("request_conversation device={} thread={}", device_id, thread_id);
+    let Some(tid) = requestable_thread_id(device_id, thread_id) else { return; };
+    if let Err(e) = crate::backend::permission_guard(device_id, "sms") {
+        warn!("request_conversation skipped: {}", e);
+        return;
+    }
     let Some(client) = get_client().await else { return; };
-    let tid = thread_id.parse::<i64>().unwrap_or(0);
     match client.request_conversation(device_id, tid).await {
-        Ok(_) => eprintln!("[SMS-DBUS] request_conversation sent OK"),
-        Err(e) => eprintln!("[SMS-DBUS] request_conversation FAILED: {:?}", e),
+        Ok(_) => debug!("request_conversation sent OK"),
+        Err(e) => warn!("request_conversation failed: {:?}", e),
     }
 }
 
-pub async fn send_sms(device_id: &str, phone_number: &str, message: &str) {
-    eprintln!("[SMS-DBUS] send_sms to={} device={}", phone_number, device_id);
+/// Request up to `count` messages older than `before_timestamp` (ms).
+pub async fn request_conversation_messages_range(device_id: &str, thread_id: &str, before_timestamp: i64, count: usize) {
+    debug!(
+        "request_conversation_range device={} thread={} before={} count={}",
+        device_id, thread_id, before_timestamp, count
+    );
+    let Some(tid) = requestable_thread_id(device_id, thread_id) else { return; };
+    if let Err(e) = crate::backend::permission_guard(device_id, "sms") {
+        warn!("request_conversation_range skipped: {}", e);
+        return;
+    }
     let Some(client) = get_client().await else { return; };
+    match client.request_conversation_range(device_id, tid, before_timestamp, count as i64).await {
+        Ok(_) => debug!("request_conversation_range sent OK"),
+        Err(e) => warn!("request_conversation_range failed: {:?}", e),
+    }
+}
+
+/// Request the newest `count` messages of a thread to catch up on
+/// those after `after` (ms). Phones only page backwards, so the caller
+/// asks for older pages with `request_conversation_messages_range` until
+/// one reaches `after`.
+pub async fn request_conversation_messages_since(device_id: &str, thread_id: &str, after: i64, count: usize) {
+    debug!(
+        "request_conversation_since device={} thread={} after={} count={}",
+        device_id, thread_id, after, count
+    );
+    request_conversation_messages_range(device_id, thread_id, super::utils::now_millis(), count).await;
+}
+
+/// Send an SMS, returning the service's reason if it was rejected.
+pub async fn send_sms(device_id: &str, phone_number: &str, message: &str) -> Result<(), String> {
+    debug!("send_sms to={} device={}", phone_number, device_id);
+    crate::backend::permission_guard(device_id, "sms").map_err(|e| e.to_string())?;
+    let client = connected_client().await.map_err(|e| e.to_string())?;
     match client.send_sms(device_id, phone_number, message).await {
-        Ok(_) => eprintln!("[SMS-DBUS] send_sms OK"),
-        Err(e) => eprintln!("[SMS-DBUS] send_sms FAILED: {:?}", e),
+        Ok(_) => {
+            debug!("send_sms OK");
+            Ok(())
+        }
+        Err(e) => {
+            warn!("send_sms failed: {:?}", e);
+            Err(kdeconnect_dbus_client::error_detail(&e))
+        }
     }
 }
 
+/// `event` flag of a message with several recipients, which phones send
+/// as MMS
+const EVENT_MULTI_TARGET: i64 = 0x2;
+
+/// SIM, kind and subject of a message as the phone sent it. Plain SMS, and
+/// phones or services that leave the fields out, give (None, Text, None).
+fn parse_metadata(raw: &serde_json::Value) -> (Option<i32>, MessageEvent, Option<String>) {
+    // Phones send -1 when they don't know the SIM
+    let sub_id = raw["sub_id"].as_i64()
+        .filter(|id| *id >= 0)
+        .and_then(|id| i32::try_from(id).ok());
+    let subject = raw["subject"].as_str()
+        .map(str::trim)
+        .filter(|subject| !subject.is_empty())
+        .map(str::to_string);
+    let multi_target = raw["event"].as_i64().is_some_and(|flags| flags & EVENT_MULTI_TARGET != 0);
+    let has_attachments = raw["attachments"].as_array().is_some_and(|attachments| !attachments.is_empty());
+    let event = if multi_target || has_attachments || subject.is_some() {
+        MessageEvent::Mms
+    } else {
+        MessageEvent::Text
+    };
+    (sub_id, event, subject)
+}
+
 pub fn parse_sms_messages(messages_json: &str) -> (Vec<Message>, Vec<Conversation>) {
     use std::collections::HashMap;
 
     let sms_data = match serde_json::from_str::<kdeconnect_core::plugins::sms::SmsMessages>(messages_json) {
         Ok(d) => d,
         Err(e) => {
-            eprintln!("[SMS-DBUS] JSON parse FAILED: {:?}", e);
+            warn!("Failed to parse SMS messages JSON: {:?}", e);
             return (vec![], vec![]);
         }
     };
 
-    eprintln!("[SMS-DBUS] parsed {} messages", sms_data.messages.len());
+    debug!("parsed {} messages", sms_data.messages.len());
+
+    // MMS fields are read from the JSON itself, so they survive core
+    // versions that don't model them
+    let raw: serde_json::Value = serde_json::from_str(messages_json).unwrap_or_default();
 
-    let messages: Vec<Message> = sms_data.messages.iter().map(|msg| {
+    let messages: Vec<Message> = sms_data.messages.iter().enumerate().map(|(i, msg)| {
         let address = msg.addresses.first().map(|a| a.address.clone()).unwrap_or_default();
+        let (sub_id, event, subject) = parse_metadata(&raw["messages"][i]);
         Message {
             id: msg.id.to_string(),
             thread_id: msg.thread_id.to_string(),
@@ -85,6 +263,10 @@ pub fn parse_sms_messages(messages_json: &str) -> (Vec<Message>, Vec<Conversatio
             date: msg.date,
             type_: msg.message_type,
             read: msg.read == 1,
+            status: SendStatus::Synced,
+            sub_id,
+            event,
+            subject,
         }
     }).collect();
 
@@ -98,13 +280,44 @@ pub fn parse_sms_messages(messages_json: &str) -> (Vec<Message>, Vec<Conversatio
         let last = msgs.first().unwrap();
         Conversation {
             thread_id,
+            device_id: String::new(),
             phone_number: last.address.clone(),
             last_message: last.body.clone(),
+            last_message_sent: last.is_sent(),
+            last_message_read: last.read,
             timestamp: last.date,
             unread: msgs.iter().any(|m| !m.read),
             contact_name: String::new(),
+            pinned: false,
+            archived: false,
         }
     }).collect();
 
     (messages, conversations)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protocol_thread_id_skips_placeholders() {
+        assert_eq!(protocol_thread_id("42"), Ok(42));
+        assert_eq!(protocol_thread_id("new_1700000000000"), Err(ThreadIdError::Placeholder));
+        assert!(matches!(protocol_thread_id("abc"), Err(ThreadIdError::Invalid(_))));
+        assert!(matches!(protocol_thread_id(""), Err(ThreadIdError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_mms_metadata_parsed_when_present() {
+        let plain = serde_json::json!({"event": 1, "body": "Hi", "sub_id": -1});
+        assert_eq!(parse_metadata(&plain), (None, MessageEvent::Text, None));
+        assert_eq!(parse_metadata(&serde_json::Value::Null), (None, MessageEvent::Text, None));
+
+        let mms = serde_json::json!({"event": 3, "sub_id": 2, "subject": " Trip photos "});
+        assert_eq!(parse_metadata(&mms), (Some(2), MessageEvent::Mms, Some("Trip photos".to_string())));
+
+        let picture = serde_json::json!({"event": 1, "attachments": [{"part_id": 1}]});
+        assert_eq!(parse_metadata(&picture).1, MessageEvent::Mms);
+    }
+}
@@ -0,0 +1,115 @@
+// cosmic-connect-applet/src/plugins/sms/instance.rs
+//! One SMS window per device.
+//!
+//! Each window owns a D-Bus name derived from the devices it shows. A
+//! second launch for the same devices finds the name taken, asks the
+//! running window to come forward, and to open a thread if the launch was
+//! for one, then exits. Name requests are atomic,
+//! so two quick launches can't both win, and the bus releases the name
+//! when the window's process ends, even if it crashes.
+
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use zbus::Connection;
+use zbus::fdo::{RequestNameFlags, RequestNameReply};
+
+const NAME_PREFIX: &str = "com.system76.CosmicConnectSms.Window";
+const OBJECT_PATH: &str = "/com/system76/CosmicConnectSms/Window";
+const INTERFACE: &str = "com.system76.CosmicConnectSms.Window";
+
+/// Longest device key used in a bus name (the whole name is capped at 255)
+const MAX_KEY_LEN: usize = 200;
+
+lazy_static::lazy_static! {
+    /// Requests from later launches to raise this window, with the thread
+    /// they asked for
+    static ref ACTIVATIONS: std::sync::Mutex<Option<mpsc::UnboundedReceiver<Option<String>>>> = std::sync::Mutex::new(None);
+}
+
+/// D-Bus name owned by the window for `device_key` (the device id
+/// argument: an id, a comma-separated list or `--all`)
+pub fn bus_name(device_key: &str) -> String {
+    let element: String = device_key.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+        .take(MAX_KEY_LEN)
+        .collect();
+    // Name elements can't start with a digit
+    format!("{}.d{}", NAME_PREFIX, element)
+}
+
+struct WindowInstance {
+    activations: mpsc::UnboundedSender<Option<String>>,
+}
+
+#[zbus::interface(name = "com.system76.CosmicConnectSms.Window")]
+impl WindowInstance {
+    /// Bring the window to the front
+    fn activate(&self) {
+        let _ = self.activations.send(None);
+    }
+
+    /// Bring the window to the front showing a thread
+    fn open_thread(&self, thread_id: String) {
+        let _ = self.activations.send(Some(thread_id));
+    }
+}
+
+/// Claim the window for `device_key`. Returns the connection holding the
+/// name, which must be kept alive while the window is open, or None after
+/// asking the window that already holds it to come forward, showing
+/// `thread_id` when given.
+pub async fn claim(device_key: &str, thread_id: Option<&str>) -> zbus::Result<Option<Connection>> {
+    let name = bus_name(device_key);
+    let (sender, receiver) = mpsc::unbounded_channel();
+
+    let connection = zbus::connection::Builder::session()?
+        .serve_at(OBJECT_PATH, WindowInstance { activations: sender })?
+        .build()
+        .await?;
+
+    match connection.request_name_with_flags(name.as_str(), RequestNameFlags::DoNotQueue.into()).await {
+        Ok(RequestNameReply::PrimaryOwner | RequestNameReply::AlreadyOwner) => {
+            *ACTIVATIONS.lock().unwrap() = Some(receiver);
+            Ok(Some(connection))
+        }
+        Ok(_) | Err(zbus::Error::NameTaken) => {
+            match thread_id {
+                Some(thread_id) => {
+                    connection.call_method(Some(name.as_str()), OBJECT_PATH, Some(INTERFACE), "OpenThread", &(thread_id,)).await?;
+                }
+                None => {
+                    connection.call_method(Some(name.as_str()), OBJECT_PATH, Some(INTERFACE), "Activate", &()).await?;
+                }
+            }
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Activation requests for this window, with the thread to show if one was
+/// asked for. Empty if `claim` didn't succeed.
+pub fn activations() -> impl futures::Stream<Item = Option<String>> {
+    use futures::StreamExt;
+
+    match ACTIVATIONS.lock().unwrap().take() {
+        Some(receiver) => UnboundedReceiverStream::new(receiver).boxed(),
+        None => futures::stream::pending().boxed(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bus_name_is_valid_per_device() {
+        assert_eq!(bus_name("a1b2c3d4_e5"), "com.system76.CosmicConnectSms.Window.da1b2c3d4_e5");
+        assert_eq!(bus_name("--all"), "com.system76.CosmicConnectSms.Window.d__all");
+        assert_eq!(bus_name("1a,2b"), "com.system76.CosmicConnectSms.Window.d1a_2b");
+        assert_ne!(bus_name("phone"), bus_name("tablet"));
+
+        let long = "x".repeat(1000);
+        assert!(bus_name(&long).len() <= 255);
+    }
+}
@@ -0,0 +1,112 @@
+// cosmic-connect-applet/src/plugins/sms/layout.rs
+//! Panel layout of the SMS window.
+//!
+//! Windows narrower than SINGLE_PANEL_BELOW show either the conversation
+//! list or the open thread, with a back button. Wider windows show both,
+//! split by a divider that can be dragged to resize the list. The list
+//! width is stored in ~/.config/cosmic-connect/sms_layout.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// Window width, in pixels, below which only one panel is shown
+pub const SINGLE_PANEL_BELOW: f32 = 640.0;
+
+pub const DEFAULT_LIST_WIDTH: f32 = 300.0;
+
+/// Narrowest conversation list that still fits a name and preview
+pub const MIN_LIST_WIDTH: f32 = 220.0;
+
+/// Narrowest thread panel left beside the list
+pub const MIN_THREAD_WIDTH: f32 = 320.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmsLayoutConfig {
+    /// Width of the conversation list in the two-panel layout
+    pub list_width: f32,
+}
+
+impl Default for SmsLayoutConfig {
+    fn default() -> Self {
+        Self { list_width: DEFAULT_LIST_WIDTH }
+    }
+}
+
+impl SmsLayoutConfig {
+    /// Load config from file
+    pub fn load() -> io::Result<Self> {
+        let path = Self::get_config_path();
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        Ok(Self::parse(&fs::read_to_string(&path)?))
+    }
+
+    fn parse(content: &str) -> Self {
+        let mut config = Self::default();
+        for line in content.lines() {
+            if let Some((key, value)) = line.trim().split_once('=') {
+                if key.trim() == "listWidth" {
+                    config.list_width = value.trim().parse::<f32>()
+                        .ok()
+                        .filter(|width| width.is_finite())
+                        .map_or(DEFAULT_LIST_WIDTH, |width| width.max(MIN_LIST_WIDTH));
+                }
+            }
+        }
+        config
+    }
+
+    /// Save config to file
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::get_config_path();
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = fs::File::create(&path)?;
+        writeln!(file, "[General]")?;
+        writeln!(file, "listWidth={}", self.list_width.round())?;
+
+        Ok(())
+    }
+
+    /// Width of the list in a window `window_width` wide, leaving the
+    /// thread at least MIN_THREAD_WIDTH
+    pub fn list_width_in(&self, window_width: f32) -> f32 {
+        self.list_width.min(window_width - MIN_THREAD_WIDTH).max(MIN_LIST_WIDTH)
+    }
+
+    fn get_config_path() -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+        PathBuf::from(format!("{}/.config/cosmic-connect/sms_layout", home))
+    }
+}
+
+/// Whether a window `window_width` wide shows one panel at a time
+pub fn is_single_panel(window_width: f32) -> bool {
+    window_width < SINGLE_PANEL_BELOW
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_width_fits_the_window() {
+        assert!(is_single_panel(480.0));
+        assert!(!is_single_panel(SINGLE_PANEL_BELOW));
+
+        let config = SmsLayoutConfig::parse("[General]\nlistWidth=500\n");
+        assert_eq!(config.list_width_in(1600.0), 500.0);
+        assert_eq!(config.list_width_in(700.0), 700.0 - MIN_THREAD_WIDTH);
+        assert_eq!(SmsLayoutConfig { list_width: 10.0 }.list_width_in(1600.0), MIN_LIST_WIDTH);
+
+        assert_eq!(SmsLayoutConfig::parse("listWidth=100").list_width, MIN_LIST_WIDTH);
+        assert_eq!(SmsLayoutConfig::parse("listWidth=NaN"), SmsLayoutConfig::default());
+    }
+}
@@ -2,20 +2,33 @@
 
 // #[allow(dead_code)] = Placeholder for code that will be used once features are fully integrated
 
+mod appearance;
+mod backend;
+mod cache;
 mod emoji;
+mod layout;
 mod messages;
+mod retention;
+mod storage;
+mod undo;
 mod utils;
 mod views;
 
 pub mod dbus;
+pub mod instance;
 pub mod models;
 pub mod app;
+pub mod auto_open;
+pub mod unread;
 
 pub use app::SmsWindow;
+pub use retention::RetentionConfig;
+pub use storage::{load_contacts, QuickRepliesConfig, SendConfirmConfig, MAX_QUICK_REPLIES};
+pub use utils::{find_contact_name, validate_sms};
 
-/// Run the SMS window application
+/// Run the SMS window application, opening `thread_id` once it is known
 #[allow(dead_code)]
-pub fn run(device_id: String, device_name: String) -> cosmic::iced::Result {
+pub fn run(device_id: String, device_name: String, thread_id: Option<String>) -> cosmic::iced::Result {
     let settings = cosmic::app::Settings::default();
-    cosmic::app::run::<SmsWindow>(settings, (device_id, device_name))
+    cosmic::app::run::<SmsWindow>(settings, (device_id, device_name, thread_id))
 }
\ No newline at end of file
@@ -11,12 +11,22 @@ use std::collections::HashMap;
 #[derive(Debug, Clone)]
 pub struct Conversation {
     pub thread_id: String,
+    /// Device the conversation lives on, empty if unknown
+    pub device_id: String,
     pub contact_name: String,
     pub phone_number: String,
     pub last_message: String,
+    /// The last message was sent by the user rather than received
+    pub last_message_sent: bool,
+    /// The phone has marked the last message read
+    pub last_message_read: bool,
     pub timestamp: i64,
     #[allow(dead_code)] // Used for future read/unread tracking
     pub unread: bool,
+    /// Shown above all other conversations
+    pub pinned: bool,
+    /// Moved to the collapsible "Archived" section
+    pub archived: bool,
 }
 
 /// Represents an individual SMS message.
@@ -31,6 +41,34 @@ pub struct Message {
     pub type_: i32,
     #[allow(dead_code)] // Used for future read receipt tracking
     pub read: bool,
+    pub status: SendStatus,
+    /// SIM the message went through on dual-SIM phones, when the phone says
+    pub sub_id: Option<i32>,
+    pub event: MessageEvent,
+    /// MMS subject line
+    pub subject: Option<String>,
+}
+
+/// Kind of message, from the protocol's `event` flags and MMS fields
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MessageEvent {
+    /// Plain SMS
+    #[default]
+    Text,
+    /// MMS: a group message, or one with a subject or attachments
+    Mms,
+}
+
+/// Delivery state of a message sent from this window
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum SendStatus {
+    /// Synced from the phone
+    #[default]
+    Synced,
+    /// Handed to the service, waiting for the phone's copy
+    Sending,
+    /// Rejected by the service or the phone, with the reason
+    Failed(String),
 }
 
 impl Message {
@@ -46,9 +84,23 @@ impl Message {
 pub enum ProtocolEvent {
     MessageReceived(Message),
     ConversationsReceived(Vec<Conversation>),
+    /// The service's event stream was lost; reconnecting with backoff
+    Disconnected,
+    /// Events are arriving again after `Disconnected`
+    Reconnected,
     #[allow(dead_code)] // Used for error handling in event processing
     Error(String),
 }
 
+/// Progress of reading the contacts synced from the phone
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ContactsState {
+    #[default]
+    Loading,
+    Loaded,
+    /// Reading them failed, with the reason
+    Error(String),
+}
+
 /// Type alias for contacts map (phone_number -> contact_name).
 pub type ContactsMap = HashMap<String, String>;
\ No newline at end of file
@@ -0,0 +1,213 @@
+// cosmic-connect-applet/src/plugins/sms/retention.rs
+//! Limits on how much SMS history is kept.
+//!
+//! Stored in ~/.config/cosmic-connect/sms_retention and edited in the
+//! settings window. Every limit is off by default. SMS windows prune their
+//! conversations, loaded messages and the on-disk caches when they start
+//! and every PRUNE_INTERVAL after that. The oldest go first, and the open
+//! thread is never dropped.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use super::models::{Conversation, Message};
+
+/// How often an open SMS window prunes
+pub const PRUNE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetentionConfig {
+    /// Most conversations kept, newest first
+    pub max_conversations: Option<usize>,
+    /// Most messages kept per thread, newest first
+    pub max_messages_per_thread: Option<usize>,
+    /// Conversations and messages older than this many days are dropped
+    pub max_age_days: Option<u32>,
+}
+
+impl RetentionConfig {
+    /// Load config from file
+    pub fn load() -> io::Result<Self> {
+        let path = Self::get_config_path();
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        Ok(Self::parse(&fs::read_to_string(&path)?))
+    }
+
+    fn parse(content: &str) -> Self {
+        let mut config = Self::default();
+        for line in content.lines() {
+            if let Some((key, value)) = line.trim().split_once('=') {
+                // 0 or anything unparsable means unlimited
+                let value = value.trim().parse::<u32>().ok().filter(|&n| n > 0);
+                match key.trim() {
+                    "maxConversations" => config.max_conversations = value.map(|n| n as usize),
+                    "maxMessagesPerThread" => config.max_messages_per_thread = value.map(|n| n as usize),
+                    "maxAgeDays" => config.max_age_days = value,
+                    _ => {}
+                }
+            }
+        }
+        config
+    }
+
+    /// Save config to file
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::get_config_path();
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = fs::File::create(&path)?;
+        writeln!(file, "[General]")?;
+        writeln!(file, "maxConversations={}", self.max_conversations.unwrap_or(0))?;
+        writeln!(file, "maxMessagesPerThread={}", self.max_messages_per_thread.unwrap_or(0))?;
+        writeln!(file, "maxAgeDays={}", self.max_age_days.unwrap_or(0))?;
+
+        Ok(())
+    }
+
+    /// Whether every limit is off
+    pub fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Oldest timestamp kept at `now`, in ms since the epoch
+    fn cutoff(&self, now: i64) -> Option<i64> {
+        self.max_age_days.map(|days| now - i64::from(days) * DAY_MS)
+    }
+
+    /// Drop conversations past the age limit, then the oldest beyond the
+    /// count limit, keeping `keep` (the open thread) whatever its age.
+    /// Returns the dropped thread ids.
+    pub fn prune_conversations(&self, conversations: &mut Vec<Conversation>, keep: Option<&str>, now: i64) -> Vec<String> {
+        let cutoff = self.cutoff(now);
+        let mut newest_first: Vec<(i64, String)> = conversations.iter()
+            .map(|c| (c.timestamp, c.thread_id.clone()))
+            .collect();
+        newest_first.sort_by(|a, b| b.0.cmp(&a.0));
+
+        let mut kept = 0;
+        let mut dropped = Vec::new();
+        for (timestamp, thread_id) in newest_first {
+            let is_kept = keep == Some(thread_id.as_str());
+            let too_old = cutoff.is_some_and(|cutoff| timestamp < cutoff);
+            let too_many = self.max_conversations.is_some_and(|max| kept >= max);
+            if !is_kept && (too_old || too_many) {
+                dropped.push(thread_id);
+            } else {
+                kept += 1;
+            }
+        }
+        conversations.retain(|c| !dropped.contains(&c.thread_id));
+        dropped
+    }
+
+    /// Drop messages of one thread, oldest first, past the age and count
+    /// limits. `messages` is sorted oldest first.
+    pub fn prune_thread(&self, messages: &mut Vec<Message>, now: i64) {
+        if let Some(cutoff) = self.cutoff(now) {
+            messages.retain(|m| m.date >= cutoff);
+        }
+        if let Some(max) = self.max_messages_per_thread {
+            let excess = messages.len().saturating_sub(max);
+            messages.drain(..excess);
+        }
+    }
+
+    /// Prune messages kept per thread, dropping threads left empty, except
+    /// `keep`
+    pub fn prune_threads(&self, threads: &mut HashMap<String, Vec<Message>>, keep: Option<&str>, now: i64) {
+        threads.retain(|thread_id, messages| {
+            if keep == Some(thread_id.as_str()) {
+                return true;
+            }
+            self.prune_thread(messages, now);
+            !messages.is_empty()
+        });
+    }
+
+    fn get_config_path() -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+        PathBuf::from(format!("{}/.config/cosmic-connect/sms_retention", home))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::sms::models::{MessageEvent, SendStatus};
+
+    fn conversation(thread_id: &str, timestamp: i64) -> Conversation {
+        Conversation {
+            thread_id: thread_id.to_string(),
+            device_id: String::new(),
+            contact_name: String::new(),
+            phone_number: "5551234567".to_string(),
+            last_message: String::new(),
+            last_message_sent: false,
+            last_message_read: false,
+            timestamp,
+            unread: false,
+            pinned: false,
+            archived: false,
+        }
+    }
+
+    fn message(thread_id: &str, date: i64) -> Message {
+        Message {
+            id: date.to_string(),
+            thread_id: thread_id.to_string(),
+            body: String::new(),
+            address: "5551234567".to_string(),
+            date,
+            type_: 1,
+            read: true,
+            status: SendStatus::Synced,
+            sub_id: None,
+            event: MessageEvent::Text,
+            subject: None,
+        }
+    }
+
+    #[test]
+    fn test_prunes_oldest_first_and_keeps_open_thread() {
+        assert!(RetentionConfig::parse("[General]\nmaxConversations=0\nmaxAgeDays=x\n").is_unlimited());
+        let config = RetentionConfig::parse("[General]\nmaxConversations=2\nmaxMessagesPerThread=3\nmaxAgeDays=30\n");
+        assert_eq!(config.max_age_days, Some(30));
+
+        let now = 100 * DAY_MS;
+        let mut conversations = vec![
+            conversation("old", now - 40 * DAY_MS),
+            conversation("a", now - 1),
+            conversation("b", now - 2),
+            conversation("c", now - 3),
+        ];
+        let dropped = config.prune_conversations(&mut conversations, Some("old"), now);
+        assert_eq!(dropped, vec!["c".to_string()]);
+        let kept: Vec<&str> = conversations.iter().map(|c| c.thread_id.as_str()).collect();
+        assert_eq!(kept, vec!["old", "a", "b"]);
+
+        let mut threads: HashMap<String, Vec<Message>> = HashMap::new();
+        threads.insert("a".to_string(), (1..=5).map(|i| message("a", now - 10 + i)).collect());
+        threads.insert("old".to_string(), vec![message("old", now - 40 * DAY_MS)]);
+        threads.insert("gone".to_string(), vec![message("gone", now - 40 * DAY_MS)]);
+        config.prune_threads(&mut threads, Some("old"), now);
+        assert_eq!(threads["a"].iter().map(|m| m.date).collect::<Vec<_>>(), vec![now - 7, now - 6, now - 5]);
+        assert_eq!(threads["old"].len(), 1);
+        assert!(!threads.contains_key("gone"));
+
+        // Unlimited by default
+        assert!(RetentionConfig::default().prune_conversations(&mut conversations, None, now).is_empty());
+        assert_eq!(conversations.len(), 3);
+    }
+}
@@ -0,0 +1,644 @@
+// cosmic-connect-applet/src/plugins/sms/storage.rs
+//! Persistent state for the SMS window.
+//!
+//! Per-device state is stored under ~/.config/cosmic-connect/{device_id}/
+//! using the same key=value section format as the KDE Connect plugin
+//! configs. State that must be shared between windows, like muted threads,
+//! lives directly in ~/.config/cosmic-connect/.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// Pinned, archived and deleted conversation threads for a device
+#[derive(Debug, Clone, Default)]
+pub struct ThreadStateConfig {
+    /// Thread ids shown above all other conversations
+    pub pinned: HashSet<String>,
+    /// Thread ids moved to the collapsible "Archived" section
+    pub archived: HashSet<String>,
+    /// Deleted thread ids and the timestamp of their newest message at the
+    /// time. Hidden until a newer message arrives.
+    pub deleted: HashMap<String, i64>,
+}
+
+impl ThreadStateConfig {
+    /// Load thread state from file
+    pub fn load(device_id: &str) -> io::Result<Self> {
+        let config_path = Self::get_config_path(device_id);
+
+        if !config_path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&config_path)?;
+
+        let mut config = Self::default();
+        let mut section = String::new();
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with('[') && line.ends_with(']') {
+                section = line[1..line.len()-1].to_string();
+                continue;
+            }
+
+            if let Some((key, value)) = line.split_once('=') {
+                let thread_id = key.trim().to_string();
+                if section == "Deleted" {
+                    if let Ok(timestamp) = value.trim().parse::<i64>() {
+                        config.deleted.insert(thread_id, timestamp);
+                    }
+                    continue;
+                }
+                if !value.trim().parse::<bool>().unwrap_or(false) {
+                    continue;
+                }
+
+                match section.as_str() {
+                    "Pinned" => {
+                        config.pinned.insert(thread_id);
+                    }
+                    "Archived" => {
+                        config.archived.insert(thread_id);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Save thread state to file
+    pub fn save(&self, device_id: &str) -> io::Result<()> {
+        let config_path = Self::get_config_path(device_id);
+
+        // Ensure directory exists
+        if let Some(parent) = config_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = fs::File::create(&config_path)?;
+
+        writeln!(file, "[Pinned]")?;
+        for thread_id in &self.pinned {
+            writeln!(file, "{}=true", thread_id)?;
+        }
+        writeln!(file)?;
+
+        writeln!(file, "[Archived]")?;
+        for thread_id in &self.archived {
+            writeln!(file, "{}=true", thread_id)?;
+        }
+        writeln!(file)?;
+
+        writeln!(file, "[Deleted]")?;
+        for (thread_id, timestamp) in &self.deleted {
+            writeln!(file, "{}={}", thread_id, timestamp)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn is_pinned(&self, thread_id: &str) -> bool {
+        self.pinned.contains(thread_id)
+    }
+
+    pub fn is_archived(&self, thread_id: &str) -> bool {
+        self.archived.contains(thread_id)
+    }
+
+    /// Whether a thread was deleted and has had no message since
+    pub fn is_deleted(&self, thread_id: &str, timestamp: i64) -> bool {
+        self.deleted.get(thread_id).is_some_and(|&deleted_at| timestamp <= deleted_at)
+    }
+
+    /// Hide a thread whose newest message is at `timestamp`, dropping its
+    /// pinned and archived flags
+    pub fn delete(&mut self, thread_id: &str, timestamp: i64) {
+        self.pinned.remove(thread_id);
+        self.archived.remove(thread_id);
+        self.deleted.insert(thread_id.to_string(), timestamp);
+    }
+
+    /// Toggle the pinned flag, returning the new state
+    pub fn toggle_pinned(&mut self, thread_id: &str) -> bool {
+        if !self.pinned.remove(thread_id) {
+            self.pinned.insert(thread_id.to_string());
+            return true;
+        }
+        false
+    }
+
+    /// Toggle the archived flag, returning the new state
+    pub fn toggle_archived(&mut self, thread_id: &str) -> bool {
+        if !self.archived.remove(thread_id) {
+            self.archived.insert(thread_id.to_string());
+            return true;
+        }
+        false
+    }
+
+    /// Get the state file path for a device
+    fn get_config_path(device_id: &str) -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+        PathBuf::from(format!("{}/.config/cosmic-connect/{}/sms_threads", home, device_id))
+    }
+}
+
+/// Window state saved on close and restored on the next launch
+#[derive(Debug, Clone, Default)]
+pub struct SessionState {
+    /// Thread that was open when the window closed
+    pub selected_thread: Option<String>,
+    /// Unsent message text per thread id
+    pub drafts: HashMap<String, String>,
+}
+
+impl SessionState {
+    /// Load session state from file
+    pub fn load(device_id: &str) -> io::Result<Self> {
+        let config_path = Self::get_config_path(device_id);
+
+        if !config_path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&config_path)?;
+
+        let mut state = Self::default();
+        let mut section = String::new();
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with('[') && line.ends_with(']') {
+                section = line[1..line.len()-1].to_string();
+                continue;
+            }
+
+            if let Some((key, value)) = line.split_once('=') {
+                let key = key.trim();
+                match (section.as_str(), key) {
+                    ("General", "selectedThread") if !value.is_empty() => {
+                        state.selected_thread = Some(value.to_string());
+                    }
+                    ("Drafts", thread_id) => {
+                        state.drafts.insert(thread_id.to_string(), unescape(value));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(state)
+    }
+
+    /// Save session state to file
+    pub fn save(&self, device_id: &str) -> io::Result<()> {
+        let config_path = Self::get_config_path(device_id);
+
+        // Ensure directory exists
+        if let Some(parent) = config_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = fs::File::create(&config_path)?;
+
+        writeln!(file, "[General]")?;
+        if let Some(thread_id) = &self.selected_thread {
+            writeln!(file, "selectedThread={}", thread_id)?;
+        }
+        writeln!(file)?;
+
+        writeln!(file, "[Drafts]")?;
+        for (thread_id, draft) in &self.drafts {
+            writeln!(file, "{}={}", thread_id, escape(draft))?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the state file path for a device
+    fn get_config_path(device_id: &str) -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+        PathBuf::from(format!("{}/.config/cosmic-connect/{}/sms_session", home, device_id))
+    }
+}
+
+/// Conversations whose notifications are silenced.
+///
+/// Keyed by device and thread id, since thread ids are only unique per
+/// phone. Shared by every SMS window, single-device or unified.
+#[derive(Debug, Clone, Default)]
+pub struct MutedThreads {
+    threads: HashSet<(String, String)>,
+}
+
+impl MutedThreads {
+    /// Load muted threads from file
+    pub fn load() -> io::Result<Self> {
+        let config_path = Self::get_config_path();
+
+        if !config_path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&config_path)?;
+
+        let mut muted = Self::default();
+        let mut section = String::new();
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with('[') && line.ends_with(']') {
+                section = line[1..line.len()-1].to_string();
+                continue;
+            }
+
+            if section != "Muted" {
+                continue;
+            }
+            if let Some((key, value)) = line.split_once('=') {
+                if !value.trim().parse::<bool>().unwrap_or(false) {
+                    continue;
+                }
+                // Device ids never contain '/', thread ids are numeric
+                if let Some((device_id, thread_id)) = key.trim().split_once('/') {
+                    muted.threads.insert((device_id.to_string(), thread_id.to_string()));
+                }
+            }
+        }
+
+        Ok(muted)
+    }
+
+    /// Save muted threads to file
+    pub fn save(&self) -> io::Result<()> {
+        let config_path = Self::get_config_path();
+
+        // Ensure directory exists
+        if let Some(parent) = config_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = fs::File::create(&config_path)?;
+
+        writeln!(file, "[Muted]")?;
+        for (device_id, thread_id) in &self.threads {
+            writeln!(file, "{}/{}=true", device_id, thread_id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Whether notifications for the thread should be skipped
+    pub fn is_muted(&self, device_id: &str, thread_id: &str) -> bool {
+        self.threads.contains(&(device_id.to_string(), thread_id.to_string()))
+    }
+
+    /// Unmute a thread, returning whether it was muted
+    pub fn remove(&mut self, device_id: &str, thread_id: &str) -> bool {
+        self.threads.remove(&(device_id.to_string(), thread_id.to_string()))
+    }
+
+    /// Toggle the muted flag, returning the new state
+    pub fn toggle(&mut self, device_id: &str, thread_id: &str) -> bool {
+        let key = (device_id.to_string(), thread_id.to_string());
+        if !self.threads.remove(&key) {
+            self.threads.insert(key);
+            return true;
+        }
+        false
+    }
+
+    /// Get the state file path
+    fn get_config_path() -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+        PathBuf::from(format!("{}/.config/cosmic-connect/sms_muted", home))
+    }
+}
+
+/// Directory of contacts synced from a device, one vCard per contact, laid
+/// out like KDE Connect's ~/.local/share/kdeconnect/{device_id}/kdeconnect_contacts
+pub fn contacts_dir(device_id: &str) -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+    PathBuf::from(format!("{}/.local/share/kdeconnect/{}/kdeconnect_contacts", home, device_id))
+}
+
+/// Contacts synced from a device, as phone number -> name. Read from the
+/// vCards in `contacts_dir`; a device that never synced has none.
+pub fn load_contacts(device_id: &str) -> io::Result<HashMap<String, String>> {
+    let dir = contacts_dir(device_id);
+    if !dir.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let mut contacts = HashMap::new();
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "vcf") {
+            continue;
+        }
+        let Ok(content) = fs::read_to_string(&path) else { continue; };
+        let (name, phones) = super::utils::parse_vcard(&content);
+        let Some(name) = name.filter(|n| !n.is_empty()) else { continue; };
+        for phone in phones {
+            contacts.insert(phone, name.clone());
+        }
+    }
+    Ok(contacts)
+}
+
+/// Add a contact for `phone_number` to a device's synced contacts, replacing
+/// one previously added here for the same number. Returns the vCard path.
+pub fn save_contact(device_id: &str, name: &str, phone_number: &str) -> io::Result<PathBuf> {
+    let digits = super::utils::normalize_phone_number(phone_number);
+    if digits.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "phone number has no digits"));
+    }
+
+    let dir = contacts_dir(device_id);
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("cosmic-connect-{}.vcf", digits));
+    fs::write(&path, super::utils::contact_vcard(name, phone_number))?;
+    Ok(path)
+}
+
+/// Replies offered when nothing has been configured yet
+const DEFAULT_QUICK_REPLIES: [&str; 5] = [
+    "On my way",
+    "Can't talk now, I'll call you later",
+    "Running late",
+    "Sounds good",
+    "Thanks!",
+];
+
+/// Most quick replies kept, so the chips stay on a couple of rows
+pub const MAX_QUICK_REPLIES: usize = 12;
+
+/// Canned replies shown as chips above the SMS input.
+///
+/// Shared by every SMS window and edited in the settings window.
+#[derive(Debug, Clone)]
+pub struct QuickRepliesConfig {
+    replies: Vec<String>,
+    /// Tapping a reply sends it instead of filling the input
+    pub send_immediately: bool,
+}
+
+impl Default for QuickRepliesConfig {
+    fn default() -> Self {
+        Self {
+            replies: DEFAULT_QUICK_REPLIES.iter().map(|r| r.to_string()).collect(),
+            send_immediately: false,
+        }
+    }
+}
+
+impl QuickRepliesConfig {
+    /// Load quick replies from file
+    pub fn load() -> io::Result<Self> {
+        let config_path = Self::get_config_path();
+
+        if !config_path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&config_path)?;
+
+        let mut config = Self { replies: Vec::new(), send_immediately: false };
+        let mut section = String::new();
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with('[') && line.ends_with(']') {
+                section = line[1..line.len()-1].to_string();
+                continue;
+            }
+
+            if let Some((key, value)) = line.split_once('=') {
+                match (section.as_str(), key.trim()) {
+                    ("General", "sendImmediately") => {
+                        config.send_immediately = value.trim().parse().unwrap_or(false);
+                    }
+                    // Keys are positions; file order is kept
+                    ("Replies", _) => {
+                        config.add(&unescape(value));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Save quick replies to file
+    pub fn save(&self) -> io::Result<()> {
+        let config_path = Self::get_config_path();
+
+        // Ensure directory exists
+        if let Some(parent) = config_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = fs::File::create(&config_path)?;
+
+        writeln!(file, "[General]")?;
+        writeln!(file, "sendImmediately={}", self.send_immediately)?;
+        writeln!(file)?;
+        writeln!(file, "[Replies]")?;
+        for (i, reply) in self.replies.iter().enumerate() {
+            writeln!(file, "{}={}", i, escape(reply))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn replies(&self) -> &[String] {
+        &self.replies
+    }
+
+    /// Add a reply at the end. Blank, duplicate and excess replies are
+    /// ignored; returns whether it was added.
+    pub fn add(&mut self, reply: &str) -> bool {
+        let reply = reply.trim();
+        if reply.is_empty() || self.replies.len() >= MAX_QUICK_REPLIES || self.replies.iter().any(|r| r == reply) {
+            return false;
+        }
+        self.replies.push(reply.to_string());
+        true
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<String> {
+        (index < self.replies.len()).then(|| self.replies.remove(index))
+    }
+
+    /// Get the config file path
+    fn get_config_path() -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+        PathBuf::from(format!("{}/.config/cosmic-connect/sms_quick_replies", home))
+    }
+}
+
+/// Segments a message may have before sending asks first, by default
+pub const DEFAULT_CONFIRM_SEGMENTS: usize = 3;
+
+/// Asking before sending messages that split into many SMS segments, each
+/// of which the carrier may charge for.
+///
+/// Shared by every SMS window and edited in the settings window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SendConfirmConfig {
+    pub enabled: bool,
+    /// Ask when a message has more segments than this
+    pub max_segments: usize,
+}
+
+impl Default for SendConfirmConfig {
+    fn default() -> Self {
+        Self { enabled: true, max_segments: DEFAULT_CONFIRM_SEGMENTS }
+    }
+}
+
+impl SendConfirmConfig {
+    /// Load the confirmation settings from file
+    pub fn load() -> io::Result<Self> {
+        let config_path = Self::get_config_path();
+
+        if !config_path.exists() {
+            return Ok(Self::default());
+        }
+
+        Ok(Self::parse(&fs::read_to_string(&config_path)?))
+    }
+
+    fn parse(content: &str) -> Self {
+        let mut config = Self::default();
+        for line in content.lines() {
+            if let Some((key, value)) = line.trim().split_once('=') {
+                match key.trim() {
+                    "confirmLongMessages" => config.enabled = value.trim().parse().unwrap_or(true),
+                    "maxSegments" => {
+                        if let Ok(max) = value.trim().parse::<usize>() {
+                            config.max_segments = max.max(1);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        config
+    }
+
+    /// Save the confirmation settings to file
+    pub fn save(&self) -> io::Result<()> {
+        let config_path = Self::get_config_path();
+
+        if let Some(parent) = config_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = fs::File::create(&config_path)?;
+        writeln!(file, "[General]")?;
+        writeln!(file, "confirmLongMessages={}", self.enabled)?;
+        writeln!(file, "maxSegments={}", self.max_segments)?;
+
+        Ok(())
+    }
+
+    /// Whether a message of `segments` segments needs confirming
+    pub fn should_confirm(&self, segments: usize) -> bool {
+        self.enabled && segments > self.max_segments
+    }
+
+    /// Get the config file path
+    fn get_config_path() -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+        PathBuf::from(format!("{}/.config/cosmic-connect/sms_send_confirm", home))
+    }
+}
+
+/// Keep multi-line drafts on a single key=value line
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quick_replies_add_and_remove() {
+        let mut config = QuickRepliesConfig::default();
+        assert_eq!(config.replies().len(), DEFAULT_QUICK_REPLIES.len());
+
+        assert!(!config.add("On my way"));
+        assert!(!config.add("   "));
+        assert!(config.add("  Call you back in 5  "));
+        assert_eq!(config.replies().last().map(String::as_str), Some("Call you back in 5"));
+
+        while config.replies().len() < MAX_QUICK_REPLIES {
+            let n = config.replies().len();
+            assert!(config.add(&format!("Reply {}", n)));
+        }
+        assert!(!config.add("One too many"));
+
+        assert_eq!(config.remove(0).as_deref(), Some("On my way"));
+        assert_eq!(config.remove(MAX_QUICK_REPLIES), None);
+    }
+
+    #[test]
+    fn test_delete_thread_clears_metadata() {
+        let mut state = ThreadStateConfig::default();
+        state.toggle_pinned("42");
+        state.toggle_archived("42");
+        let mut muted = MutedThreads::default();
+        muted.toggle("phone", "42");
+
+        state.delete("42", 1_000);
+        assert!(muted.remove("phone", "42"));
+        assert!(!state.is_pinned("42") && !state.is_archived("42"));
+        assert!(!muted.is_muted("phone", "42"));
+
+        // Hidden through re-syncs until the phone has something newer
+        assert!(state.is_deleted("42", 1_000));
+        assert!(!state.is_deleted("42", 1_001));
+        assert!(!state.is_deleted("7", 0));
+    }
+}
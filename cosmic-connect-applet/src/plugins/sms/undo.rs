@@ -0,0 +1,104 @@
+// cosmic-connect-applet/src/plugins/sms/undo.rs
+//! Undo and redo for the message composer.
+//!
+//! A step is kept at word boundaries and before the text is replaced or
+//! cleared (sending, pasting, picking a quick reply), so one undo takes
+//! back about a word rather than a single character.
+
+use std::collections::VecDeque;
+
+/// Undo steps kept per conversation
+pub const MAX_UNDO_STEPS: usize = 20;
+
+#[derive(Debug, Clone, Default)]
+pub struct InputHistory {
+    undo: VecDeque<String>,
+    redo: Vec<String>,
+}
+
+impl InputHistory {
+    /// Note an edit from `before` to `after`, keeping `before` as an undo
+    /// step if the edit is a significant one
+    pub fn record(&mut self, before: &str, after: &str) {
+        if before == after {
+            return;
+        }
+        self.redo.clear();
+        if is_significant(before, after) {
+            self.push(before.to_string());
+        }
+    }
+
+    /// The text before the last step, given the current text
+    pub fn undo(&mut self, current: &str) -> Option<String> {
+        let previous = loop {
+            let text = self.undo.pop_back()?;
+            if text != current {
+                break text;
+            }
+        };
+        self.redo.push(current.to_string());
+        Some(previous)
+    }
+
+    /// Take back the last undo
+    pub fn redo(&mut self, current: &str) -> Option<String> {
+        let next = self.redo.pop()?;
+        self.push(current.to_string());
+        Some(next)
+    }
+
+    fn push(&mut self, text: String) {
+        if self.undo.back() == Some(&text) {
+            return;
+        }
+        if self.undo.len() == MAX_UNDO_STEPS {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(text);
+    }
+}
+
+/// Starting to type, finishing a word, pasting, cutting and clearing are
+/// worth an undo step; adding or deleting one character inside a word is not
+fn is_significant(before: &str, after: &str) -> bool {
+    before.is_empty()
+        || before.chars().count().abs_diff(after.chars().count()) != 1
+        || after.ends_with(char::is_whitespace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_steps_by_word_and_clear() {
+        let mut history = InputHistory::default();
+        let mut text = String::new();
+        for c in "On my way".chars() {
+            let next = format!("{}{}", text, c);
+            history.record(&text, &next);
+            text = next;
+        }
+        // Sending clears the composer
+        history.record(&text, "");
+
+        assert_eq!(history.undo("").as_deref(), Some("On my way"));
+        assert_eq!(history.undo("On my way").as_deref(), Some("On my "));
+        assert_eq!(history.undo("On my ").as_deref(), Some("On "));
+        assert_eq!(history.redo("On ").as_deref(), Some("On my "));
+        assert_eq!(history.undo("On my ").as_deref(), Some("On "));
+        assert_eq!(history.undo("On ").as_deref(), Some(""));
+        assert_eq!(history.undo(""), None);
+
+        // A new edit drops the redo steps
+        history.record("", "x");
+        assert_eq!(history.redo("x"), None);
+
+        let mut long = InputHistory::default();
+        for i in 0..MAX_UNDO_STEPS * 2 {
+            long.record(&"a".repeat(i * 2), &"a".repeat(i * 2 + 2));
+        }
+        assert_eq!(long.undo.len(), MAX_UNDO_STEPS);
+    }
+}
@@ -0,0 +1,111 @@
+// cosmic-connect-applet/src/plugins/sms/unread.rs
+//! Unread text messages counted by the applet for its panel badge.
+//!
+//! The applet sees every SMS batch from the service but doesn't keep the
+//! conversations; it only remembers the ids of unread received messages
+//! per device. Phones resend messages on every sync, so counting ids
+//! keeps a message from being counted twice, and one resent as read stops
+//! counting. Opening a device's SMS window clears its count. Newly counted
+//! messages that arrived recently are handed back to be announced.
+
+use std::collections::{HashMap, HashSet};
+
+use super::auto_open::MAX_MESSAGE_AGE_MS;
+use super::models::Message;
+use super::utils::now_millis;
+
+#[derive(Debug, Default)]
+pub struct UnreadSms {
+    unread: HashMap<String, HashSet<String>>,
+}
+
+impl UnreadSms {
+    /// Note a batch of messages from `device_id`. Returns the newly counted
+    /// ones worth a notification; old unread messages replayed by a sync
+    /// are counted but not returned.
+    pub fn observe<'a>(&mut self, device_id: &str, messages: &'a [Message]) -> Vec<&'a Message> {
+        self.observe_at(device_id, messages, now_millis())
+    }
+
+    /// `observe` at `now` (ms since the epoch)
+    fn observe_at<'a>(&mut self, device_id: &str, messages: &'a [Message], now: i64) -> Vec<&'a Message> {
+        let unread = self.unread.entry(device_id.to_string()).or_default();
+        let mut fresh = Vec::new();
+        for message in messages {
+            if message.type_ == 1 && !message.read {
+                if unread.insert(message.id.clone()) && now - message.date <= MAX_MESSAGE_AGE_MS {
+                    fresh.push(message);
+                }
+            } else {
+                unread.remove(&message.id);
+            }
+        }
+        fresh
+    }
+
+    /// Forget a device's unread messages, once its SMS window is open
+    pub fn clear(&mut self, device_id: &str) {
+        self.unread.remove(device_id);
+    }
+
+    pub fn count(&self, device_id: &str) -> usize {
+        self.unread.get(device_id).map_or(0, HashSet::len)
+    }
+
+    /// Unread messages across all devices
+    pub fn total(&self) -> usize {
+        self.unread.values().map(HashSet::len).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::sms::models::{MessageEvent, SendStatus};
+
+    fn message(id: &str, type_: i32, read: bool) -> Message {
+        Message {
+            id: id.to_string(),
+            thread_id: "1".to_string(),
+            body: "Hi".to_string(),
+            address: "+15550100".to_string(),
+            date: 0,
+            type_,
+            read,
+            status: SendStatus::Synced,
+            sub_id: None,
+            event: MessageEvent::Text,
+            subject: None,
+        }
+    }
+
+    #[test]
+    fn test_counts_unread_received_messages_once() {
+        let mut unread = UnreadSms::default();
+        unread.observe("phone", &[message("1", 1, false), message("2", 1, true), message("3", 2, false)]);
+        unread.observe("phone", &[message("1", 1, false)]);
+        unread.observe("tablet", &[message("1", 1, false)]);
+        assert_eq!(unread.count("phone"), 1, "resent messages count once");
+        assert_eq!(unread.total(), 2);
+
+        // Read on the phone, then resent
+        unread.observe("phone", &[message("1", 1, true)]);
+        assert_eq!(unread.count("phone"), 0);
+
+        unread.clear("tablet");
+        assert_eq!(unread.total(), 0);
+    }
+
+    #[test]
+    fn test_returns_new_recent_messages_to_announce() {
+        let mut unread = UnreadSms::default();
+        let now = MAX_MESSAGE_AGE_MS * 2;
+        let recent = Message { date: now - 1000, ..message("1", 1, false) };
+        let synced = message("2", 1, false);
+        let fresh = unread.observe_at("phone", &[recent.clone(), synced], now);
+        assert_eq!(fresh.iter().map(|m| m.id.as_str()).collect::<Vec<_>>(), ["1"]);
+        assert_eq!(unread.count("phone"), 2, "old messages still count");
+
+        assert!(unread.observe_at("phone", &[recent], now).is_empty(), "announced once");
+    }
+}
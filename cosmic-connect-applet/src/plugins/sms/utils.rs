@@ -5,23 +5,87 @@
 
 #![allow(dead_code)]
 
+use std::ops::Range;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-/// Formats a Unix timestamp (in milliseconds) to a human-readable relative time.
+use std::collections::HashMap;
+
+use jiff::tz::TimeZone;
+use jiff::Timestamp;
+
+use super::models::{Conversation, Message, SendStatus};
+
+/// Prefix of the synthetic id given to a sent message until the phone echoes it back
+pub const OPTIMISTIC_ID_PREFIX: &str = "sending_";
+
+/// How far apart (ms) an optimistic message and its echo may be timestamped
+const ECHO_WINDOW_MS: i64 = 300_000;
+
+/// Android message types of an outgoing message not sent yet: draft,
+/// outbox and queued
+const PENDING_TYPES: [i32; 3] = [3, 4, 6];
+
+/// How far ahead of now (ms) a timestamp may be and still read "Just now".
+/// Phone clocks are often a little ahead of the desktop's.
+const CLOCK_SKEW_MS: i64 = 60_000;
+
+/// Formats a Unix timestamp (in milliseconds) relative to now, in the local time zone.
 pub fn format_timestamp(timestamp: i64) -> String {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs() as i64;
-    
-    let diff = now - (timestamp / 1000);
-    
-    match diff {
-        d if d < 60 => "Just now".to_string(),
-        d if d < 3600 => format!("{} min ago", d / 60),
-        d if d < 86400 => format!("{} hours ago", d / 3600),
-        d if d < 604800 => format!("{} days ago", d / 86400),
-        _ => "More than a week ago".to_string(),
+    relative_time(timestamp, now_millis(), &TimeZone::system())
+}
+
+/// Describe `timestamp` relative to `now` (both ms since the epoch), using
+/// `tz` for calendar days: "Just now", "5 minutes ago", "2 hours ago" the
+/// same day, then "Yesterday", a weekday within the last week and an
+/// absolute date beyond that. Times ahead of `now` read "In a moment",
+/// or as a date when more than an hour ahead.
+pub fn relative_time(timestamp: i64, now: i64, tz: &TimeZone) -> String {
+    let (Ok(then), Ok(current)) = (Timestamp::from_millisecond(timestamp), Timestamp::from_millisecond(now)) else {
+        return String::new();
+    };
+    let then = then.to_zoned(tz.clone());
+    let current = current.to_zoned(tz.clone());
+    let diff_ms = now - timestamp;
+
+    if diff_ms < -CLOCK_SKEW_MS {
+        return if diff_ms >= -3_600_000 {
+            "In a moment".to_string()
+        } else {
+            absolute_date(&then, &current)
+        };
+    }
+
+    let minutes = diff_ms / 60_000;
+    if minutes < 1 {
+        return "Just now".to_string();
+    }
+    if minutes < 60 {
+        return plural(minutes, "minute");
+    }
+
+    let days = then.date().until(current.date()).map(|span| span.get_days()).unwrap_or(i32::MAX);
+    match days {
+        0 => plural(minutes / 60, "hour"),
+        1 => "Yesterday".to_string(),
+        2..=6 => then.strftime("%A").to_string(),
+        _ => absolute_date(&then, &current),
+    }
+}
+
+fn plural(count: i64, unit: &str) -> String {
+    if count == 1 {
+        format!("1 {} ago", unit)
+    } else {
+        format!("{} {}s ago", count, unit)
+    }
+}
+
+/// "Mar 5" this year, "Mar 5, 2023" otherwise
+fn absolute_date(then: &jiff::Zoned, current: &jiff::Zoned) -> String {
+    if then.year() == current.year() {
+        then.strftime("%b %-d").to_string()
+    } else {
+        then.strftime("%b %-d, %Y").to_string()
     }
 }
 
@@ -74,6 +138,200 @@ pub fn phone_numbers_match(phone1: &str, phone2: &str) -> bool {
     false
 }
 
+/// Shortest and longest plausible recipient, in digits (short codes to E.164)
+const MIN_PHONE_DIGITS: usize = 3;
+const MAX_PHONE_DIGITS: usize = 15;
+
+/// Checks an outgoing SMS before it is handed to the phone, returning a
+/// user-facing reason when it should not be sent.
+pub fn validate_sms(phone: &str, body: &str) -> Result<(), String> {
+    let digits = normalize_phone_number(phone).len();
+    if !(MIN_PHONE_DIGITS..=MAX_PHONE_DIGITS).contains(&digits) {
+        return Err(format!("\"{}\" is not a valid phone number", phone));
+    }
+    if body.trim().is_empty() {
+        return Err("Message is empty".to_string());
+    }
+    Ok(())
+}
+
+/// Characters of the GSM 03.38 default alphabet, one septet each
+const GSM7_BASIC: &str = "@£$¥èéùìòÇ\nØø\rÅåΔ_ΦΓΛΩΠΨΣΘΞÆæßÉ !\"#¤%&'()*+,-./0123456789:;<=>?¡ABCDEFGHIJKLMNOPQRSTUVWXYZÄÖÑÜ§¿abcdefghijklmnopqrstuvwxyzäöñüà";
+
+/// Characters of the GSM 03.38 extension table, two septets each
+const GSM7_EXTENDED: &str = "^{}\\[~]|€\u{c}";
+
+/// How a message body is encoded on the carrier network
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmsEncoding {
+    /// The GSM default alphabet: 160 characters in a single SMS
+    Gsm7,
+    /// Anything outside it (emoji, most non-Latin scripts): 70 per SMS
+    Ucs2,
+}
+
+/// Size of a message once split into SMS segments
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SegmentInfo {
+    pub encoding: SmsEncoding,
+    /// Segments the carrier will send; 0 for an empty body
+    pub segments: usize,
+    /// Characters that still fit in the last segment
+    pub remaining: usize,
+}
+
+/// Count the SMS segments a body is sent as. A single segment holds 160
+/// GSM characters or 70 UCS-2 units; a multi-part message loses 7 or 3 of
+/// them per segment to the concatenation header. Extension characters
+/// such as '€' or '{' take two GSM units.
+pub fn segment_info(body: &str) -> SegmentInfo {
+    let gsm_units: Option<usize> = body.chars()
+        .map(|c| {
+            if GSM7_BASIC.contains(c) {
+                Some(1)
+            } else if GSM7_EXTENDED.contains(c) {
+                Some(2)
+            } else {
+                None
+            }
+        })
+        .sum();
+    let (encoding, units, single, multi) = match gsm_units {
+        Some(units) => (SmsEncoding::Gsm7, units, 160, 153),
+        None => (SmsEncoding::Ucs2, body.encode_utf16().count(), 70, 67),
+    };
+    let (segments, capacity) = match units {
+        0 => (0, single),
+        n if n <= single => (1, single),
+        n => {
+            let segments = n.div_ceil(multi);
+            (segments, segments * multi)
+        }
+    };
+    SegmentInfo { encoding, segments, remaining: capacity - units }
+}
+
+/// Fill in contact names for conversations whose number is a known
+/// contact, matching numbers across formats (+1, spacing, dashes).
+/// An exact number match wins over a fuzzy one.
+pub fn apply_contact_names(conversations: &mut [Conversation], contacts: &HashMap<String, String>) {
+    for conv in conversations.iter_mut() {
+        if let Some(name) = find_contact_name(contacts, &conv.phone_number) {
+            conv.contact_name = name.to_string();
+        }
+    }
+}
+
+/// Name of the contact with `phone_number`, matching across formats.
+/// An exact number match wins over a fuzzy one.
+pub fn find_contact_name<'a>(contacts: &'a HashMap<String, String>, phone_number: &str) -> Option<&'a str> {
+    contacts.get(phone_number)
+        .or_else(|| {
+            contacts.iter()
+                .find(|(phone, _)| phone_numbers_match(phone, phone_number))
+                .map(|(_, name)| name)
+        })
+        .map(String::as_str)
+}
+
+/// Index `offset` places from `current` in a list of `len` items, stopping
+/// at the ends. With no current item, moving selects the first item.
+pub fn adjacent_index(len: usize, current: Option<usize>, offset: isize) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    let Some(current) = current else { return Some(0); };
+    let target = current.checked_add_signed(offset)?;
+    (target < len && target != current).then_some(target)
+}
+
+/// Rows of a virtualized list to build for the current scroll position
+#[derive(Debug, Clone, PartialEq)]
+pub struct VisibleRows {
+    pub range: std::ops::Range<usize>,
+    /// Height of the rows skipped above and below, kept as empty space so
+    /// the scrollbar still reflects the whole list
+    pub space_before: f32,
+    pub space_after: f32,
+}
+
+/// Rows intersecting the viewport at `offset`, plus `buffer` rows on each
+/// side. An offset past the end (after the list shrank) is clamped so the
+/// last rows are shown.
+pub fn visible_rows(heights: &[f32], offset: f32, viewport: f32, buffer: usize) -> VisibleRows {
+    let total: f32 = heights.iter().sum();
+    let offset = offset.clamp(0.0, (total - viewport).max(0.0));
+
+    let mut top = 0.0;
+    let mut first = None;
+    let mut end = heights.len();
+    for (i, height) in heights.iter().enumerate() {
+        if top >= offset + viewport {
+            end = i;
+            break;
+        }
+        if first.is_none() && top + height > offset {
+            first = Some(i);
+        }
+        top += height;
+    }
+
+    let start = first.unwrap_or(0).saturating_sub(buffer);
+    let end = (end + buffer).min(heights.len());
+    VisibleRows {
+        space_before: heights[..start].iter().sum(),
+        space_after: heights[end..].iter().sum(),
+        range: start..end,
+    }
+}
+
+/// Conversation list preview of the last message, prefixed with "You: "
+/// when the user sent it. The prefix counts toward `max_chars`.
+pub fn conversation_preview(conv: &Conversation, max_chars: usize) -> String {
+    let prefix = if conv.last_message_sent { "You: " } else { "" };
+    let budget = max_chars.saturating_sub(prefix.chars().count());
+    let body = conv.last_message.lines().next().unwrap_or_default();
+    if body.chars().count() > budget {
+        let cut: String = body.chars().take(budget.saturating_sub(3)).collect();
+        format!("{}{}...", prefix, cut.trim_end())
+    } else {
+        format!("{}{}", prefix, body)
+    }
+}
+
+/// State of a conversation's last message, shown as a glyph in the list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LastMessageState {
+    /// Received and read
+    Received,
+    /// Received and not read yet, shown in bold
+    Unread,
+    /// Sent from this window, waiting for the phone's copy
+    Sending,
+    /// Sent from this window and rejected
+    Failed,
+    /// Sent (single check)
+    Sent,
+    /// Sent and marked read on the phone (double check)
+    Read,
+}
+
+/// Glyph state for a conversation. `pending` is the status of a message
+/// sent from this window that is newer than the phone's last message.
+pub fn last_message_state(conv: &Conversation, pending: Option<&SendStatus>) -> LastMessageState {
+    match pending {
+        Some(SendStatus::Failed(_)) => return LastMessageState::Failed,
+        Some(SendStatus::Sending) => return LastMessageState::Sending,
+        Some(SendStatus::Synced) | None => {}
+    }
+    match (conv.last_message_sent, conv.last_message_read) {
+        (true, true) => LastMessageState::Read,
+        (true, false) => LastMessageState::Sent,
+        (false, _) if conv.unread && !conv.last_message_read => LastMessageState::Unread,
+        (false, _) => LastMessageState::Received,
+    }
+}
+
 /// Truncates a string to a maximum length, adding ellipsis if needed.
 pub fn truncate_message(s: &str, max_len: usize) -> String {
     if s.len() > max_len {
@@ -83,6 +341,170 @@ pub fn truncate_message(s: &str, max_len: usize) -> String {
     }
 }
 
+/// Whether `optimistic` is the locally inserted copy of the sent message `real`.
+///
+/// Matched by thread, body and approximate timestamp, since the phone assigns
+/// its own id and date. Placeholder threads from a new chat match any thread.
+fn is_echo_of(optimistic: &Message, real: &Message) -> bool {
+    optimistic.id.starts_with(OPTIMISTIC_ID_PREFIX)
+        && optimistic.is_sent()
+        && (real.is_sent() || is_pending(real))
+        && (optimistic.thread_id == real.thread_id || optimistic.thread_id.starts_with("new_"))
+        && optimistic.body == real.body
+        && (optimistic.date - real.date).abs() < ECHO_WINDOW_MS
+}
+
+fn is_pending(message: &Message) -> bool {
+    PENDING_TYPES.contains(&message.type_)
+}
+
+/// Whether `existing` and `incoming` are copies of one message: the same
+/// phone id in the same thread (ids are only unique per device, and
+/// threads of a unified inbox are keyed by device), our optimistic copy,
+/// or a draft or outbox entry that `incoming` confirms as sent under a
+/// new id.
+fn is_copy_of(existing: &Message, incoming: &Message) -> bool {
+    (existing.id == incoming.id && existing.thread_id == incoming.thread_id)
+        || is_echo_of(existing, incoming)
+        || (is_pending(existing)
+            && incoming.is_sent()
+            && existing.thread_id == incoming.thread_id
+            && existing.body == incoming.body
+            && incoming.date >= existing.date)
+}
+
+/// Fold a later copy of a message into the one shown. Copies can arrive
+/// out of order, so a message never turns unread again, and a stale
+/// draft or outbox copy doesn't undo a sent or received one.
+fn merge_copy(existing: &mut Message, incoming: Message) {
+    let read = existing.read || incoming.read;
+    let stale = is_pending(&incoming)
+        && !is_pending(existing)
+        && !existing.id.starts_with(OPTIMISTIC_ID_PREFIX);
+    if !stale {
+        *existing = incoming;
+    }
+    existing.read = read;
+}
+
+/// Insert a received message into a thread, merging it into an existing
+/// copy (see `is_copy_of`) instead of appending a duplicate. Copies left
+/// over from earlier deliveries are dropped. Keeps `messages` sorted.
+pub fn merge_message(messages: &mut Vec<Message>, incoming: Message) {
+    let mut copies = messages.iter().enumerate()
+        .filter(|(_, m)| is_copy_of(m, &incoming))
+        .map(|(i, _)| i);
+    match copies.next() {
+        Some(first) => {
+            let extra: Vec<usize> = copies.collect();
+            merge_copy(&mut messages[first], incoming);
+            for i in extra.into_iter().rev() {
+                messages.remove(i);
+            }
+        }
+        None => messages.push(incoming),
+    }
+    sort_messages(messages);
+}
+
+/// Whether `message` is a received message newer than everything in the
+/// thread, as opposed to a copy of one shown already or an older page
+pub fn is_new_arrival(messages: &[Message], message: &Message) -> bool {
+    !message.is_sent()
+        && !messages.iter().any(|m| is_copy_of(m, message))
+        && messages.last().is_none_or(|newest| message.date > newest.date)
+}
+
+/// Date of the newest message synced from the phone, leaving out ones
+/// still sending or failed
+pub fn latest_synced(messages: &[Message]) -> Option<i64> {
+    messages.iter()
+        .filter(|m| m.status == SendStatus::Synced)
+        .map(|m| m.date)
+        .max()
+}
+
+/// Sort by date, then by phone id so messages with the same timestamp
+/// keep one order across syncs. Optimistic copies go after synced ones.
+pub fn sort_messages(messages: &mut [Message]) {
+    fn id_order(id: &str) -> (i64, &str) {
+        (id.parse().unwrap_or(i64::MAX), id)
+    }
+    messages.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| id_order(&a.id).cmp(&id_order(&b.id))));
+}
+
+/// Kind of link detected in a message body
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkKind {
+    /// http:// or https:// URL
+    Web,
+    /// tel: URI
+    Phone,
+    /// mailto: URI
+    Email,
+}
+
+const LINK_SCHEMES: &[(&str, LinkKind)] = &[
+    ("https://", LinkKind::Web),
+    ("http://", LinkKind::Web),
+    ("tel:", LinkKind::Phone),
+    ("mailto:", LinkKind::Email),
+];
+
+/// Finds http(s), tel: and mailto: links in a message body.
+///
+/// Returns byte ranges into `body`. Links end at whitespace; trailing
+/// sentence punctuation and unbalanced closing brackets are left out.
+pub fn find_links(body: &str) -> Vec<(Range<usize>, LinkKind)> {
+    let mut links = Vec::new();
+    let lower = body.to_ascii_lowercase();
+    let mut pos = 0;
+
+    while pos < body.len() {
+        let found = LINK_SCHEMES.iter()
+            .filter_map(|(scheme, kind)| lower[pos..].find(scheme).map(|i| (pos + i, *scheme, *kind)))
+            .min_by_key(|(start, _, _)| *start);
+        let Some((start, scheme, kind)) = found else { break; };
+
+        // Only match at a word boundary, not inside e.g. "xhttp://"
+        let at_boundary = body[..start].chars().next_back()
+            .is_none_or(|c| !c.is_alphanumeric());
+
+        let rest = &body[start..];
+        let mut end = start + rest.find(char::is_whitespace).unwrap_or(rest.len());
+        end = start + trim_link_end(&body[start..end]);
+
+        if at_boundary && end > start + scheme.len() {
+            links.push((start..end, kind));
+            pos = end;
+        } else {
+            pos = start + scheme.len();
+        }
+    }
+
+    links
+}
+
+/// Length of `candidate` without trailing punctuation that belongs to the
+/// surrounding sentence rather than the link.
+fn trim_link_end(candidate: &str) -> usize {
+    let mut end = candidate.len();
+    while let Some(c) = candidate[..end].chars().next_back() {
+        let unbalanced = |open: char, close: char| {
+            c == close && candidate[..end].matches(open).count() < candidate[..end].matches(close).count()
+        };
+        if matches!(c, '.' | ',' | ';' | ':' | '!' | '?' | '\'' | '"')
+            || unbalanced('(', ')')
+            || unbalanced('[', ']')
+        {
+            end -= c.len_utf8();
+        } else {
+            break;
+        }
+    }
+    end
+}
+
 /// Parses a VCard format string to extract name and phone numbers.
 pub fn parse_vcard(content: &str) -> (Option<String>, Vec<String>) {
     let mut name: Option<String> = None;
@@ -122,9 +544,35 @@ pub fn parse_vcard(content: &str) -> (Option<String>, Vec<String>) {
     (name, phones)
 }
 
+/// Builds a minimal vCard 3.0 for a single phone number.
+pub fn contact_vcard(name: &str, phone_number: &str) -> String {
+    // Commas, semicolons and backslashes must be escaped in vCard values
+    let name = name.replace('\\', "\\\\").replace(',', "\\,").replace(';', "\\;");
+    format!(
+        "BEGIN:VCARD\r\nVERSION:3.0\r\nFN:{}\r\nTEL;TYPE=CELL:{}\r\nEND:VCARD\r\n",
+        name.trim(),
+        phone_number.trim(),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::plugins::sms::models::{MessageEvent, SendStatus};
+
+    #[test]
+    fn test_segment_info() {
+        assert_eq!(segment_info(""), SegmentInfo { encoding: SmsEncoding::Gsm7, segments: 0, remaining: 160 });
+        assert_eq!(segment_info(&"a".repeat(160)).segments, 1);
+        assert_eq!(segment_info(&"a".repeat(161)), SegmentInfo { encoding: SmsEncoding::Gsm7, segments: 2, remaining: 145 });
+        // Extension characters take two units
+        assert_eq!(segment_info(&"€".repeat(80)).segments, 1);
+        assert_eq!(segment_info(&"€".repeat(81)).segments, 2);
+        // One emoji switches the whole message to UCS-2, and is two units
+        let info = segment_info(&format!("{}😀", "a".repeat(68)));
+        assert_eq!((info.encoding, info.segments, info.remaining), (SmsEncoding::Ucs2, 1, 0));
+        assert_eq!(segment_info(&"é".repeat(153 * 3 + 1)).segments, 4);
+    }
 
     #[test]
     fn test_phone_number_normalization() {
@@ -138,4 +586,289 @@ mod tests {
         assert!(phone_numbers_match("5551234567", "15551234567"));
         assert!(phone_numbers_match("+1-555-123-4567", "5551234567"));
     }
+
+    #[test]
+    fn test_apply_contact_names_to_new_conversation() {
+        let contacts: HashMap<String, String> =
+            [("+1 (555) 123-4567".to_string(), "Alice".to_string())].into();
+
+        // Contacts were loaded first; a conversation for a known number
+        // arrives later in a different format
+        let mut conversations = vec![Conversation {
+            thread_id: "7".to_string(),
+            device_id: String::new(),
+            contact_name: String::new(),
+            phone_number: "5551234567".to_string(),
+            last_message: "hi".to_string(),
+            last_message_sent: false,
+            last_message_read: false,
+            timestamp: 0,
+            unread: false,
+            pinned: false,
+            archived: false,
+        }];
+        apply_contact_names(&mut conversations, &contacts);
+        assert_eq!(conversations[0].contact_name, "Alice");
+    }
+
+    #[test]
+    fn test_conversation_preview_marks_sent() {
+        let mut conv = Conversation {
+            thread_id: "1".to_string(),
+            device_id: String::new(),
+            contact_name: String::new(),
+            phone_number: "5551234567".to_string(),
+            last_message: "See you at eight tonight".to_string(),
+            last_message_sent: true,
+            last_message_read: false,
+            timestamp: 0,
+            unread: false,
+            pinned: false,
+            archived: false,
+        };
+        assert_eq!(conversation_preview(&conv, 40), "You: See you at eight tonight");
+
+        let preview = conversation_preview(&conv, 16);
+        assert_eq!(preview, "You: See you...");
+        assert!(preview.chars().count() <= 16);
+
+        conv.last_message_sent = false;
+        assert_eq!(conversation_preview(&conv, 40), "See you at eight tonight");
+    }
+
+    #[test]
+    fn test_last_message_state() {
+        let mut conv = Conversation {
+            thread_id: "1".to_string(),
+            device_id: String::new(),
+            contact_name: String::new(),
+            phone_number: "5551234567".to_string(),
+            last_message: "On my way".to_string(),
+            last_message_sent: true,
+            last_message_read: true,
+            timestamp: 0,
+            unread: false,
+            pinned: false,
+            archived: false,
+        };
+        assert_eq!(last_message_state(&conv, None), LastMessageState::Read);
+        conv.last_message_read = false;
+        assert_eq!(last_message_state(&conv, None), LastMessageState::Sent);
+
+        // A newer message from this window wins over the phone's last one
+        assert_eq!(last_message_state(&conv, Some(&SendStatus::Sending)), LastMessageState::Sending);
+        let failed = SendStatus::Failed("No service".to_string());
+        assert_eq!(last_message_state(&conv, Some(&failed)), LastMessageState::Failed);
+
+        conv.last_message_sent = false;
+        conv.unread = true;
+        assert_eq!(last_message_state(&conv, None), LastMessageState::Unread);
+        conv.last_message_read = true;
+        assert_eq!(last_message_state(&conv, None), LastMessageState::Received);
+    }
+
+    #[test]
+    fn test_visible_rows_for_500_threads() {
+        let heights = vec![64.0; 500];
+
+        let top = visible_rows(&heights, 0.0, 600.0, 5);
+        assert_eq!(top.range, 0..15);
+        assert_eq!(top.space_before, 0.0);
+        assert_eq!(top.space_after, 485.0 * 64.0);
+
+        // Mid-list: 10 visible rows plus 5 on each side instead of all 500
+        let middle = visible_rows(&heights, 6400.0, 600.0, 5);
+        assert_eq!(middle.range, 95..115);
+        assert_eq!(middle.space_before + middle.space_after + 20.0 * 64.0, 500.0 * 64.0);
+
+        // Scrolled past the end after a search shrank the list
+        let shrunk = visible_rows(&heights[..3], 6400.0, 600.0, 5);
+        assert_eq!(shrunk.range, 0..3);
+
+        assert_eq!(visible_rows(&[], 0.0, 600.0, 5).range, 0..0);
+    }
+
+    #[test]
+    fn test_relative_time_boundaries() {
+        // Friday 2024-03-15 12:00 UTC
+        let now: i64 = "2024-03-15T12:00:00Z".parse::<Timestamp>().unwrap().as_millisecond();
+        let at = |s: &str| s.parse::<Timestamp>().unwrap().as_millisecond();
+        let fmt = |ts: i64| relative_time(ts, now, &TimeZone::UTC);
+
+        assert_eq!(fmt(now), "Just now");
+        assert_eq!(fmt(now - 59_999), "Just now");
+        assert_eq!(fmt(now - 60_000), "1 minute ago");
+        assert_eq!(fmt(now - 120_000), "2 minutes ago");
+        assert_eq!(fmt(now - 3_599_999), "59 minutes ago");
+        assert_eq!(fmt(now - 3_600_000), "1 hour ago");
+        assert_eq!(fmt(at("2024-03-15T00:00:00Z")), "12 hours ago");
+        assert_eq!(fmt(at("2024-03-14T23:59:59Z")), "Yesterday");
+        assert_eq!(fmt(at("2024-03-13T12:00:00Z")), "Wednesday");
+        assert_eq!(fmt(at("2024-03-09T00:00:00Z")), "Saturday");
+        assert_eq!(fmt(at("2024-03-08T23:00:00Z")), "Mar 8");
+        assert_eq!(fmt(at("2023-12-25T09:00:00Z")), "Dec 25, 2023");
+
+        // Future timestamps: small skew, soon, and far ahead
+        assert_eq!(fmt(now + 30_000), "Just now");
+        assert_eq!(fmt(now + 600_000), "In a moment");
+        assert_eq!(fmt(at("2024-03-18T12:00:00Z")), "Mar 18");
+
+        // Calendar days follow the time zone, not UTC
+        let tz = TimeZone::fixed(jiff::tz::offset(-5));
+        assert_eq!(relative_time(at("2024-03-15T04:00:00Z"), now, &tz), "Yesterday");
+
+        assert_eq!(relative_time(i64::MAX, now, &TimeZone::UTC), "");
+    }
+
+    #[test]
+    fn test_contact_vcard_round_trip() {
+        let vcard = contact_vcard("Jane Doe", "+1 555 123 4567");
+        assert!(vcard.starts_with("BEGIN:VCARD\r\nVERSION:3.0\r\n"));
+        assert_eq!(
+            parse_vcard(&vcard),
+            (Some("Jane Doe".to_string()), vec!["+1 555 123 4567".to_string()])
+        );
+
+        // With no resolved name the number doubles as the name
+        let (name, phones) = parse_vcard(&contact_vcard("5551234567", "5551234567"));
+        assert_eq!(name.as_deref(), Some("5551234567"));
+        assert_eq!(phones, vec!["5551234567"]);
+    }
+
+    #[test]
+    fn test_adjacent_index() {
+        assert_eq!(adjacent_index(3, Some(1), 1), Some(2));
+        assert_eq!(adjacent_index(3, Some(1), -1), Some(0));
+        // Stops at both ends instead of wrapping
+        assert_eq!(adjacent_index(3, Some(2), 1), None);
+        assert_eq!(adjacent_index(3, Some(0), -1), None);
+        // Selected thread filtered out of the list
+        assert_eq!(adjacent_index(3, None, -1), Some(0));
+        assert_eq!(adjacent_index(0, None, 1), None);
+    }
+
+    #[test]
+    fn test_find_links() {
+        let body = "See https://example.com/a?b=1, or call tel:+15551234567.";
+        let links = find_links(body);
+        assert_eq!(links.len(), 2);
+        assert_eq!(&body[links[0].0.clone()], "https://example.com/a?b=1");
+        assert_eq!(links[0].1, LinkKind::Web);
+        assert_eq!(&body[links[1].0.clone()], "tel:+15551234567");
+        assert_eq!(links[1].1, LinkKind::Phone);
+
+        // Parenthesised link keeps balanced brackets only
+        let body = "(docs at http://en.wikipedia.org/wiki/Rust_(language))";
+        let links = find_links(body);
+        assert_eq!(&body[links[0].0.clone()], "http://en.wikipedia.org/wiki/Rust_(language)");
+
+        // Whole message is a link
+        let body = "mailto:someone@example.com";
+        assert_eq!(find_links(body), vec![(0..body.len(), LinkKind::Email)]);
+
+        assert!(find_links("No links here, just http talk").is_empty());
+        assert!(find_links("Bare scheme https:// only").is_empty());
+    }
+
+    #[test]
+    fn test_merge_message_replaces_optimistic_copy() {
+        let sent = |id: &str, date: i64| Message {
+            id: id.to_string(),
+            thread_id: "42".to_string(),
+            body: "On my way".to_string(),
+            address: "5551234567".to_string(),
+            date,
+            type_: 2,
+            read: true,
+            status: SendStatus::Sending,
+            sub_id: None,
+            event: MessageEvent::Text,
+            subject: None,
+        };
+
+        let mut messages = vec![sent("sending_1000", 1_000)];
+        merge_message(&mut messages, sent("9876", 4_000));
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].id, "9876");
+        assert_eq!(messages[0].date, 4_000);
+
+        // A re-sync of the same message is not duplicated either
+        merge_message(&mut messages, sent("9876", 4_000));
+        assert_eq!(messages.len(), 1);
+    }
+
+    #[test]
+    fn test_merge_message_duplicates_and_out_of_order() {
+        let message = |id: &str, date: i64, type_: i32, read: bool| Message {
+            id: id.to_string(),
+            thread_id: "42".to_string(),
+            body: "See you".to_string(),
+            address: "5551234567".to_string(),
+            date,
+            type_,
+            read,
+            status: SendStatus::Synced,
+            sub_id: None,
+            event: MessageEvent::Text,
+            subject: None,
+        };
+
+        // Same timestamp: ordered by numeric id whatever the arrival order
+        let mut messages = Vec::new();
+        merge_message(&mut messages, message("10", 5_000, 1, false));
+        merge_message(&mut messages, message("9", 5_000, 1, false));
+        merge_message(&mut messages, message("3", 1_000, 1, true));
+        let ids: Vec<&str> = messages.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, ["3", "9", "10"]);
+
+        // A read update with a corrected date moves the message; a late
+        // unread copy doesn't undo it
+        merge_message(&mut messages, message("10", 4_000, 1, true));
+        merge_message(&mut messages, message("10", 4_000, 1, false));
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1].id, "10");
+        assert!(messages[1].read);
+
+        // The same id in another thread is a different message
+        let mut other = message("10", 4_000, 1, false);
+        other.thread_id = "43".to_string();
+        merge_message(&mut messages, other);
+        assert_eq!(messages.len(), 4);
+        messages.retain(|m| m.thread_id == "42");
+
+        // An outbox copy confirmed as sent under a new id, then the
+        // outbox copy again, late
+        merge_message(&mut messages, message("20", 6_000, 4, true));
+        merge_message(&mut messages, message("21", 6_500, 2, true));
+        merge_message(&mut messages, message("21", 6_500, 4, true));
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[3].id, "21");
+        assert_eq!(messages[3].type_, 2);
+    }
+
+    #[test]
+    fn test_is_new_arrival() {
+        let message = |id: &str, date: i64, type_: i32| Message {
+            id: id.to_string(),
+            thread_id: "42".to_string(),
+            body: "Hi".to_string(),
+            address: "5551234567".to_string(),
+            date,
+            type_,
+            read: false,
+            status: SendStatus::Synced,
+            sub_id: None,
+            event: MessageEvent::Text,
+            subject: None,
+        };
+        let messages = vec![message("1", 1_000, 1), message("2", 2_000, 2)];
+
+        assert!(is_new_arrival(&messages, &message("3", 3_000, 1)));
+        assert!(is_new_arrival(&[], &message("3", 3_000, 1)));
+        // Our own sends, re-deliveries and older pages don't count
+        assert!(!is_new_arrival(&messages, &message("3", 3_000, 2)));
+        assert!(!is_new_arrival(&messages, &message("2", 2_000, 2)));
+        assert!(!is_new_arrival(&messages, &message("0", 500, 1)));
+    }
 }
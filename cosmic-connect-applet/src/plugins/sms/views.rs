@@ -1,24 +1,193 @@
 // cosmic-connect-applet/src/plugins/sms/views.rs
 //! UI view implementations for the SMS window.
 
-use cosmic::iced::{Alignment, Length};
+use cosmic::iced::{Alignment, Color, Length};
 use cosmic::widget;
 use cosmic::Element;
 
 use super::app::{SmsWindow, SmsMessage};
-use super::models::Conversation;
-use super::utils::{format_timestamp, normalize_phone_number, phone_numbers_match};
+use super::appearance::{BubbleColor, MAX_CORNER_RADIUS};
+use super::layout;
+use super::models::{ContactsState, Conversation, SendStatus};
+use super::utils::{self, conversation_preview, LastMessageState, find_links, format_timestamp, normalize_phone_number, phone_numbers_match};
+
+/// Longest last-message preview in the conversation list, in characters
+const PREVIEW_MAX_CHARS: usize = 60;
+
+/// The segment counter shows once fewer characters than this are left in
+/// a single-segment message
+const SEGMENT_COUNTER_FROM: usize = 20;
+
+/// Fixed heights of conversation list rows, so the list can be virtualized.
+/// A conversation row includes its divider.
+const CONVERSATION_ROW_HEIGHT: f32 = 64.0;
+const THREAD_MENU_HEIGHT: f32 = 40.0;
+const ARCHIVED_HEADER_HEIGHT: f32 = 40.0;
+
+/// Rows built beyond each edge of the viewport, so fast scrolling doesn't
+/// show blank space. For 500 threads in a 600px list at most 21 rows are
+/// built instead of 500.
+const CONVERSATION_ROW_BUFFER: usize = 5;
+
+/// Message composer, focused when a thread opens and after sending
+pub static COMPOSER_INPUT: std::sync::LazyLock<widget::Id> = std::sync::LazyLock::new(|| widget::Id::new("sms-composer"));
+
+/// Message list of the open thread, scrolled by the jump-to-latest button
+pub static MESSAGE_LIST: std::sync::LazyLock<widget::Id> = std::sync::LazyLock::new(|| widget::Id::new("sms-message-list"));
+
+/// Phone number field of the new chat dialog
+pub static NEW_CHAT_PHONE_INPUT: std::sync::LazyLock<widget::Id> = std::sync::LazyLock::new(|| widget::Id::new("sms-new-chat-phone"));
+
+/// One row of the conversation list
+enum ConversationRow<'a> {
+    Conversation(&'a Conversation),
+    ArchivedHeader(usize),
+}
 
-/// Main view - conversations list + thread view
+/// Main view - conversations list + thread view, or one of them in a
+/// narrow window
 pub fn view_main(app: &SmsWindow) -> Element<'_, SmsMessage> {
     let spacing = cosmic::theme::active().cosmic().spacing;
     
-    widget::row()
-        .spacing(0)
-        .push(view_conversations_list(app, &spacing))
-        .push(widget::divider::vertical::default())
-        .push(view_thread_panel(app, &spacing))
-        .into()
+    let main: Element<'_, SmsMessage> = if layout::is_single_panel(app.window_width) {
+        match app.selected_thread {
+            Some(_) => view_thread_panel(app, &spacing),
+            None => view_conversations_list(app, &spacing, Length::Fill),
+        }
+    } else {
+        let list_width = app.layout.list_width_in(app.window_width);
+        let resize_handle = widget::mouse_area(
+            widget::container(widget::divider::vertical::default()).padding([0, spacing.space_xxxs])
+        )
+        .on_press(SmsMessage::StartListResize)
+        .interaction(cosmic::iced::mouse::Interaction::ResizingHorizontally);
+
+        let panels = widget::row()
+            .spacing(0)
+            .push(view_conversations_list(app, &spacing, Length::Fixed(list_width)))
+            .push(resize_handle)
+            .push(view_thread_panel(app, &spacing));
+
+        // Follow the pointer over the whole window while dragging
+        let mut panels = widget::mouse_area(panels).on_release(SmsMessage::EndListResize);
+        if app.resizing_list {
+            panels = panels.on_move(|position| SmsMessage::ListResized(position.x));
+        }
+        panels.into()
+    };
+
+    // What is shown may be out of date until the service is back
+    let main = if app.service_lost {
+        widget::container(main).class(stale_data()).into()
+    } else {
+        main
+    };
+
+    let mut column = widget::column();
+    if app.service_lost {
+        column = column.push(view_reconnect_bar(&spacing));
+    }
+    if app.resend_offered && !app.failed_sends.is_empty() {
+        column = column.push(view_resend_bar(app, &spacing));
+    }
+    column.push(main).into()
+}
+
+/// Say that updates stopped while the service is unreachable
+fn view_reconnect_bar<'a>(spacing: &cosmic::cosmic_theme::Spacing) -> Element<'a, SmsMessage> {
+    widget::container(
+        widget::text("Lost connection to the KDE Connect service. Reconnecting… Messages may be out of date.")
+            .size(13)
+    )
+    .padding(spacing.space_xs)
+    .class(cosmic::theme::Container::Card)
+    .width(Length::Fill)
+    .into()
+}
+
+/// Faded text for data that may be stale
+fn stale_data() -> cosmic::theme::Container<'static> {
+    cosmic::theme::Container::custom(|theme| {
+        let mut text_color = Color::from(theme.cosmic().background.on);
+        text_color.a *= 0.5;
+        cosmic::iced::widget::container::Style {
+            text_color: Some(text_color),
+            ..Default::default()
+        }
+    })
+}
+
+/// Offer to re-send failed messages after a device reconnects
+fn view_resend_bar<'a>(app: &'a SmsWindow, spacing: &cosmic::cosmic_theme::Spacing) -> Element<'a, SmsMessage> {
+    let count = app.failed_sends.len();
+    let label = if app.resending {
+        format!("Resending {} failed message{}…", count, if count == 1 { "" } else { "s" })
+    } else {
+        format!("Resend {} failed message{}?", count, if count == 1 { "" } else { "s" })
+    };
+
+    widget::container(
+        widget::row()
+            .push(widget::text(label).size(13).width(Length::Fill))
+            .push(
+                widget::button::suggested("Resend")
+                    .on_press_maybe((!app.resending).then_some(SmsMessage::ResendFailed))
+            )
+            .push(widget::button::text("Dismiss").on_press(SmsMessage::DismissResend))
+            .spacing(spacing.space_xs)
+            .align_y(Alignment::Center)
+            .padding(spacing.space_xs)
+    )
+    .class(cosmic::theme::Container::Card)
+    .width(Length::Fill)
+    .into()
+}
+
+/// Bubble color and shape settings
+pub fn view_appearance_dialog(app: &SmsWindow) -> Element<'_, SmsMessage> {
+    let spacing = cosmic::theme::active().cosmic().spacing;
+    let appearance = &app.appearance;
+    let radius = appearance.corner_radius.unwrap_or(8);
+
+    let section = widget::settings::section()
+        .title("Message bubbles")
+        .add(widget::settings::item(
+            "Sent color",
+            widget::dropdown(&BubbleColor::LABELS, Some(appearance.sent_color.index()), SmsMessage::SetSentColor),
+        ))
+        .add(widget::settings::item(
+            "Received color",
+            widget::dropdown(&BubbleColor::LABELS, Some(appearance.received_color.index()), SmsMessage::SetReceivedColor),
+        ))
+        .add(widget::settings::item(
+            "Corner radius",
+            widget::row()
+                .push(widget::slider(0..=MAX_CORNER_RADIUS, radius, SmsMessage::SetCornerRadius).width(Length::Fixed(160.0)))
+                .push(widget::text(format!("{} px", radius)).size(12))
+                .spacing(spacing.space_xs)
+                .align_y(Alignment::Center),
+        ));
+
+    let actions = widget::row()
+        .push(
+            widget::button::standard("Reset to theme")
+                .on_press_maybe((!appearance.is_default()).then_some(SmsMessage::ResetAppearance))
+        )
+        .push(widget::horizontal_space())
+        .push(widget::button::suggested("Done").on_press(SmsMessage::ToggleAppearance))
+        .spacing(spacing.space_xs);
+
+    widget::container(
+        widget::column()
+            .push(widget::text("Appearance").size(20).font(cosmic::font::bold()))
+            .push(section)
+            .push(actions)
+            .spacing(spacing.space_m)
+            .padding(spacing.space_l)
+    )
+    .class(cosmic::theme::Container::Card)
+    .width(Length::Fixed(500.0))
+    .into()
 }
 
 /// New chat dialog view
@@ -43,6 +212,7 @@ pub fn view_new_chat_dialog(app: &SmsWindow) -> Element<'_, SmsMessage> {
             .push(widget::text("Enter phone number or contact name:").size(14))
             .push(
                 widget::text_input("e.g., +1-555-123-4567 or John Doe", &app.new_chat_phone_input)
+                    .id(NEW_CHAT_PHONE_INPUT.clone())
                     .on_input(SmsMessage::UpdateNewChatPhone)
                     .width(Length::Fill)
             )
@@ -83,8 +253,21 @@ fn view_new_chat_actions<'a>(app: &'a SmsWindow, spacing: &cosmic::cosmic_theme:
 }
 
 fn view_contacts_list<'a>(app: &'a SmsWindow, spacing: &cosmic::cosmic_theme::Spacing) -> Element<'a, SmsMessage> {
-    if app.contacts.is_empty() {
-        return widget::text("No contacts available").size(12).into();
+    match &app.contacts_state {
+        ContactsState::Loading => return widget::text("Loading contacts…").size(12).into(),
+        ContactsState::Error(error) => {
+            return widget::column()
+                .spacing(spacing.space_xs)
+                .push(widget::text(format!("Contacts couldn't be loaded: {}", error)).size(12))
+                .push(widget::button::standard("Retry").on_press(SmsMessage::RetryContacts))
+                .into();
+        }
+        ContactsState::Loaded if app.contacts.is_empty() => {
+            return widget::text(
+                "No contacts have been synced from the phone. Check that the Contacts plugin is enabled for it."
+            ).size(12).into();
+        }
+        ContactsState::Loaded => {}
     }
 
     let mut contacts_list = widget::column().spacing(spacing.space_xxs);
@@ -140,19 +323,46 @@ fn get_filtered_contacts(app: &SmsWindow) -> Vec<(&String, &String)> {
 }
 
 /// Conversations list panel
-fn view_conversations_list<'a>(app: &'a SmsWindow, spacing: &cosmic::cosmic_theme::Spacing) -> Element<'a, SmsMessage> {
+fn view_conversations_list<'a>(app: &'a SmsWindow, spacing: &cosmic::cosmic_theme::Spacing, width: Length) -> Element<'a, SmsMessage> {
     let mut content = widget::column().spacing(spacing.space_xs);
 
     // Start Chat button
     content = content.push(
         widget::container(
-            widget::button::suggested("Start Chat")
-                .on_press(SmsMessage::OpenNewChatDialog)
-                .width(Length::Fill)
+            widget::row()
+                .push(
+                    widget::button::suggested("Start Chat")
+                        .on_press(SmsMessage::OpenNewChatDialog)
+                        .width(Length::Fill)
+                )
+                .push(
+                    widget::button::icon(widget::icon::from_name("view-refresh-symbolic"))
+                        .on_press_maybe((!app.is_loading).then_some(SmsMessage::SyncConversations))
+                        .tooltip("Sync")
+                )
+                .push(
+                    widget::button::icon(widget::icon::from_name("preferences-desktop-appearance-symbolic"))
+                        .on_press(SmsMessage::ToggleAppearance)
+                        .tooltip("Appearance")
+                )
+                .spacing(spacing.space_xs)
+                .align_y(Alignment::Center)
         )
         .padding(spacing.space_s)
     );
 
+    if app.is_loading {
+        content = content.push(
+            widget::container(widget::text::caption("Syncing conversations…"))
+                .padding([0, spacing.space_s])
+        );
+    } else if let Some(error) = &app.sync_error {
+        content = content.push(
+            widget::container(widget::text::caption(format!("Sync failed: {}", error)))
+                .padding([0, spacing.space_s])
+        );
+    }
+
     // Search input
     content = content.push(
         widget::text_input("Search conversations...", &app.search_query)
@@ -161,16 +371,9 @@ fn view_conversations_list<'a>(app: &'a SmsWindow, spacing: &cosmic::cosmic_them
     );
     content = content.push(widget::divider::horizontal::default());
 
-    // Filter conversations
-    let mut filtered: Vec<_> = app.conversations
-        .iter()
-        .filter(|c| conversation_matches_search(app, c))
-        .collect();
-    
-    // Sort by timestamp (most recent first)
-    filtered.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    let (filtered, archived) = app.sorted_conversations();
 
-    if filtered.is_empty() {
+    if filtered.is_empty() && archived.is_empty() {
         let msg = if app.search_query.is_empty() {
             "No conversations"
         } else {
@@ -184,33 +387,77 @@ fn view_conversations_list<'a>(app: &'a SmsWindow, spacing: &cosmic::cosmic_them
                 .center_x(Length::Fill)
         );
     } else {
-        let mut list = widget::column().spacing(0);
-
-        for conv in filtered {
-            list = list.push(view_conversation_item(app, conv, spacing));
-            list = list.push(widget::divider::horizontal::light());
+        // Search and sort run over every conversation; only the rows near
+        // the viewport are turned into widgets
+        let mut rows: Vec<ConversationRow> = filtered.into_iter().map(ConversationRow::Conversation).collect();
+        if !archived.is_empty() {
+            rows.push(ConversationRow::ArchivedHeader(archived.len()));
+            if app.show_archived {
+                rows.extend(archived.into_iter().map(ConversationRow::Conversation));
+            }
+        }
+        let heights: Vec<f32> = rows.iter()
+            .map(|row| match row {
+                ConversationRow::Conversation(conv) if app.thread_menu.as_ref() == Some(&conv.thread_id) => {
+                    CONVERSATION_ROW_HEIGHT + THREAD_MENU_HEIGHT
+                }
+                ConversationRow::Conversation(_) => CONVERSATION_ROW_HEIGHT,
+                ConversationRow::ArchivedHeader(_) => ARCHIVED_HEADER_HEIGHT,
+            })
+            .collect();
+        let (offset, viewport) = app.conversation_scroll;
+        let visible = utils::visible_rows(&heights, offset, viewport, CONVERSATION_ROW_BUFFER);
+
+        let mut list = widget::column()
+            .spacing(0)
+            .push(widget::Space::with_height(Length::Fixed(visible.space_before)));
+
+        for (row, height) in rows[visible.range.clone()].iter().zip(&heights[visible.range.clone()]) {
+            let row: Element<'a, SmsMessage> = match row {
+                ConversationRow::Conversation(conv) => widget::column()
+                    .push(view_conversation_item(app, *conv, spacing))
+                    .push(widget::divider::horizontal::light())
+                    .into(),
+                ConversationRow::ArchivedHeader(count) => widget::button::custom(
+                    widget::row()
+                        .push(widget::icon::from_name(
+                            if app.show_archived { "go-down-symbolic" } else { "go-next-symbolic" }
+                        ).size(16))
+                        .push(widget::text(format!("Archived ({})", count)).size(12).font(cosmic::font::bold()))
+                        .spacing(spacing.space_xs)
+                        .align_y(Alignment::Center)
+                        .padding(spacing.space_s)
+                )
+                .on_press(SmsMessage::ToggleArchivedSection)
+                .width(Length::Fill)
+                .class(cosmic::theme::Button::Text)
+                .into(),
+            };
+            list = list.push(
+                widget::container(row)
+                    .height(Length::Fixed(*height))
+                    .clip(true)
+            );
         }
 
-        content = content.push(widget::scrollable(list).height(Length::Fill));
+        list = list.push(widget::Space::with_height(Length::Fixed(visible.space_after)));
+
+        content = content.push(
+            widget::scrollable(list)
+                .height(Length::Fill)
+                .on_scroll(|viewport| SmsMessage::ConversationListScrolled(
+                    viewport.absolute_offset().y,
+                    viewport.bounds().height,
+                ))
+        );
     }
 
     widget::container(content)
-        .width(Length::Fixed(300.0))
+        .width(width)
         .height(Length::Fill)
         .into()
 }
 
-fn conversation_matches_search(app: &SmsWindow, conv: &Conversation) -> bool {
-    if app.search_query.is_empty() {
-        return true;
-    }
-    
-    let query = app.search_query.to_lowercase();
-    conv.contact_name.to_lowercase().contains(&query)
-        || conv.phone_number.contains(&app.search_query)
-        || normalize_phone_number(&conv.phone_number).contains(&normalize_phone_number(&app.search_query))
-}
-
 fn view_conversation_item<'a>(
     app: &'a SmsWindow,
     conv: &'a Conversation,
@@ -221,23 +468,60 @@ fn view_conversation_item<'a>(
     let display_name = get_contact_name(app, &conv.phone_number)
         .unwrap_or_else(|| conv.phone_number.clone());
     
+    let mut title_row = widget::row()
+        .spacing(spacing.space_xs)
+        .align_y(Alignment::Center);
+    if conv.pinned {
+        title_row = title_row.push(widget::icon::from_name("view-pin-symbolic").size(12));
+    }
+    let is_muted = app.is_thread_muted(&conv.thread_id);
+    if is_muted {
+        title_row = title_row.push(widget::icon::from_name("notifications-disabled-symbolic").size(12));
+    }
+    
+    let state = app.last_message_state(conv);
+    let glyph: Option<Element<'a, SmsMessage>> = match state {
+        LastMessageState::Read => Some(widget::text("✓✓").size(12).into()),
+        LastMessageState::Sent => Some(widget::text("✓").size(12).into()),
+        LastMessageState::Sending => Some(widget::icon::from_name("content-loading-symbolic").size(12).into()),
+        LastMessageState::Failed => Some(widget::icon::from_name("dialog-error-symbolic").size(12).into()),
+        LastMessageState::Unread => Some(widget::text("●").size(10).into()),
+        LastMessageState::Received => None,
+    };
+    let mut preview = widget::text(conversation_preview(conv, PREVIEW_MAX_CHARS)).size(12);
+    if state == LastMessageState::Unread {
+        preview = preview.font(cosmic::font::bold());
+    }
+
     let button = widget::button::custom(
         widget::column()
             .push(
-                widget::row()
+                title_row
                     .push(widget::text(display_name).size(14).font(cosmic::font::bold()))
+                    .push_maybe(
+                        app.unified
+                            .then(|| app.device_names.get(&conv.device_id))
+                            .flatten()
+                            .map(|name| widget::text(name.as_str()).size(10))
+                    )
                     .push(widget::horizontal_space())
                     .push(widget::text(format_timestamp(conv.timestamp)).size(11))
                     .spacing(spacing.space_xs)
             )
-            .push(widget::text(&conv.last_message).size(12))
+            .push(
+                widget::row()
+                    .push_maybe(glyph)
+                    .push(preview)
+                    .spacing(spacing.space_xxs)
+                    .align_y(Alignment::Center)
+            )
             .spacing(spacing.space_xxs)
             .padding(spacing.space_s)
     )
     .on_press(SmsMessage::SelectThread(conv.thread_id.clone()))
     .width(Length::Fill);
     
-    if is_selected {
+    let item: Element<'a, SmsMessage> = if is_selected {
         widget::container(button)
             .class(cosmic::theme::Container::Primary)
             .into()
@@ -245,7 +529,62 @@ fn view_conversation_item<'a>(
         button
             .class(cosmic::theme::Button::Text)
             .into()
+    };
+
+    // Right-click opens the per-conversation actions
+    let item = widget::mouse_area(item)
+        .on_right_press(SmsMessage::ToggleThreadMenu(conv.thread_id.clone()));
+
+    if app.thread_menu.as_ref() != Some(&conv.thread_id) {
+        return item.into();
     }
+
+    let actions = widget::row()
+        .push(
+            widget::button::text(if conv.pinned { "Unpin" } else { "Pin" })
+                .on_press(SmsMessage::PinThread(conv.thread_id.clone()))
+        )
+        .push(
+            widget::button::text(if is_muted { "Unmute" } else { "Mute" })
+                .on_press(SmsMessage::ToggleMuteThread(conv.thread_id.clone()))
+        )
+        .push(
+            widget::button::text(if conv.archived { "Unarchive" } else { "Archive" })
+                .on_press(SmsMessage::ArchiveThread(conv.thread_id.clone()))
+        )
+        .push(
+            widget::button::text("Delete")
+                .on_press(SmsMessage::RequestDeleteThread(conv.thread_id.clone()))
+        )
+        .spacing(spacing.space_xs)
+        .padding([0, spacing.space_s, spacing.space_xs, spacing.space_s]);
+
+    if app.confirm_delete_thread.as_ref() == Some(&conv.thread_id) {
+        let confirm = widget::column()
+            .push(widget::text::caption(
+                "Delete this conversation from this computer? It stays on the phone and comes back with its next message."
+            ))
+            .push(
+                widget::row()
+                    .push(widget::button::text("Cancel").on_press(SmsMessage::CancelDeleteThread))
+                    .push(
+                        widget::button::destructive("Delete")
+                            .on_press(SmsMessage::DeleteThread(conv.thread_id.clone()))
+                    )
+                    .spacing(spacing.space_xs)
+            )
+            .spacing(spacing.space_xxs)
+            .padding([0, spacing.space_s, spacing.space_xs, spacing.space_s]);
+        return widget::column()
+            .push(item)
+            .push(confirm)
+            .into();
+    }
+
+    widget::column()
+        .push(item)
+        .push(actions)
+        .into()
 }
 
 /// Thread panel (messages + input)
@@ -301,11 +640,52 @@ fn view_thread_header<'a>(
     let display_name = get_contact_name(app, &conv.phone_number)
         .unwrap_or_else(|| conv.phone_number.clone());
     
+    let previous = app.adjacent_thread(-1).map(SmsMessage::SelectThread);
+    let next = app.adjacent_thread(1).map(SmsMessage::SelectThread);
+
+    let back = layout::is_single_panel(app.window_width).then(|| {
+        widget::button::icon(widget::icon::from_name("go-previous-symbolic"))
+            .on_press(SmsMessage::BackToList)
+            .tooltip("Back to conversations")
+    });
+
     widget::container(
-        widget::column()
-            .push(widget::text(display_name).size(16).font(cosmic::font::bold()))
-            .push(widget::text(&conv.phone_number).size(12))
+        widget::row()
+            .push_maybe(back)
+            .push(
+                widget::column()
+                    .push(widget::text(display_name).size(16).font(cosmic::font::bold()))
+                    .push(widget::text(&conv.phone_number).size(12))
+                    .spacing(spacing.space_xxs)
+                    .width(Length::Fill)
+            )
+            .push(
+                widget::button::icon(widget::icon::from_name("edit-copy-symbolic"))
+                    .on_press(SmsMessage::CopyPhoneNumber(conv.phone_number.clone()))
+                    .tooltip("Copy number")
+            )
+            .push(
+                widget::button::icon(widget::icon::from_name("call-start-symbolic"))
+                    .on_press(SmsMessage::CallPhoneNumber(conv.phone_number.clone()))
+                    .tooltip("Call")
+            )
+            .push(
+                widget::button::icon(widget::icon::from_name("contact-new-symbolic"))
+                    .on_press(SmsMessage::AddToContacts(conv.thread_id.clone()))
+                    .tooltip("Add to contacts")
+            )
+            .push(
+                widget::button::icon(widget::icon::from_name("go-up-symbolic"))
+                    .on_press_maybe(previous)
+                    .tooltip("Previous conversation (Alt+Up)")
+            )
+            .push(
+                widget::button::icon(widget::icon::from_name("go-down-symbolic"))
+                    .on_press_maybe(next)
+                    .tooltip("Next conversation (Alt+Down)")
+            )
             .spacing(spacing.space_xxs)
+            .align_y(Alignment::Center)
             .padding(spacing.space_s)
     )
     .class(cosmic::theme::Container::Card)
@@ -332,17 +712,75 @@ fn view_messages_list<'a>(app: &'a SmsWindow, spacing: &cosmic::cosmic_theme::Sp
             .padding(spacing.space_xl)
         );
     } else {
-        for msg in &app.messages {
+        if app.has_older_messages() {
+            let load_button = if app.loading_older {
+                widget::button::text("Loading older messages...")
+            } else {
+                widget::button::text("Load older messages")
+                    .on_press(SmsMessage::LoadOlderMessages)
+            };
+            messages_column = messages_column.push(
+                widget::container(load_button)
+                    .width(Length::Fill)
+                    .center_x(Length::Fill)
+            );
+        }
+
+        // Only the loaded window is materialized to keep the widget tree small
+        for msg in app.visible_messages() {
+            if app.unread_divider.as_ref() == Some(&msg.id) {
+                messages_column = messages_column.push(view_unread_divider(spacing));
+            }
             messages_column = messages_column.push(view_message_bubble(app, msg, spacing));
         }
     }
 
-    widget::scrollable(messages_column)
+    // Anchored to the end, so the absolute offset is the distance from
+    // the bottom and the reversed one the distance from the top
+    let list = widget::scrollable(messages_column)
+        .id(MESSAGE_LIST.clone())
         .height(Length::Fill)
+        .on_scroll(|viewport| SmsMessage::MessagesScrolled(
+            viewport.absolute_offset_reversed().y,
+            viewport.absolute_offset().y,
+        ))
         .direction(cosmic::iced::widget::scrollable::Direction::Vertical(
             cosmic::iced::widget::scrollable::Scrollbar::new()
                 .anchor(cosmic::iced::widget::scrollable::Anchor::End)
-        ))
+        ));
+
+    if app.messages_at_bottom {
+        return list.into();
+    }
+
+    let label = match app.new_below {
+        0 => "↓ Latest".to_string(),
+        1 => "↓ 1 new message".to_string(),
+        n => format!("↓ {} new messages", n),
+    };
+    let jump = widget::container(
+        widget::button::suggested(label).on_press(SmsMessage::JumpToLatest)
+    )
+    .align_bottom(Length::Fill)
+    .center_x(Length::Fill)
+    .padding(spacing.space_s);
+
+    cosmic::iced::widget::Stack::new()
+        .push(list)
+        .push(jump)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+}
+
+/// Line above the first message received while scrolled up
+fn view_unread_divider<'a>(spacing: &cosmic::cosmic_theme::Spacing) -> Element<'a, SmsMessage> {
+    widget::row()
+        .push(widget::divider::horizontal::default())
+        .push(widget::text::caption("Unread messages"))
+        .push(widget::divider::horizontal::default())
+        .spacing(spacing.space_xs)
+        .align_y(Alignment::Center)
         .into()
 }
 
@@ -369,20 +807,55 @@ fn view_message_bubble<'a>(
         );
     }
     
+    let footer = match &msg.status {
+        SendStatus::Synced => format_timestamp(msg.date),
+        SendStatus::Sending => format!("{} · Sending…", format_timestamp(msg.date)),
+        SendStatus::Failed(_) => format!("{} · Not sent", format_timestamp(msg.date)),
+    };
+
+    if let Some(subject) = &msg.subject {
+        message_content = message_content.push(
+            widget::text(subject.as_str())
+                .size(14)
+                .font(cosmic::font::bold())
+        );
+    }
+
+    let body = match &app.selecting {
+        Some((id, content)) if *id == msg.id => cosmic::iced::widget::text_editor(content)
+            .on_action(SmsMessage::SelectionAction)
+            .size(14)
+            .into(),
+        _ => view_message_body(&msg.body),
+    };
+
     message_content = message_content
-        .push(widget::text(&msg.body).size(14))
-        .push(widget::text(format_timestamp(msg.date)).size(11))
+        .push(body)
+        .push(widget::text(footer).size(11))
         .padding(spacing.space_s);
     
-    let message_bubble = if is_sent {
-        widget::container(message_content)
-            .class(cosmic::theme::Container::Primary)
-            .max_width(500.0)
-    } else {
-        widget::container(message_content)
-            .class(cosmic::theme::Container::Transparent)
-            .max_width(500.0)
-    };
+    if app.message_menu.as_ref() == Some(&msg.id) {
+        message_content = message_content.push(
+            widget::row()
+                .push(widget::button::text("Copy text").on_press(SmsMessage::CopyMessage(msg.id.clone())))
+                .push(widget::button::text(if app.selecting.as_ref().is_some_and(|(id, _)| *id == msg.id) {
+                    "Done selecting"
+                } else {
+                    "Select text"
+                }).on_press(SmsMessage::SelectMessageText(msg.id.clone())))
+                .push(widget::button::text("Forward").on_press(SmsMessage::ForwardMessage(msg.id.clone())))
+                .push(widget::button::destructive("Delete").on_press(SmsMessage::DeleteMessage(msg.id.clone())))
+                .spacing(spacing.space_xxs)
+        );
+    }
+    
+    let message_bubble = widget::container(message_content)
+        .class(app.appearance.bubble_class(is_sent))
+        .max_width(500.0);
+
+    // Right-click opens the per-message actions
+    let message_bubble = widget::mouse_area(message_bubble)
+        .on_right_press(SmsMessage::ToggleMessageMenu(msg.id.clone()));
 
     if is_sent {
         widget::row()
@@ -399,21 +872,122 @@ fn view_message_bubble<'a>(
 }
 
 fn view_message_input<'a>(app: &'a SmsWindow, spacing: &cosmic::cosmic_theme::Spacing) -> Element<'a, SmsMessage> {
-    widget::row()
+    let mut input_row = widget::row()
         .push(
             widget::text_input("Type a message...", &app.message_input)
+                .id(COMPOSER_INPUT.clone())
                 .on_input(SmsMessage::UpdateInput)
                 .on_submit(|_| SmsMessage::SendMessage)
                 .padding(spacing.space_s)
                 .width(Length::Fill)
-        )
+        );
+
+    // Segment counter, once the message is long enough to matter
+    let segments = utils::segment_info(&app.message_input);
+    if segments.segments > 1 || (segments.segments == 1 && segments.remaining < SEGMENT_COUNTER_FROM) {
+        input_row = input_row.push(widget::text::caption(format!("{} · {} SMS", segments.remaining, segments.segments)));
+    }
+
+    let input_row = input_row
         .push(
             widget::button::suggested("Send")
                 .on_press(SmsMessage::SendMessage)
         )
         .spacing(spacing.space_xs)
         .padding(spacing.space_s)
-        .align_y(Alignment::Center)
+        .align_y(Alignment::Center);
+
+    let mut column = widget::column();
+
+    if let Some(segments) = app.confirm_send {
+        column = column.push(view_send_confirmation(app, segments, spacing));
+    }
+
+    if let Some(error) = &app.send_error {
+        column = column.push(
+            widget::container(widget::text(error.as_str()).size(12))
+                .padding([spacing.space_xxs, spacing.space_s])
+                .class(cosmic::theme::Container::Card)
+        );
+    }
+
+    if !app.quick_replies.replies().is_empty() {
+        let chips: Vec<Element<'a, SmsMessage>> = app.quick_replies.replies().iter()
+            .enumerate()
+            .map(|(i, reply)| {
+                widget::button::standard(reply.as_str())
+                    .on_press(SmsMessage::UseQuickReply(i))
+                    .into()
+            })
+            .collect();
+        column = column.push(
+            widget::container(
+                widget::flex_row(chips)
+                    .row_spacing(spacing.space_xxs)
+                    .column_spacing(spacing.space_xxs)
+            )
+            .padding([spacing.space_xs, spacing.space_s, 0, spacing.space_s])
+        );
+    }
+
+    column.push(input_row).into()
+}
+
+/// Ask before sending a message that splits into many SMS
+fn view_send_confirmation<'a>(app: &'a SmsWindow, segments: usize, spacing: &cosmic::cosmic_theme::Spacing) -> Element<'a, SmsMessage> {
+    widget::container(
+        widget::column()
+            .push(widget::text(format!(
+                "This message will be sent as {} SMS. Your carrier may charge for each one.", segments
+            )).size(13))
+            .push(
+                widget::row()
+                    .push(
+                        widget::checkbox("Don't ask again", app.dont_ask_again)
+                            .on_toggle(SmsMessage::DontAskAgain)
+                            .width(Length::Fill)
+                    )
+                    .push(widget::button::text("Cancel").on_press(SmsMessage::ConfirmSend(false)))
+                    .push(widget::button::suggested("Send anyway").on_press(SmsMessage::ConfirmSend(true)))
+                    .spacing(spacing.space_xs)
+                    .align_y(Alignment::Center)
+            )
+            .spacing(spacing.space_xs)
+            .padding(spacing.space_xs)
+    )
+    .class(cosmic::theme::Container::Card)
+    .width(Length::Fill)
+    .into()
+}
+
+/// Message text with detected links rendered as clickable spans
+fn view_message_body(body: &str) -> Element<'_, SmsMessage> {
+    let links = find_links(body);
+    if links.is_empty() {
+        return widget::text(body).size(14).into();
+    }
+
+    let mut spans = Vec::with_capacity(links.len() * 2 + 1);
+    let mut last = 0;
+    for (range, _) in links {
+        if range.start > last {
+            spans.push(cosmic::iced::widget::span(&body[last..range.start]));
+        }
+        let url = &body[range.clone()];
+        spans.push(
+            cosmic::iced::widget::span(url)
+                .link(url.to_string())
+                .underline(true)
+        );
+        last = range.end;
+    }
+    if last < body.len() {
+        spans.push(cosmic::iced::widget::span(&body[last..]));
+    }
+
+    cosmic::iced::widget::rich_text(spans)
+        .size(14)
+        .on_link_click(SmsMessage::OpenLink)
         .into()
 }
 
@@ -3,6 +3,8 @@
 
 use ashpd::desktop::file_chooser::SelectedFiles;
 use percent_encoding::percent_decode;
+use std::path::PathBuf;
+use tracing::{debug, warn};
 
 pub async fn pick_files(
     title: impl Into<String>,
@@ -25,25 +27,20 @@ pub async fn pick_files(
                     let paths: Vec<String> = files
                         .uris()
                         .iter()
-                        .map(|u| {
-                            percent_decode(u.path().as_bytes())
-                                .decode_utf8()
-                                .unwrap_or_default()
-                                .to_string()
-                        })
+                        .map(|u| decode_uri_path(u.path()))
                         .filter(|s| !s.is_empty())
                         .collect();
                     
-                    eprintln!("Selected {} file(s)", paths.len());
+                    debug!("Selected {} file(s)", paths.len());
                     return paths;
                 }
                 Err(e) => {
-                    eprintln!("Failed to get file picker response: {}", e);
+                    warn!("Failed to get file picker response: {}", e);
                 }
             }
         }
         Err(e) => {
-            eprintln!("Failed to open file picker: {}", e);
+            warn!("Failed to open file picker: {}", e);
         }
     }
     
@@ -97,10 +94,7 @@ pub async fn pick_folder(title: impl Into<String>) -> Option<String> {
             match request.response() {
                 Ok(files) => {
                     if let Some(uri) = files.uris().first() {
-                        let path = percent_decode(uri.path().as_bytes())
-                            .decode_utf8()
-                            .unwrap_or_default()
-                            .to_string();
+                        let path = decode_uri_path(uri.path());
                         
                         if !path.is_empty() {
                             return Some(path);
@@ -108,21 +102,146 @@ pub async fn pick_folder(title: impl Into<String>) -> Option<String> {
                     }
                 }
                 Err(e) => {
-                    eprintln!("Failed to get folder picker response: {}", e);
+                    warn!("Failed to get folder picker response: {}", e);
                 }
             }
         }
         Err(e) => {
-            eprintln!("Failed to open folder picker: {}", e);
+            warn!("Failed to open folder picker: {}", e);
         }
     }
     
     None
 }
 
-/// Read clipboard content using wl-paste
+/// Ask where to save a file, starting in ~/Downloads with `suggested_name`.
+/// Returns `None` if the dialog was cancelled or couldn't be shown.
+#[allow(dead_code)]
+pub async fn save_file(title: impl Into<String>, suggested_name: &str) -> Option<PathBuf> {
+    let title_str = title.into();
+    let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+    let downloads = PathBuf::from(format!("{}/Downloads", home));
+
+    let request = SelectedFiles::save_file()
+        .title(title_str.as_str())
+        .accept_label("Save")
+        .modal(true)
+        .current_name(suggested_name);
+    // A missing Downloads folder leaves the starting folder to the portal
+    let request = if downloads.is_dir() {
+        match request.current_folder(&downloads) {
+            Ok(request) => request,
+            Err(e) => {
+                warn!("Failed to set save dialog folder: {}", e);
+                return None;
+            }
+        }
+    } else {
+        request
+    };
+
+    match request.send().await {
+        Ok(request) => match request.response() {
+            Ok(files) => {
+                let path = files.uris().first().map(|u| decode_uri_path(u.path()))?;
+                if path.is_empty() { None } else { Some(PathBuf::from(path)) }
+            }
+            Err(e) => {
+                debug!("Save dialog closed without a file: {}", e);
+                None
+            }
+        },
+        Err(e) => {
+            warn!("Failed to open save dialog: {}", e);
+            None
+        }
+    }
+}
+
+/// Write `bytes` to a path chosen with `save_file`
+#[allow(dead_code)]
+pub async fn write_file(path: PathBuf, bytes: Vec<u8>) -> Result<(), std::io::Error> {
+    tokio::fs::write(&path, bytes).await?;
+    debug!("Saved {}", path.display());
+    Ok(())
+}
+
+/// Percent-decode the path component of a file URI
+fn decode_uri_path(path: &str) -> String {
+    percent_decode(path.as_bytes())
+        .decode_utf8()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Convert a dropped `text/uri-list` payload into local file paths,
+/// decoding them the same way as the file picker results.
+pub fn paths_from_uri_list(data: &[u8]) -> Vec<PathBuf> {
+    String::from_utf8_lossy(data)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.strip_prefix("file://"))
+        // Skip an optional host component ("file://localhost/...")
+        .filter_map(|rest| rest.find('/').map(|i| &rest[i..]))
+        .map(decode_uri_path)
+        .filter(|s| !s.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// Write text to the clipboard using wl-copy
+pub async fn write_clipboard(content: &str) -> Result<(), std::io::Error> {
+    use tokio::io::AsyncWriteExt;
+
+    let mut child = tokio::process::Command::new("wl-copy")
+        .stdin(std::process::Stdio::piped())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(content.as_bytes()).await?;
+    }
+
+    let status = child.wait().await?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "Failed to write clipboard"
+        ))
+    }
+}
+
+/// Text types offered by the clipboard, best first
+const CLIPBOARD_TEXT_TYPES: [&str; 5] = ["text/plain;charset=utf-8", "UTF8_STRING", "text/plain", "STRING", "TEXT"];
+
+/// The clipboard type to read as text, if any. Images and other binary
+/// content are never offered as text.
+fn clipboard_text_type(types: &str) -> Option<&str> {
+    let types: Vec<&str> = types.lines().map(str::trim).collect();
+    CLIPBOARD_TEXT_TYPES.into_iter()
+        .find(|t| types.contains(t))
+        .or_else(|| types.into_iter().find(|t| t.starts_with("text/")))
+}
+
+/// Read clipboard text using wl-paste. Fails with `InvalidData` when the
+/// clipboard only holds non-text content such as an image.
 pub async fn read_clipboard() -> Result<String, std::io::Error> {
+    let types = tokio::process::Command::new("wl-paste")
+        .arg("--list-types")
+        .output()
+        .await?;
+    let types = String::from_utf8_lossy(&types.stdout);
+    let Some(mime) = clipboard_text_type(&types) else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "The clipboard doesn't contain text"
+        ));
+    };
+
     let output = tokio::process::Command::new("wl-paste")
+        .args(["--type", mime])
         .output()
         .await?;
     
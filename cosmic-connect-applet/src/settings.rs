@@ -1,7 +1,18 @@
 // cosmic-connect-applet/src/settings.rs
 
+use cosmic::{app::Core, iced::Length, widget, Application, Element, Task, Action};
 use cosmic_connect_applet::backend;
+use cosmic_connect_applet::device_aliases::DeviceAliases;
 use cosmic_connect_applet::models::Device;
+use cosmic_connect_applet::plugins::sms::{QuickRepliesConfig, RetentionConfig, SendConfirmConfig, MAX_QUICK_REPLIES};
+use cosmic_connect_applet::plugins::sms::auto_open::AutoOpenConfig;
+use cosmic_connect_applet::portal;
+use cosmic_connect_applet::settings_backup::{self, SettingsBackup};
+use cosmic_connect_common::plugin_config::{DevicePluginsConfig, NOTIFICATIONS_PLUGIN, TELEPHONY_PLUGIN};
+use kdeconnect_dbus_client::{Diagnostics, LoggedPacket};
+use std::collections::{BTreeSet, HashMap};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
 
 #[derive(Debug, Clone)]
 pub struct DevicePermissions {
@@ -34,102 +45,1096 @@ pub async fn fetch_devices() -> Vec<Device> {
 }
 
 pub async fn pair_device(device_id: String) {
-    eprintln!("=== Requesting Pairing ===");
-    eprintln!("Device: {}", device_id);
+    debug!("Requesting pairing with {}", device_id);
     
     match backend::pair_device(device_id).await {
-        Ok(_) => eprintln!("✓ Pairing request sent successfully"),
-        Err(e) => eprintln!("✗ Failed to send pairing request: {:?}", e),
+        Ok(_) => info!("Pairing request sent successfully"),
+        Err(e) => warn!("Failed to send pairing request: {:?}", e),
     }
 }
 
-pub async fn unpair_device(device_id: String) {
-    eprintln!("=== Unpairing Device ===");
-    eprintln!("Device: {}", device_id);
+pub async fn unpair_device(device_id: String, keep_data: bool) {
+    debug!("Unpairing device {}", device_id);
     
-    match backend::unpair_device(device_id).await {
-        Ok(_) => eprintln!("✓ Device unpaired successfully"),
-        Err(e) => eprintln!("✗ Failed to unpair device: {:?}", e),
+    match backend::unpair_device(device_id, keep_data).await {
+        Ok(_) => info!("Device unpaired successfully"),
+        Err(e) => warn!("Failed to unpair device: {:?}", e),
     }
 }
 
 pub async fn ping_device(device_id: String) {
-    eprintln!("=== Pinging Device ===");
-    eprintln!("Device: {}", device_id);
+    debug!("Pinging device {}", device_id);
     
-    match backend::ping_device(device_id).await {
-        Ok(_) => eprintln!("✓ Ping sent successfully"),
-        Err(e) => eprintln!("✗ Failed to send ping: {:?}", e),
+    match backend::ping_device(device_id, None).await {
+        Ok(_) => info!("Ping sent successfully"),
+        Err(e) => warn!("Failed to send ping: {:?}", e),
     }
 }
 
 pub async fn ring_device(device_id: String) {
-    eprintln!("=== Ringing Device (Find My Phone) ===");
-    eprintln!("Device: {}", device_id);
+    debug!("Ringing device {} (find my phone)", device_id);
     
     match backend::ring_device(device_id).await {
-        Ok(_) => eprintln!("✓ Ring command sent successfully"),
-        Err(e) => eprintln!("✗ Failed to ring device: {:?}", e),
+        Ok(_) => info!("Ring command sent successfully"),
+        Err(e) => warn!("Failed to ring device: {:?}", e),
     }
 }
 
 pub async fn send_files(device_id: String, files: Vec<String>) {
-    eprintln!("=== Sending Files ===");
-    eprintln!("Device: {}", device_id);
-    eprintln!("Files: {} file(s)", files.len());
+    debug!("Sending {} file(s) to {}", files.len(), device_id);
     
     match backend::send_files(device_id, files).await {
-        Ok(_) => eprintln!("✓ Files sent successfully"),
-        Err(e) => eprintln!("✗ Failed to send files: {:?}", e),
+        Ok(_) => info!("Files sent successfully"),
+        Err(e) => warn!("Failed to send files: {:?}", e),
     }
 }
 
 pub async fn browse_device(device_id: String) {
-    eprintln!("=== Browsing Device Filesystem ===");
-    eprintln!("Device: {}", device_id);
+    debug!("Browsing filesystem of {}", device_id);
     
     match backend::browse_device_filesystem(device_id).await {
-        Ok(_) => eprintln!("✓ Browse command sent successfully"),
-        Err(e) => eprintln!("✗ Failed to browse device: {:?}", e),
+        Ok(_) => info!("Browse command sent successfully"),
+        Err(e) => warn!("Failed to browse device: {:?}", e),
     }
 }
 
-pub async fn set_plugin_enabled_internal(_device_id: String, plugin_name: String, enabled: bool) -> Result<(), String> {
-    eprintln!("=== {} Plugin ===", if enabled { "Enabling" } else { "Disabling" });
-    eprintln!("Plugin: {}", plugin_name);
-    eprintln!("⚠️  Plugin configuration not yet implemented in backend");
-    Ok(())
+/// Switch a plugin on or off for a device in its KDE Connect config. The
+/// service reads the switch each time the plugin would act.
+pub async fn set_plugin_enabled_internal(device_id: String, plugin_name: String, enabled: bool) -> Result<(), String> {
+    debug!("{} plugin {} for {}", if enabled { "Enabling" } else { "Disabling" }, plugin_name, device_id);
+    let mut config = DevicePluginsConfig::load(&device_id)
+        .map_err(|e| format!("Couldn't read plugin settings for {}: {}", device_id, e))?;
+    config.set_enabled(&plugin_name, enabled);
+    config.save(&device_id)
+        .map_err(|e| format!("Couldn't save plugin settings for {}: {}", device_id, e))
 }
 
-pub async fn load_device_permissions(_device_id: String) -> DevicePermissions {
+/// Persisted plugin switches for a device, with the defaults for unset ones
+pub async fn load_device_permissions(device_id: String) -> DevicePermissions {
+    let config = DevicePluginsConfig::load(&device_id).unwrap_or_else(|e| {
+        warn!("Failed to load plugin settings for {}: {}", device_id, e);
+        DevicePluginsConfig::default()
+    });
     DevicePermissions {
-        battery: true,
-        clipboard: true,
-        connectivity_report: true,
-        contacts: false,
-        findmyphone: true,
-        lockdevice: false,
-        mousepad: false,
-        mpris: false,
-        notification: true,
-        photo: false,
-        ping: true,
-        presenter: false,
-        remotekeyboard: false,
-        remotecommands: false,
-        remotesystemvolume: false,
-        runcommand: false,
-        sendnotifications: true,
-        sftp: true,
-        share: true,
-        sms: true,
-        telephony: false,
-        virtualmonitor: false,
-    }
-}
-
-// Placeholder main function for settings binary
-fn main() {
-    eprintln!("Settings app not yet implemented");
-    eprintln!("This will be a full COSMIC settings application");
+        battery: config.allows("battery"),
+        clipboard: config.allows("clipboard"),
+        connectivity_report: config.allows("connectivity_report"),
+        contacts: config.allows("contacts"),
+        findmyphone: config.allows("findmyphone"),
+        lockdevice: config.allows("lockdevice"),
+        mousepad: config.allows("mousepad"),
+        mpris: config.allows("mpriscontrol"),
+        notification: config.allows(NOTIFICATIONS_PLUGIN),
+        photo: config.allows("photo"),
+        ping: config.allows("ping"),
+        presenter: config.allows("presenter"),
+        remotekeyboard: config.allows("remotekeyboard"),
+        remotecommands: config.allows("remotecommands"),
+        remotesystemvolume: config.allows("remotesystemvolume"),
+        runcommand: config.allows("runcommand"),
+        sendnotifications: config.allows("sendnotifications"),
+        sftp: config.allows("sftp"),
+        share: config.allows("share"),
+        sms: config.allows("sms"),
+        telephony: config.allows(TELEPHONY_PLUGIN),
+        virtualmonitor: config.allows("virtualmonitor"),
+    }
+}
+
+/// Environment variable that shows developer tools, shared with the service
+const DEV_MODE_ENV: &str = "COSMIC_CONNECT_DEV";
+
+/// Packet log time ranges in seconds (0 = everything still in the log)
+const PACKET_RANGES: [u64; 4] = [60, 300, 900, 0];
+const PACKET_RANGE_LABELS: [&str; 4] = ["Last minute", "Last 5 minutes", "Last 15 minutes", "All"];
+
+/// Segment counts offered for the long-message confirmation
+const CONFIRM_SEGMENTS: [usize; 5] = [1, 2, 3, 5, 10];
+const CONFIRM_SEGMENT_LABELS: [&str; 5] = ["More than 1 SMS", "More than 2 SMS", "More than 3 SMS", "More than 5 SMS", "More than 10 SMS"];
+
+/// SMS retention limits offered (None = unlimited). Caches never hold more
+/// than 50 messages per thread.
+const RETENTION_CONVERSATIONS: [Option<usize>; 5] = [None, Some(500), Some(250), Some(100), Some(50)];
+const RETENTION_CONVERSATION_LABELS: [&str; 5] = ["Unlimited", "500", "250", "100", "50"];
+const RETENTION_MESSAGES: [Option<usize>; 3] = [None, Some(25), Some(10)];
+const RETENTION_MESSAGE_LABELS: [&str; 3] = ["Newest 50", "Newest 25", "Newest 10"];
+const RETENTION_AGE_DAYS: [Option<u32>; 4] = [None, Some(365), Some(90), Some(30)];
+const RETENTION_AGE_LABELS: [&str; 4] = ["Forever", "1 year", "90 days", "30 days"];
+
+#[derive(Clone, Debug)]
+pub enum SettingsMessage {
+    Connected(Result<(), String>),
+    TogglePacketLog,
+    RefreshPacketLog,
+    PacketLogLoaded(Result<Vec<LoggedPacket>, String>),
+    PacketFilterChanged(String),
+    PacketRangeSelected(usize),
+    DevicesLoaded(Vec<Device>),
+    DeviceSelected(String, bool),
+    AliasInput(String, String), // device_id, typed name
+    SaveAlias(String),
+    RequestBulkUnpair,
+    CancelBulkUnpair,
+    BulkUnpairKeepData(bool),
+    ConfirmBulkUnpair,
+    BulkUnpairFinished(Vec<(String, Result<(), String>)>),
+    QuickReplyInput(String),
+    AddQuickReply,
+    RemoveQuickReply(usize),
+    QuickReplySendImmediately(bool),
+    AutoOpenToggled(bool),
+    SendConfirmToggled(bool),
+    ConfirmSegmentsSelected(usize),
+    RetentionConversationsSelected(usize),
+    RetentionMessagesSelected(usize),
+    RetentionAgeSelected(usize),
+    ToggleTroubleshooting,
+    RefreshDiagnostics,
+    DiagnosticsLoaded(Result<Diagnostics, String>),
+    /// Copy the diagnostics report to the clipboard for bug reports
+    CopyDiagnostics,
+    DiagnosticsCopied,
+    BackupIncludeSecrets(bool),
+    ExportSettings,
+    /// Ok(message) or Err(reason); None if the save dialog was cancelled
+    SettingsExported(Option<Result<String, String>>),
+    ImportSettings,
+    /// A backup was picked and checked; None if the dialog was cancelled
+    ImportLoaded(Option<Result<SettingsBackup, String>>),
+    ConfirmImport,
+    CancelImport,
+    /// Paths restored, relative to ~/.config
+    ImportFinished(Result<Vec<String>, String>),
+}
+
+pub struct SettingsApp {
+    core: Core,
+    connected: bool,
+    paired_devices: Vec<Device>,
+    /// Names typed into the rename fields, by device id
+    alias_inputs: HashMap<String, String>,
+    alias_error: Option<String>,
+    /// Devices ticked for bulk actions
+    selected_devices: BTreeSet<String>,
+    /// The bulk unpair confirmation is showing
+    confirm_bulk_unpair: bool,
+    bulk_unpair_keep_data: bool,
+    /// Devices with an unpair in flight; they can't be selected or renamed
+    unpairing: BTreeSet<String>,
+    bulk_unpair_errors: Vec<String>,
+    /// Canned replies offered in SMS windows
+    quick_replies: QuickRepliesConfig,
+    new_quick_reply: String,
+    /// When SMS windows ask before sending long messages
+    send_confirm: SendConfirmConfig,
+    /// SMS windows open on the first message of a new conversation
+    auto_open: AutoOpenConfig,
+    /// How much SMS history windows keep
+    retention: RetentionConfig,
+    /// Developer tools are shown (COSMIC_CONNECT_DEV is set)
+    dev_mode: bool,
+    show_packet_log: bool,
+    packets: Option<Result<Vec<LoggedPacket>, String>>,
+    /// Substring matched against the packet type
+    packet_filter: String,
+    /// Index into PACKET_RANGES
+    packet_range: usize,
+    show_troubleshooting: bool,
+    diagnostics: Option<Result<Diagnostics, String>>,
+    loading_diagnostics: bool,
+    /// The current report was copied, until the next refresh
+    copied: bool,
+    /// Export certificate and key files too
+    backup_include_secrets: bool,
+    /// Backup waiting for the user to confirm the import
+    pending_import: Option<SettingsBackup>,
+    /// Outcome of the last export or import: Ok(message) or Err(reason)
+    backup_status: Option<Result<String, String>>,
+}
+
+impl Application for SettingsApp {
+    type Executor = cosmic::executor::Default;
+    type Flags = ();
+    type Message = SettingsMessage;
+    const APP_ID: &'static str = "com.system76.CosmicConnectSettings";
+
+    fn core(&self) -> &Core { &self.core }
+    fn core_mut(&mut self) -> &mut Core { &mut self.core }
+
+    fn init(core: Core, _flags: Self::Flags) -> (Self, Task<Action<Self::Message>>) {
+        let app = Self {
+            core,
+            connected: false,
+            paired_devices: Vec::new(),
+            alias_inputs: HashMap::new(),
+            alias_error: None,
+            selected_devices: BTreeSet::new(),
+            confirm_bulk_unpair: false,
+            bulk_unpair_keep_data: false,
+            unpairing: BTreeSet::new(),
+            bulk_unpair_errors: Vec::new(),
+            quick_replies: QuickRepliesConfig::load().unwrap_or_else(|e| {
+                warn!("Failed to load quick replies: {}", e);
+                QuickRepliesConfig::default()
+            }),
+            new_quick_reply: String::new(),
+            send_confirm: SendConfirmConfig::load().unwrap_or_else(|e| {
+                warn!("Failed to load send confirmation settings: {}", e);
+                SendConfirmConfig::default()
+            }),
+            auto_open: AutoOpenConfig::load().unwrap_or_else(|e| {
+                warn!("Failed to load SMS auto-open setting: {}", e);
+                AutoOpenConfig::default()
+            }),
+            retention: RetentionConfig::load().unwrap_or_else(|e| {
+                warn!("Failed to load SMS retention limits: {}", e);
+                RetentionConfig::default()
+            }),
+            dev_mode: std::env::var(DEV_MODE_ENV).is_ok_and(|v| !v.is_empty() && v != "0"),
+            show_packet_log: false,
+            packets: None,
+            packet_filter: String::new(),
+            packet_range: 1,
+            show_troubleshooting: false,
+            diagnostics: None,
+            loading_diagnostics: false,
+            copied: false,
+            backup_include_secrets: false,
+            pending_import: None,
+            backup_status: None,
+        };
+
+        let connect = cosmic::task::future(async {
+            Action::App(SettingsMessage::Connected(backend::initialize().await.map_err(|e| e.to_string())))
+        });
+
+        (app, connect)
+    }
+
+    fn update(&mut self, message: Self::Message) -> Task<Action<Self::Message>> {
+        match message {
+            SettingsMessage::Connected(result) => {
+                if let Err(e) = result {
+                    warn!("Failed to connect to kdeconnect-service: {}", e);
+                    return Task::none();
+                }
+                self.connected = true;
+                let devices = cosmic::task::future(async {
+                    Action::App(SettingsMessage::DevicesLoaded(backend::fetch_devices().await))
+                });
+                let mut tasks = vec![devices];
+                if self.show_troubleshooting {
+                    tasks.push(cosmic::task::message(Action::App(SettingsMessage::RefreshDiagnostics)));
+                }
+                return Task::batch(tasks);
+            }
+            SettingsMessage::DevicesLoaded(devices) => {
+                self.paired_devices = devices.into_iter().filter(|d| d.is_paired).collect();
+                self.alias_inputs = self.paired_devices.iter()
+                    .map(|d| (d.id.clone(), d.name.clone()))
+                    .collect();
+                let paired: BTreeSet<&String> = self.paired_devices.iter().map(|d| &d.id).collect();
+                self.selected_devices.retain(|id| paired.contains(id));
+            }
+            SettingsMessage::QuickReplyInput(text) => {
+                self.new_quick_reply = text;
+            }
+            SettingsMessage::AddQuickReply => {
+                if self.quick_replies.add(&self.new_quick_reply) {
+                    self.new_quick_reply.clear();
+                    self.save_quick_replies();
+                }
+            }
+            SettingsMessage::RemoveQuickReply(index) => {
+                if self.quick_replies.remove(index).is_some() {
+                    self.save_quick_replies();
+                }
+            }
+            SettingsMessage::QuickReplySendImmediately(send) => {
+                self.quick_replies.send_immediately = send;
+                self.save_quick_replies();
+            }
+            SettingsMessage::SendConfirmToggled(enabled) => {
+                self.send_confirm.enabled = enabled;
+                self.save_send_confirm();
+            }
+            SettingsMessage::ConfirmSegmentsSelected(index) => {
+                if let Some(&segments) = CONFIRM_SEGMENTS.get(index) {
+                    self.send_confirm.max_segments = segments;
+                    self.save_send_confirm();
+                }
+            }
+            SettingsMessage::RetentionConversationsSelected(index) => {
+                if let Some(&max) = RETENTION_CONVERSATIONS.get(index) {
+                    self.retention.max_conversations = max;
+                    self.save_retention();
+                }
+            }
+            SettingsMessage::RetentionMessagesSelected(index) => {
+                if let Some(&max) = RETENTION_MESSAGES.get(index) {
+                    self.retention.max_messages_per_thread = max;
+                    self.save_retention();
+                }
+            }
+            SettingsMessage::RetentionAgeSelected(index) => {
+                if let Some(&days) = RETENTION_AGE_DAYS.get(index) {
+                    self.retention.max_age_days = days;
+                    self.save_retention();
+                }
+            }
+            SettingsMessage::AutoOpenToggled(enabled) => {
+                self.auto_open.enabled = enabled;
+                if let Err(e) = self.auto_open.save() {
+                    warn!("Failed to save SMS auto-open setting: {}", e);
+                }
+            }
+            SettingsMessage::DeviceSelected(device_id, selected) => {
+                if self.unpairing.contains(&device_id) {
+                    return Task::none();
+                }
+                if selected {
+                    self.selected_devices.insert(device_id);
+                } else {
+                    self.selected_devices.remove(&device_id);
+                }
+                if self.selected_devices.is_empty() {
+                    self.confirm_bulk_unpair = false;
+                }
+            }
+            SettingsMessage::AliasInput(device_id, name) => {
+                self.alias_inputs.insert(device_id, name);
+            }
+            SettingsMessage::SaveAlias(device_id) => {
+                if self.unpairing.contains(&device_id) {
+                    return Task::none();
+                }
+                let name = self.alias_inputs.get(&device_id).cloned().unwrap_or_default();
+                let saved = DeviceAliases::load().and_then(|mut aliases| {
+                    if aliases.set(&device_id, &name) {
+                        aliases.save()?;
+                    }
+                    Ok(())
+                });
+                if let Err(e) = saved {
+                    warn!("Failed to save alias for {}: {}", device_id, e);
+                    self.alias_error = Some(format!("Couldn't rename device: {}", e));
+                    return Task::none();
+                }
+                self.alias_error = None;
+                // Reload so a cleared alias shows the device's own name again
+                return cosmic::task::future(async {
+                    Action::App(SettingsMessage::DevicesLoaded(backend::fetch_devices().await))
+                });
+            }
+            SettingsMessage::RequestBulkUnpair => {
+                if !self.selected_devices.is_empty() && self.unpairing.is_empty() {
+                    self.confirm_bulk_unpair = true;
+                    self.bulk_unpair_errors.clear();
+                }
+            }
+            SettingsMessage::CancelBulkUnpair => {
+                self.confirm_bulk_unpair = false;
+            }
+            SettingsMessage::BulkUnpairKeepData(keep) => {
+                self.bulk_unpair_keep_data = keep;
+            }
+            SettingsMessage::ConfirmBulkUnpair => {
+                self.confirm_bulk_unpair = false;
+                if !self.unpairing.is_empty() {
+                    return Task::none();
+                }
+                let device_ids: Vec<String> = std::mem::take(&mut self.selected_devices).into_iter().collect();
+                self.unpairing.extend(device_ids.iter().cloned());
+                let keep_data = self.bulk_unpair_keep_data;
+                return cosmic::task::future(async move {
+                    let mut results = Vec::with_capacity(device_ids.len());
+                    for device_id in device_ids {
+                        info!("Unpairing {}", device_id);
+                        let result = backend::unpair_device(device_id.clone(), keep_data).await
+                            .map_err(|e| e.to_string());
+                        results.push((device_id, result));
+                    }
+                    Action::App(SettingsMessage::BulkUnpairFinished(results))
+                });
+            }
+            SettingsMessage::BulkUnpairFinished(results) => {
+                for (device_id, result) in results {
+                    self.unpairing.remove(&device_id);
+                    if let Err(e) = result {
+                        warn!("Failed to unpair {}: {}", device_id, e);
+                        let name = self.paired_devices.iter()
+                            .find(|d| d.id == device_id)
+                            .map_or(device_id.clone(), |d| d.name.clone());
+                        self.bulk_unpair_errors.push(format!("{}: {}", name, e));
+                    }
+                }
+                return cosmic::task::future(async {
+                    Action::App(SettingsMessage::DevicesLoaded(backend::fetch_devices().await))
+                });
+            }
+            SettingsMessage::TogglePacketLog => {
+                self.show_packet_log = !self.show_packet_log;
+                if self.show_packet_log {
+                    return cosmic::task::message(Action::App(SettingsMessage::RefreshPacketLog));
+                }
+            }
+            SettingsMessage::RefreshPacketLog => {
+                let range = PACKET_RANGES.get(self.packet_range).copied().unwrap_or(0);
+                let since_ms = if range == 0 { 0 } else { now_millis() - (range * 1000) as i64 };
+                return cosmic::task::future(async move {
+                    Action::App(SettingsMessage::PacketLogLoaded(backend::fetch_packet_log(since_ms).await))
+                });
+            }
+            SettingsMessage::PacketLogLoaded(result) => {
+                self.packets = Some(result);
+            }
+            SettingsMessage::PacketFilterChanged(filter) => {
+                self.packet_filter = filter;
+            }
+            SettingsMessage::PacketRangeSelected(index) => {
+                self.packet_range = index;
+                return cosmic::task::message(Action::App(SettingsMessage::RefreshPacketLog));
+            }
+            SettingsMessage::BackupIncludeSecrets(include) => {
+                self.backup_include_secrets = include;
+            }
+            SettingsMessage::ExportSettings => {
+                self.backup_status = None;
+                let include_secrets = self.backup_include_secrets;
+                return cosmic::task::future(async move {
+                    let Some(path) = portal::save_file("Export settings", settings_backup::DEFAULT_FILE_NAME).await else {
+                        return Action::App(SettingsMessage::SettingsExported(None));
+                    };
+                    let result = match SettingsBackup::collect(include_secrets) {
+                        Ok(backup) => portal::write_file(path.clone(), backup.to_json().into_bytes()).await
+                            .map(|()| format!("Exported {} file(s) to {}", backup.len(), path.display()))
+                            .map_err(|e| format!("Couldn't write {}: {}", path.display(), e)),
+                        Err(e) => Err(format!("Couldn't read settings: {}", e)),
+                    };
+                    Action::App(SettingsMessage::SettingsExported(Some(result)))
+                });
+            }
+            SettingsMessage::SettingsExported(result) => {
+                self.backup_status = result;
+            }
+            SettingsMessage::ImportSettings => {
+                self.backup_status = None;
+                self.pending_import = None;
+                return cosmic::task::future(async {
+                    let Some(path) = portal::pick_files("Import settings", false, None).await.into_iter().next() else {
+                        return Action::App(SettingsMessage::ImportLoaded(None));
+                    };
+                    let result = tokio::fs::read_to_string(&path).await
+                        .map_err(|e| format!("Couldn't read {}: {}", path, e))
+                        .and_then(|content| SettingsBackup::parse(&content));
+                    Action::App(SettingsMessage::ImportLoaded(Some(result)))
+                });
+            }
+            SettingsMessage::ImportLoaded(result) => match result {
+                Some(Ok(backup)) if backup.is_empty() => {
+                    self.backup_status = Some(Err("The backup holds no settings".to_string()));
+                }
+                Some(Ok(backup)) => self.pending_import = Some(backup),
+                Some(Err(e)) => self.backup_status = Some(Err(e)),
+                None => {}
+            },
+            SettingsMessage::CancelImport => {
+                self.pending_import = None;
+            }
+            SettingsMessage::ConfirmImport => {
+                let Some(backup) = self.pending_import.take() else { return Task::none(); };
+                return cosmic::task::future(async move {
+                    let result = backup.restore().map_err(|e| format!("Couldn't restore settings: {}", e));
+                    Action::App(SettingsMessage::ImportFinished(result))
+                });
+            }
+            SettingsMessage::ImportFinished(result) => {
+                match result {
+                    Ok(restored) => {
+                        info!("Imported {} settings file(s)", restored.len());
+                        self.backup_status = Some(Ok(format!(
+                            "Restored {} file(s): {}. Restart Cosmic Connect to apply them everywhere.",
+                            restored.len(),
+                            restored.join(", ")
+                        )));
+                    }
+                    Err(e) => self.backup_status = Some(Err(e)),
+                }
+                // Show what was restored
+                self.quick_replies = QuickRepliesConfig::load().unwrap_or_default();
+                self.send_confirm = SendConfirmConfig::load().unwrap_or_default();
+                self.auto_open = AutoOpenConfig::load().unwrap_or_default();
+                self.retention = RetentionConfig::load().unwrap_or_default();
+                if self.connected {
+                    return cosmic::task::future(async {
+                        Action::App(SettingsMessage::DevicesLoaded(backend::fetch_devices().await))
+                    });
+                }
+            }
+            SettingsMessage::ToggleTroubleshooting => {
+                self.show_troubleshooting = !self.show_troubleshooting;
+                if self.show_troubleshooting && self.diagnostics.is_none() {
+                    return cosmic::task::message(Action::App(SettingsMessage::RefreshDiagnostics));
+                }
+            }
+            SettingsMessage::RefreshDiagnostics => {
+                self.loading_diagnostics = true;
+                self.copied = false;
+                return cosmic::task::future(async {
+                    Action::App(SettingsMessage::DiagnosticsLoaded(backend::fetch_diagnostics().await))
+                });
+            }
+            SettingsMessage::DiagnosticsLoaded(result) => {
+                self.loading_diagnostics = false;
+                if let Err(e) = &result {
+                    warn!("Failed to fetch diagnostics: {}", e);
+                }
+                self.diagnostics = Some(result);
+            }
+            SettingsMessage::CopyDiagnostics => {
+                if let Some(Ok(diagnostics)) = &self.diagnostics {
+                    let report = diagnostics_report(diagnostics);
+                    return cosmic::task::future(async move {
+                        if let Err(e) = portal::write_clipboard(&report).await {
+                            warn!("Failed to copy diagnostics: {:?}", e);
+                        }
+                        Action::App(SettingsMessage::DiagnosticsCopied)
+                    });
+                }
+            }
+            SettingsMessage::DiagnosticsCopied => {
+                self.copied = true;
+            }
+        }
+        Task::none()
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        let spacing = cosmic::theme::active().cosmic().spacing;
+
+        let status = if self.connected {
+            "Connected to kdeconnect-service"
+        } else {
+            "Not connected to kdeconnect-service"
+        };
+
+        let expander_icon = if self.show_troubleshooting {
+            "go-down-symbolic"
+        } else {
+            "go-next-symbolic"
+        };
+        let expander = widget::button::custom(
+            widget::row()
+                .push(widget::icon::from_name(expander_icon).size(16))
+                .push(widget::text::heading("Troubleshooting"))
+                .spacing(spacing.space_xs)
+                .align_y(cosmic::iced::Alignment::Center),
+        )
+        .class(cosmic::theme::Button::Text)
+        .on_press(SettingsMessage::ToggleTroubleshooting);
+
+        let mut content = widget::column()
+            .push(widget::text::title3("KDE Connect"))
+            .push(widget::text::caption(status))
+            .push(self.devices_view(spacing))
+            .push(self.quick_replies_view(spacing))
+            .push(self.long_messages_view(spacing))
+            .push(self.new_conversations_view(spacing))
+            .push(self.sms_history_view(spacing))
+            .push(self.backup_view(spacing))
+            .push(expander)
+            .spacing(spacing.space_s)
+            .padding(spacing.space_m);
+
+        if self.show_troubleshooting {
+            content = content.push(self.troubleshooting_view(spacing));
+        }
+
+        if self.dev_mode {
+            let expander = widget::button::custom(
+                widget::row()
+                    .push(widget::icon::from_name(if self.show_packet_log { "go-down-symbolic" } else { "go-next-symbolic" }).size(16))
+                    .push(widget::text::heading("Packet log"))
+                    .spacing(spacing.space_xs)
+                    .align_y(cosmic::iced::Alignment::Center),
+            )
+            .class(cosmic::theme::Button::Text)
+            .on_press(SettingsMessage::TogglePacketLog);
+            content = content.push(expander);
+
+            if self.show_packet_log {
+                content = content.push(self.packet_log_view(spacing));
+            }
+        }
+
+        widget::container(widget::scrollable(content))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    }
+}
+
+impl SettingsApp {
+    /// Paired devices with rename fields and bulk unpair
+    fn devices_view(&self, spacing: cosmic::cosmic_theme::Spacing) -> Element<'_, SettingsMessage> {
+        let mut section = widget::settings::section().title("Paired devices");
+
+        for device in &self.paired_devices {
+            let busy = self.unpairing.contains(&device.id);
+            let select_id = device.id.clone();
+            let input_id = device.id.clone();
+            let submit_id = device.id.clone();
+            let name = self.alias_inputs.get(&device.id).map(String::as_str).unwrap_or(&device.name);
+
+            let mut checkbox = widget::checkbox("", self.selected_devices.contains(&device.id));
+            let mut name_input = widget::text_input("Device name", name).width(Length::Fill);
+            if !busy {
+                checkbox = checkbox.on_toggle(move |selected| SettingsMessage::DeviceSelected(select_id.clone(), selected));
+                name_input = name_input
+                    .on_input(move |name| SettingsMessage::AliasInput(input_id.clone(), name))
+                    .on_submit(move |_| SettingsMessage::SaveAlias(submit_id.clone()));
+            }
+
+            let mut row = widget::row()
+                .push(checkbox)
+                .push(name_input)
+                .spacing(spacing.space_xs)
+                .align_y(cosmic::iced::Alignment::Center);
+            if busy {
+                row = row.push(widget::text::caption("Unpairing…"));
+            }
+            section = section.add(widget::settings::item_row(vec![row.into()]));
+        }
+
+        let selected = self.selected_devices.len();
+        let mut column = widget::column()
+            .push(widget::text::caption(
+                "Names typed here replace the ones the devices announce. Press Enter to save; clear a name to go back to the device's own."
+            ))
+            .push(section)
+            .spacing(spacing.space_xs);
+
+        if self.confirm_bulk_unpair {
+            let names: Vec<&str> = self.paired_devices.iter()
+                .filter(|d| self.selected_devices.contains(&d.id))
+                .map(|d| d.name.as_str())
+                .collect();
+            column = column.push(
+                widget::container(
+                    widget::column()
+                        .push(widget::text::heading(format!(
+                            "Unpair {} device{}?",
+                            names.len(),
+                            if names.len() == 1 { "" } else { "s" }
+                        )))
+                        .push(widget::text::body(names.join(", ")))
+                        .push(
+                            widget::checkbox("Keep their data for pairing again", self.bulk_unpair_keep_data)
+                                .on_toggle(SettingsMessage::BulkUnpairKeepData)
+                        )
+                        .push(
+                            widget::row()
+                                .push(widget::button::standard("Cancel").on_press(SettingsMessage::CancelBulkUnpair))
+                                .push(widget::button::destructive("Unpair").on_press(SettingsMessage::ConfirmBulkUnpair))
+                                .spacing(spacing.space_xs)
+                        )
+                        .spacing(spacing.space_xs)
+                )
+                .padding(spacing.space_s)
+                .class(cosmic::theme::Container::Card)
+            );
+        } else {
+            column = column.push(
+                widget::button::destructive(if selected > 0 {
+                    format!("Unpair selected ({})", selected)
+                } else {
+                    "Unpair selected".to_string()
+                })
+                .on_press_maybe((selected > 0 && self.unpairing.is_empty()).then_some(SettingsMessage::RequestBulkUnpair))
+            );
+        }
+
+        if let Some(e) = &self.alias_error {
+            column = column.push(widget::text::caption(e.as_str()));
+        }
+        for e in &self.bulk_unpair_errors {
+            column = column.push(widget::text::caption(format!("Couldn't unpair {}", e)));
+        }
+
+        column.into()
+    }
+
+    fn save_quick_replies(&self) {
+        if let Err(e) = self.quick_replies.save() {
+            warn!("Failed to save quick replies: {}", e);
+        }
+    }
+
+    fn save_send_confirm(&self) {
+        if let Err(e) = self.send_confirm.save() {
+            warn!("Failed to save send confirmation settings: {}", e);
+        }
+    }
+
+    fn save_retention(&self) {
+        if let Err(e) = self.retention.save() {
+            warn!("Failed to save SMS retention limits: {}", e);
+        }
+    }
+
+    /// Canned replies shown above the SMS input
+    fn quick_replies_view(&self, spacing: cosmic::cosmic_theme::Spacing) -> Element<'_, SettingsMessage> {
+        let mut section = widget::settings::section()
+            .title("Quick replies")
+            .add(widget::settings::item(
+                "Send when tapped",
+                widget::toggler(self.quick_replies.send_immediately)
+                    .on_toggle(SettingsMessage::QuickReplySendImmediately),
+            ));
+
+        for (i, reply) in self.quick_replies.replies().iter().enumerate() {
+            section = section.add(widget::settings::item(
+                reply.as_str(),
+                widget::button::standard("Remove").on_press(SettingsMessage::RemoveQuickReply(i)),
+            ));
+        }
+
+        let can_add = self.quick_replies.replies().len() < MAX_QUICK_REPLIES
+            && !self.new_quick_reply.trim().is_empty();
+        section = section.add(widget::settings::item_row(vec![
+            widget::text_input("New reply", &self.new_quick_reply)
+                .on_input(SettingsMessage::QuickReplyInput)
+                .on_submit(|_| SettingsMessage::AddQuickReply)
+                .width(Length::Fill)
+                .into(),
+            widget::button::standard("Add")
+                .on_press_maybe(can_add.then_some(SettingsMessage::AddQuickReply))
+                .into(),
+        ]));
+
+        widget::column()
+            .push(widget::text::caption(
+                "Shown as chips above the message box in SMS windows. Unless \"Send when tapped\" is on, a chip fills in the message so it can be edited first."
+            ))
+            .push(section)
+            .spacing(spacing.space_xs)
+            .into()
+    }
+
+    /// Asking before sending messages split into many SMS
+    fn long_messages_view(&self, spacing: cosmic::cosmic_theme::Spacing) -> Element<'_, SettingsMessage> {
+        let selected = CONFIRM_SEGMENTS.iter().position(|&s| s == self.send_confirm.max_segments);
+        let section = widget::settings::section()
+            .title("Long messages")
+            .add(widget::settings::item(
+                "Ask before sending",
+                widget::toggler(self.send_confirm.enabled)
+                    .on_toggle(SettingsMessage::SendConfirmToggled),
+            ))
+            .add(widget::settings::item(
+                "Ask for messages of",
+                widget::dropdown(&CONFIRM_SEGMENT_LABELS, selected, SettingsMessage::ConfirmSegmentsSelected),
+            ));
+
+        widget::column()
+            .push(section)
+            .push(widget::text::caption(
+                "Long messages are sent as several SMS, and carriers may charge for each one."
+            ))
+            .spacing(spacing.space_xs)
+            .into()
+    }
+
+    /// Opening the SMS window when someone new writes
+    fn new_conversations_view(&self, spacing: cosmic::cosmic_theme::Spacing) -> Element<'_, SettingsMessage> {
+        let section = widget::settings::section()
+            .title("New conversations")
+            .add(widget::settings::item(
+                "Open the SMS window",
+                widget::toggler(self.auto_open.enabled)
+                    .on_toggle(SettingsMessage::AutoOpenToggled),
+            ));
+
+        widget::column()
+            .push(section)
+            .push(widget::text::caption(
+                "When a message starts a conversation not seen before, its SMS window opens on it. At most one window opens every 30 seconds."
+            ))
+            .spacing(spacing.space_xs)
+            .into()
+    }
+
+    /// Limits on the SMS history kept by windows and their caches
+    fn sms_history_view(&self, spacing: cosmic::cosmic_theme::Spacing) -> Element<'_, SettingsMessage> {
+        let conversations = RETENTION_CONVERSATIONS.iter().position(|&max| max == self.retention.max_conversations);
+        let messages = RETENTION_MESSAGES.iter().position(|&max| max == self.retention.max_messages_per_thread);
+        let age = RETENTION_AGE_DAYS.iter().position(|&days| days == self.retention.max_age_days);
+        let section = widget::settings::section()
+            .title("SMS history")
+            .add(widget::settings::item(
+                "Conversations kept",
+                widget::dropdown(&RETENTION_CONVERSATION_LABELS, conversations, SettingsMessage::RetentionConversationsSelected),
+            ))
+            .add(widget::settings::item(
+                "Messages cached per conversation",
+                widget::dropdown(&RETENTION_MESSAGE_LABELS, messages, SettingsMessage::RetentionMessagesSelected),
+            ))
+            .add(widget::settings::item(
+                "Keep history for",
+                widget::dropdown(&RETENTION_AGE_LABELS, age, SettingsMessage::RetentionAgeSelected),
+            ));
+
+        widget::column()
+            .push(section)
+            .push(widget::text::caption(
+                "Older conversations and messages are removed from SMS windows and their caches, oldest first. The open conversation is always kept, and the phone still has everything."
+            ))
+            .spacing(spacing.space_xs)
+            .into()
+    }
+
+    /// Export and import of settings, for moving to another computer
+    fn backup_view(&self, spacing: cosmic::cosmic_theme::Spacing) -> Element<'_, SettingsMessage> {
+        let section = widget::settings::section()
+            .title("Backup")
+            .add(widget::settings::item(
+                "Include certificates and keys",
+                widget::toggler(self.backup_include_secrets)
+                    .on_toggle(SettingsMessage::BackupIncludeSecrets),
+            ))
+            .add(widget::settings::item_row(vec![
+                widget::button::standard("Export settings…")
+                    .on_press(SettingsMessage::ExportSettings)
+                    .into(),
+                widget::button::standard("Import settings…")
+                    .on_press_maybe(self.pending_import.is_none().then_some(SettingsMessage::ImportSettings))
+                    .into(),
+            ]));
+
+        let mut column = widget::column()
+            .push(widget::text::caption(
+                "Plugin settings, device names and quick replies are saved to one file that can be imported on another computer."
+            ))
+            .push(section)
+            .spacing(spacing.space_xs);
+
+        if let Some(backup) = &self.pending_import {
+            column = column.push(
+                widget::container(
+                    widget::column()
+                        .push(widget::text::body(format!(
+                            "Import {} file(s)? Current settings with the same names will be replaced.",
+                            backup.len()
+                        )))
+                        .push(
+                            widget::row()
+                                .push(widget::button::standard("Cancel").on_press(SettingsMessage::CancelImport))
+                                .push(widget::button::destructive("Import").on_press(SettingsMessage::ConfirmImport))
+                                .spacing(spacing.space_xs)
+                        )
+                        .spacing(spacing.space_xs)
+                )
+                .padding(spacing.space_s)
+                .class(cosmic::theme::Container::Card)
+            );
+        }
+
+        if let Some(status) = &self.backup_status {
+            let text = match status {
+                Ok(message) => message.clone(),
+                Err(e) => e.clone(),
+            };
+            column = column.push(widget::text::caption(text));
+        }
+
+        column.into()
+    }
+
+    /// Developer inspector for packets the service exchanged with devices
+    fn packet_log_view(&self, spacing: cosmic::cosmic_theme::Spacing) -> Element<'_, SettingsMessage> {
+        let controls = widget::row()
+            .push(
+                widget::text_input("Filter by type", self.packet_filter.as_str())
+                    .on_input(SettingsMessage::PacketFilterChanged)
+                    .width(Length::Fill)
+            )
+            .push(widget::dropdown(&PACKET_RANGE_LABELS, Some(self.packet_range), SettingsMessage::PacketRangeSelected))
+            .push(widget::button::standard("Refresh").on_press(SettingsMessage::RefreshPacketLog))
+            .spacing(spacing.space_xs)
+            .align_y(cosmic::iced::Alignment::Center);
+
+        let packets = match &self.packets {
+            None => return widget::column().push(controls).push(widget::text::body("Loading…")).spacing(spacing.space_xs).into(),
+            Some(Err(e)) => {
+                return widget::column()
+                    .push(controls)
+                    .push(widget::text::body(format!("Packet log unavailable: {}", e)))
+                    .spacing(spacing.space_xs)
+                    .into();
+            }
+            Some(Ok(packets)) => packets,
+        };
+
+        let filter = self.packet_filter.trim().to_lowercase();
+        let now = now_millis();
+        let mut list = widget::column().spacing(spacing.space_xs);
+        let mut shown = 0;
+        // Newest first
+        for packet in packets.iter().rev().filter(|p| filter.is_empty() || p.packet_type.to_lowercase().contains(&filter)) {
+            shown += 1;
+            let age = format_duration(((now - packet.timestamp_ms).max(0) / 1000) as u64);
+            let header = format!(
+                "{} {} {} · {} ago",
+                if packet.outbound { "→" } else { "←" },
+                packet.packet_type,
+                if packet.device_id.is_empty() { "(unknown device)" } else { packet.device_id.as_str() },
+                age,
+            );
+            let body = serde_json::from_str::<serde_json::Value>(&packet.body_json)
+                .and_then(|v| serde_json::to_string_pretty(&v))
+                .unwrap_or_else(|_| packet.body_json.clone());
+
+            list = list.push(
+                widget::container(
+                    widget::column()
+                        .push(widget::text::caption_heading(header))
+                        .push(widget::text(body).size(11).font(cosmic::font::mono()))
+                        .spacing(spacing.space_xxs)
+                )
+                .padding(spacing.space_xs)
+                .class(cosmic::theme::Container::Card)
+                .width(Length::Fill)
+            );
+        }
+
+        if shown == 0 {
+            list = list.push(widget::text::body("No packets in this range"));
+        }
+
+        widget::column()
+            .push(controls)
+            .push(widget::scrollable(list).height(Length::Fixed(400.0)))
+            .spacing(spacing.space_xs)
+            .into()
+    }
+
+    fn troubleshooting_view(&self, spacing: cosmic::cosmic_theme::Spacing) -> Element<'_, SettingsMessage> {
+        let refresh = widget::button::standard(if self.loading_diagnostics { "Refreshing…" } else { "Refresh" })
+            .on_press_maybe((!self.loading_diagnostics).then_some(SettingsMessage::RefreshDiagnostics));
+        let copy = widget::button::text(if self.copied { "Copied" } else { "Copy report" })
+            .on_press_maybe(matches!(self.diagnostics, Some(Ok(_))).then_some(SettingsMessage::CopyDiagnostics));
+        let actions = widget::row().push(refresh).push(copy).spacing(spacing.space_xs);
+
+        let diagnostics = match &self.diagnostics {
+            None => return widget::column().push(widget::text::body("Loading…")).push(actions).into(),
+            Some(Err(e)) => {
+                return widget::column()
+                    .push(widget::text::body(format!("Diagnostics unavailable: {}", e)))
+                    .push(actions)
+                    .spacing(spacing.space_xs)
+                    .into();
+            }
+            Some(Ok(d)) => d,
+        };
+
+        let ports = if diagnostics.listening_ports.is_empty() {
+            "None".to_string()
+        } else {
+            diagnostics.listening_ports.iter().map(u16::to_string).collect::<Vec<_>>().join(", ")
+        };
+
+        let service = widget::settings::section()
+            .title("Service")
+            .add(widget::settings::item("Uptime", widget::text::body(format_duration(diagnostics.uptime_secs))))
+            .add(widget::settings::item("Known devices", widget::text::body(diagnostics.known_devices.to_string())))
+            .add(widget::settings::item("Last broadcast", widget::text::body(last_broadcast_label(diagnostics.last_broadcast_secs))))
+            .add(widget::settings::item("Listening ports", widget::text::body(ports)))
+            .add(widget::settings::item(
+                "Discovery socket",
+                widget::text::body(if diagnostics.discovery_bound { "Bound" } else { "Not bound" }),
+            ));
+
+        let mut links = widget::settings::section().title("Device links");
+        if diagnostics.links.is_empty() {
+            links = links.add(widget::settings::item("No devices discovered", widget::Space::with_width(0)));
+        }
+        for link in &diagnostics.links {
+            links = links.add(widget::settings::item(link.name.clone(), widget::text::body(link_label(link))));
+        }
+
+        widget::column()
+            .push(service)
+            .push(links)
+            .push(actions)
+            .spacing(spacing.space_s)
+            .into()
+    }
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+fn format_duration(secs: u64) -> String {
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+fn last_broadcast_label(secs_ago: i64) -> String {
+    if secs_ago < 0 {
+        "Never".to_string()
+    } else {
+        format!("{} ago", format_duration(secs_ago as u64))
+    }
+}
+
+fn link_label(link: &kdeconnect_dbus_client::LinkStatus) -> String {
+    let mut label = if link.is_reachable && !link.connection_type.is_empty() {
+        format!("Reachable ({})", link.connection_type)
+    } else if link.is_reachable {
+        "Reachable".to_string()
+    } else {
+        "Unreachable".to_string()
+    };
+    if !link.send_transport.is_empty() {
+        label.push_str(&format!(", sending over {}", link.send_transport));
+    }
+    if !link.is_paired {
+        label.push_str(", not paired");
+    }
+    if link.pending_packets > 0 {
+        label.push_str(&format!(", {} queued", link.pending_packets));
+    }
+    label
+}
+
+/// Plain-text report for pasting into bug reports
+fn diagnostics_report(d: &Diagnostics) -> String {
+    let mut report = format!(
+        "uptime: {}\nknown devices: {}\nlast broadcast: {}\nlistening ports: {:?}\ndiscovery socket bound: {}\n",
+        format_duration(d.uptime_secs),
+        d.known_devices,
+        last_broadcast_label(d.last_broadcast_secs),
+        d.listening_ports,
+        d.discovery_bound,
+    );
+    for link in &d.links {
+        report.push_str(&format!("{} ({}): {}\n", link.name, link.id, link_label(link)));
+    }
+    report
+}
+
+fn main() -> cosmic::iced::Result {
+    cosmic_connect_applet::logging::init();
+    info!("Starting settings window");
+    cosmic::app::run::<SettingsApp>(cosmic::app::Settings::default(), ())
 }
\ No newline at end of file
@@ -0,0 +1,235 @@
+// cosmic-connect-applet/src/settings_backup.rs
+//! Exporting and importing settings, for moving to another computer.
+//!
+//! A backup is one JSON file holding the text of every file under
+//! ~/.config/cosmic-connect and each device folder in ~/.config/kdeconnect
+//! (plugin configs, aliases, trusted devices, quick replies, ...), keyed by
+//! path relative to ~/.config. Certificate and key files are left out unless
+//! asked for. Files in the top level of ~/.config/kdeconnect belong to
+//! kdeconnect-core and are never included.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use tracing::{debug, info};
+
+/// Value of the `format` field identifying a settings backup
+pub const FORMAT: &str = "cosmic-connect-settings";
+
+/// Backup layout version written, and the newest one accepted
+pub const VERSION: u64 = 1;
+
+/// Largest file included, in bytes; config files are far smaller
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+const APP_DIR: &str = "cosmic-connect";
+const KDECONNECT_DIR: &str = "kdeconnect";
+
+/// Suggested file name in the export dialog
+pub const DEFAULT_FILE_NAME: &str = "cosmic-connect-settings.json";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SettingsBackup {
+    /// File contents by path relative to ~/.config
+    files: BTreeMap<String, String>,
+}
+
+impl SettingsBackup {
+    /// Gather the settings files, with secrets only if `include_secrets`
+    pub fn collect(include_secrets: bool) -> io::Result<Self> {
+        Self::collect_from(&config_root(), include_secrets)
+    }
+
+    fn collect_from(root: &Path, include_secrets: bool) -> io::Result<Self> {
+        let mut backup = Self::default();
+        backup.add_dir(root, &root.join(APP_DIR), include_secrets)?;
+
+        let devices = root.join(KDECONNECT_DIR);
+        if devices.is_dir() {
+            for entry in fs::read_dir(&devices)? {
+                let path = entry?.path();
+                if path.is_dir() && !path.is_symlink() {
+                    backup.add_dir(root, &path, include_secrets)?;
+                }
+            }
+        }
+        Ok(backup)
+    }
+
+    fn add_dir(&mut self, root: &Path, dir: &Path, include_secrets: bool) -> io::Result<()> {
+        if !dir.is_dir() {
+            return Ok(());
+        }
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                self.add_dir(root, &path, include_secrets)?;
+                continue;
+            }
+            if !file_type.is_file() || entry.metadata()?.len() > MAX_FILE_BYTES {
+                continue;
+            }
+
+            let Some(relative) = path.strip_prefix(root).ok().and_then(Path::to_str) else { continue; };
+            if is_secret(relative) && !include_secrets {
+                continue;
+            }
+            match fs::read_to_string(&path) {
+                Ok(content) => {
+                    self.files.insert(relative.to_string(), content);
+                }
+                // Not a text config file
+                Err(e) => debug!("Leaving {} out of the backup: {}", path.display(), e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Paths in the backup, relative to ~/.config
+    pub fn paths(&self) -> impl Iterator<Item = &String> {
+        self.files.keys()
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub fn to_json(&self) -> String {
+        let value = serde_json::json!({
+            "format": FORMAT,
+            "version": VERSION,
+            "files": self.files,
+        });
+        serde_json::to_string_pretty(&value).unwrap_or_default()
+    }
+
+    /// Read and check a backup: its format and version, and that every
+    /// path stays inside the folders a backup may write to
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let value: serde_json::Value = serde_json::from_str(content)
+            .map_err(|e| format!("Not a settings backup: {}", e))?;
+        if value["format"].as_str() != Some(FORMAT) {
+            return Err("Not a Cosmic Connect settings backup".to_string());
+        }
+        match value["version"].as_u64() {
+            Some(version) if version <= VERSION => {}
+            Some(version) => return Err(format!("Backup version {} is newer than this app supports", version)),
+            None => return Err("Backup has no version".to_string()),
+        }
+        let Some(entries) = value["files"].as_object() else {
+            return Err("Backup has no files".to_string());
+        };
+
+        let mut files = BTreeMap::new();
+        for (path, content) in entries {
+            if !is_restorable(path) {
+                return Err(format!("Backup contains an unexpected path: {}", path));
+            }
+            let Some(content) = content.as_str() else {
+                return Err(format!("Backup entry {} isn't text", path));
+            };
+            files.insert(path.clone(), content.to_string());
+        }
+        Ok(Self { files })
+    }
+
+    /// Write every file, replacing existing ones. Returns the restored
+    /// paths.
+    pub fn restore(&self) -> io::Result<Vec<String>> {
+        self.restore_to(&config_root())
+    }
+
+    fn restore_to(&self, root: &Path) -> io::Result<Vec<String>> {
+        let mut restored = Vec::with_capacity(self.files.len());
+        for (relative, content) in &self.files {
+            let path = root.join(relative);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, content)?;
+            restored.push(relative.clone());
+        }
+        info!("Restored {} settings file(s)", restored.len());
+        Ok(restored)
+    }
+}
+
+/// Certificate and key material
+fn is_secret(relative: &str) -> bool {
+    let name = relative.rsplit('/').next().unwrap_or(relative);
+    name.ends_with(".pem") || name.ends_with(".key")
+}
+
+/// Relative, without `..`, and under ~/.config/cosmic-connect or a device
+/// folder of ~/.config/kdeconnect
+fn is_restorable(relative: &str) -> bool {
+    let path = Path::new(relative);
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part),
+            _ => return false,
+        }
+    }
+    match parts.as_slice() {
+        [dir, _, ..] if *dir == APP_DIR => true,
+        [dir, _, _, ..] if *dir == KDECONNECT_DIR => true,
+        _ => false,
+    }
+}
+
+fn config_root() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+    PathBuf::from(format!("{}/.config", home))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_round_trip_and_validation() {
+        let root = std::env::temp_dir().join(format!("cosmic-connect-backup-{}", std::process::id()));
+        let write = |relative: &str, content: &str| {
+            let path = root.join(relative);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        write("cosmic-connect/device_aliases", "[Aliases]\nphone=Work phone\n");
+        write("kdeconnect/phone/certificate.pem", "secret");
+        write("kdeconnect/phone/kdeconnect_ping/config", "[General]\nmessage=Hi\n");
+        write("kdeconnect/privateKey.pem", "secret");
+
+        let backup = SettingsBackup::collect_from(&root, false).unwrap();
+        let paths: Vec<&str> = backup.paths().map(String::as_str).collect();
+        assert_eq!(paths, ["cosmic-connect/device_aliases", "kdeconnect/phone/kdeconnect_ping/config"]);
+        assert_eq!(SettingsBackup::collect_from(&root, true).unwrap().len(), 3);
+
+        let parsed = SettingsBackup::parse(&backup.to_json()).unwrap();
+        assert_eq!(parsed, backup);
+
+        let target = root.join("restored");
+        assert_eq!(parsed.restore_to(&target).unwrap().len(), 2);
+        assert_eq!(
+            fs::read_to_string(target.join("kdeconnect/phone/kdeconnect_ping/config")).unwrap(),
+            "[General]\nmessage=Hi\n"
+        );
+        fs::remove_dir_all(&root).unwrap();
+
+        let backup_with = |path: &str| {
+            format!(r#"{{"format":"{}","version":1,"files":{{"{}":"x"}}}}"#, FORMAT, path)
+        };
+        assert!(SettingsBackup::parse(&backup_with("cosmic-connect/../../.bashrc")).is_err());
+        assert!(SettingsBackup::parse(&backup_with("/etc/passwd")).is_err());
+        assert!(SettingsBackup::parse(&backup_with("kdeconnect/privateKey.pem")).is_err());
+        assert!(SettingsBackup::parse(r#"{"format":"other","version":1,"files":{}}"#).is_err());
+        assert!(SettingsBackup::parse(&format!(r#"{{"format":"{}","version":99,"files":{{}}}}"#, FORMAT)).is_err());
+    }
+}
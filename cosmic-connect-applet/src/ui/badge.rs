@@ -0,0 +1,132 @@
+// cosmic-connect-applet/src/ui/badge.rs
+//! Status badge shown on the panel icon.
+
+use std::collections::HashMap;
+use crate::applet_config::PanelStyle;
+use crate::models::Device;
+
+/// What the panel icon should indicate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PanelBadge {
+    /// Paired devices that are currently reachable
+    pub connected: usize,
+    /// A device is waiting for the user to accept or reject pairing
+    pub attention: bool,
+    /// Battery level of the connected device, when exactly one is connected
+    pub battery: Option<i32>,
+    /// Unread text messages across devices
+    pub unread_sms: usize,
+}
+
+impl PanelBadge {
+    /// Text for the overlay in the given style, None when nothing needs
+    /// showing. Pairing requests are flagged in every style.
+    pub fn label(&self, style: PanelStyle) -> Option<String> {
+        if self.attention && (self.connected == 0 || style == PanelStyle::IconOnly) {
+            return Some("!".to_string());
+        }
+        match (style, self.connected) {
+            (PanelStyle::IconOnly, _) | (_, 0) => None,
+            (PanelStyle::Battery, n) => Some(self.battery.map_or_else(|| n.to_string(), |level| format!("{}%", level))),
+            (PanelStyle::IconCount, n) => Some(n.to_string()),
+        }
+    }
+
+    /// Unread text messages, shown beside the label in every style
+    pub fn unread_label(&self) -> Option<String> {
+        match self.unread_sms {
+            0 => None,
+            n if n > 99 => Some("99+".to_string()),
+            n => Some(n.to_string()),
+        }
+    }
+}
+
+/// Summarize the known devices into the panel badge
+pub fn panel_badge(devices: &HashMap<String, Device>) -> PanelBadge {
+    let connected: Vec<&Device> = devices.values()
+        .filter(|d| d.is_paired && d.is_reachable)
+        .collect();
+    PanelBadge {
+        connected: connected.len(),
+        attention: devices.values()
+            .any(|d| d.pairing_requests > 0 && !d.is_paired),
+        battery: match connected.as_slice() {
+            [device] => device.battery_level,
+            _ => None,
+        },
+        unread_sms: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(id: &str, is_paired: bool, is_reachable: bool, pairing_requests: i32) -> Device {
+        Device {
+            id: id.to_string(),
+            name: id.to_string(),
+            device_type: "phone".to_string(),
+            is_reachable,
+            is_paired,
+            battery_level: None,
+            is_charging: None,
+            has_battery: false,
+            has_ping: true,
+            has_share: true,
+            has_findmyphone: true,
+            has_sms: true,
+            has_clipboard: true,
+            has_contacts: false,
+            has_mpris: false,
+            has_remote_keyboard: false,
+            has_sftp: false,
+            has_presenter: false,
+            has_lockdevice: false,
+            has_virtualmonitor: false,
+            pairing_requests,
+            pending_packets: 0,
+            signal_strength: None,
+            network_type: None,
+            connection_type: None,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn test_panel_badge_counts() {
+        let mut devices = HashMap::new();
+        assert_eq!(panel_badge(&devices).label(PanelStyle::IconCount), None);
+
+        devices.insert("a".to_string(), device("a", true, true, 0));
+        devices.insert("b".to_string(), device("b", true, false, 0));
+        devices.insert("c".to_string(), device("c", false, true, 0));
+        let badge = panel_badge(&devices);
+        assert_eq!(badge.connected, 1);
+        assert!(!badge.attention);
+        assert_eq!(badge.label(PanelStyle::IconCount).as_deref(), Some("1"));
+
+        devices.insert("d".to_string(), device("d", false, true, 1));
+        assert!(panel_badge(&devices).attention);
+    }
+
+    #[test]
+    fn test_panel_badge_styles() {
+        let mut devices = HashMap::new();
+        let mut phone = device("a", true, true, 0);
+        phone.battery_level = Some(80);
+        devices.insert("a".to_string(), phone);
+
+        let badge = panel_badge(&devices);
+        assert_eq!(badge.label(PanelStyle::IconOnly), None);
+        assert_eq!(badge.label(PanelStyle::Battery).as_deref(), Some("80%"));
+
+        // Battery is ambiguous with two devices connected; show the count
+        devices.insert("b".to_string(), device("b", true, true, 0));
+        assert_eq!(panel_badge(&devices).label(PanelStyle::Battery).as_deref(), Some("2"));
+
+        devices.insert("c".to_string(), device("c", false, true, 1));
+        assert_eq!(panel_badge(&devices).label(PanelStyle::IconOnly).as_deref(), Some("!"));
+    }
+}
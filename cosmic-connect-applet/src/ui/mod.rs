@@ -1,2 +1,3 @@
 // cosmic-connect-applet/src/ui/mod.rs
+pub mod badge;
 pub mod popup;
\ No newline at end of file
@@ -1,10 +1,51 @@
 // cosmic-connect-applet/src/ui/popup.rs
 use cosmic::app::Core;
-use cosmic::iced::{Alignment, Length};
+use cosmic::iced::widget::container;
+use cosmic::iced::{Alignment, Color, Length};
 use cosmic::{widget, Element};
-use std::collections::HashMap;
-use crate::models::Device;
+use std::collections::{HashMap, HashSet};
+use crate::daemon::DaemonStatus;
+use crate::device_groups::{DeviceGroups, UNGROUPED};
+use crate::models::{ClipboardHistory, Device};
 use crate::messages::Message;
+use crate::portal;
+
+/// First-run guide shown until a device is paired or it is dismissed
+#[derive(Debug, Clone, Default)]
+pub struct Onboarding {
+    pub dismissed: bool,
+}
+
+/// Clipboard items shared this session, for the "Clipboard history" section
+#[derive(Debug, Clone, Copy)]
+pub struct ClipboardMenu<'a> {
+    /// Device whose section is expanded
+    pub open_for: Option<&'a String>,
+    pub history: &'a ClipboardHistory,
+    /// Device and text of the last share's warning or error
+    pub notice: Option<(&'a String, &'a str)>,
+}
+
+/// Device groups, for the grouped list and each card's group picker
+#[derive(Debug, Clone, Copy)]
+pub struct Groups<'a> {
+    pub config: &'a DeviceGroups,
+    /// Headings of collapsed groups, including UNGROUPED
+    pub collapsed: &'a HashSet<String>,
+    /// New group name typed on the expanded card
+    pub input: &'a str,
+}
+
+/// The shared service, for the status card under the header
+#[derive(Debug, Clone, Copy)]
+pub struct Daemon<'a> {
+    pub status: &'a DaemonStatus,
+    /// Why starting it or enabling autostart failed
+    pub error: Option<&'a str>,
+}
+
+/// Longest clipboard item label in the history list, in characters
+const CLIPBOARD_LABEL_CHARS: usize = 40;
 
 /// Build the popup view using the real application Core so popup_container
 /// has proper applet context, theme, and sizing.
@@ -13,6 +54,15 @@ pub fn create_popup_view<'a>(
     devices: &'a HashMap<String, Device>,
     expanded_device: Option<&'a String>,
     _expanded_player_menu: Option<&'a String>,
+    drop_target: Option<&'a String>,
+    ping_message: &'a str,
+    confirm_unpair: Option<&'a String>,
+    onboarding: &'a Onboarding,
+    compact: bool,
+    service_error: Option<&'a str>,
+    daemon: Daemon<'a>,
+    clipboard_menu: ClipboardMenu<'a>,
+    groups: Groups<'a>,
 ) -> Element<'a, Message> {
     let spacing = cosmic::theme::active().cosmic().spacing;
     let mut content = widget::column().spacing(spacing.space_s).padding(spacing.space_s);
@@ -29,6 +79,27 @@ pub fn create_popup_view<'a>(
             .align_y(Alignment::Center)
     );
 
+    if let Some(card) = create_daemon_card(daemon, &spacing) {
+        content = content.push(card);
+    } else if let Some(reason) = service_error {
+        content = content.push(
+            widget::container(
+                widget::row()
+                    .spacing(spacing.space_xs)
+                    .align_y(Alignment::Center)
+                    .push(widget::icon::from_name("dialog-warning-symbolic").size(16))
+                    .push(
+                        widget::column()
+                            .width(Length::Fill)
+                            .push(widget::text("KDE Connect service not ready").size(12).font(cosmic::font::bold()))
+                            .push(widget::text(reason).size(11))
+                    )
+            )
+            .padding(spacing.space_xs)
+            .class(cosmic::theme::Container::Card)
+        );
+    }
+
     content = content.push(widget::divider::horizontal::default());
 
     // Pairing requests
@@ -62,14 +133,22 @@ pub fn create_popup_view<'a>(
                     .push(
                         widget::row()
                             .push(
-                                widget::button::suggested("Accept")
-                                    .on_press(Message::AcceptPairing(device_id_accept))
-                                    .width(Length::Fill)
+                                named_button(
+                                    widget::container(widget::text("Accept")).center_x(Length::Fill),
+                                    format!("Accept pairing from {}", device.name),
+                                    cosmic::theme::Button::Suggested,
+                                )
+                                .on_press(Message::AcceptPairing(device_id_accept))
+                                .width(Length::Fill)
                             )
                             .push(
-                                widget::button::destructive("Reject")
-                                    .on_press(Message::RejectPairing(device_id_reject))
-                                    .width(Length::Fill)
+                                named_button(
+                                    widget::container(widget::text("Reject")).center_x(Length::Fill),
+                                    format!("Reject pairing from {}", device.name),
+                                    cosmic::theme::Button::Destructive,
+                                )
+                                .on_press(Message::RejectPairing(device_id_reject))
+                                .width(Length::Fill)
                             )
                             .spacing(spacing.space_xs)
                     )
@@ -91,7 +170,9 @@ pub fn create_popup_view<'a>(
         .collect();
     paired_devices.sort_by(|a, b| a.name.cmp(&b.name));
 
-    if paired_devices.is_empty() {
+    if paired_devices.is_empty() && !onboarding.dismissed {
+        content = content.push(create_onboarding(&spacing));
+    } else if paired_devices.is_empty() {
         content = content.push(
             widget::container(widget::text("No paired devices").size(14))
                 .padding(spacing.space_m)
@@ -99,11 +180,41 @@ pub fn create_popup_view<'a>(
                 .center_x(Length::Fill)
         );
     } else {
-        content = content.push(widget::text("Devices").size(14).font(cosmic::font::bold()));
+        content = content.push(
+            widget::row()
+                .push(widget::text("Devices").size(14).font(cosmic::font::bold()).width(Length::Fill))
+                .push(
+                    widget::button::icon(widget::icon::from_name(
+                        if compact { "view-list-symbolic" } else { "view-compact-symbolic" }
+                    ))
+                    .on_press(Message::ToggleCompactView)
+                    .tooltip(if compact { "Expanded view" } else { "Compact view" })
+                )
+                .align_y(Alignment::Center)
+        );
 
-        for device in paired_devices {
-            content = content.push(create_device_card(device, &spacing, expanded_device));
+        // Pairing request cards above always stay full size
+        let mut list = widget::column().spacing(if compact { spacing.space_xxxs } else { spacing.space_s });
+        let card = |device: &'a Device| {
+            create_device_card(device, &spacing, compact, expanded_device, drop_target, ping_message, confirm_unpair, clipboard_menu, groups)
+        };
+        if groups.config.is_empty() {
+            for device in paired_devices {
+                list = list.push(card(device));
+            }
+        } else {
+            for (group, members) in groups.config.sections(paired_devices, |d| d.id.as_str()) {
+                let title = group.unwrap_or(UNGROUPED);
+                let collapsed = groups.collapsed.contains(title);
+                list = list.push(create_group_header(title, members.len(), collapsed, &spacing));
+                if !collapsed {
+                    for device in members {
+                        list = list.push(card(device));
+                    }
+                }
+            }
         }
+        content = content.push(list);
     }
 
     let popup_content = widget::container(widget::scrollable(content))
@@ -116,77 +227,501 @@ pub fn create_popup_view<'a>(
     core.applet.popup_container(popup_content).into()
 }
 
+/// Heading of a device group, collapsing or expanding it when pressed
+fn create_group_header<'a>(
+    title: &'a str,
+    count: usize,
+    collapsed: bool,
+    spacing: &cosmic::cosmic_theme::Spacing,
+) -> Element<'a, Message> {
+    widget::button::custom(
+        widget::row()
+            .push(widget::icon::from_name(if collapsed { "go-next-symbolic" } else { "go-down-symbolic" }).size(12))
+            .push(widget::text(title).size(12).font(cosmic::font::bold()).width(Length::Fill))
+            .push(widget::text(count.to_string()).size(11))
+            .spacing(spacing.space_xxs)
+            .align_y(Alignment::Center)
+    )
+    .on_press(Message::ToggleGroupCollapsed(title.to_string()))
+    .width(Length::Fill)
+    .padding([spacing.space_xxxs, spacing.space_xs])
+    .class(cosmic::theme::Button::Text)
+    .name(format!("{} group {}, {} devices", if collapsed { "Expand" } else { "Collapse" }, title, count))
+    .into()
+}
+
+/// Steps for getting a first phone connected
+fn create_onboarding<'a>(spacing: &cosmic::cosmic_theme::Spacing) -> Element<'a, Message> {
+    let steps = [
+        "Install KDE Connect on your phone from Google Play or F-Droid.",
+        "Connect the phone to the same network as this computer.",
+        "Open KDE Connect on the phone and pick this computer to pair.",
+    ];
+
+    let mut col = widget::column()
+        .spacing(spacing.space_xs)
+        .push(
+            widget::row()
+                .spacing(spacing.space_xs)
+                .align_y(Alignment::Center)
+                .push(widget::icon::from_name("phone-symbolic").size(24))
+                .push(widget::text("Connect your phone").size(14).font(cosmic::font::bold()))
+        );
+
+    for (i, step) in steps.iter().enumerate() {
+        col = col.push(
+            widget::row()
+                .spacing(spacing.space_xs)
+                .push(widget::text(format!("{}.", i + 1)).size(12))
+                .push(widget::text(*step).size(12).width(Length::Fill))
+        );
+    }
+
+    col = col.push(
+        widget::row()
+            .spacing(spacing.space_xs)
+            .push(widget::horizontal_space())
+            .push(widget::button::text("Dismiss").on_press(Message::DismissOnboarding))
+    );
+
+    widget::container(col)
+        .padding(spacing.space_s)
+        .class(cosmic::theme::Container::Card)
+        .width(Length::Fill)
+        .into()
+}
+
+/// Card for a service that is missing, starting or incompatible; `None`
+/// while it is running normally
+fn create_daemon_card<'a>(daemon: Daemon<'a>, spacing: &cosmic::cosmic_theme::Spacing) -> Option<Element<'a, Message>> {
+    let (title, detail, actions): (&str, String, Vec<Element<'a, Message>>) = match daemon.status {
+        DaemonStatus::Checking | DaemonStatus::Running { .. } => return None,
+        DaemonStatus::NotRunning => (
+            "KDE Connect service isn't running",
+            "Devices, SMS and file sharing need kdeconnect-service.".to_string(),
+            vec![
+                widget::button::suggested("Start").on_press(Message::StartDaemon(false)).into(),
+                widget::button::standard("Start at login").on_press(Message::StartDaemon(true)).into(),
+            ],
+        ),
+        DaemonStatus::Starting => (
+            "Starting KDE Connect service…",
+            String::new(),
+            vec![],
+        ),
+        DaemonStatus::Incompatible { version, api_version } => (
+            "KDE Connect service doesn't match this applet",
+            match (version, api_version) {
+                (Some(version), Some(api)) => format!(
+                    "The running service is version {} (API {}), this applet needs API {}. Restart the service after updating both.",
+                    version, api, kdeconnect_dbus_client::API_VERSION
+                ),
+                _ => "The running service is too old to report its version. Update and restart it.".to_string(),
+            },
+            vec![widget::button::standard("Check again").on_press(Message::CheckDaemon).into()],
+        ),
+        DaemonStatus::Unreachable(reason) => (
+            "Can't reach the session bus",
+            reason.clone(),
+            vec![widget::button::standard("Check again").on_press(Message::CheckDaemon).into()],
+        ),
+    };
+
+    let mut col = widget::column()
+        .spacing(spacing.space_xxs)
+        .width(Length::Fill)
+        .push(widget::text(title).size(12).font(cosmic::font::bold()));
+    if !detail.is_empty() {
+        col = col.push(widget::text(detail).size(11));
+    }
+    if let Some(error) = daemon.error {
+        col = col.push(widget::text(error).size(11));
+    }
+    if !actions.is_empty() {
+        col = col.push(widget::row::with_children(actions).spacing(spacing.space_xs));
+    }
+
+    Some(
+        widget::container(
+            widget::row()
+                .spacing(spacing.space_xs)
+                .align_y(Alignment::Center)
+                .push(widget::icon::from_name("dialog-warning-symbolic").size(16))
+                .push(col)
+        )
+        .padding(spacing.space_xs)
+        .class(cosmic::theme::Container::Card)
+        .into()
+    )
+}
+
+/// One-line label for a clipboard item
+fn clipboard_label(content: &str) -> String {
+    let line = content.trim().lines().next().unwrap_or_default();
+    if line.chars().count() > CLIPBOARD_LABEL_CHARS {
+        format!("{}…", line.chars().take(CLIPBOARD_LABEL_CHARS - 1).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+/// Expandable list of earlier clipboard items to send again
+fn create_clipboard_history<'a>(
+    device: &'a Device,
+    spacing: &cosmic::cosmic_theme::Spacing,
+    clipboard_menu: ClipboardMenu<'a>,
+) -> Element<'a, Message> {
+    let is_open = clipboard_menu.open_for == Some(&device.id);
+
+    let mut col = widget::column().spacing(spacing.space_xxs).push(
+        widget::button::custom(
+            widget::row()
+                .push(widget::text("Clipboard history").width(Length::Fill))
+                .push(widget::icon::from_name(if is_open { "go-up-symbolic" } else { "go-down-symbolic" }).size(12))
+                .align_y(Alignment::Center)
+        )
+        .on_press(Message::ToggleClipboardHistory(device.id.clone()))
+        .width(Length::Fill)
+        .class(cosmic::theme::Button::Text)
+        .name(format!("{} clipboard history for {}", if is_open { "Hide" } else { "Show" }, device.name))
+    );
+
+    if !is_open {
+        return col.into();
+    }
+
+    for (index, content) in clipboard_menu.history.items().enumerate() {
+        let label = clipboard_label(content);
+        col = col.push(
+            named_button(
+                widget::text(label.clone()),
+                format!("Send \"{}\" to {}", label, device.name),
+                cosmic::theme::Button::Text,
+            )
+            .on_press(Message::ResendClipboard(device.id.clone(), index))
+            .width(Length::Fill)
+        );
+    }
+    col = col.push(
+        widget::container(
+            widget::button::standard("Clear history").on_press(Message::ClearClipboardHistory)
+        )
+        .padding([0, spacing.space_s])
+    );
+
+    col.into()
+}
+
+/// The device's group, the other groups to move it to, and a field to
+/// start a new one
+fn create_group_row<'a>(
+    device: &'a Device,
+    spacing: &cosmic::cosmic_theme::Spacing,
+    groups: Groups<'a>,
+) -> Element<'a, Message> {
+    let current = groups.config.group_of(&device.id);
+
+    let mut choices: Vec<Element<'a, Message>> = groups.config.names()
+        .map(|name| {
+            let is_current = current == Some(name);
+            named_button(
+                widget::text(name).size(12),
+                format!("Move {} to {}", device.name, name),
+                if is_current { cosmic::theme::Button::Suggested } else { cosmic::theme::Button::Standard },
+            )
+            .on_press_maybe((!is_current).then(|| Message::AssignGroup(device.id.clone(), Some(name.to_string()))))
+            .into()
+        })
+        .collect();
+    if current.is_some() {
+        choices.push(
+            named_button(widget::text("No group").size(12), format!("Remove {} from its group", device.name), cosmic::theme::Button::Text)
+                .on_press(Message::AssignGroup(device.id.clone(), None))
+                .into()
+        );
+    }
+
+    let submit_id = device.id.clone();
+    let new_group = groups.input.trim();
+    let mut col = widget::column()
+        .spacing(spacing.space_xxs)
+        .push(widget::text("Group").size(12));
+    if !choices.is_empty() {
+        col = col.push(
+            widget::flex_row(choices)
+                .row_spacing(spacing.space_xxs)
+                .column_spacing(spacing.space_xxs)
+        );
+    }
+    col = col.push(
+        widget::row()
+            .spacing(spacing.space_xxs)
+            .align_y(Alignment::Center)
+            .push(
+                widget::text_input("New group", groups.input)
+                    .on_input(Message::GroupNameChanged)
+                    .on_submit(move |name| Message::AssignGroup(submit_id.clone(), Some(name)))
+                    .width(Length::Fill)
+            )
+            .push(
+                widget::button::standard("Add")
+                    .on_press_maybe((!new_group.is_empty()).then(|| Message::AssignGroup(device.id.clone(), Some(new_group.to_string()))))
+            )
+    );
+
+    widget::container(col)
+        .padding([spacing.space_xxs, spacing.space_m])
+        .into()
+}
+
+/// Unpair button, or the confirmation offering to keep the device's data
+fn create_unpair_row<'a>(
+    device: &'a Device,
+    spacing: &cosmic::cosmic_theme::Spacing,
+    confirming: bool,
+) -> Element<'a, Message> {
+    if !confirming {
+        return widget::container(
+            named_button(widget::text("Unpair"), format!("Unpair {}", device.name), cosmic::theme::Button::Text)
+                .on_press(Message::UnpairDevice(device.id.clone()))
+                .width(Length::Fill)
+        )
+        .padding([0, spacing.space_xs])
+        .into();
+    }
+
+    widget::container(
+        widget::column()
+            .spacing(spacing.space_xs)
+            .push(
+                widget::row()
+                    .spacing(spacing.space_xxs)
+                    .align_y(Alignment::Center)
+                    .push(widget::icon::from_name("dialog-warning-symbolic").size(16))
+                    .push(widget::text(format!("Unpair {}?", device.name)).size(12).font(cosmic::font::bold()))
+            )
+            .push(
+                widget::text(
+                    "Its plugin settings, SMS history and synced contacts will be deleted from this computer. \
+                     Keep them if you plan to pair again."
+                )
+                .size(12)
+            )
+            .push(
+                widget::row()
+                    .spacing(spacing.space_xxs)
+                    .push(widget::horizontal_space())
+                    .push(widget::button::standard("Cancel").on_press(Message::CancelUnpair))
+                    .push(
+                        widget::button::standard("Keep data")
+                            .on_press(Message::ConfirmUnpair(device.id.clone(), true))
+                    )
+                    .push(
+                        widget::button::destructive("Delete data")
+                            .on_press(Message::ConfirmUnpair(device.id.clone(), false))
+                    )
+            )
+    )
+    .padding([spacing.space_xs, spacing.space_m])
+    .class(cosmic::theme::Container::Background)
+    .into()
+}
+
+/// Card style for a disabled device: text and icons at half opacity
+fn muted_card() -> cosmic::theme::Container<'static> {
+    cosmic::theme::Container::custom(|theme| {
+        let mut text_color = Color::from(theme.cosmic().background.on);
+        text_color.a *= 0.5;
+        container::Style {
+            text_color: Some(text_color),
+            ..Default::default()
+        }
+    })
+}
+
+/// Button announced to screen readers as `name`. Labels such as "Ping" or
+/// "Accept" don't say which device they act on, so the name does.
+fn named_button<'a>(
+    content: impl Into<Element<'a, Message>>,
+    name: String,
+    class: cosmic::theme::Button,
+) -> widget::Button<'a, Message> {
+    let spacing = cosmic::theme::active().cosmic().spacing;
+    widget::button::custom(content)
+        .padding([spacing.space_xxs, spacing.space_s])
+        .class(class)
+        .name(name)
+}
+
 fn create_device_card<'a>(
     device: &'a Device,
     spacing: &cosmic::cosmic_theme::Spacing,
+    compact: bool,
     expanded_device: Option<&'a String>,
+    drop_target: Option<&'a String>,
+    ping_message: &'a str,
+    confirm_unpair: Option<&'a String>,
+    clipboard_menu: ClipboardMenu<'a>,
+    groups: Groups<'a>,
 ) -> Element<'a, Message> {
     let is_expanded = expanded_device == Some(&device.id);
+    let is_drop_target = drop_target == Some(&device.id);
     let is_online = device.is_reachable;
+    let is_disabled = device.is_paired && !device.enabled;
+
+    // Compact cards are a single short row until expanded
+    let (icon_size, name_size, status_size) = if compact { (16, 12, 10) } else { (20, 14, 11) };
 
     let mut name_row = widget::row()
-        .push(widget::icon::from_name(device.device_icon()).size(20))
-        .push(widget::text(&device.name).size(14).width(Length::Fill))
+        .push(widget::icon::from_name(device.device_icon()).size(icon_size))
+        .push(widget::text(&device.name).size(name_size).width(Length::Fill))
         .spacing(spacing.space_xs)
         .align_y(Alignment::Center);
 
-    if !is_online {
-        name_row = name_row.push(widget::text("Offline").size(11));
-    } else if let Some(level) = device.battery_level {
-        name_row = name_row.push(widget::text(format!("{}%", level)).size(11));
+    if let Some(icon) = device.connection_icon() {
+        name_row = name_row.push(widget::icon::from_name(icon).size(12));
     }
 
-    name_row = name_row.push(
-        widget::button::icon(
-            widget::icon::from_name(if is_expanded { "go-up-symbolic" } else { "go-down-symbolic" })
-        )
-        .on_press(Message::ToggleDeviceMenu(device.id.clone()))
-        .class(cosmic::theme::Button::Icon)
-    );
+    let status = if is_disabled {
+        Some("Disabled".to_string())
+    } else if !is_online {
+        Some(if device.pending_packets > 0 {
+            format!("Offline · {} pending", device.pending_packets)
+        } else {
+            "Offline".to_string()
+        })
+    } else {
+        device.battery_level.map(|level| format!("{}%", level))
+    };
+    if let Some(status) = &status {
+        name_row = name_row.push(widget::text(status.clone()).size(status_size));
+    }
 
-    let device_button = widget::button::custom(name_row)
+    // The card and its chevron are siblings, not nested buttons, so each
+    // takes focus once: Tab reaches the card, then the chevron, then the
+    // actions below. Both toggle on Enter or Space.
+    let card_name = match (&status, is_online && !is_disabled) {
+        (Some(status), true) => format!("{}, battery {}", device.name, status),
+        (Some(status), false) => format!("{}, {}", device.name, status),
+        (None, _) => device.name.clone(),
+    };
+    let mut device_button = widget::button::custom(name_row)
         .on_press(Message::ToggleDeviceMenu(device.id.clone()))
         .width(Length::Fill)
-        .class(cosmic::theme::Button::Text);
+        .class(cosmic::theme::Button::Text)
+        .name(card_name);
+    if compact {
+        device_button = device_button.padding([spacing.space_xxxs, spacing.space_xs]);
+    }
+
+    let chevron = named_button(
+        widget::icon::from_name(if is_expanded { "go-up-symbolic" } else { "go-down-symbolic" }).size(16),
+        format!("{} {}", if is_expanded { "Collapse" } else { "Expand" }, device.name),
+        cosmic::theme::Button::Icon,
+    )
+    .on_press(Message::ToggleDeviceMenu(device.id.clone()));
 
-    let mut col = widget::column().push(device_button);
+    let mut col = widget::column().push(
+        widget::row()
+            .push(device_button)
+            .push(chevron)
+            .align_y(Alignment::Center)
+    );
+
+    if is_expanded && device.is_paired {
+        let toggle_id = device.id.clone();
+        col = col.push(
+            widget::container(
+                widget::row()
+                    .spacing(spacing.space_xs)
+                    .align_y(Alignment::Center)
+                    .push(widget::text("Enabled").size(12).width(Length::Fill))
+                    .push(
+                        widget::toggler(device.enabled)
+                            .on_toggle(move |enabled| Message::SetDeviceEnabled(toggle_id.clone(), enabled))
+                    )
+            )
+            .padding([spacing.space_xxs, spacing.space_m])
+        );
+        col = col.push(create_group_row(device, spacing, groups));
+        col = col.push(create_unpair_row(device, spacing, confirm_unpair == Some(&device.id)));
+    }
 
-    if is_expanded && is_online {
+    if is_expanded && is_disabled {
+        col = col.push(
+            widget::container(
+                widget::text("Syncing is paused. The device stays paired.").size(12)
+            )
+            .padding([spacing.space_xs, spacing.space_m])
+            .class(cosmic::theme::Container::Background)
+        );
+    } else if is_expanded && is_online {
         let mut menu_items = widget::column().spacing(spacing.space_xxs);
 
         menu_items = menu_items.push(
             widget::text("Quick Actions").size(12).font(cosmic::font::bold())
         );
         menu_items = menu_items.push(
-            widget::button::text("Ping")
+            named_button(widget::text("Ping"), format!("Ping {}", device.name), cosmic::theme::Button::Text)
                 .on_press(Message::PingDevice(device.id.clone()))
                 .width(Length::Fill)
-                .class(cosmic::theme::Button::Text)
+        );
+
+        // Optional custom ping text; the button above sends the configured default
+        let ping_id = device.id.clone();
+        menu_items = menu_items.push(
+            widget::row()
+                .spacing(spacing.space_xxs)
+                .align_y(Alignment::Center)
+                .padding([0, spacing.space_s])
+                .push(
+                    widget::text_input("Custom ping message", ping_message)
+                        .on_input(Message::PingMessageChanged)
+                        .on_submit(move |_| Message::SendCustomPing(ping_id.clone()))
+                        .size(12)
+                        .width(Length::Fill)
+                )
+                .push(
+                    named_button(
+                        widget::icon::from_name("mail-send-symbolic").size(16),
+                        format!("Send custom ping to {}", device.name),
+                        cosmic::theme::Button::Icon,
+                    )
+                    .on_press_maybe(
+                        (!ping_message.trim().is_empty())
+                            .then(|| Message::SendCustomPing(device.id.clone()))
+                    )
+                )
         );
 
         if device.has_findmyphone {
             menu_items = menu_items.push(
-                widget::button::text("Find my phone")
+                named_button(widget::text("Find my phone"), format!("Ring {}", device.name), cosmic::theme::Button::Text)
                     .on_press(Message::RingDevice(device.id.clone()))
                     .width(Length::Fill)
-                    .class(cosmic::theme::Button::Text)
             );
         }
 
         if device.has_clipboard {
             menu_items = menu_items.push(
-                widget::button::text("Share clipboard")
+                named_button(widget::text("Share clipboard"), format!("Share clipboard with {}", device.name), cosmic::theme::Button::Text)
                     .on_press(Message::ShareClipboard(device.id.clone()))
                     .width(Length::Fill)
-                    .class(cosmic::theme::Button::Text)
             );
+            if let Some((_, notice)) = clipboard_menu.notice.filter(|(id, _)| *id == &device.id) {
+                menu_items = menu_items.push(
+                    widget::container(widget::text(notice).size(11)).padding([0, spacing.space_s])
+                );
+            }
+            if !clipboard_menu.history.is_empty() {
+                menu_items = menu_items.push(create_clipboard_history(device, spacing, clipboard_menu));
+            }
         }
 
         menu_items = menu_items.push(
-            widget::button::text("SMS Messages")
+            named_button(widget::text("SMS Messages"), format!("SMS messages with {}", device.name), cosmic::theme::Button::Text)
                 .on_press(Message::SendSMS(device.id.clone()))
                 .width(Length::Fill)
-                .class(cosmic::theme::Button::Text)
         );
 
         if device.has_share || device.has_sftp {
@@ -197,19 +732,17 @@ fn create_device_card<'a>(
 
             if device.has_share {
                 menu_items = menu_items.push(
-                    widget::button::text("Send file")
+                    named_button(widget::text("Send file"), format!("Send file to {}", device.name), cosmic::theme::Button::Text)
                         .on_press(Message::SendFiles(device.id.clone()))
                         .width(Length::Fill)
-                        .class(cosmic::theme::Button::Text)
                 );
             }
 
             if device.has_sftp {
                 menu_items = menu_items.push(
-                    widget::button::text("Browse device")
+                    named_button(widget::text("Browse device"), format!("Browse files on {}", device.name), cosmic::theme::Button::Text)
                         .on_press(Message::BrowseDevice(device.id.clone()))
                         .width(Length::Fill)
-                        .class(cosmic::theme::Button::Text)
                 );
             }
         }
@@ -229,5 +762,27 @@ fn create_device_card<'a>(
         );
     }
 
-    col.into()
+    let card = widget::container(col)
+        .class(if is_disabled {
+            muted_card()
+        } else if is_drop_target {
+            cosmic::theme::Container::Primary
+        } else {
+            cosmic::theme::Container::Transparent
+        });
+
+    if is_disabled || !(is_online && device.has_share) {
+        return card.into();
+    }
+
+    // Dropping files onto a reachable device card sends them to it
+    let enter_id = device.id.clone();
+    let drop_id = device.id.clone();
+    widget::dnd_destination(card, vec!["text/uri-list".into()])
+        .on_enter(move |_, _, _| Message::DropTargetChanged(Some(enter_id.clone())))
+        .on_leave(|| Message::DropTargetChanged(None))
+        .on_finish(move |_mime, data, _action, _, _| {
+            Message::FilesDropped(drop_id.clone(), portal::paths_from_uri_list(&data))
+        })
+        .into()
 }
@@ -0,0 +1,9 @@
+// cosmic-connect-common/src/lib.rs
+//! Settings and text handling shared by the applet and the service.
+//!
+//! The applet's settings window writes these config files and the service
+//! acts on them, so both read them through the same code.
+
+pub mod notification_text;
+pub mod plugin_config;
+pub mod trusted_devices;
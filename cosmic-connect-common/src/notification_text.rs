@@ -0,0 +1,87 @@
+// cosmic-connect-common/src/notification_text.rs
+//! Cleaning device-supplied text for desktop notifications.
+//!
+//! Device names, app names and message bodies come from the phone and go
+//! straight into notifications. Summaries are flattened to one line, bodies
+//! lose control characters and are escaped when the notification server
+//! renders markup, and both are truncated.
+
+/// Longest summary kept, in characters
+pub const MAX_SUMMARY_CHARS: usize = 120;
+
+/// Longest body kept, in characters
+pub const MAX_BODY_CHARS: usize = 1000;
+
+/// One line of plain text: control characters and line breaks become
+/// spaces, runs of whitespace collapse and the result is truncated
+pub fn summary(text: &str) -> String {
+    let flat = text
+        .split(|c: char| c.is_whitespace() || c.is_control())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    truncate(&flat, MAX_SUMMARY_CHARS)
+}
+
+/// Body text with line breaks kept and other control characters removed,
+/// truncated, and escaped if the server interprets `markup`
+pub fn body(text: &str, markup: bool) -> String {
+    let cleaned: String = text.trim()
+        .chars()
+        .filter(|c| *c == '\n' || !c.is_control())
+        .collect();
+    let truncated = truncate(&cleaned, MAX_BODY_CHARS);
+    if markup {
+        escape_markup(&truncated)
+    } else {
+        truncated
+    }
+}
+
+/// Escape the characters notification markup gives meaning to
+pub fn escape_markup(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Cut to `max` characters, ending in "…" when anything was cut
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(max - 1).collect();
+    cut.push('…');
+    cut
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notification_text_from_hostile_names() {
+        assert_eq!(summary("Bob's \"Pixel\"\n\nrm -rf ~"), "Bob's \"Pixel\" rm -rf ~");
+        assert_eq!(summary("Tab\tand\r\nbell\u{7}"), "Tab and bell");
+        assert_eq!(summary(&"é".repeat(500)).chars().count(), MAX_SUMMARY_CHARS);
+
+        let name = "<b>Phone</b> & \"friends\"";
+        assert_eq!(body(name, true), "&lt;b&gt;Phone&lt;/b&gt; &amp; &quot;friends&quot;");
+        assert_eq!(body(name, false), name);
+        assert_eq!(body("<a href='x'>link</a>", true), "&lt;a href=&apos;x&apos;&gt;link&lt;/a&gt;");
+
+        assert_eq!(body("  line one\nline two\u{0}\u{1b}[31m  ", false), "line one\nline two[31m");
+        let long = body(&"x".repeat(5000), false);
+        assert_eq!(long.chars().count(), MAX_BODY_CHARS);
+        assert!(long.ends_with('…'));
+    }
+}
@@ -1,18 +1,42 @@
-// cosmic-connect-applet/src/plugin_config.rs
+// cosmic-connect-common/src/plugin_config.rs
 //! Plugin configuration management for KDE Connect plugins.
 //!
 //! This module handles reading and writing plugin-specific configuration
 //! settings for each device, stored in ~/.config/kdeconnect/{device_id}/{plugin_name}/config
 
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::fs;
 use std::io::{self, Write};
+use tracing::{debug, info};
 
 /// Configuration for the Share plugin (file transfer)
 #[derive(Debug, Clone)]
 pub struct SharePluginConfig {
     /// Directory where received files are saved
     pub destination_path: String,
+    /// Save into a subfolder named after the sending device
+    pub organize_by_device: bool,
+    /// Save into a `YYYY-MM-DD` subfolder for the day received
+    pub organize_by_date: bool,
+}
+
+/// Longest subfolder name made from a device name, in characters
+const MAX_FOLDER_NAME_CHARS: usize = 64;
+
+/// Make a device name safe to use as a single path component: no
+/// separators, control characters or leading dots
+pub fn sanitize_folder_name(name: &str) -> String {
+    let cleaned: String = name.chars()
+        .map(|c| if c == '/' || c == '\\' || c.is_control() { '_' } else { c })
+        .take(MAX_FOLDER_NAME_CHARS)
+        .collect();
+    let cleaned = cleaned.trim().trim_start_matches('.').trim();
+    if cleaned.is_empty() {
+        "Unknown device".to_string()
+    } else {
+        cleaned.to_string()
+    }
 }
 
 impl Default for SharePluginConfig {
@@ -23,6 +47,8 @@ impl Default for SharePluginConfig {
         
         Self {
             destination_path: default_path,
+            organize_by_device: false,
+            organize_by_date: false,
         }
     }
 }
@@ -33,7 +59,7 @@ impl SharePluginConfig {
         let config_path = Self::get_config_path(device_id);
         
         if !config_path.exists() {
-            eprintln!("Share plugin config not found for device {}, using defaults", device_id);
+            debug!("Share plugin config not found for device {}, using defaults", device_id);
             return Ok(Self::default());
         }
         
@@ -56,6 +82,12 @@ impl SharePluginConfig {
                     "incomingPath" | "destinationPath" => {
                         config.destination_path = value.to_string();
                     }
+                    "organizeByDevice" => {
+                        config.organize_by_device = value.parse::<bool>().unwrap_or(false);
+                    }
+                    "organizeByDate" => {
+                        config.organize_by_date = value.parse::<bool>().unwrap_or(false);
+                    }
                     _ => {}
                 }
             }
@@ -77,14 +109,30 @@ impl SharePluginConfig {
         let mut file = fs::File::create(&config_path)?;
         writeln!(file, "[General]")?;
         writeln!(file, "incomingPath={}", self.destination_path)?;
+        writeln!(file, "organizeByDevice={}", self.organize_by_device)?;
+        writeln!(file, "organizeByDate={}", self.organize_by_date)?;
         
-        eprintln!("✓ Saved share plugin config for device {}", device_id);
-        eprintln!("  Path: {}", config_path.display());
-        eprintln!("  Destination: {}", self.destination_path);
+        info!("Saved share plugin config for device {}", device_id);
+        debug!("  Path: {}", config_path.display());
+        debug!("  Destination: {}", self.destination_path);
         
         Ok(())
     }
     
+    /// Folder that files from `device_name` received on `date`
+    /// (`YYYY-MM-DD`) are saved into: the destination, then the device's
+    /// and the day's subfolders if enabled
+    pub fn received_folder(&self, device_name: &str, date: &str) -> PathBuf {
+        let mut folder = PathBuf::from(&self.destination_path);
+        if self.organize_by_device {
+            folder.push(sanitize_folder_name(device_name));
+        }
+        if self.organize_by_date {
+            folder.push(sanitize_folder_name(date));
+        }
+        folder
+    }
+
     /// Get the config file path for a device's share plugin
     fn get_config_path(device_id: &str) -> PathBuf {
         let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
@@ -97,6 +145,9 @@ impl SharePluginConfig {
     }
 }
 
+/// Default cap on clipboard text sent to a device, in KiB
+pub const DEFAULT_CLIPBOARD_MAX_KB: usize = 100;
+
 /// Configuration for the Clipboard plugin
 #[derive(Debug, Clone)]
 pub struct ClipboardPluginConfig {
@@ -104,6 +155,8 @@ pub struct ClipboardPluginConfig {
     pub auto_share: bool,
     /// Share password content from password managers
     pub send_password: bool,
+    /// Largest clipboard text sent to the device, in KiB
+    pub max_text_kb: usize,
 }
 
 impl Default for ClipboardPluginConfig {
@@ -111,6 +164,7 @@ impl Default for ClipboardPluginConfig {
         Self {
             auto_share: true,      // Auto-sync enabled by default
             send_password: false,  // Don't share passwords by default (security)
+            max_text_kb: DEFAULT_CLIPBOARD_MAX_KB,
         }
     }
 }
@@ -121,7 +175,7 @@ impl ClipboardPluginConfig {
         let config_path = Self::get_config_path(device_id);
         
         if !config_path.exists() {
-            eprintln!("Clipboard plugin config not found for device {}, using defaults", device_id);
+            debug!("Clipboard plugin config not found for device {}, using defaults", device_id);
             return Ok(Self::default());
         }
         
@@ -147,6 +201,12 @@ impl ClipboardPluginConfig {
                     "sendPassword" => {
                         config.send_password = value.parse::<bool>().unwrap_or(false);
                     }
+                    "maxTextSizeKb" => {
+                        config.max_text_kb = value.parse::<usize>()
+                            .ok()
+                            .filter(|kb| *kb > 0)
+                            .unwrap_or(DEFAULT_CLIPBOARD_MAX_KB);
+                    }
                     _ => {}
                 }
             }
@@ -169,15 +229,21 @@ impl ClipboardPluginConfig {
         writeln!(file, "[General]")?;
         writeln!(file, "autoShare={}", self.auto_share)?;
         writeln!(file, "sendPassword={}", self.send_password)?;
+        writeln!(file, "maxTextSizeKb={}", self.max_text_kb)?;
         
-        eprintln!("✓ Saved clipboard plugin config for device {}", device_id);
-        eprintln!("  Path: {}", config_path.display());
-        eprintln!("  Auto share: {}", self.auto_share);
-        eprintln!("  Send passwords: {}", self.send_password);
+        info!("Saved clipboard plugin config for device {}", device_id);
+        debug!("  Path: {}", config_path.display());
+        debug!("  Auto share: {}", self.auto_share);
+        debug!("  Send passwords: {}", self.send_password);
         
         Ok(())
     }
     
+    /// Largest clipboard text sent to the device, in bytes
+    pub fn max_text_bytes(&self) -> usize {
+        self.max_text_kb.saturating_mul(1024)
+    }
+
     /// Get the config file path for a device's clipboard plugin
     fn get_config_path(device_id: &str) -> PathBuf {
         let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
@@ -229,7 +295,7 @@ impl RunCommandPluginConfig {
         let config_path = Self::get_config_path(device_id);
         
         if !config_path.exists() {
-            eprintln!("RunCommand plugin config not found for device {}, using defaults", device_id);
+            debug!("RunCommand plugin config not found for device {}, using defaults", device_id);
             return Ok(Self::default());
         }
         
@@ -313,9 +379,9 @@ impl RunCommandPluginConfig {
             writeln!(file)?; // Empty line between groups
         }
         
-        eprintln!("✓ Saved runcommand plugin config for device {}", device_id);
-        eprintln!("  Path: {}", config_path.display());
-        eprintln!("  Commands: {}", self.commands.len());
+        info!("Saved runcommand plugin config for device {}", device_id);
+        debug!("  Path: {}", config_path.display());
+        debug!("  Commands: {}", self.commands.len());
         
         Ok(())
     }
@@ -370,7 +436,7 @@ impl PauseMusicPluginConfig {
         let config_path = Self::get_config_path(device_id);
         
         if !config_path.exists() {
-            eprintln!("PauseMusic plugin config not found for device {}, using defaults", device_id);
+            debug!("PauseMusic plugin config not found for device {}, using defaults", device_id);
             return Ok(Self::default());
         }
         
@@ -431,13 +497,13 @@ impl PauseMusicPluginConfig {
         writeln!(file, "muteSystemSound={}", self.mute_system_sound)?;
         writeln!(file, "resumeAfterCall={}", self.resume_after_call)?;
         
-        eprintln!("✓ Saved pausemusic plugin config for device {}", device_id);
-        eprintln!("  Path: {}", config_path.display());
-        eprintln!("  Pause on ringing: {}", self.pause_on_ringing);
-        eprintln!("  Pause only on talking: {}", self.pause_only_on_talking);
-        eprintln!("  Pause media: {}", self.pause_media);
-        eprintln!("  Mute system: {}", self.mute_system_sound);
-        eprintln!("  Resume after call: {}", self.resume_after_call);
+        info!("Saved pausemusic plugin config for device {}", device_id);
+        debug!("  Path: {}", config_path.display());
+        debug!("  Pause on ringing: {}", self.pause_on_ringing);
+        debug!("  Pause only on talking: {}", self.pause_only_on_talking);
+        debug!("  Pause media: {}", self.pause_media);
+        debug!("  Mute system: {}", self.mute_system_sound);
+        debug!("  Resume after call: {}", self.resume_after_call);
         
         Ok(())
     }
@@ -487,7 +553,7 @@ impl FindMyPhonePluginConfig {
         let config_path = Self::get_config_path(device_id);
         
         if !config_path.exists() {
-            eprintln!("FindMyPhone plugin config not found for device {}, using defaults", device_id);
+            debug!("FindMyPhone plugin config not found for device {}, using defaults", device_id);
             return Ok(Self::default());
         }
         
@@ -532,9 +598,9 @@ impl FindMyPhonePluginConfig {
         writeln!(file, "[General]")?;
         writeln!(file, "ringtone={}", self.ringtone_path)?;
         
-        eprintln!("✓ Saved findmyphone plugin config for device {}", device_id);
-        eprintln!("  Path: {}", config_path.display());
-        eprintln!("  Ringtone: {}", self.ringtone_path);
+        info!("Saved findmyphone plugin config for device {}", device_id);
+        debug!("  Path: {}", config_path.display());
+        debug!("  Ringtone: {}", self.ringtone_path);
         
         Ok(())
     }
@@ -551,6 +617,91 @@ impl FindMyPhonePluginConfig {
     }
 }
 
+/// Default text sent by a one-click ping
+pub const DEFAULT_PING_MESSAGE: &str = "Ping from COSMIC!";
+
+/// Configuration for the Ping plugin
+#[derive(Debug, Clone)]
+pub struct PingPluginConfig {
+    /// Message sent when pinging without custom text
+    pub message: String,
+}
+
+impl Default for PingPluginConfig {
+    fn default() -> Self {
+        Self {
+            message: DEFAULT_PING_MESSAGE.to_string(),
+        }
+    }
+}
+
+impl PingPluginConfig {
+    /// Load configuration from file
+    pub fn load(device_id: &str) -> io::Result<Self> {
+        let config_path = Self::get_config_path(device_id);
+        
+        if !config_path.exists() {
+            debug!("Ping plugin config not found for device {}, using defaults", device_id);
+            return Ok(Self::default());
+        }
+        
+        let content = fs::read_to_string(&config_path)?;
+        
+        // Parse the KDE config file format (key=value)
+        let mut config = Self::default();
+        
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+                continue;
+            }
+            
+            if let Some((key, value)) = line.split_once('=') {
+                let key = key.trim();
+                let value = value.trim();
+                
+                if key == "message" && !value.is_empty() {
+                    config.message = value.to_string();
+                }
+            }
+        }
+        
+        Ok(config)
+    }
+    
+    /// Save configuration to file
+    pub fn save(&self, device_id: &str) -> io::Result<()> {
+        let config_path = Self::get_config_path(device_id);
+        
+        // Ensure directory exists
+        if let Some(parent) = config_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        
+        // Write config file in KDE config format
+        let mut file = fs::File::create(&config_path)?;
+        writeln!(file, "[General]")?;
+        writeln!(file, "message={}", self.message)?;
+        
+        info!("Saved ping plugin config for device {}", device_id);
+        debug!("  Path: {}", config_path.display());
+        debug!("  Message: {}", self.message);
+        
+        Ok(())
+    }
+    
+    /// Get the config file path for a device's ping plugin
+    fn get_config_path(device_id: &str) -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+        PathBuf::from(format!("{}/.config/kdeconnect/{}/kdeconnect_ping/config", home, device_id))
+    }
+    
+    /// Check if a config file exists for the device
+    pub fn exists(device_id: &str) -> bool {
+        Self::get_config_path(device_id).exists()
+    }
+}
+
 /// Notification urgency level
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UrgencyLevel {
@@ -626,7 +777,7 @@ impl SendNotificationsPluginConfig {
         let config_path = Self::get_config_path(device_id);
         
         if !config_path.exists() {
-            eprintln!("SendNotifications plugin config not found for device {}, using defaults", device_id);
+            debug!("SendNotifications plugin config not found for device {}, using defaults", device_id);
             return Ok(Self::default());
         }
         
@@ -718,14 +869,14 @@ impl SendNotificationsPluginConfig {
             }
         }
         
-        eprintln!("✓ Saved sendnotifications plugin config for device {}", device_id);
-        eprintln!("  Path: {}", config_path.display());
-        eprintln!("  Persistent only: {}", self.persistent_only);
-        eprintln!("  Include body: {}", self.include_body);
-        eprintln!("  Sync icons: {}", self.sync_icons);
-        eprintln!("  Min urgency: {:?}", self.min_urgency);
-        eprintln!("  Mode: {}", if self.use_blocklist { "Blocklist" } else { "Allowlist" });
-        eprintln!("  App rules: {}", self.app_settings.len());
+        info!("Saved sendnotifications plugin config for device {}", device_id);
+        debug!("  Path: {}", config_path.display());
+        debug!("  Persistent only: {}", self.persistent_only);
+        debug!("  Include body: {}", self.include_body);
+        debug!("  Sync icons: {}", self.sync_icons);
+        debug!("  Min urgency: {:?}", self.min_urgency);
+        debug!("  Mode: {}", if self.use_blocklist { "Blocklist" } else { "Allowlist" });
+        debug!("  App rules: {}", self.app_settings.len());
         
         Ok(())
     }
@@ -742,6 +893,123 @@ impl SendNotificationsPluginConfig {
     }
 }
 
+/// Plugin id of mirrored phone notifications, as used in `[Plugins]`
+pub const NOTIFICATIONS_PLUGIN: &str = "notifications";
+
+/// Plugin id of call notifications from the phone
+pub const TELEPHONY_PLUGIN: &str = "telephony";
+
+/// Whether a plugin is on for a device where it was never switched,
+/// matching what KDE Connect enables out of the box
+pub fn plugin_enabled_by_default(plugin: &str) -> bool {
+    matches!(
+        plugin,
+        "battery" | "clipboard" | "connectivity_report" | "findmyphone" | NOTIFICATIONS_PLUGIN
+            | "ping" | "sendnotifications" | "sftp" | "share" | "sms"
+    )
+}
+
+/// Which plugins are switched on for a device.
+///
+/// Stored like KDE Connect does, in the `[Plugins]` group of
+/// ~/.config/kdeconnect/{device_id}/config as `kdeconnect_{plugin}Enabled=bool`.
+/// Other groups in that file are written back unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DevicePluginsConfig {
+    enabled: BTreeMap<String, bool>,
+    /// Lines outside `[Plugins]`, kept as they were
+    other: Vec<String>,
+}
+
+impl DevicePluginsConfig {
+    /// Load plugin switches from file
+    pub fn load(device_id: &str) -> io::Result<Self> {
+        let config_path = Self::get_config_path(device_id);
+
+        if !config_path.exists() {
+            return Ok(Self::default());
+        }
+
+        Ok(Self::parse(&fs::read_to_string(&config_path)?))
+    }
+
+    fn parse(content: &str) -> Self {
+        let mut config = Self::default();
+        let mut in_plugins = false;
+
+        for line in content.lines() {
+            let trimmed = line.trim();
+            if trimmed.starts_with('[') && trimmed.ends_with(']') {
+                in_plugins = trimmed == "[Plugins]";
+                if in_plugins {
+                    continue;
+                }
+            }
+            if !in_plugins {
+                if !trimmed.is_empty() {
+                    config.other.push(line.to_string());
+                }
+                continue;
+            }
+
+            let Some((key, value)) = trimmed.split_once('=') else { continue };
+            let plugin = key.trim()
+                .strip_prefix("kdeconnect_")
+                .and_then(|k| k.strip_suffix("Enabled"));
+            if let (Some(plugin), Ok(enabled)) = (plugin, value.trim().parse::<bool>()) {
+                config.enabled.insert(plugin.to_string(), enabled);
+            }
+        }
+
+        config
+    }
+
+    fn render(&self) -> String {
+        let mut content = String::new();
+        for line in &self.other {
+            content.push_str(line);
+            content.push('\n');
+        }
+        content.push_str("[Plugins]\n");
+        for (plugin, enabled) in &self.enabled {
+            content.push_str(&format!("kdeconnect_{}Enabled={}\n", plugin, enabled));
+        }
+        content
+    }
+
+    /// Save plugin switches to file
+    pub fn save(&self, device_id: &str) -> io::Result<()> {
+        let config_path = Self::get_config_path(device_id);
+
+        if let Some(parent) = config_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&config_path, self.render())?;
+
+        debug!("Saved plugin switches for device {}", device_id);
+        Ok(())
+    }
+
+    /// Whether `plugin` (e.g. "ping") is on, or `default` if never set
+    pub fn is_enabled(&self, plugin: &str, default: bool) -> bool {
+        self.enabled.get(plugin).copied().unwrap_or(default)
+    }
+
+    /// Whether `plugin` is on, with KDE Connect's default if never set
+    pub fn allows(&self, plugin: &str) -> bool {
+        self.is_enabled(plugin, plugin_enabled_by_default(plugin))
+    }
+
+    pub fn set_enabled(&mut self, plugin: &str, enabled: bool) {
+        self.enabled.insert(plugin.to_string(), enabled);
+    }
+
+    fn get_config_path(device_id: &str) -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+        PathBuf::from(format!("{}/.config/kdeconnect/{}/config", home, device_id))
+    }
+}
+
 /// All plugin-specific configurations
 #[derive(Debug, Clone)]
 pub struct PluginConfigs {
@@ -750,6 +1018,7 @@ pub struct PluginConfigs {
     pub runcommand: RunCommandPluginConfig,
     pub pausemusic: PauseMusicPluginConfig,
     pub findmyphone: FindMyPhonePluginConfig,
+    pub ping: PingPluginConfig,
     pub sendnotifications: SendNotificationsPluginConfig,
 }
 
@@ -762,6 +1031,7 @@ impl PluginConfigs {
             runcommand: RunCommandPluginConfig::load(device_id).unwrap_or_default(),
             pausemusic: PauseMusicPluginConfig::load(device_id).unwrap_or_default(),
             findmyphone: FindMyPhonePluginConfig::load(device_id).unwrap_or_default(),
+            ping: PingPluginConfig::load(device_id).unwrap_or_default(),
             sendnotifications: SendNotificationsPluginConfig::load(device_id).unwrap_or_default(),
         }
     }
@@ -773,11 +1043,26 @@ impl PluginConfigs {
         self.runcommand.save(device_id)?;
         self.pausemusic.save(device_id)?;
         self.findmyphone.save(device_id)?;
+        self.ping.save(device_id)?;
         self.sendnotifications.save(device_id)?;
         Ok(())
     }
 }
 
+/// Cut clipboard text down to at most `max_bytes`, on a character boundary.
+/// Returns whether anything was cut.
+pub fn truncate_clipboard_text(content: &mut String, max_bytes: usize) -> bool {
+    if content.len() <= max_bytes {
+        return false;
+    }
+    let mut end = max_bytes;
+    while !content.is_char_boundary(end) {
+        end -= 1;
+    }
+    content.truncate(end);
+    true
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -787,4 +1072,60 @@ mod tests {
         let config = SharePluginConfig::default();
         assert!(config.destination_path.ends_with("/Downloads"));
     }
+
+    #[test]
+    fn test_received_folder_subfolders() {
+        let mut config = SharePluginConfig {
+            destination_path: "/home/user/Downloads".to_string(),
+            ..SharePluginConfig::default()
+        };
+        assert_eq!(config.received_folder("Pixel", "2026-10-16"), PathBuf::from("/home/user/Downloads"));
+
+        config.organize_by_device = true;
+        assert_eq!(config.received_folder("Pixel", "2026-10-16"), PathBuf::from("/home/user/Downloads/Pixel"));
+        assert_eq!(
+            config.received_folder("../Alex's phone/", "2026-10-16"),
+            PathBuf::from("/home/user/Downloads/_Alex's phone_")
+        );
+
+        config.organize_by_date = true;
+        assert_eq!(
+            config.received_folder("Pixel", "2026-10-16"),
+            PathBuf::from("/home/user/Downloads/Pixel/2026-10-16")
+        );
+        assert_eq!(sanitize_folder_name(" .. "), "Unknown device");
+    }
+
+    #[test]
+    fn test_device_plugins_round_trip() {
+        let mut config = DevicePluginsConfig::parse(
+            "[General]\nname=Phone\n\n[Plugins]\nkdeconnect_pingEnabled=true\nkdeconnect_notificationsEnabled=false\n"
+        );
+        assert!(config.is_enabled("ping", false));
+        assert!(!config.is_enabled(NOTIFICATIONS_PLUGIN, true));
+        assert!(config.is_enabled("share", true), "unset plugins use the default");
+
+        config.set_enabled(NOTIFICATIONS_PLUGIN, true);
+        let reloaded = DevicePluginsConfig::parse(&config.render());
+        assert_eq!(reloaded, config);
+        assert!(reloaded.is_enabled(NOTIFICATIONS_PLUGIN, false));
+        assert!(reloaded.render().starts_with("[General]\nname=Phone\n"));
+    }
+
+    #[test]
+    fn test_clipboard_truncation_threshold() {
+        let limit = ClipboardPluginConfig::default().max_text_bytes();
+        assert_eq!(limit, 100 * 1024);
+
+        let mut text = "a".repeat(limit);
+        assert!(!truncate_clipboard_text(&mut text, limit), "exactly at the limit");
+        text.push('a');
+        assert!(truncate_clipboard_text(&mut text, limit));
+        assert_eq!(text.len(), limit);
+
+        // Never splits a multi-byte character
+        let mut text = "é".repeat(3); // 6 bytes
+        assert!(truncate_clipboard_text(&mut text, 5));
+        assert_eq!(text, "éé");
+    }
 }
\ No newline at end of file
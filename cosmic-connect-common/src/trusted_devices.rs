@@ -0,0 +1,169 @@
+// cosmic-connect-common/src/trusted_devices.rs
+//! Devices whose pairing requests are accepted without asking.
+//!
+//! Stored in ~/.config/cosmic-connect/trusted_devices with one [device_id]
+//! section per device, holding the certificate fingerprint it was trusted
+//! with. A request is only auto-accepted while the device presents that
+//! same certificate.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrustedDevice {
+    pub id: String,
+    pub name: String,
+    /// SHA-256 fingerprint of the certificate the device was trusted with
+    pub fingerprint: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TrustedDevicesConfig {
+    devices: BTreeMap<String, TrustedDevice>,
+}
+
+impl TrustedDevicesConfig {
+    /// Load trusted devices from file
+    pub fn load() -> io::Result<Self> {
+        let config_path = Self::get_config_path();
+
+        if !config_path.exists() {
+            return Ok(Self::default());
+        }
+
+        Ok(Self::parse(&fs::read_to_string(&config_path)?))
+    }
+
+    fn parse(content: &str) -> Self {
+        let mut config = Self::default();
+        let mut current: Option<TrustedDevice> = None;
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with('[') && line.ends_with(']') {
+                if let Some(device) = current.take() {
+                    config.insert(device);
+                }
+                current = Some(TrustedDevice {
+                    id: line[1..line.len()-1].to_string(),
+                    name: String::new(),
+                    fingerprint: String::new(),
+                });
+                continue;
+            }
+
+            if let (Some(device), Some((key, value))) = (current.as_mut(), line.split_once('=')) {
+                match key.trim() {
+                    "name" => device.name = value.trim().to_string(),
+                    "fingerprint" => device.fingerprint = value.trim().to_string(),
+                    _ => {}
+                }
+            }
+        }
+        if let Some(device) = current {
+            config.insert(device);
+        }
+
+        config
+    }
+
+    /// Entries without a fingerprint could never be verified, so they are dropped
+    fn insert(&mut self, device: TrustedDevice) {
+        if !device.id.is_empty() && !device.fingerprint.is_empty() {
+            self.devices.insert(device.id.clone(), device);
+        }
+    }
+
+    /// Save trusted devices to file
+    pub fn save(&self) -> io::Result<()> {
+        let config_path = Self::get_config_path();
+
+        // Ensure directory exists
+        if let Some(parent) = config_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = fs::File::create(&config_path)?;
+
+        for device in self.devices.values() {
+            writeln!(file, "[{}]", device.id)?;
+            writeln!(file, "name={}", device.name)?;
+            writeln!(file, "fingerprint={}", device.fingerprint)?;
+            writeln!(file)?;
+        }
+
+        Ok(())
+    }
+
+    /// Trusted devices, ordered by id
+    pub fn devices(&self) -> impl Iterator<Item = &TrustedDevice> {
+        self.devices.values()
+    }
+
+    pub fn contains(&self, device_id: &str) -> bool {
+        self.devices.contains_key(device_id)
+    }
+
+    /// Trust a device with the certificate it currently presents. A device
+    /// whose fingerprint is unknown can't be trusted.
+    pub fn add(&mut self, device_id: &str, name: &str, fingerprint: &str) -> Result<(), String> {
+        if fingerprint.trim().is_empty() {
+            return Err(format!("{}'s certificate fingerprint is unknown", name));
+        }
+        self.insert(TrustedDevice {
+            id: device_id.to_string(),
+            name: name.to_string(),
+            fingerprint: fingerprint.trim().to_string(),
+        });
+        Ok(())
+    }
+
+    /// Stop trusting a device, returning whether it was trusted
+    pub fn remove(&mut self, device_id: &str) -> bool {
+        self.devices.remove(device_id).is_some()
+    }
+
+    /// Whether a pairing request from `device_id` presenting `fingerprint`
+    /// may be accepted without asking. Never true for a fingerprint other
+    /// than the one the device was trusted with.
+    pub fn should_auto_accept(&self, device_id: &str, fingerprint: &str) -> bool {
+        self.devices.get(device_id)
+            .is_some_and(|d| !fingerprint.is_empty() && d.fingerprint.eq_ignore_ascii_case(fingerprint))
+    }
+
+    /// Get the config file path
+    fn get_config_path() -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+        PathBuf::from(format!("{}/.config/cosmic-connect/trusted_devices", home))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trusted_device_requires_matching_fingerprint() {
+        let config = TrustedDevicesConfig::parse(
+            "[phone]\nname=Pixel\nfingerprint=AA:BB\n\n[tablet]\nname=No fingerprint\n",
+        );
+        assert!(config.contains("phone"));
+        assert!(!config.contains("tablet"));
+
+        assert!(config.should_auto_accept("phone", "aa:bb"));
+        assert!(!config.should_auto_accept("phone", "AA:CC"));
+        assert!(!config.should_auto_accept("phone", ""));
+        assert!(!config.should_auto_accept("laptop", "AA:BB"));
+
+        let mut config = config;
+        assert!(config.add("laptop", "Laptop", "").is_err());
+        assert!(config.remove("phone"));
+        assert!(!config.should_auto_accept("phone", "AA:BB"));
+    }
+}
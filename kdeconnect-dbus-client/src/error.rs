@@ -0,0 +1,152 @@
+// kdeconnect-dbus-client/src/error.rs
+//! Error kinds shared by kdeconnect-service and its clients.
+//!
+//! The service reports each kind under a fixed D-Bus error name, so a
+//! caller can tell an offline phone from a stopped service and show the
+//! right message. kdeconnect-service uses this type through its
+//! dependency on this crate.
+//!
+//! | Kind                | D-Bus error                                   |
+//! |---------------------|-----------------------------------------------|
+//! | `ServiceUnavailable`| `org.freedesktop.DBus.Error.ServiceUnknown`   |
+//! | `UnknownDevice`     | `org.freedesktop.DBus.Error.UnknownObject`    |
+//! | `DeviceUnreachable` | `org.freedesktop.DBus.Error.Disconnected`     |
+//! | `DeviceDisabled`    | `org.freedesktop.DBus.Error.AccessDenied`     |
+//! | `ChannelClosed`     | `org.freedesktop.DBus.Error.IOError`          |
+//! | `InvalidArgs`       | `org.freedesktop.DBus.Error.InvalidArgs`      |
+//! | anything else       | `org.freedesktop.DBus.Error.Failed`           |
+
+use std::fmt;
+use zbus::DBusError;
+
+const UNKNOWN_DEVICE: &str = "Unknown device: ";
+const DEVICE_UNREACHABLE: &str = "Device not reachable: ";
+const DEVICE_DISABLED: &str = "Device is disabled: ";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KdeConnectError {
+    /// The client hasn't connected to the service yet
+    NotInitialized,
+    /// kdeconnect-service isn't on the session bus
+    ServiceUnavailable,
+    UnknownDevice(String),
+    DeviceUnreachable(String),
+    /// Paired, but syncing with the device is turned off
+    DeviceDisabled(String),
+    /// The plugin a request belongs to is switched off for the device
+    PluginDisabled(String),
+    /// kdeconnect-core stopped accepting packets
+    ChannelClosed,
+    InvalidArgs(String),
+    /// Any other failure reported by the service or the bus
+    Protocol(String),
+}
+
+impl fmt::Display for KdeConnectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotInitialized => f.write_str("Not connected to the KDE Connect service"),
+            Self::ServiceUnavailable => f.write_str("The KDE Connect service isn't running"),
+            Self::UnknownDevice(id) => write!(f, "{}{}", UNKNOWN_DEVICE, id),
+            Self::DeviceUnreachable(id) => write!(f, "{}{}", DEVICE_UNREACHABLE, id),
+            Self::DeviceDisabled(id) => write!(f, "{}{}", DEVICE_DISABLED, id),
+            Self::PluginDisabled(plugin) => write!(f, "The {} plugin is disabled for this device", plugin),
+            Self::ChannelClosed => f.write_str("The service's connection to devices is closed"),
+            Self::InvalidArgs(detail) | Self::Protocol(detail) => f.write_str(detail),
+        }
+    }
+}
+
+impl std::error::Error for KdeConnectError {}
+
+impl KdeConnectError {
+    /// Classify a D-Bus error by name, with its message
+    fn from_dbus(name: &str, detail: String) -> Self {
+        let device = |prefix: &str| detail.strip_prefix(prefix).unwrap_or(&detail).to_string();
+        match name {
+            "org.freedesktop.DBus.Error.ServiceUnknown" | "org.freedesktop.DBus.Error.NameHasNoOwner" => {
+                Self::ServiceUnavailable
+            }
+            "org.freedesktop.DBus.Error.UnknownObject" => Self::UnknownDevice(device(UNKNOWN_DEVICE)),
+            "org.freedesktop.DBus.Error.Disconnected" => Self::DeviceUnreachable(device(DEVICE_UNREACHABLE)),
+            "org.freedesktop.DBus.Error.AccessDenied" => Self::DeviceDisabled(device(DEVICE_DISABLED)),
+            "org.freedesktop.DBus.Error.IOError" => Self::ChannelClosed,
+            "org.freedesktop.DBus.Error.InvalidArgs" => Self::InvalidArgs(detail),
+            _ => Self::Protocol(detail),
+        }
+    }
+}
+
+impl From<zbus::Error> for KdeConnectError {
+    fn from(error: zbus::Error) -> Self {
+        match error {
+            zbus::Error::MethodError(name, detail, _) => {
+                let detail = detail.unwrap_or_else(|| name.to_string());
+                Self::from_dbus(name.as_str(), detail)
+            }
+            zbus::Error::FDO(error) => {
+                let detail = error.description().unwrap_or_default().to_string();
+                Self::from_dbus(error.name().as_str(), detail)
+            }
+            other => Self::Protocol(other.to_string()),
+        }
+    }
+}
+
+impl From<anyhow::Error> for KdeConnectError {
+    fn from(error: anyhow::Error) -> Self {
+        match error.downcast::<zbus::Error>() {
+            Ok(error) => error.into(),
+            Err(error) => Self::Protocol(error.to_string()),
+        }
+    }
+}
+
+impl From<KdeConnectError> for zbus::fdo::Error {
+    fn from(error: KdeConnectError) -> Self {
+        let detail = error.to_string();
+        match error {
+            KdeConnectError::ServiceUnavailable => Self::ServiceUnknown(detail),
+            KdeConnectError::UnknownDevice(_) => Self::UnknownObject(detail),
+            KdeConnectError::DeviceUnreachable(_) => Self::Disconnected(detail),
+            KdeConnectError::DeviceDisabled(_) => Self::AccessDenied(detail),
+            KdeConnectError::ChannelClosed => Self::IOError(detail),
+            KdeConnectError::InvalidArgs(_) => Self::InvalidArgs(detail),
+            KdeConnectError::NotInitialized | KdeConnectError::PluginDisabled(_) | KdeConnectError::Protocol(_) => {
+                Self::Failed(detail)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_kind_survives_dbus() {
+        let over_dbus = |error: KdeConnectError| {
+            KdeConnectError::from(zbus::Error::from(zbus::fdo::Error::from(error)))
+        };
+
+        for error in [
+            KdeConnectError::ServiceUnavailable,
+            KdeConnectError::UnknownDevice("phone".to_string()),
+            KdeConnectError::DeviceUnreachable("phone".to_string()),
+            KdeConnectError::DeviceDisabled("phone".to_string()),
+            KdeConnectError::ChannelClosed,
+            KdeConnectError::InvalidArgs("Message body is empty".to_string()),
+            KdeConnectError::Protocol("Timed out".to_string()),
+        ] {
+            assert_eq!(over_dbus(error.clone()), error);
+        }
+        assert_eq!(
+            over_dbus(KdeConnectError::NotInitialized),
+            KdeConnectError::Protocol("Not connected to the KDE Connect service".to_string())
+        );
+        assert_eq!(
+            KdeConnectError::from(anyhow::anyhow!("boom")),
+            KdeConnectError::Protocol("boom".to_string())
+        );
+    }
+}
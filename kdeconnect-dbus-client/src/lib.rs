@@ -1,11 +1,15 @@
 // kdeconnect-dbus-client/src/lib.rs
 //! D-Bus client library for KDE Connect service
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use std::time::Duration;
 use serde::{Deserialize, Serialize};
 use zbus::{Connection, proxy};
 use futures::StreamExt;
 
+mod error;
+pub use error::KdeConnectError;
+
 /// Device information
 #[derive(Debug, Clone, Serialize, Deserialize, zbus::zvariant::Type, zbus::zvariant::Value, zbus::zvariant::OwnedValue)]
 pub struct Device {
@@ -14,6 +18,51 @@ pub struct Device {
     pub device_type: String,
     pub is_paired: bool,
     pub is_reachable: bool,
+    /// Packets buffered by the service while the device is unreachable
+    pub pending_packets: u32,
+    /// Active transport ("lan", "bluetooth"), empty when unknown
+    pub connection_type: String,
+    /// Syncing is on; disabled devices stay paired but exchange no packets
+    pub enabled: bool,
+}
+
+/// Link state of one device in a diagnostics report
+#[derive(Debug, Clone, Serialize, Deserialize, zbus::zvariant::Type, zbus::zvariant::Value, zbus::zvariant::OwnedValue)]
+pub struct LinkStatus {
+    pub id: String,
+    pub name: String,
+    pub is_paired: bool,
+    pub is_reachable: bool,
+    pub connection_type: String,
+    /// Link sends go out on right now, empty while unreachable
+    pub send_transport: String,
+    pub pending_packets: u32,
+}
+
+/// Service health snapshot for troubleshooting
+#[derive(Debug, Clone, Serialize, Deserialize, zbus::zvariant::Type, zbus::zvariant::Value, zbus::zvariant::OwnedValue)]
+pub struct Diagnostics {
+    pub uptime_secs: u64,
+    pub known_devices: u32,
+    /// Seconds since the last identity broadcast from any device, -1 if none yet
+    pub last_broadcast_secs: i64,
+    /// TCP ports in the KDE Connect range accepting connections
+    pub listening_ports: Vec<u16>,
+    /// The UDP discovery port is bound
+    pub discovery_bound: bool,
+    pub links: Vec<LinkStatus>,
+}
+
+/// One entry of the service's developer packet log
+#[derive(Debug, Clone, Serialize, Deserialize, zbus::zvariant::Type, zbus::zvariant::Value, zbus::zvariant::OwnedValue)]
+pub struct LoggedPacket {
+    /// Milliseconds since the Unix epoch
+    pub timestamp_ms: i64,
+    /// Sent to the device (true) or received from it (false)
+    pub outbound: bool,
+    pub device_id: String,
+    pub packet_type: String,
+    pub body_json: String,
 }
 
 /// Events from the D-Bus service
@@ -22,7 +71,59 @@ pub enum ServiceEvent {
     DeviceConnected(String, Device),
     DevicePaired(String, Device),
     DeviceDisconnected(String),
-    SmsMessagesReceived(String), // JSON string
+    DeviceRenamed(String, String), // device_id, new name
+    SmsMessagesReceived(String, String), // device_id (empty if unknown), JSON string
+}
+
+/// Well-known bus name of the service
+pub const SERVICE_NAME: &str = "org.cosmic.KdeConnect";
+
+/// D-Bus API revision, bumped when methods or signals change incompatibly.
+/// The service reports this value; a client seeing another one is
+/// incompatible.
+pub const API_VERSION: u32 = 3;
+
+/// Whether anything currently owns the service's bus name
+pub async fn service_running() -> Result<bool> {
+    let connection = Connection::session().await?;
+    let dbus = zbus::fdo::DBusProxy::new(&connection).await?;
+    Ok(dbus.name_has_owner(zbus::names::BusName::try_from(SERVICE_NAME)?).await?)
+}
+
+/// The running service's version and API revision. `None` if it is too old
+/// to report them.
+pub async fn service_version() -> Result<Option<(String, u32)>> {
+    let connection = Connection::session().await?;
+    match DaemonProxy::new(&connection).await?.version().await {
+        Ok(version) => Ok(Some(version)),
+        Err(zbus::Error::MethodError(name, _, _)) if name.as_str() == "org.freedesktop.DBus.Error.UnknownMethod" => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Longest wait between attempts to resubscribe to service signals
+pub const MAX_RESUBSCRIBE_DELAY: Duration = Duration::from_secs(30);
+
+/// How long to wait before resubscribing after `failures` consecutive
+/// failed attempts: 1s, doubling up to `MAX_RESUBSCRIBE_DELAY`
+pub fn resubscribe_delay(failures: u32) -> Duration {
+    Duration::from_secs(1u64 << failures.min(5)).min(MAX_RESUBSCRIBE_DELAY)
+}
+
+/// Await a signal subscription, naming the signal if it fails. Subscribing
+/// fails when the service hasn't registered its interfaces yet.
+async fn subscribe<S>(signal: &str, subscription: impl Future<Output = zbus::Result<S>>) -> Result<S> {
+    subscription.await
+        .with_context(|| format!("Failed to subscribe to {} signals", signal))
+}
+
+/// Human-readable reason from a failed service call, e.g. the message of
+/// an `InvalidArgs` or `LimitsExceeded` error returned by the daemon
+pub fn error_detail(error: &anyhow::Error) -> String {
+    match error.downcast_ref::<zbus::Error>() {
+        Some(zbus::Error::MethodError(_, Some(detail), _)) => detail.clone(),
+        _ => error.to_string(),
+    }
 }
 
 /// D-Bus proxy for daemon interface
@@ -32,19 +133,34 @@ pub enum ServiceEvent {
     default_path = "/org/cosmic/KdeConnect/Daemon"
 )]
 trait Daemon {
+    async fn version(&self) -> zbus::Result<(String, u32)>;
     async fn list_devices(&self) -> zbus::Result<Vec<Device>>;
+    async fn get_device(&self, device_id: &str) -> zbus::Result<Device>;
+    async fn set_device_enabled(&self, device_id: &str, enabled: bool) -> zbus::Result<Device>;
+    async fn diagnostics(&self) -> zbus::Result<Diagnostics>;
+    async fn packet_log(&self, since_ms: i64) -> zbus::Result<Vec<LoggedPacket>>;
     async fn pair_device(&self, device_id: &str) -> zbus::Result<()>;
     async fn unpair_device(&self, device_id: &str) -> zbus::Result<()>;
     async fn send_ping(&self, device_id: &str, message: &str) -> zbus::Result<()>;
     async fn send_files(&self, device_id: &str, files: Vec<String>) -> zbus::Result<()>;
     async fn send_clipboard(&self, device_id: &str, content: &str) -> zbus::Result<()>;
+    async fn send_text(&self, device_id: &str, text: &str) -> zbus::Result<()>;
     async fn ring_device(&self, device_id: &str) -> zbus::Result<()>;
+    async fn reset_connections(&self) -> zbus::Result<u32>;
+    async fn send_raw_packet(&self, device_id: &str, packet_type: &str, body_json: &str) -> zbus::Result<()>;
+    async fn inject_packet(&self, device_id: &str, packet_type: &str, body_json: &str) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn find_requested(&self, device_id: String, device_name: String) -> zbus::Result<()>;
 
     #[zbus(signal)]
     async fn device_connected(&self, device_id: String, device: Device) -> zbus::Result<()>;
     
     #[zbus(signal)]
     async fn device_paired(&self, device_id: String, device: Device) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn device_renamed(&self, device_id: String, name: String) -> zbus::Result<()>;
     
     #[zbus(signal)]
     async fn device_disconnected(&self, device_id: String) -> zbus::Result<()>;
@@ -59,10 +175,11 @@ trait Daemon {
 trait Sms {
     async fn request_conversations(&self, device_id: &str) -> zbus::Result<()>;
     async fn request_conversation(&self, device_id: &str, thread_id: i64) -> zbus::Result<()>;
+    async fn request_conversation_range(&self, device_id: &str, thread_id: i64, range_start_timestamp: i64, number_to_request: i64) -> zbus::Result<()>;
     async fn send_sms(&self, device_id: &str, phone_number: &str, message: &str) -> zbus::Result<()>;
 
     #[zbus(signal)]
-    async fn sms_messages_received(&self, messages_json: String) -> zbus::Result<()>;
+    async fn sms_messages_received(&self, device_id: String, messages_json: String) -> zbus::Result<()>;
 }
 
 /// Main client for KDE Connect service
@@ -90,6 +207,26 @@ impl KdeConnectClient {
         Ok(self.daemon_proxy.list_devices().await?)
     }
 
+    /// Get a single device by id
+    pub async fn get_device(&self, device_id: &str) -> Result<Device> {
+        Ok(self.daemon_proxy.get_device(device_id).await?)
+    }
+
+    /// Fetch the service health snapshot
+    pub async fn diagnostics(&self) -> Result<Diagnostics> {
+        Ok(self.daemon_proxy.diagnostics().await?)
+    }
+
+    /// Packets logged since `since_ms` (developer mode only)
+    pub async fn packet_log(&self, since_ms: i64) -> Result<Vec<LoggedPacket>> {
+        Ok(self.daemon_proxy.packet_log(since_ms).await?)
+    }
+
+    /// Turn syncing with a paired device on or off, keeping the pairing
+    pub async fn set_device_enabled(&self, device_id: &str, enabled: bool) -> Result<Device> {
+        Ok(self.daemon_proxy.set_device_enabled(device_id, enabled).await?)
+    }
+
     /// Pair with a device
     pub async fn pair_device(&self, device_id: &str) -> Result<()> {
         Ok(self.daemon_proxy.pair_device(device_id).await?)
@@ -115,11 +252,27 @@ impl KdeConnectClient {
         Ok(self.daemon_proxy.send_clipboard(device_id, content).await?)
     }
 
+    /// Share text, as if shared from an app
+    pub async fn send_text(&self, device_id: &str, text: &str) -> Result<()> {
+        Ok(self.daemon_proxy.send_text(device_id, text).await?)
+    }
+
     /// Ring a device (findmyphone)
     pub async fn ring_device(&self, device_id: &str) -> Result<()> {
         Ok(self.daemon_proxy.ring_device(device_id).await?)
     }
 
+    /// Mark every device unreachable, e.g. after resuming from suspend.
+    /// Returns how many devices were reset.
+    pub async fn reset_connections(&self) -> Result<u32> {
+        Ok(self.daemon_proxy.reset_connections().await?)
+    }
+
+    /// Send a packet of any type known to the service, with a JSON object body
+    pub async fn send_raw_packet(&self, device_id: &str, packet_type: &str, body_json: &str) -> Result<()> {
+        Ok(self.daemon_proxy.send_raw_packet(device_id, packet_type, body_json).await?)
+    }
+
     /// Request SMS conversations
     pub async fn request_conversations(&self, device_id: &str) -> Result<()> {
         Ok(self.sms_proxy.request_conversations(device_id).await?)
@@ -130,17 +283,24 @@ impl KdeConnectClient {
         Ok(self.sms_proxy.request_conversation(device_id, thread_id).await?)
     }
 
+    /// Request a page of messages older than `before_timestamp`
+    pub async fn request_conversation_range(&self, device_id: &str, thread_id: i64, before_timestamp: i64, count: i64) -> Result<()> {
+        Ok(self.sms_proxy.request_conversation_range(device_id, thread_id, before_timestamp, count).await?)
+    }
+
     /// Send SMS
     pub async fn send_sms(&self, device_id: &str, phone_number: &str, message: &str) -> Result<()> {
         Ok(self.sms_proxy.send_sms(device_id, phone_number, message).await?)
     }
 
-    /// Listen for service events (signals)
-    pub async fn listen_for_events(&self) -> impl futures::Stream<Item = ServiceEvent> + '_ {
-        let daemon_connected = self.daemon_proxy.receive_device_connected().await.unwrap();
-        let daemon_paired = self.daemon_proxy.receive_device_paired().await.unwrap();
-        let daemon_disconnected = self.daemon_proxy.receive_device_disconnected().await.unwrap();
-        let sms_messages = self.sms_proxy.receive_sms_messages_received().await.unwrap();
+    /// Listen for service events (signals). Fails if any subscription
+    /// can't be set up, e.g. while the service is still starting.
+    pub async fn listen_for_events(&self) -> Result<impl futures::Stream<Item = ServiceEvent> + '_> {
+        let daemon_connected = subscribe("DeviceConnected", self.daemon_proxy.receive_device_connected()).await?;
+        let daemon_paired = subscribe("DevicePaired", self.daemon_proxy.receive_device_paired()).await?;
+        let daemon_disconnected = subscribe("DeviceDisconnected", self.daemon_proxy.receive_device_disconnected()).await?;
+        let daemon_renamed = subscribe("DeviceRenamed", self.daemon_proxy.receive_device_renamed()).await?;
+        let sms_messages = subscribe("SmsMessagesReceived", self.sms_proxy.receive_sms_messages_received()).await?;
 
         // Map each stream to ServiceEvent - args() returns specific Args structs
         let connected_stream = daemon_connected.filter_map(|signal| async move {
@@ -173,9 +333,19 @@ impl KdeConnectClient {
             }
         });
 
+        let renamed_stream = daemon_renamed.filter_map(|signal| async move {
+            match signal.args() {
+                Ok(args) => Some(ServiceEvent::DeviceRenamed(args.device_id, args.name)),
+                Err(e) => {
+                    eprintln!("Failed to parse DeviceRenamed signal: {:?}", e);
+                    None
+                }
+            }
+        });
+
         let sms_stream = sms_messages.filter_map(|signal| async move {
             match signal.args() {
-                Ok(args) => Some(ServiceEvent::SmsMessagesReceived(args.messages_json)),
+                Ok(args) => Some(ServiceEvent::SmsMessagesReceived(args.device_id, args.messages_json)),
                 Err(e) => {
                     eprintln!("Failed to parse SmsMessagesReceived signal: {:?}", e);
                     None
@@ -185,11 +355,38 @@ impl KdeConnectClient {
 
         // Merge all streams
         use futures::stream::select_all;
-        select_all(vec![
+        Ok(select_all(vec![
             Box::pin(connected_stream) as std::pin::Pin<Box<dyn futures::Stream<Item = ServiceEvent> + Send + '_>>,
             Box::pin(paired_stream),
             Box::pin(disconnected_stream),
+            Box::pin(renamed_stream),
             Box::pin(sms_stream),
-        ])
+        ]))
+    }
+
+    /// Yields each time the service leaves the bus. Signal streams stay
+    /// open while it is gone and simply go quiet, so listeners use this to
+    /// tell a stopped service from an idle one.
+    pub async fn listen_for_service_loss(&self) -> Result<impl futures::Stream<Item = ()> + '_> {
+        let owners = self.daemon_proxy.inner().receive_owner_changed().await?;
+        Ok(owners.filter_map(|owner| async move { owner.is_none().then_some(()) }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_failed_subscription_is_an_error() {
+        let failed = async { Err::<(), _>(zbus::Error::InterfaceNotFound) };
+        let error = subscribe("DeviceConnected", failed).await.unwrap_err();
+        assert!(error.to_string().contains("DeviceConnected"));
+
+        assert_eq!(subscribe("DevicePaired", async { Ok(7) }).await.unwrap(), 7);
+
+        assert_eq!(resubscribe_delay(0), Duration::from_secs(1));
+        assert_eq!(resubscribe_delay(2), Duration::from_secs(4));
+        assert_eq!(resubscribe_delay(40), MAX_RESUBSCRIBE_DELAY);
     }
 }
@@ -2,6 +2,11 @@
 //! D-Bus interface implementation for KDE Connect service
 
 use anyhow::Result;
+use cosmic_connect_common::notification_text;
+use cosmic_connect_common::plugin_config::{
+    ClipboardPluginConfig, DevicePluginsConfig, FindMyPhonePluginConfig, PauseMusicPluginConfig, SendNotificationsPluginConfig,
+    NOTIFICATIONS_PLUGIN, TELEPHONY_PLUGIN,
+};
 use kdeconnect_core::{
     KdeConnectCore,
     event::{AppEvent, ConnectionEvent},
@@ -9,15 +14,30 @@ use kdeconnect_core::{
     ProtocolPacket,
     PacketType,
 };
+use kdeconnect_dbus_client::{KdeConnectError, API_VERSION};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, Mutex};
-use tracing::info;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
 use zbus::{Connection, interface};
 use zbus::object_server::SignalEmitter;
 
+use crate::diagnostics::{self, ServiceStats};
+use crate::disabled_devices::DisabledDevices;
+use crate::find_desktop::{self, Ringer};
+use crate::inbound::{self, ConnectivityReport, Route};
+use crate::notification_filter::{self, IncomingNotification};
+use crate::packet_log::{self, PacketLog};
+use crate::rate_limit::RateLimiter;
+use crate::raw_packet;
+use crate::send_queue::{QueuedKind, QueuedPacket, SendQueue};
+use crate::telephony::{self, CallEvent, CallState, IncomingCall};
+use crate::transport::{self, Transport};
+
 const SERVICE_NAME: &str = "org.cosmic.KdeConnect";
 const DAEMON_PATH: &str = "/org/cosmic/KdeConnect/Daemon";
 const SMS_PATH: &str = "/org/cosmic/KdeConnect/Sms";
@@ -30,16 +50,98 @@ pub struct DbusDevice {
     pub device_type: String,
     pub is_paired: bool,
     pub is_reachable: bool,
+    /// Packets buffered while the device is unreachable
+    pub pending_packets: u32,
+    /// Active transport ("lan", "bluetooth"), empty when unknown or offline
+    pub connection_type: String,
+    /// Syncing is on; a disabled device stays paired but no packets are
+    /// exchanged with it
+    pub enabled: bool,
+}
+
+/// Link state of one device, as reported by Diagnostics
+#[derive(Debug, Clone, Serialize, Deserialize, zbus::zvariant::Type, zbus::zvariant::Value, zbus::zvariant::OwnedValue)]
+pub struct DbusLinkStatus {
+    pub id: String,
+    pub name: String,
+    pub is_paired: bool,
+    pub is_reachable: bool,
+    pub connection_type: String,
+    /// Link sends go out on right now, empty while unreachable
+    pub send_transport: String,
+    pub pending_packets: u32,
+}
+
+/// Service health snapshot for troubleshooting
+#[derive(Debug, Clone, Serialize, Deserialize, zbus::zvariant::Type, zbus::zvariant::Value, zbus::zvariant::OwnedValue)]
+pub struct DbusDiagnostics {
+    pub uptime_secs: u64,
+    pub known_devices: u32,
+    /// Seconds since the last identity broadcast from any device, -1 if none yet
+    pub last_broadcast_secs: i64,
+    /// TCP ports in the KDE Connect range accepting connections
+    pub listening_ports: Vec<u16>,
+    /// The UDP discovery port is bound
+    pub discovery_bound: bool,
+    pub links: Vec<DbusLinkStatus>,
+}
+
+/// One entry of the developer packet log
+#[derive(Debug, Clone, Serialize, Deserialize, zbus::zvariant::Type, zbus::zvariant::Value, zbus::zvariant::OwnedValue)]
+pub struct DbusLoggedPacket {
+    /// Milliseconds since the Unix epoch
+    pub timestamp_ms: i64,
+    /// Sent to the device (true) or received from it (false)
+    pub outbound: bool,
+    pub device_id: String,
+    pub packet_type: String,
+    pub body_json: String,
+}
+
+/// Accepted digit count for SMS recipients (short codes up to E.164 length)
+const MIN_PHONE_DIGITS: usize = 3;
+const MAX_PHONE_DIGITS: usize = 15;
+
+/// A phone number for the log: only its last two digits are kept
+fn redacted_number(phone_number: &str) -> String {
+    let digits: Vec<char> = phone_number.chars().filter(char::is_ascii_digit).collect();
+    let shown: String = digits[digits.len().saturating_sub(2)..].iter().collect();
+    format!("…{}", shown)
 }
 
+type SharedSendQueue = Arc<Mutex<SendQueue<ProtocolPacket>>>;
+type SharedStats = Arc<Mutex<ServiceStats>>;
+/// Present only in developer mode
+type SharedPacketLog = Option<Arc<Mutex<PacketLog>>>;
+
 /// Main daemon D-Bus interface
 pub struct DaemonInterface {
     event_sender: Arc<mpsc::UnboundedSender<AppEvent>>,
     devices: Arc<Mutex<HashMap<String, DbusDevice>>>,
+    send_queue: SharedSendQueue,
+    stats: SharedStats,
+    packet_log: SharedPacketLog,
+    /// Keeps repeated Ring taps from stacking rings on a device
+    ring_limiter: Mutex<RateLimiter>,
+    inbound: InboundState,
+}
+
+/// State kept for packets handled through `handle_packet`
+#[derive(Debug, Default)]
+struct InboundState {
+    calls: Mutex<CallState>,
+    /// A phone ringing this computer
+    ringer: Arc<Mutex<Ringer>>,
 }
 
 #[interface(name = "org.cosmic.KdeConnect.Daemon")]
 impl DaemonInterface {
+    /// Service version and D-Bus API revision, so clients can tell an
+    /// incompatible service apart from a missing one
+    async fn version(&self) -> (String, u32) {
+        (env!("CARGO_PKG_VERSION").to_string(), API_VERSION)
+    }
+
     /// List all known devices
     async fn list_devices(&self) -> Vec<DbusDevice> {
         info!("D-Bus: ListDevices called");
@@ -49,6 +151,116 @@ impl DaemonInterface {
         device_list
     }
 
+    /// Get a single known device by id
+    async fn get_device(&self, device_id: String) -> zbus::fdo::Result<DbusDevice> {
+        info!("D-Bus: GetDevice called for {}", device_id);
+        self.devices.lock().await
+            .get(&device_id)
+            .cloned()
+            .ok_or_else(|| zbus::fdo::Error::from(KdeConnectError::UnknownDevice(device_id.clone())))
+    }
+
+    /// Turn syncing with a paired device on or off without unpairing it.
+    /// Re-enabling sends anything still queued for it.
+    async fn set_device_enabled(
+        &self,
+        #[zbus(connection)] connection: &Connection,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+        device_id: String,
+        enabled: bool,
+    ) -> zbus::fdo::Result<DbusDevice> {
+        info!("D-Bus: SetDeviceEnabled called for {} ({})", device_id, enabled);
+        let mut disabled = DisabledDevices::load().map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
+        if disabled.set_enabled(&device_id, enabled) {
+            disabled.save().map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
+        }
+
+        let device = {
+            let mut devices = self.devices.lock().await;
+            let device = devices.get_mut(&device_id)
+                .ok_or_else(|| zbus::fdo::Error::from(KdeConnectError::UnknownDevice(device_id.clone())))?;
+            device.enabled = enabled;
+            device.clone()
+        };
+
+        if enabled && device.is_reachable {
+            flush_send_queue(connection, &self.event_sender, &self.send_queue, &device_id).await;
+        }
+        Self::device_connected(&emitter, device_id, device.clone()).await?;
+        Ok(device)
+    }
+
+    /// Drop every device's link after the host resumes from suspend, when
+    /// TCP connections to phones are dead but not yet detected. Devices stay
+    /// listed as unreachable until they reconnect. Returns how many were reset.
+    ///
+    /// kdeconnect-core has no AppEvent to re-broadcast our identity, so
+    /// devices come back on their own next identity broadcast.
+    async fn reset_connections(
+        &self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> zbus::fdo::Result<u32> {
+        info!("D-Bus: ResetConnections called");
+        let stale: Vec<String> = {
+            let mut devices = self.devices.lock().await;
+            devices.values_mut()
+                .filter(|d| d.is_reachable)
+                .map(|d| {
+                    d.is_reachable = false;
+                    d.connection_type.clear();
+                    d.id.clone()
+                })
+                .collect()
+        };
+        for device_id in &stale {
+            Self::device_disconnected(&emitter, device_id.clone()).await?;
+        }
+        Ok(stale.len() as u32)
+    }
+
+    /// Service health snapshot for troubleshooting
+    async fn diagnostics(&self) -> zbus::fdo::Result<DbusDiagnostics> {
+        info!("D-Bus: Diagnostics called");
+
+        let (uptime_secs, last_broadcast_secs) = {
+            let stats = self.stats.lock().await;
+            (
+                stats.started.elapsed().as_secs(),
+                stats.last_identity.map_or(-1, |t| t.elapsed().as_secs() as i64),
+            )
+        };
+
+        let mut links: Vec<DbusLinkStatus> = self.devices.lock().await
+            .values()
+            .map(|d| DbusLinkStatus {
+                id: d.id.clone(),
+                name: d.name.clone(),
+                is_paired: d.is_paired,
+                is_reachable: d.is_reachable,
+                connection_type: d.connection_type.clone(),
+                send_transport: active_transports(d).first().map(|t| t.to_string()).unwrap_or_default(),
+                pending_packets: d.pending_packets,
+            })
+            .collect();
+        links.sort_by(|a, b| a.name.cmp(&b.name));
+
+        // Socket probes block briefly, keep them off the async executor
+        let (listening_ports, discovery_bound) = tokio::task::spawn_blocking(|| {
+            (diagnostics::listening_ports(), diagnostics::discovery_socket_bound())
+        })
+        .await
+        .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
+
+        Ok(DbusDiagnostics {
+            uptime_secs,
+            known_devices: links.len() as u32,
+            last_broadcast_secs,
+            listening_ports,
+            discovery_bound,
+            links,
+        })
+    }
+
     /// Pair with a device
     async fn pair_device(&self, device_id: String) -> zbus::fdo::Result<()> {
         info!("D-Bus: PairDevice called for {}", device_id);
@@ -65,8 +277,34 @@ impl DaemonInterface {
         Ok(())
     }
 
+    /// Packets logged since `since_ms` (Unix epoch milliseconds), oldest
+    /// first. Only available when the service runs in developer mode.
+    async fn packet_log(&self, since_ms: i64) -> zbus::fdo::Result<Vec<DbusLoggedPacket>> {
+        let Some(log) = &self.packet_log else {
+            return Err(zbus::fdo::Error::AccessDenied(format!(
+                "Packet log is disabled; start the service with {}=1",
+                packet_log::DEV_MODE_ENV
+            )));
+        };
+
+        Ok(log.lock().await.since(since_ms)
+            .map(|e| DbusLoggedPacket {
+                timestamp_ms: e.timestamp_ms,
+                outbound: e.outbound,
+                device_id: e.device_id.clone(),
+                packet_type: e.packet_type.clone(),
+                body_json: e.body.to_string(),
+            })
+            .collect())
+    }
+
     /// Send a ping to a device
-    async fn send_ping(&self, device_id: String, message: String) -> zbus::fdo::Result<()> {
+    async fn send_ping(
+        &self,
+        #[zbus(connection)] connection: &Connection,
+        device_id: String,
+        message: String,
+    ) -> zbus::fdo::Result<()> {
         info!("D-Bus: SendPing called for {} with message: {}", device_id, message);
         
         // Use SendPacket directly for instant response
@@ -75,35 +313,70 @@ impl DaemonInterface {
             json!({ "message": message })
         );
         
-        self.event_sender.send(AppEvent::SendPacket(DeviceId(device_id), packet))
-            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
-        
-        Ok(())
+        send_or_queue(connection, &self.event_sender, &self.devices, &self.send_queue, device_id, QueuedKind::Ping, packet).await
     }
 
     /// Send files to a device
     async fn send_files(&self, device_id: String, files: Vec<String>) -> zbus::fdo::Result<()> {
         info!("D-Bus: SendFiles called for {} ({} files)", device_id, files.len());
+        ensure_enabled(&self.devices, &device_id).await?;
         self.event_sender.send(AppEvent::SendFiles((DeviceId(device_id), files)))
             .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
         Ok(())
     }
 
     /// Send clipboard content
-    async fn send_clipboard(&self, device_id: String, content: String) -> zbus::fdo::Result<()> {
+    async fn send_clipboard(
+        &self,
+        #[zbus(connection)] connection: &Connection,
+        device_id: String,
+        content: String,
+    ) -> zbus::fdo::Result<()> {
         info!("D-Bus: SendClipboard called for {}", device_id);
+        // Clients trim to this limit first; refuse anything bigger outright
+        let config = ClipboardPluginConfig::load(&device_id).unwrap_or_default();
+        if content.len() > config.max_text_bytes() {
+            return Err(zbus::fdo::Error::InvalidArgs(format!(
+                "Clipboard text is over the {} KB limit",
+                config.max_text_kb
+            )));
+        }
         let packet = ProtocolPacket::new(
             PacketType::Clipboard,
             json!({ "content": content })
         );
-        self.event_sender.send(AppEvent::SendPacket(DeviceId(device_id), packet))
-            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
-        Ok(())
+        send_or_queue(connection, &self.event_sender, &self.devices, &self.send_queue, device_id, QueuedKind::Clipboard, packet).await
     }
 
-    /// Ring a device (findmyphone)
+    /// Share text with a device, which opens or copies it like text shared
+    /// from another app. Queued while the device is unreachable.
+    async fn send_text(
+        &self,
+        #[zbus(connection)] connection: &Connection,
+        device_id: String,
+        text: String,
+    ) -> zbus::fdo::Result<()> {
+        info!("D-Bus: SendText called for {} ({} bytes)", device_id, text.len());
+        if text.is_empty() {
+            return Err(zbus::fdo::Error::InvalidArgs("No text to send".to_string()));
+        }
+        let packet_type = raw_packet::parse_type("kdeconnect.share.request")
+            .map_err(zbus::fdo::Error::NotSupported)?;
+        let packet = ProtocolPacket::new(packet_type, json!({ "text": text }));
+        send_or_queue(connection, &self.event_sender, &self.devices, &self.send_queue, device_id, QueuedKind::Text, packet).await
+    }
+
+    /// Ring a device (findmyphone). Rings within `RING_COOLDOWN` of the
+    /// last one are refused.
     async fn ring_device(&self, device_id: String) -> zbus::fdo::Result<()> {
         info!("D-Bus: RingDevice called for {}", device_id);
+        ensure_enabled(&self.devices, &device_id).await?;
+        if let Err(retry_in) = self.ring_limiter.lock().await.check(&device_id, Instant::now()) {
+            return Err(zbus::fdo::Error::LimitsExceeded(format!(
+                "Already ringing, try again in {}s",
+                retry_in.as_secs().max(1)
+            )));
+        }
         
         let packet = ProtocolPacket::new(
             PacketType::FindMyPhoneRequest,
@@ -111,15 +384,89 @@ impl DaemonInterface {
         );
         
         self.event_sender.send(AppEvent::SendPacket(DeviceId(device_id), packet))
-            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
+            .map_err(|_| KdeConnectError::ChannelClosed)?;
         
         Ok(())
     }
 
+    /// Send an arbitrary packet, given its wire type (e.g. `kdeconnect.ping`)
+    /// and a JSON object body. Goes through the same event pipeline as the
+    /// typed methods.
+    async fn send_raw_packet(
+        &self,
+        device_id: String,
+        packet_type: String,
+        body_json: String,
+    ) -> zbus::fdo::Result<()> {
+        info!("D-Bus: SendRawPacket called for {} ({})", device_id, packet_type);
+        if !self.devices.lock().await.contains_key(&device_id) {
+            return Err(zbus::fdo::Error::from(KdeConnectError::UnknownDevice(device_id.clone())));
+        }
+        ensure_enabled(&self.devices, &device_id).await?;
+        let packet = raw_packet::build(&packet_type, &body_json)
+            .map_err(zbus::fdo::Error::InvalidArgs)?;
+
+        self.event_sender.send(AppEvent::SendPacket(DeviceId(device_id), packet))
+            .map_err(|_| KdeConnectError::ChannelClosed)?;
+        Ok(())
+    }
+
+    /// Handle a packet as if `device_id` had sent it, for packets
+    /// kdeconnect-core doesn't surface yet (see `inbound`). Developer mode
+    /// only.
+    async fn inject_packet(
+        &self,
+        #[zbus(connection)] connection: &Connection,
+        device_id: String,
+        packet_type: String,
+        body_json: String,
+    ) -> zbus::fdo::Result<()> {
+        info!("D-Bus: InjectPacket called for {} ({})", device_id, packet_type);
+        let Some(log) = &self.packet_log else {
+            return Err(zbus::fdo::Error::NotSupported(
+                "Injecting packets needs developer mode".to_string(),
+            ));
+        };
+        if !self.devices.lock().await.contains_key(&device_id) {
+            return Err(zbus::fdo::Error::from(KdeConnectError::UnknownDevice(device_id.clone())));
+        }
+        let body = raw_packet::parse_body(&body_json).map_err(zbus::fdo::Error::InvalidArgs)?;
+        log.lock().await.record(false, &device_id, &packet_type, body.clone());
+
+        handle_packet(connection, &self.event_sender, &self.devices, &self.inbound, &device_id, &packet_type, &body).await
+            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))
+    }
+
+    /// Signal: A notification posted on a device and not filtered out, as
+    /// the packet's JSON body
+    #[zbus(signal)]
+    async fn notification_received(signal_emitter: &SignalEmitter<'_>, device_id: String, notification_json: String) -> zbus::Result<()>;
+
+    /// Signal: A device's cellular network type and signal strength (0-4)
+    #[zbus(signal)]
+    async fn connectivity_changed(signal_emitter: &SignalEmitter<'_>, device_id: String, network_type: String, signal_strength: i32) -> zbus::Result<()>;
+
+    /// Signal: Media player state on a device, as the packet's JSON body
+    #[zbus(signal)]
+    async fn media_state_changed(signal_emitter: &SignalEmitter<'_>, device_id: String, state_json: String) -> zbus::Result<()>;
+
+    /// Signal: A device is looking for this computer, which is ringing
+    #[zbus(signal)]
+    async fn find_requested(signal_emitter: &SignalEmitter<'_>, device_id: String, device_name: String) -> zbus::Result<()>;
+
+    /// Signal: A call on a device: event is "ringing", "talking" or
+    /// "missedCall", and ended is set once the call is over
+    #[zbus(signal)]
+    async fn call_state_changed(signal_emitter: &SignalEmitter<'_>, device_id: String, event: String, caller: String, ended: bool) -> zbus::Result<()>;
+
     /// Signal: Device connected
     #[zbus(signal)]
     async fn device_connected(signal_emitter: &SignalEmitter<'_>, device_id: String, device: DbusDevice) -> zbus::Result<()>;
 
+    /// Signal: A device announced a new name (renamed in the phone app)
+    #[zbus(signal)]
+    async fn device_renamed(signal_emitter: &SignalEmitter<'_>, device_id: String, name: String) -> zbus::Result<()>;
+
     /// Signal: Device paired
     #[zbus(signal)]
     async fn device_paired(signal_emitter: &SignalEmitter<'_>, device_id: String, device: DbusDevice) -> zbus::Result<()>;
@@ -132,6 +479,9 @@ impl DaemonInterface {
 /// SMS-specific D-Bus interface
 pub struct SmsInterface {
     event_sender: Arc<mpsc::UnboundedSender<AppEvent>>,
+    devices: Arc<Mutex<HashMap<String, DbusDevice>>>,
+    send_queue: SharedSendQueue,
+    rate_limiter: Mutex<RateLimiter>,
 }
 
 #[interface(name = "org.cosmic.KdeConnect.Sms")]
@@ -139,31 +489,27 @@ impl SmsInterface {
     /// Request all conversations from device
     async fn request_conversations(&self, device_id: String) -> zbus::fdo::Result<()> {
         info!("D-Bus: RequestConversations called for {}", device_id);
-        eprintln!("=== SMS D-Bus Request ===");
-        eprintln!("Device: {}", device_id);
+        ensure_enabled(&self.devices, &device_id).await?;
     
         let packet = ProtocolPacket::new(
             PacketType::SmsRequestConversations,
             json!({})
         );
     
-        eprintln!("Packet type: {:?}", packet.packet_type);
-    
         self.event_sender.send(AppEvent::SendPacket(DeviceId(device_id.clone()), packet))
             .map_err(|e| {
-                eprintln!("✗ Failed to send packet: {}", e);
-                zbus::fdo::Error::Failed(e.to_string())
+                warn!("Failed to send packet: {}", e);
+                KdeConnectError::ChannelClosed
             })?;
     
-        eprintln!("✓ Request sent to core");
+        debug!("Conversations request sent to core");
         Ok(())
     }
 
     /// Request messages from a specific conversation
     async fn request_conversation(&self, device_id: String, thread_id: i64) -> zbus::fdo::Result<()> {
         info!("D-Bus: RequestConversation called for {} thread {}", device_id, thread_id);
-        eprintln!("=== SMS Conversation Request ===");
-        eprintln!("Device: {}, Thread: {}", device_id, thread_id);
+        ensure_enabled(&self.devices, &device_id).await?;
         
         let packet = ProtocolPacket::new(
             PacketType::SmsRequestConversation,
@@ -172,25 +518,76 @@ impl SmsInterface {
             })
         );
         
-        eprintln!("Packet: {:?}", packet.packet_type);
+        self.event_sender.send(AppEvent::SendPacket(DeviceId(device_id), packet))
+            .map_err(|e| {
+                warn!("Failed to send packet: {}", e);
+                KdeConnectError::ChannelClosed
+            })?;
+        
+        debug!("Conversation request sent to core");
+        Ok(())
+    }
+
+    /// Request a window of messages from a conversation, newest first,
+    /// starting before `range_start_timestamp` (ms since epoch)
+    async fn request_conversation_range(
+        &self,
+        device_id: String,
+        thread_id: i64,
+        range_start_timestamp: i64,
+        number_to_request: i64,
+    ) -> zbus::fdo::Result<()> {
+        info!(
+            "D-Bus: RequestConversationRange called for {} thread {} before {} (count {})",
+            device_id, thread_id, range_start_timestamp, number_to_request
+        );
+        ensure_enabled(&self.devices, &device_id).await?;
+        
+        let packet = ProtocolPacket::new(
+            PacketType::SmsRequestConversation,
+            json!({
+                "threadID": thread_id,
+                "rangeStartTimestamp": range_start_timestamp,
+                "numberToRequest": number_to_request
+            })
+        );
         
         self.event_sender.send(AppEvent::SendPacket(DeviceId(device_id), packet))
             .map_err(|e| {
-                eprintln!("✗ Failed to send packet: {}", e);
-                zbus::fdo::Error::Failed(e.to_string())
+                warn!("Failed to send packet: {}", e);
+                KdeConnectError::ChannelClosed
             })?;
         
-        eprintln!("✓ Conversation request sent");
+        debug!("Conversation range request sent to core");
         Ok(())
     }
 
     /// Send an SMS message
-    async fn send_sms(&self, device_id: String, phone_number: String, message: String) -> zbus::fdo::Result<()> {
+    async fn send_sms(
+        &self,
+        #[zbus(connection)] connection: &Connection,
+        device_id: String,
+        phone_number: String,
+        message: String,
+    ) -> zbus::fdo::Result<()> {
         info!("D-Bus: SendSms called for {}", device_id);
-        eprintln!("=== SMS Send Request ===");
-        eprintln!("Device: {}", device_id);
-        eprintln!("To: {}", phone_number);
-        eprintln!("Message: {}", message);
+        debug!("SMS to {} ({} chars)", redacted_number(&phone_number), message.chars().count());
+
+        let digits: String = phone_number.chars().filter(|c| c.is_ascii_digit()).collect();
+        if !(MIN_PHONE_DIGITS..=MAX_PHONE_DIGITS).contains(&digits.len()) {
+            return Err(zbus::fdo::Error::InvalidArgs(format!("Invalid phone number: {}", phone_number)));
+        }
+        if message.trim().is_empty() {
+            return Err(zbus::fdo::Error::InvalidArgs("Message body is empty".to_string()));
+        }
+        if let Err(retry_in) = self.rate_limiter.lock().await.check(&digits, Instant::now()) {
+            warn!("SMS to {} rate limited", redacted_number(&phone_number));
+            return Err(zbus::fdo::Error::LimitsExceeded(format!(
+                "Too many messages to {}, try again in {}s",
+                phone_number,
+                retry_in.as_secs().max(1)
+            )));
+        }
         
         let packet = ProtocolPacket::new(
             PacketType::SmsRequest,
@@ -201,19 +598,588 @@ impl SmsInterface {
             })
         );
         
-        self.event_sender.send(AppEvent::SendPacket(DeviceId(device_id), packet))
-            .map_err(|e| {
-                eprintln!("✗ Failed to send SMS: {}", e);
-                zbus::fdo::Error::Failed(e.to_string())
-            })?;
+        let kind = QueuedKind::Sms { phone_number };
+        send_or_queue(connection, &self.event_sender, &self.devices, &self.send_queue, device_id, kind, packet).await
+            .inspect_err(|e| warn!("Failed to send SMS: {}", e))?;
         
-        eprintln!("✓ SMS send request sent");
+        debug!("SMS send request sent");
         Ok(())
     }
 
     /// Signal: SMS messages received
+    ///
+    /// `device_id` is the phone the batch came from, or empty when it can't
+    /// be attributed.
     #[zbus(signal)]
-    async fn sms_messages_received(signal_emitter: &SignalEmitter<'_>, messages_json: String) -> zbus::Result<()>;
+    async fn sms_messages_received(signal_emitter: &SignalEmitter<'_>, device_id: String, messages_json: String) -> zbus::Result<()>;
+}
+
+/// Send a packet now if the device is reachable, otherwise buffer it until
+/// the device reconnects.
+async fn send_or_queue(
+    connection: &Connection,
+    event_sender: &mpsc::UnboundedSender<AppEvent>,
+    devices: &Arc<Mutex<HashMap<String, DbusDevice>>>,
+    send_queue: &SharedSendQueue,
+    device_id: String,
+    kind: QueuedKind,
+    packet: ProtocolPacket,
+) -> zbus::fdo::Result<()> {
+    ensure_enabled(devices, &device_id).await?;
+
+    let active = devices.lock().await
+        .get(&device_id)
+        .map(active_transports)
+        .unwrap_or_default();
+
+    if !active.is_empty() {
+        let transport = send_over_best_link(event_sender, &device_id, &active, packet).map_err(|e| {
+            warn!("Failed to send to {}: {}", device_id, e);
+            KdeConnectError::ChannelClosed
+        })?;
+        info!("Sent {:?} to {} over {}", kind, device_id, transport);
+        return Ok(());
+    }
+
+    let (dropped, depth) = {
+        let mut queue = send_queue.lock().await;
+        let dropped = queue.push(&device_id, kind, packet, Instant::now());
+        (dropped, queue.depth(&device_id))
+    };
+    info!("Device {} unreachable, queued packet ({} pending)", device_id, depth);
+
+    if let Some(device) = devices.lock().await.get_mut(&device_id) {
+        device.pending_packets = depth as u32;
+    }
+    if let Some(entry) = dropped {
+        report_dropped(connection, &device_id, &entry).await;
+    }
+
+    Ok(())
+}
+
+/// Links a reachable device can be sent to, best first. kdeconnect-core
+/// doesn't say which link a device uses, so an unknown type counts as LAN.
+fn active_transports(device: &DbusDevice) -> Vec<Transport> {
+    if !device.is_reachable {
+        return Vec::new();
+    }
+    vec![Transport::parse(&device.connection_type).unwrap_or(Transport::Lan)]
+}
+
+/// Hand a packet to kdeconnect-core over the best active link, moving on
+/// to the next one if it is refused. Returns the link used.
+///
+/// kdeconnect-core routes packets itself and only has the LAN link, so
+/// today there is never a second link to fail over to.
+fn send_over_best_link(
+    event_sender: &mpsc::UnboundedSender<AppEvent>,
+    device_id: &str,
+    active: &[Transport],
+    packet: ProtocolPacket,
+) -> Result<Transport, String> {
+    let mut packet = Some(packet);
+    transport::send_with_failover(active, |transport| {
+        let Some(p) = packet.take() else {
+            return Err("packet already handed over".to_string());
+        };
+        event_sender.send(AppEvent::SendPacket(DeviceId(device_id.to_string()), p)).map_err(|e| {
+            // Take the packet back for the next link
+            if let AppEvent::SendPacket(_, p) = e.0 {
+                packet = Some(p);
+            }
+            format!("{} link closed", transport)
+        })
+    })
+    .map_err(|e| e.unwrap_or_else(|| format!("No link to {}", device_id)))
+}
+
+/// Whether syncing with a device is on, per the persisted disabled set
+fn device_enabled(device_id: &str) -> bool {
+    DisabledDevices::load()
+        .map(|disabled| disabled.is_enabled(device_id))
+        .unwrap_or_else(|e| {
+            warn!("Failed to load disabled devices: {}", e);
+            true
+        })
+}
+
+/// Refuse to send to a device the user has disabled
+async fn ensure_enabled(
+    devices: &Arc<Mutex<HashMap<String, DbusDevice>>>,
+    device_id: &str,
+) -> zbus::fdo::Result<()> {
+    let enabled = match devices.lock().await.get(device_id) {
+        Some(device) => device.enabled,
+        None => device_enabled(device_id),
+    };
+    if enabled {
+        Ok(())
+    } else {
+        Err(KdeConnectError::DeviceDisabled(device_id.to_string()).into())
+    }
+}
+
+/// Send everything buffered while a device was away, in order
+async fn flush_send_queue(
+    connection: &Connection,
+    event_sender: &mpsc::UnboundedSender<AppEvent>,
+    send_queue: &SharedSendQueue,
+    device_id: &str,
+) {
+    let (ready, expired) = send_queue.lock().await.drain(device_id, Instant::now());
+    for entry in &expired {
+        report_dropped(connection, device_id, entry).await;
+    }
+    if !ready.is_empty() {
+        info!("Flushing {} queued packet(s) to {}", ready.len(), device_id);
+        for packet in ready {
+            if let Err(e) = event_sender.send(AppEvent::SendPacket(DeviceId(device_id.to_string()), packet)) {
+                warn!("Failed to flush queued packet to {}: {}", device_id, e);
+                break;
+            }
+        }
+    }
+}
+
+/// Log a queued packet that will never be sent, telling the user if it was an SMS
+async fn report_dropped(connection: &Connection, device_id: &str, entry: &QueuedPacket<ProtocolPacket>) {
+    warn!("Dropped undelivered {:?} for {}", entry.kind, device_id);
+
+    if let QueuedKind::Sms { phone_number } = &entry.kind {
+        notify_desktop(
+            connection,
+            "SMS not delivered",
+            &format!("A queued message to {} was dropped because the device did not reconnect in time.", phone_number),
+        ).await;
+    }
+}
+
+/// Show a notification mirrored from a device, filtered through that
+/// device's notification settings. Nothing is shown while the device's
+/// notifications plugin is switched off.
+async fn handle_device_notification(connection: &Connection, device_id: &str, body: &serde_json::Value) -> Result<()> {
+    if !device_enabled(device_id) {
+        return Ok(());
+    }
+    let plugins = DevicePluginsConfig::load(device_id).unwrap_or_else(|e| {
+        warn!("Failed to load plugin switches for {}: {}", device_id, e);
+        DevicePluginsConfig::default()
+    });
+    if !plugins.is_enabled(NOTIFICATIONS_PLUGIN, true) {
+        return Ok(());
+    }
+    let Some(notification) = IncomingNotification::from_packet_body(body) else {
+        return Ok(());
+    };
+
+    let config = SendNotificationsPluginConfig::load(device_id).unwrap_or_else(|e| {
+        warn!("Failed to load notification settings for {}: {}", device_id, e);
+        SendNotificationsPluginConfig::default()
+    });
+
+    let Some(notification) = notification_filter::apply(&config, notification) else {
+        info!("Filtered notification from {} on {}", body["appName"], device_id);
+        return Ok(());
+    };
+
+    let iface_ref = connection.object_server()
+        .interface::<_, DaemonInterface>(DAEMON_PATH).await?;
+    DaemonInterface::notification_received(iface_ref.signal_emitter(), device_id.to_string(), body.to_string()).await?;
+
+    let summary = if notification.app_name.is_empty() {
+        notification.title.clone()
+    } else {
+        format!("{}: {}", notification.app_name, notification.title)
+    };
+    notify_desktop(connection, &summary, &notification.body).await;
+    Ok(())
+}
+
+/// Handle a call event from a device. Desktop media is paused and resumed
+/// per the device's pause-music settings. When the telephony plugin is on,
+/// ringing calls also get a notification with a Mute action and missed
+/// calls are reported.
+async fn handle_telephony(
+    connection: &Connection,
+    event_sender: &Arc<mpsc::UnboundedSender<AppEvent>>,
+    calls: &Mutex<CallState>,
+    device_id: &str,
+    body: &serde_json::Value,
+) -> Result<()> {
+    if !device_enabled(device_id) {
+        return Ok(());
+    }
+    let Some(call) = IncomingCall::from_packet_body(body) else {
+        return Ok(());
+    };
+    info!("Call event {:?} from {} (ended: {})", call.event, device_id, call.cancelled);
+
+    let mut calls = calls.lock().await;
+    let pause_config = PauseMusicPluginConfig::load(device_id).unwrap_or_else(|e| {
+        warn!("Failed to load pause-music settings for {}: {}", device_id, e);
+        PauseMusicPluginConfig::default()
+    });
+    if let Some(action) = telephony::media_action(&pause_config, &call) {
+        calls.apply_media(connection, &pause_config, action).await;
+    }
+
+    let plugins = DevicePluginsConfig::load(device_id).unwrap_or_else(|e| {
+        warn!("Failed to load plugin switches for {}: {}", device_id, e);
+        DevicePluginsConfig::default()
+    });
+    if !plugins.is_enabled(TELEPHONY_PLUGIN, false) {
+        return Ok(());
+    }
+
+    let iface_ref = connection.object_server()
+        .interface::<_, DaemonInterface>(DAEMON_PATH).await?;
+    let event = body["event"].as_str().unwrap_or_default().to_string();
+    DaemonInterface::call_state_changed(iface_ref.signal_emitter(), device_id.to_string(), event, call.caller().to_string(), call.cancelled).await?;
+
+    match call.event {
+        CallEvent::Ringing if !call.cancelled => {
+            let replaces = calls.notifications.get(device_id).copied().unwrap_or(0);
+            match show_call_notification(connection, event_sender, device_id, call.caller(), replaces).await {
+                Ok(id) => {
+                    calls.notifications.insert(device_id.to_string(), id);
+                }
+                Err(e) => warn!("Failed to show call notification: {}", e),
+            }
+        }
+        CallEvent::MissedCall if !call.cancelled => {
+            notify_desktop(connection, &format!("Missed call from {}", call.caller()), "").await;
+        }
+        // Answered or ended: the ringing notification is no longer useful
+        _ => {
+            if let Some(id) = calls.notifications.remove(device_id) {
+                close_notification(connection, id).await;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Forward a device's cellular state to D-Bus clients
+async fn handle_connectivity(connection: &Connection, device_id: &str, body: &serde_json::Value) -> Result<()> {
+    if !device_enabled(device_id) {
+        return Ok(());
+    }
+    let Some(report) = ConnectivityReport::from_packet_body(body) else {
+        return Ok(());
+    };
+
+    let iface_ref = connection.object_server()
+        .interface::<_, DaemonInterface>(DAEMON_PATH).await?;
+    DaemonInterface::connectivity_changed(iface_ref.signal_emitter(), device_id.to_string(), report.network_type, report.signal_strength).await?;
+    Ok(())
+}
+
+/// Forward a device's media player state to D-Bus clients
+async fn handle_media_state(connection: &Connection, device_id: &str, body: &serde_json::Value) -> Result<()> {
+    if !device_enabled(device_id) {
+        return Ok(());
+    }
+
+    let iface_ref = connection.object_server()
+        .interface::<_, DaemonInterface>(DAEMON_PATH).await?;
+    DaemonInterface::media_state_changed(iface_ref.signal_emitter(), device_id.to_string(), body.to_string()).await?;
+    Ok(())
+}
+
+/// Ring this computer for a device looking for it, naming the device in
+/// a notification that stops the ringing. See `find_desktop`.
+async fn handle_find_request(
+    connection: &Connection,
+    devices: &Arc<Mutex<HashMap<String, DbusDevice>>>,
+    ringer: &Arc<Mutex<Ringer>>,
+    device_id: &str,
+) -> Result<()> {
+    if !device_enabled(device_id) {
+        return Ok(());
+    }
+    let Some(stop) = ringer.lock().await.start(device_id) else {
+        info!("Already ringing, ignoring find request from {}", device_id);
+        return Ok(());
+    };
+    let device_name = devices.lock().await.get(device_id)
+        .map_or_else(|| device_id.to_string(), |device| device.name.clone());
+    info!("{} is looking for this computer", device_name);
+
+    let ringtone = FindMyPhonePluginConfig::load(device_id).unwrap_or_else(|e| {
+        warn!("Failed to load find settings for {}: {}", device_id, e);
+        FindMyPhonePluginConfig::default()
+    }).ringtone_path;
+    let notification = match show_find_notification(connection, &device_name, stop.clone()).await {
+        Ok(id) => Some(id),
+        Err(e) => {
+            warn!("Failed to show find notification: {}", e);
+            None
+        }
+    };
+
+    let ringer = ringer.clone();
+    let connection_clone = connection.clone();
+    tokio::spawn(async move {
+        find_desktop::ring(ringtone, stop.clone()).await;
+        ringer.lock().await.finished(&stop);
+        if let Some(id) = notification {
+            close_notification(&connection_clone, id).await;
+        }
+    });
+
+    let iface_ref = connection.object_server()
+        .interface::<_, DaemonInterface>(DAEMON_PATH).await?;
+    DaemonInterface::find_requested(iface_ref.signal_emitter(), device_id.to_string(), device_name).await?;
+    Ok(())
+}
+
+/// Handle an inbound packet kdeconnect-core doesn't turn into a
+/// ConnectionEvent, following `inbound::ROUTES`. Other types are dropped.
+async fn handle_packet(
+    connection: &Connection,
+    event_sender: &Arc<mpsc::UnboundedSender<AppEvent>>,
+    devices: &Arc<Mutex<HashMap<String, DbusDevice>>>,
+    state: &InboundState,
+    device_id: &str,
+    packet_type: &str,
+    body: &serde_json::Value,
+) -> Result<()> {
+    let Some(route) = inbound::route(packet_type) else {
+        info!("Dropping {} from {}: not handled", packet_type, device_id);
+        return Ok(());
+    };
+    match route {
+        Route::Notification => handle_device_notification(connection, device_id, body).await,
+        Route::Connectivity => handle_connectivity(connection, device_id, body).await,
+        Route::Mpris => handle_media_state(connection, device_id, body).await,
+        Route::Telephony => handle_telephony(connection, event_sender, &state.calls, device_id, body).await,
+        Route::FindMyDesktop => handle_find_request(connection, devices, &state.ringer, device_id).await,
+    }
+}
+
+/// Summary of a core event for the packet log, as (device id, kind, body).
+///
+/// kdeconnect-core only hands over parsed events, not the raw packets they
+/// came from, so this is the closest view of inbound traffic available.
+fn describe_event(event: &ConnectionEvent) -> (String, &'static str, serde_json::Value) {
+    match event {
+        ConnectionEvent::Connected((device_id, device)) => {
+            (device_id.0.clone(), "connected", json!({ "name": device.name }))
+        }
+        ConnectionEvent::DevicePaired((device_id, device)) => {
+            (device_id.0.clone(), "paired", json!({ "name": device.name }))
+        }
+        ConnectionEvent::Disconnected(device_id) => (device_id.0.clone(), "disconnected", json!({})),
+        ConnectionEvent::SmsMessages(sms_data) => (
+            String::new(),
+            "kdeconnect.sms.messages",
+            serde_json::to_value(sms_data).unwrap_or_default(),
+        ),
+        _ => (String::new(), "other", json!({})),
+    }
+}
+
+/// Whether the notification server renders markup in bodies, asked once
+static BODY_MARKUP: tokio::sync::OnceCell<bool> = tokio::sync::OnceCell::const_new();
+
+/// Bodies are escaped only for servers that render markup; others would
+/// show the entities literally
+async fn body_markup(connection: &Connection) -> bool {
+    *BODY_MARKUP.get_or_init(|| async {
+        let reply = connection.call_method(
+            Some("org.freedesktop.Notifications"),
+            "/org/freedesktop/Notifications",
+            Some("org.freedesktop.Notifications"),
+            "GetCapabilities",
+            &(),
+        ).await;
+        match reply.and_then(|reply| reply.body().deserialize::<Vec<String>>()) {
+            Ok(capabilities) => capabilities.iter().any(|c| c == "body-markup"),
+            Err(e) => {
+                // Escaping is the safe choice when the server can't be asked
+                warn!("Failed to get notification server capabilities: {}", e);
+                true
+            }
+        }
+    }).await
+}
+
+/// Show a desktop notification through org.freedesktop.Notifications.
+/// Summary and body may hold device-supplied text and are cleaned here.
+async fn notify_desktop(connection: &Connection, summary: &str, body: &str) {
+    let summary = notification_text::summary(summary);
+    let body = notification_text::body(body, body_markup(connection).await);
+    let result = connection.call_method(
+        Some("org.freedesktop.Notifications"),
+        "/org/freedesktop/Notifications",
+        Some("org.freedesktop.Notifications"),
+        "Notify",
+        &(
+            "KDE Connect",
+            0u32,
+            "phone-symbolic",
+            summary.as_str(),
+            body.as_str(),
+            Vec::<&str>::new(),
+            HashMap::<&str, zbus::zvariant::Value<'_>>::new(),
+            -1i32,
+        ),
+    ).await;
+
+    if let Err(e) = result {
+        warn!("Failed to show desktop notification: {}", e);
+    }
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Notifications {
+    #[zbus(signal)]
+    fn action_invoked(&self, id: u32, action_key: String) -> zbus::Result<()>;
+    #[zbus(signal)]
+    fn notification_closed(&self, id: u32, reason: u32) -> zbus::Result<()>;
+}
+
+/// Show an incoming call with a Mute action that silences the phone's
+/// ringer. Returns the notification id.
+async fn show_call_notification(
+    connection: &Connection,
+    event_sender: &Arc<mpsc::UnboundedSender<AppEvent>>,
+    device_id: &str,
+    caller: &str,
+    replaces_id: u32,
+) -> Result<u32> {
+    use futures::StreamExt;
+
+    // Subscribe first so a click right after the notification appears isn't missed
+    let proxy = NotificationsProxy::new(connection).await?;
+    let mut actions = proxy.receive_action_invoked().await?;
+    let mut closed = proxy.receive_notification_closed().await?;
+
+    let hints = HashMap::from([
+        ("category", zbus::zvariant::Value::from("call.incoming")),
+        ("urgency", zbus::zvariant::Value::U8(2)),
+    ]);
+    let reply = connection.call_method(
+        Some("org.freedesktop.Notifications"),
+        "/org/freedesktop/Notifications",
+        Some("org.freedesktop.Notifications"),
+        "Notify",
+        &(
+            "KDE Connect",
+            replaces_id,
+            "call-start-symbolic",
+            notification_text::summary(&format!("Incoming call from {}", caller)),
+            "",
+            vec!["mute", "Mute"],
+            hints,
+            0i32,
+        ),
+    ).await?;
+    let id: u32 = reply.body().deserialize()?;
+
+    let event_sender = event_sender.clone();
+    let device_id = device_id.to_string();
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                Some(signal) = actions.next() => {
+                    let Ok(args) = signal.args() else { continue };
+                    if args.id != id {
+                        continue;
+                    }
+                    if args.action_key == "mute" {
+                        match raw_packet::build(telephony::MUTE_REQUEST, "{}") {
+                            Ok(packet) => {
+                                info!("Asking {} to mute its ringer", device_id);
+                                let _ = event_sender.send(AppEvent::SendPacket(DeviceId(device_id.clone()), packet));
+                            }
+                            Err(e) => warn!("Can't mute {}: {}", device_id, e),
+                        }
+                    }
+                    break;
+                }
+                Some(signal) = closed.next() => {
+                    if signal.args().is_ok_and(|args| args.id == id) {
+                        break;
+                    }
+                }
+                else => break,
+            }
+        }
+    });
+
+    Ok(id)
+}
+
+/// Show the "looking for this computer" notification; its "Found it"
+/// action, or dismissing it, stops the ringing
+async fn show_find_notification(connection: &Connection, device_name: &str, stop: Arc<tokio::sync::Notify>) -> Result<u32> {
+    use futures::StreamExt;
+
+    // Subscribe first so a click right after the notification appears isn't missed
+    let proxy = NotificationsProxy::new(connection).await?;
+    let mut actions = proxy.receive_action_invoked().await?;
+    let mut closed = proxy.receive_notification_closed().await?;
+
+    let hints = HashMap::from([
+        ("urgency", zbus::zvariant::Value::U8(2)),
+    ]);
+    let reply = connection.call_method(
+        Some("org.freedesktop.Notifications"),
+        "/org/freedesktop/Notifications",
+        Some("org.freedesktop.Notifications"),
+        "Notify",
+        &(
+            "KDE Connect",
+            0u32,
+            "find-location-symbolic",
+            notification_text::summary(&format!("{} is looking for this computer", device_name)),
+            "",
+            vec!["found", "Found it"],
+            hints,
+            0i32,
+        ),
+    ).await?;
+    let id: u32 = reply.body().deserialize()?;
+
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                Some(signal) = actions.next() => {
+                    if signal.args().is_ok_and(|args| args.id == id) {
+                        break;
+                    }
+                }
+                Some(signal) = closed.next() => {
+                    if signal.args().is_ok_and(|args| args.id == id) {
+                        break;
+                    }
+                }
+                else => break,
+            }
+        }
+        stop.notify_one();
+    });
+
+    Ok(id)
+}
+
+async fn close_notification(connection: &Connection, id: u32) {
+    let result = connection.call_method(
+        Some("org.freedesktop.Notifications"),
+        "/org/freedesktop/Notifications",
+        Some("org.freedesktop.Notifications"),
+        "CloseNotification",
+        &(id,),
+    ).await;
+    if let Err(e) = result {
+        warn!("Failed to close notification {}: {}", id, e);
+    }
 }
 
 /// Main service coordinator
@@ -224,88 +1190,210 @@ pub struct KdeConnectService {
     event_sender: Arc<mpsc::UnboundedSender<AppEvent>>,
     #[allow(dead_code)]
     devices: Arc<Mutex<HashMap<String, DbusDevice>>>,
+    /// Task running kdeconnect-core; aborting it drops the core and its sockets
+    core_handle: JoinHandle<()>,
+    /// Event processor and housekeeping tasks
+    tasks: Vec<JoinHandle<()>>,
 }
 
+/// How long to wait for the discovery socket to close after shutdown
+const SHUTDOWN_PORT_TIMEOUT: Duration = Duration::from_secs(2);
+
 /// Tracks devices that have already received an initial SMS sync this session.
 /// Prevents re-flooding the phone with SmsRequestConversations on every ~90s keepalive.
 type SmsSyncedSet = Arc<Mutex<std::collections::HashSet<String>>>;
 
 impl KdeConnectService {
     pub async fn new() -> Result<Self> {
-        eprintln!("=== Initializing KDE Connect D-Bus Service ===");
+        info!("Initializing KDE Connect D-Bus service");
         
         let connection = Connection::session().await?;
-        eprintln!("✓ D-Bus session connection established");
+        debug!("D-Bus session connection established");
 
         // Request service name
         connection.request_name(SERVICE_NAME).await?;
-        eprintln!("✓ D-Bus service name '{}' registered", SERVICE_NAME);
+        info!("D-Bus service name '{}' registered", SERVICE_NAME);
 
         // Initialize kdeconnect-core
-        eprintln!("Initializing kdeconnect-core...");
+        debug!("Initializing kdeconnect-core");
         let (mut core, mut event_receiver) = KdeConnectCore::new().await?;
-        let event_sender = core.take_events();
-        eprintln!("✓ kdeconnect-core initialized");
+        let core_sender = core.take_events();
+        info!("kdeconnect-core initialized");
+
+        // In developer mode, route app events through a tap that logs
+        // outgoing packets before handing them to the core
+        let packet_log: SharedPacketLog = packet_log::dev_mode_enabled()
+            .then(|| Arc::new(Mutex::new(PacketLog::default())));
+        let mut tasks = Vec::new();
+        let event_sender = match &packet_log {
+            Some(log) => {
+                warn!("Developer mode: packet log enabled");
+                let (tap_sender, mut tap_receiver) = mpsc::unbounded_channel::<AppEvent>();
+                let log = log.clone();
+                tasks.push(tokio::spawn(async move {
+                    while let Some(event) = tap_receiver.recv().await {
+                        if let AppEvent::SendPacket(device_id, packet) = &event {
+                            let packet = serde_json::to_value(packet).unwrap_or_default();
+                            let packet_type = packet["type"].as_str().unwrap_or("unknown").to_string();
+                            log.lock().await.record(true, &device_id.0, &packet_type, packet["body"].clone());
+                        }
+                        if core_sender.send(event).is_err() {
+                            break;
+                        }
+                    }
+                }));
+                Arc::new(tap_sender)
+            }
+            None => core_sender,
+        };
 
         let devices = Arc::new(Mutex::new(HashMap::new()));
+        let send_queue: SharedSendQueue = Arc::new(Mutex::new(SendQueue::default()));
+        let stats: SharedStats = Arc::new(Mutex::new(ServiceStats::default()));
 
         // Register daemon interface
         let daemon_interface = DaemonInterface {
             event_sender: event_sender.clone(),
             devices: devices.clone(),
+            send_queue: send_queue.clone(),
+            stats: stats.clone(),
+            packet_log: packet_log.clone(),
+            ring_limiter: Mutex::new(RateLimiter::new(1, find_desktop::RING_COOLDOWN)),
+            inbound: InboundState::default(),
         };
         connection.object_server().at(DAEMON_PATH, daemon_interface).await?;
-        eprintln!("✓ Daemon interface registered at {}", DAEMON_PATH);
+        debug!("Daemon interface registered at {}", DAEMON_PATH);
 
         // Register SMS interface
         let sms_interface = SmsInterface {
             event_sender: event_sender.clone(),
+            devices: devices.clone(),
+            send_queue: send_queue.clone(),
+            rate_limiter: Mutex::new(RateLimiter::default()),
         };
         connection.object_server().at(SMS_PATH, sms_interface).await?;
-        eprintln!("✓ SMS interface registered at {}", SMS_PATH);
+        debug!("SMS interface registered at {}", SMS_PATH);
 
         // Spawn core event loop
-        eprintln!("Starting core event loop...");
-        tokio::spawn(async move {
+        let core_handle = tokio::spawn(async move {
             core.run_event_loop().await;
         });
-        eprintln!("✓ Core event loop started");
+        debug!("Core event loop started");
 
         // Spawn event processor
-        eprintln!("Starting event processor...");
         let connection_clone = connection.clone();
         let devices_clone = devices.clone();
         let event_sender_clone = event_sender.clone();
+        let send_queue_clone = send_queue.clone();
+        let stats_clone = stats.clone();
+        let packet_log_clone = packet_log.clone();
         let sms_synced: SmsSyncedSet = Arc::new(Mutex::new(std::collections::HashSet::new()));
-        tokio::spawn(async move {
-            eprintln!("Event processor task running");
+        tasks.push(tokio::spawn(async move {
             loop {
                 if let Some(event) = event_receiver.recv().await {
-                    eprintln!("📨 Received event from core");
-                    if let Err(e) = Self::handle_event(event, &connection_clone, &devices_clone, &event_sender_clone, &sms_synced).await {
-                        eprintln!("❌ Error handling event: {:?}", e);
+                    if matches!(event, ConnectionEvent::Connected(_)) {
+                        stats_clone.lock().await.last_identity = Some(Instant::now());
+                    }
+                    if let Some(log) = &packet_log_clone {
+                        let (device_id, kind, body) = describe_event(&event);
+                        log.lock().await.record(false, &device_id, kind, body);
+                    }
+                    if let Err(e) = Self::handle_event(event, &connection_clone, &devices_clone, &event_sender_clone, &send_queue_clone, &sms_synced).await {
+                        error!("Error handling event: {:?}", e);
                     }
                 } else {
-                    eprintln!("⚠️  Event receiver channel closed");
+                    warn!("Event receiver channel closed");
                     break;
                 }
             }
-        });
-        eprintln!("✓ Event processor started");
+        }));
+        debug!("Event processor started");
+
+        // Periodically drop queued packets that outlived their TTL
+        let connection_clone = connection.clone();
+        let devices_clone = devices.clone();
+        let send_queue_clone = send_queue.clone();
+        tasks.push(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(60));
+            loop {
+                interval.tick().await;
+                let expired = send_queue_clone.lock().await.expire(Instant::now());
+                if expired.is_empty() {
+                    continue;
+                }
+
+                for (device_id, entry) in &expired {
+                    report_dropped(&connection_clone, device_id, entry).await;
+                }
+
+                let queue = send_queue_clone.lock().await;
+                for device in devices_clone.lock().await.values_mut() {
+                    device.pending_packets = queue.depth(&device.id) as u32;
+                }
+            }
+        }));
         
-        eprintln!("=== KDE Connect D-Bus Service Ready ===");
+        info!("KDE Connect D-Bus service ready");
 
         Ok(Self {
             connection,
             event_sender,
             devices,
+            core_handle,
+            tasks,
         })
     }
 
+    /// Stop the core and background tasks and give up the D-Bus name.
+    /// Aborting the core task drops kdeconnect-core, closing its sockets.
+    pub async fn shutdown(self) {
+        info!("KDE Connect D-Bus service stopping");
+
+        for task in &self.tasks {
+            task.abort();
+        }
+        self.core_handle.abort();
+        match self.core_handle.await {
+            Err(e) if !e.is_cancelled() => warn!("Core event loop ended abnormally: {}", e),
+            _ => {}
+        }
+
+        if let Err(e) = self.connection.release_name(SERVICE_NAME).await {
+            warn!("Failed to release D-Bus name: {}", e);
+        }
+
+        // The core may close its sockets from its own drop handlers; give
+        // them a moment before checking the discovery port
+        let deadline = Instant::now() + SHUTDOWN_PORT_TIMEOUT;
+        loop {
+            let bound = tokio::task::spawn_blocking(diagnostics::discovery_socket_bound)
+                .await
+                .unwrap_or(false);
+            if !bound {
+                info!("Discovery port {} released", diagnostics::DISCOVERY_PORT);
+                break;
+            }
+            if Instant::now() >= deadline {
+                warn!("Discovery port {} still bound after shutdown", diagnostics::DISCOVERY_PORT);
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        info!("Service stopped");
+    }
+
+    /// Serve until SIGINT or SIGTERM, then shut down cleanly
     pub async fn run(self) -> Result<()> {
-        eprintln!("Service running, waiting for events...");
-        // Keep service alive
-        std::future::pending::<()>().await;
+        debug!("Service running, waiting for events");
+
+        let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result?,
+            _ = terminate.recv() => {}
+        }
+
+        self.shutdown().await;
         Ok(())
     }
 
@@ -314,29 +1402,53 @@ impl KdeConnectService {
         connection: &Connection,
         devices: &Arc<Mutex<HashMap<String, DbusDevice>>>,
         event_sender: &Arc<mpsc::UnboundedSender<AppEvent>>,
+        send_queue: &SharedSendQueue,
         sms_synced: &SmsSyncedSet,
     ) -> Result<()> {
         match event {
             ConnectionEvent::Connected((device_id, device)) => {
                 info!("Event: Device connected - {}", device.name);
-                eprintln!("🔌 Device connected: {} ({})", device.name, device_id.0);
                 
                 let is_paired = matches!(device.pair_state, PairState::Paired);
+
                 let dbus_device = DbusDevice {
                     id: device_id.0.clone(),
                     name: device.name.clone(),
                     device_type: "phone".to_string(),
                     is_paired,
                     is_reachable: true,
+                    pending_packets: 0,
+                    // kdeconnect-core's events don't say which link a device
+                    // came in on, so the transport is reported as unknown
+                    connection_type: String::new(),
+                    enabled: device_enabled(&device_id.0),
                 };
+                let enabled = dbus_device.enabled;
                 
-                devices.lock().await.insert(device_id.0.clone(), dbus_device.clone());
+                // Every identity packet carries the device name, so a rename
+                // in the phone app shows up on its next connect or keepalive.
+                // kdeconnect-core has no dedicated rename event.
+                let previous_name = devices.lock().await
+                    .insert(device_id.0.clone(), dbus_device.clone())
+                    .map(|previous| previous.name);
                 
                 let iface_ref = connection.object_server()
                     .interface::<_, DaemonInterface>(DAEMON_PATH).await?;
                 
                 DaemonInterface::device_connected(iface_ref.signal_emitter(), device_id.0.clone(), dbus_device).await?;
-                eprintln!("✓ Device connected signal emitted");
+                debug!("DeviceConnected emitted for {}", device_id.0);
+
+                if let Some(previous_name) = previous_name.filter(|name| *name != device.name) {
+                    info!("Device {} renamed from {} to {}", device_id.0, previous_name, device.name);
+                    DaemonInterface::device_renamed(iface_ref.signal_emitter(), device_id.0.clone(), device.name.clone()).await?;
+                }
+
+                if !enabled {
+                    info!("Device {} is disabled, not syncing", device_id.0);
+                    return Ok(());
+                }
+
+                flush_send_queue(connection, event_sender, send_queue, &device_id.0).await;
 
                 // Only request SMS once per device per session.
                 // The phone re-broadcasts its identity every ~90s; without this guard
@@ -351,16 +1463,15 @@ impl KdeConnectService {
                             tokio::time::sleep(std::time::Duration::from_millis(500)).await;
                             let sms_packet = ProtocolPacket::new(PacketType::SmsRequestConversations, json!({}));
                             let _ = sender.send(AppEvent::SendPacket(DeviceId(did), sms_packet));
-                            eprintln!("📱 Auto-requested SMS conversations (first connect this session)");
+                            debug!("Auto-requested SMS conversations (first connect this session)");
                         });
                     } else {
-                        eprintln!("📱 Skipping SMS re-sync — already synced this session");
+                        debug!("Skipping SMS re-sync, already synced this session");
                     }
                 }
             }
             ConnectionEvent::DevicePaired((device_id, device)) => {
                 info!("Event: Device paired - {}", device.name);
-                eprintln!("🔐 Device paired: {} ({})", device.name, device_id.0);
                 
                 let dbus_device = DbusDevice {
                     id: device_id.0.clone(),
@@ -368,6 +1479,11 @@ impl KdeConnectService {
                     device_type: "phone".to_string(),
                     is_paired: true,
                     is_reachable: true,
+                    pending_packets: send_queue.lock().await.depth(&device_id.0) as u32,
+                    // kdeconnect-core's events don't say which link a device
+                    // came in on, so the transport is reported as unknown
+                    connection_type: String::new(),
+                    enabled: device_enabled(&device_id.0),
                 };
                 
                 devices.lock().await.insert(device_id.0.clone(), dbus_device.clone());
@@ -376,7 +1492,7 @@ impl KdeConnectService {
                     .interface::<_, DaemonInterface>(DAEMON_PATH).await?;
                 
                 DaemonInterface::device_paired(iface_ref.signal_emitter(), device_id.0.clone(), dbus_device).await?;
-                eprintln!("✓ Device paired signal emitted");
+                debug!("DevicePaired emitted for {}", device_id.0);
 
                 // Wait 2s for the phone to settle after pairing, then request SMS.
                 // The phone may open a fresh connection after accepting — we need to
@@ -387,51 +1503,63 @@ impl KdeConnectService {
                     tokio::time::sleep(std::time::Duration::from_secs(2)).await;
                     let sms_packet = ProtocolPacket::new(PacketType::SmsRequestConversations, json!({}));
                     let _ = sender.send(AppEvent::SendPacket(DeviceId(did), sms_packet));
-                    eprintln!("📱 Auto-requested SMS conversations after pairing (delayed)");
+                    debug!("Auto-requested SMS conversations after pairing");
                 });
             }
             ConnectionEvent::Disconnected(device_id) => {
                 info!("Event: Device disconnected - {}", device_id.0);
-                eprintln!("🔌 Device disconnected: {}", device_id.0);
                 
                 // Clear sms_synced so the next genuine reconnect gets a fresh sync.
                 sms_synced.lock().await.remove(&device_id.0);
-                devices.lock().await.remove(&device_id.0);
+
+                // Keep the device listed as unreachable so queued sends stay visible
+                if let Some(device) = devices.lock().await.get_mut(&device_id.0) {
+                    device.is_reachable = false;
+                    device.connection_type.clear();
+                }
                 
                 let iface_ref = connection.object_server()
                     .interface::<_, DaemonInterface>(DAEMON_PATH).await?;
                 
                 DaemonInterface::device_disconnected(iface_ref.signal_emitter(), device_id.0).await?;
-                eprintln!("✓ Device disconnected signal emitted");
             }
             ConnectionEvent::SmsMessages(sms_data) => {
-                eprintln!("📱 !!! SMS MESSAGES EVENT RECEIVED !!!");
-                eprintln!("    Number of messages: {}", sms_data.messages.len());
                 info!("Event: SMS messages received - {} messages", sms_data.messages.len());
                 
-                // Show first few messages for debugging
-                for (i, msg) in sms_data.messages.iter().take(3).enumerate() {
-                    let preview = if msg.body.len() > 50 {
-                        format!("{}...", &msg.body[..50])
-                    } else {
-                        msg.body.clone()
-                    };
-                    eprintln!("    Message {}: thread={}, body={}", i + 1, msg.thread_id, preview);
-                }
-                
                 // Serialize to JSON for D-Bus signal
                 let messages_json = serde_json::to_string(&sms_data)?;
-                eprintln!("    JSON size: {} bytes", messages_json.len());
+                debug!("SMS batch JSON size: {} bytes", messages_json.len());
+                
+                // The core event doesn't say which device sent the batch; it is
+                // only attributable while a single paired device is connected.
+                // Drop it when every device that could have sent it is disabled.
+                let (source, all_disabled) = {
+                    let devices = devices.lock().await;
+                    let connected: Vec<&DbusDevice> = devices.values()
+                        .filter(|d| d.is_paired && d.is_reachable)
+                        .collect();
+                    let source = match connected.as_slice() {
+                        [device] => device.id.clone(),
+                        _ => String::new(),
+                    };
+                    (source, !connected.is_empty() && connected.iter().all(|d| !d.enabled))
+                };
+                if all_disabled {
+                    info!("Ignoring SMS messages from a disabled device");
+                    return Ok(());
+                }
                 
                 let iface_ref = connection.object_server()
                     .interface::<_, SmsInterface>(SMS_PATH).await?;
                 
-                eprintln!("    Emitting D-Bus signal...");
-                SmsInterface::sms_messages_received(iface_ref.signal_emitter(), messages_json).await?;
-                eprintln!("    ✓ SMS D-Bus signal emitted successfully!");
+                SmsInterface::sms_messages_received(iface_ref.signal_emitter(), source, messages_json).await?;
+                debug!("SmsMessagesReceived emitted");
             }
-            _ => {
-                eprintln!("📬 Other event received");
+            // Core events without a handler; see `inbound` for the packets
+            // the core drops before they get here
+            other => {
+                let (device_id, kind, _) = describe_event(&other);
+                info!("Dropping core event {} from {:?}: not handled", kind, device_id);
             }
         }
         
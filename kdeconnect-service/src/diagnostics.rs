@@ -0,0 +1,52 @@
+// kdeconnect-service/src/diagnostics.rs
+//! Runtime health information for troubleshooting connection problems.
+//!
+//! kdeconnect-core doesn't report its socket state, so the listening ports
+//! and discovery socket are probed from the outside.
+
+use std::net::{Ipv4Addr, TcpStream, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// Port used for UDP discovery broadcasts
+pub const DISCOVERY_PORT: u16 = 1716;
+
+/// Range KDE Connect picks its TCP link port from
+pub const LINK_PORT_RANGE: std::ops::RangeInclusive<u16> = 1716..=1764;
+
+const PROBE_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// Counters updated by the event processor
+#[derive(Debug)]
+pub struct ServiceStats {
+    pub started: Instant,
+    /// Last identity packet (connect or keepalive re-broadcast) from any device
+    pub last_identity: Option<Instant>,
+}
+
+impl Default for ServiceStats {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            last_identity: None,
+        }
+    }
+}
+
+/// TCP ports in the KDE Connect range accepting connections on this host
+pub fn listening_ports() -> Vec<u16> {
+    LINK_PORT_RANGE
+        .filter(|port| {
+            let addr = (Ipv4Addr::LOCALHOST, *port).into();
+            TcpStream::connect_timeout(&addr, PROBE_TIMEOUT).is_ok()
+        })
+        .collect()
+}
+
+/// Whether something holds the UDP discovery port. If we can bind it
+/// ourselves, discovery isn't listening.
+pub fn discovery_socket_bound() -> bool {
+    match UdpSocket::bind((Ipv4Addr::UNSPECIFIED, DISCOVERY_PORT)) {
+        Ok(_) => false,
+        Err(e) => e.kind() == std::io::ErrorKind::AddrInUse,
+    }
+}
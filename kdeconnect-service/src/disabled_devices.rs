@@ -0,0 +1,96 @@
+// kdeconnect-service/src/disabled_devices.rs
+//! Paired devices the user has switched off.
+//!
+//! Stored in ~/.config/cosmic-connect/disabled_devices as device_id=true
+//! lines. A disabled device stays paired, but packets it sends are ignored
+//! and nothing is sent to it until it is enabled again.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Default)]
+pub struct DisabledDevices {
+    ids: HashSet<String>,
+}
+
+impl DisabledDevices {
+    /// Load the disabled set from file
+    pub fn load() -> io::Result<Self> {
+        let path = Self::get_config_path();
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let mut ids = HashSet::new();
+        for line in fs::read_to_string(&path)?.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+                continue;
+            }
+            if let Some((device_id, disabled)) = line.split_once('=') {
+                if disabled.trim().parse::<bool>().unwrap_or(false) {
+                    ids.insert(device_id.trim().to_string());
+                }
+            }
+        }
+
+        Ok(Self { ids })
+    }
+
+    /// Save the disabled set to file
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::get_config_path();
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = fs::File::create(&path)?;
+        writeln!(file, "[Disabled]")?;
+        for device_id in &self.ids {
+            writeln!(file, "{}=true", device_id)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn is_enabled(&self, device_id: &str) -> bool {
+        !self.ids.contains(device_id)
+    }
+
+    /// Enable or disable a device, returning whether anything changed
+    pub fn set_enabled(&mut self, device_id: &str, enabled: bool) -> bool {
+        if enabled {
+            self.ids.remove(device_id)
+        } else {
+            self.ids.insert(device_id.to_string())
+        }
+    }
+
+    fn get_config_path() -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
+        PathBuf::from(format!("{}/.config/cosmic-connect/disabled_devices", home))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disable_and_reenable_device() {
+        let mut disabled = DisabledDevices::default();
+        assert!(disabled.is_enabled("phone"));
+
+        assert!(disabled.set_enabled("phone", false));
+        assert!(!disabled.set_enabled("phone", false));
+        assert!(!disabled.is_enabled("phone"));
+        assert!(disabled.is_enabled("tablet"));
+
+        assert!(disabled.set_enabled("phone", true));
+        assert!(disabled.is_enabled("phone"));
+    }
+}
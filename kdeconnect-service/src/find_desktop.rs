@@ -0,0 +1,118 @@
+// kdeconnect-service/src/find_desktop.rs
+//! Ringing this computer when a phone asks to find it.
+//!
+//! A `kdeconnect.findmyphone.request` from a device plays the ringtone set
+//! in its FindMyPhonePluginConfig on a loop and shows a notification
+//! naming the device, with a "Found it" action. Ringing stops on that
+//! action, when the notification is dismissed, or after RING_TIMEOUT.
+//! Requests while already ringing are ignored, so repeated taps on the
+//! phone don't stack rings.
+
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::sync::Notify;
+use tracing::{debug, warn};
+
+/// Longest a find request rings for
+pub const RING_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Shortest time between two rings sent to the same device
+pub const RING_COOLDOWN: Duration = Duration::from_secs(5);
+
+/// Sound players tried in order: PipeWire, then PulseAudio
+const PLAYERS: [&str; 2] = ["pw-play", "paplay"];
+
+/// The ring in progress, if any
+#[derive(Debug, Default)]
+pub struct Ringer {
+    ringing: Option<(String, Arc<Notify>)>,
+}
+
+impl Ringer {
+    /// Start ringing for `device_id`, returning what stops it, or None if
+    /// a ring is already going on
+    pub fn start(&mut self, device_id: &str) -> Option<Arc<Notify>> {
+        if self.ringing.is_some() {
+            return None;
+        }
+        let stop = Arc::new(Notify::new());
+        self.ringing = Some((device_id.to_string(), stop.clone()));
+        Some(stop)
+    }
+
+    /// Stop the ring in progress, returning the device that asked for it
+    pub fn stop(&mut self) -> Option<String> {
+        let (device_id, stop) = self.ringing.take()?;
+        stop.notify_one();
+        Some(device_id)
+    }
+
+    /// Forget a ring that ended on its own, unless another has started since
+    pub fn finished(&mut self, stop: &Arc<Notify>) {
+        if self.ringing.as_ref().is_some_and(|(_, current)| Arc::ptr_eq(current, stop)) {
+            self.ringing = None;
+        }
+    }
+}
+
+/// Play `ringtone` on a loop until `stop` is notified or RING_TIMEOUT
+/// passes. Without a ringtone or a working player, only waits.
+pub async fn ring(ringtone: String, stop: Arc<Notify>) {
+    let playing = async {
+        if ringtone.is_empty() {
+            debug!("No ringtone set, finding by notification only");
+            return std::future::pending().await;
+        }
+        loop {
+            let Some(status) = play_once(&ringtone).await else {
+                return std::future::pending().await;
+            };
+            if !status {
+                warn!("Failed to play ringtone {}", ringtone);
+                return std::future::pending().await;
+            }
+        }
+    };
+    tokio::select! {
+        _ = playing => {}
+        _ = stop.notified() => {}
+        _ = tokio::time::sleep(RING_TIMEOUT) => {}
+    }
+}
+
+/// Play the ringtone once with the first player available. None when no
+/// player is installed, otherwise whether it played.
+async fn play_once(ringtone: &str) -> Option<bool> {
+    for player in PLAYERS {
+        // Dropping the future on stop kills the player
+        match Command::new(player).arg(ringtone).kill_on_drop(true).status().await {
+            Ok(status) => return Some(status.success()),
+            Err(e) => debug!("{} unavailable: {}", player, e),
+        }
+    }
+    warn!("No sound player found to ring with");
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_ring_at_a_time() {
+        let mut ringer = Ringer::default();
+        let first = ringer.start("phone").unwrap();
+        assert!(ringer.start("tablet").is_none(), "repeated requests don't stack");
+
+        assert_eq!(ringer.stop().as_deref(), Some("phone"));
+        assert_eq!(ringer.stop(), None);
+
+        // A ring that times out after a newer one started leaves it alone
+        let second = ringer.start("tablet").unwrap();
+        ringer.finished(&first);
+        assert!(ringer.start("phone").is_none());
+        ringer.finished(&second);
+        assert!(ringer.start("phone").is_some());
+    }
+}
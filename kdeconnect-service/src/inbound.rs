@@ -0,0 +1,110 @@
+// kdeconnect-service/src/inbound.rs
+//! Which inbound packets the service handles, and the signal each raises.
+//!
+//! kdeconnect-core turns identity, pairing and SMS packets into
+//! ConnectionEvents and drops everything else before it reaches the
+//! service. The packets below are the ones worth handling; each is routed
+//! to a handler in `dbus_interface` that emits the listed D-Bus signal.
+//! Until the core surfaces them they only arrive through InjectPacket in
+//! developer mode, and any other type is logged as dropped.
+
+use serde_json::Value;
+
+/// Handler an inbound packet is routed to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Route {
+    Notification,
+    Connectivity,
+    Mpris,
+    Telephony,
+    FindMyDesktop,
+}
+
+/// Inbound packet types with a handler, by wire type
+pub const ROUTES: [(&str, Route); 5] = [
+    ("kdeconnect.notification", Route::Notification),
+    ("kdeconnect.connectivity_report", Route::Connectivity),
+    ("kdeconnect.mpris", Route::Mpris),
+    ("kdeconnect.telephony", Route::Telephony),
+    ("kdeconnect.findmyphone.request", Route::FindMyDesktop),
+];
+
+/// The handler for a wire packet type, None if packets of that type are
+/// dropped
+pub fn route(packet_type: &str) -> Option<Route> {
+    ROUTES.iter()
+        .find(|(wire_type, _)| *wire_type == packet_type)
+        .map(|(_, route)| *route)
+}
+
+impl Route {
+    /// Daemon interface signal raised for a handled packet
+    pub fn signal(self) -> &'static str {
+        match self {
+            Route::Notification => "NotificationReceived",
+            Route::Connectivity => "ConnectivityChanged",
+            Route::Mpris => "MediaStateChanged",
+            Route::Telephony => "CallStateChanged",
+            Route::FindMyDesktop => "FindRequested",
+        }
+    }
+}
+
+/// Cellular state from a `kdeconnect.connectivity_report` packet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectivityReport {
+    /// e.g. "LTE", "5G"; empty without service
+    pub network_type: String,
+    /// 0 (none) to 4 (full)
+    pub signal_strength: i32,
+}
+
+impl ConnectivityReport {
+    /// Parse a report body. Phones report each SIM under its subscription
+    /// id; the lowest id is taken as the main one.
+    pub fn from_packet_body(body: &Value) -> Option<Self> {
+        let sims = body.get("signalStrengths")?.as_object()?;
+        let (_, sim) = sims.iter().min_by_key(|(id, _)| id.parse::<i64>().unwrap_or(i64::MAX))?;
+        Some(Self {
+            network_type: sim.get("networkType").and_then(Value::as_str).unwrap_or_default().to_string(),
+            signal_strength: sim.get("signalStrength")
+                .and_then(Value::as_i64)
+                .and_then(|strength| i32::try_from(strength).ok())
+                .unwrap_or(0)
+                .clamp(0, 4),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_each_routed_packet_raises_its_signal() {
+        let expected = [
+            ("kdeconnect.notification", "NotificationReceived"),
+            ("kdeconnect.connectivity_report", "ConnectivityChanged"),
+            ("kdeconnect.mpris", "MediaStateChanged"),
+            ("kdeconnect.telephony", "CallStateChanged"),
+            ("kdeconnect.findmyphone.request", "FindRequested"),
+        ];
+        assert_eq!(expected.len(), ROUTES.len(), "every route is covered");
+        for (packet_type, signal) in expected {
+            assert_eq!(route(packet_type).map(Route::signal), Some(signal), "{}", packet_type);
+        }
+
+        // Requests from the phone, and types handled as ConnectionEvents
+        assert_eq!(route("kdeconnect.mpris.request"), None);
+        assert_eq!(route("kdeconnect.sms.messages"), None);
+
+        let report = json!({"signalStrengths": {"6": {"networkType": "3G", "signalStrength": 1},
+                                                "2": {"networkType": "LTE", "signalStrength": 9}}});
+        assert_eq!(
+            ConnectivityReport::from_packet_body(&report),
+            Some(ConnectivityReport { network_type: "LTE".to_string(), signal_strength: 4 })
+        );
+        assert_eq!(ConnectivityReport::from_packet_body(&json!({})), None);
+    }
+}
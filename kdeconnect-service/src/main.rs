@@ -5,6 +5,18 @@ use anyhow::Result;
 use tracing::info;
 
 mod dbus_interface;
+mod diagnostics;
+mod disabled_devices;
+mod find_desktop;
+mod inbound;
+mod media_control;
+mod notification_filter;
+mod packet_log;
+mod rate_limit;
+mod raw_packet;
+mod send_queue;
+mod telephony;
+mod transport;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -15,11 +27,9 @@ async fn main() -> Result<()> {
         )
         .init();
 
-    eprintln!("=== KDE Connect Service Starting ===");
     info!("=== KDE Connect Service Starting ===");
 
     let service = dbus_interface::KdeConnectService::new().await?;
-    eprintln!("✓ D-Bus service started on org.cosmic.KdeConnect");
     info!("✓ D-Bus service started on org.cosmic.KdeConnect");
 
     service.run().await?;
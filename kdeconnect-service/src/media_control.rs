@@ -0,0 +1,143 @@
+// kdeconnect-service/src/media_control.rs
+//! Pausing and muting desktop media, used while a phone call is going on.
+//!
+//! Players are reached over MPRIS on the session bus. Muting goes through
+//! PipeWire's `wpctl`, falling back to `pactl` on plain PulseAudio. Only
+//! what was actually paused or muted here is restored afterwards, so a
+//! player the user had paused stays paused.
+
+use tokio::process::Command;
+use tracing::{debug, info, warn};
+use zbus::Connection;
+
+const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+
+#[zbus::proxy(interface = "org.mpris.MediaPlayer2.Player", default_path = "/org/mpris/MediaPlayer2")]
+trait MediaPlayer {
+    fn pause(&self) -> zbus::Result<()>;
+    fn play(&self) -> zbus::Result<()>;
+    #[zbus(property)]
+    fn playback_status(&self) -> zbus::Result<String>;
+}
+
+/// MPRIS players paused by us
+#[derive(Debug, Default)]
+pub struct MprisBridge {
+    paused: Vec<String>,
+}
+
+impl MprisBridge {
+    /// Pause every playing player. Players paused earlier stay on the
+    /// list, so a second pause during the same call is harmless.
+    pub async fn pause_playing(&mut self, connection: &Connection) {
+        for name in playing_players(connection).await {
+            let result = match player(connection, &name).await {
+                Ok(player) => player.pause().await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => {
+                    info!("Paused {}", name);
+                    self.paused.push(name);
+                }
+                Err(e) => warn!("Failed to pause {}: {}", name, e),
+            }
+        }
+    }
+
+    /// Resume the players paused by `pause_playing`
+    pub async fn resume(&mut self, connection: &Connection) {
+        for name in std::mem::take(&mut self.paused) {
+            let result = match player(connection, &name).await {
+                Ok(player) => player.play().await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                // The player may have quit in the meantime
+                debug!("Failed to resume {}: {}", name, e);
+            }
+        }
+    }
+
+    /// Drop the paused list without resuming anything
+    pub fn forget(&mut self) {
+        self.paused.clear();
+    }
+}
+
+async fn player(connection: &Connection, name: &str) -> zbus::Result<MediaPlayerProxy<'static>> {
+    MediaPlayerProxy::builder(connection).destination(name.to_string())?.build().await
+}
+
+/// Bus names of MPRIS players currently playing
+async fn playing_players(connection: &Connection) -> Vec<String> {
+    let names = match zbus::fdo::DBusProxy::new(connection).await {
+        Ok(proxy) => proxy.list_names().await.unwrap_or_default(),
+        Err(e) => {
+            warn!("Failed to list bus names: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let mut playing = Vec::new();
+    for name in names.iter().map(|n| n.to_string()).filter(|n| n.starts_with(MPRIS_PREFIX)) {
+        let status = match player(connection, &name).await {
+            Ok(player) => player.playback_status().await.unwrap_or_default(),
+            Err(_) => continue,
+        };
+        if status == "Playing" {
+            playing.push(name);
+        }
+    }
+    playing
+}
+
+/// The default output's mute switch
+#[derive(Debug, Default)]
+pub struct SystemVolume {
+    /// Muted by us, to be unmuted later
+    muted: bool,
+}
+
+impl SystemVolume {
+    pub async fn mute(&mut self) {
+        if !self.muted {
+            self.muted = set_output_muted(true).await;
+        }
+    }
+
+    /// Unmute, if `mute` muted it
+    pub async fn restore(&mut self) {
+        if self.muted {
+            set_output_muted(false).await;
+            self.muted = false;
+        }
+    }
+
+    /// Stop tracking the mute without changing it
+    pub fn forget(&mut self) {
+        self.muted = false;
+    }
+}
+
+/// Mute or unmute the default output. Returns whether it worked.
+async fn set_output_muted(muted: bool) -> bool {
+    let flag = if muted { "1" } else { "0" };
+    let commands: [(&str, [&str; 3]); 2] = [
+        ("wpctl", ["set-mute", "@DEFAULT_AUDIO_SINK@", flag]),
+        ("pactl", ["set-sink-mute", "@DEFAULT_SINK@", flag]),
+    ];
+
+    for (program, args) in commands {
+        match Command::new(program).args(args).status().await {
+            Ok(status) if status.success() => {
+                info!("Output {} with {}", if muted { "muted" } else { "unmuted" }, program);
+                return true;
+            }
+            Ok(status) => warn!("{} exited with {}", program, status),
+            Err(e) => debug!("Couldn't run {}: {}", program, e),
+        }
+    }
+    warn!("Couldn't {} the output", if muted { "mute" } else { "unmute" });
+    false
+}
@@ -0,0 +1,160 @@
+// kdeconnect-service/src/notification_filter.rs
+//! Filtering of notifications mirrored from a phone.
+//!
+//! Applies the per-device SendNotificationsPluginConfig (urgency threshold,
+//! app block/allow list, persistent-only and body stripping) before a
+//! notification is shown on the desktop.
+
+use serde_json::Value;
+
+use cosmic_connect_common::plugin_config::{SendNotificationsPluginConfig, UrgencyLevel};
+
+/// A notification received from a device
+#[derive(Debug, Clone, PartialEq)]
+pub struct IncomingNotification {
+    pub id: String,
+    pub app_name: String,
+    pub title: String,
+    pub body: String,
+    pub urgency: UrgencyLevel,
+    /// Ongoing notifications the user can't dismiss on the phone
+    pub persistent: bool,
+}
+
+impl IncomingNotification {
+    /// Parse the body of a `kdeconnect.notification` packet. Returns None for
+    /// cancellations and packets without an id.
+    pub fn from_packet_body(body: &Value) -> Option<Self> {
+        if body.get("isCancel").and_then(Value::as_bool).unwrap_or(false) {
+            return None;
+        }
+
+        let text = |key: &str| body.get(key).and_then(Value::as_str).unwrap_or_default().to_string();
+        let id = text("id");
+        if id.is_empty() {
+            return None;
+        }
+
+        // The protocol has no urgency field; silent notifications are the
+        // closest thing to low priority.
+        let urgency = if body.get("silent").and_then(Value::as_bool).unwrap_or(false) {
+            UrgencyLevel::Low
+        } else {
+            UrgencyLevel::Normal
+        };
+
+        Some(Self {
+            id,
+            app_name: text("appName"),
+            title: text("title"),
+            body: text("text"),
+            urgency,
+            persistent: !body.get("isClearable").and_then(Value::as_bool).unwrap_or(true),
+        })
+    }
+}
+
+/// Apply a device's notification settings. Returns the notification to show,
+/// possibly with its body removed, or None if it should be dropped.
+pub fn apply(config: &SendNotificationsPluginConfig, mut notification: IncomingNotification) -> Option<IncomingNotification> {
+    if (notification.urgency as i32) < (config.min_urgency as i32) {
+        return None;
+    }
+
+    if config.persistent_only && !notification.persistent {
+        return None;
+    }
+
+    let listed = config.app_settings.iter()
+        .find(|app| app.app_name.eq_ignore_ascii_case(&notification.app_name));
+    let allowed = if config.use_blocklist {
+        // Blocklist: listed apps with enabled=true are blocked
+        !listed.is_some_and(|app| app.enabled)
+    } else {
+        // Allowlist: only listed apps with enabled=true get through
+        listed.is_some_and(|app| app.enabled)
+    };
+    if !allowed {
+        return None;
+    }
+
+    if !config.include_body {
+        notification.body.clear();
+    }
+
+    Some(notification)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmic_connect_common::plugin_config::AppNotificationSetting;
+
+    fn notification(app_name: &str, urgency: UrgencyLevel, persistent: bool) -> IncomingNotification {
+        IncomingNotification {
+            id: "1".to_string(),
+            app_name: app_name.to_string(),
+            title: "Title".to_string(),
+            body: "Body".to_string(),
+            urgency,
+            persistent,
+        }
+    }
+
+    fn rule(app_name: &str) -> AppNotificationSetting {
+        AppNotificationSetting { app_name: app_name.to_string(), enabled: true }
+    }
+
+    #[test]
+    fn test_min_urgency() {
+        let config = SendNotificationsPluginConfig {
+            min_urgency: UrgencyLevel::Normal,
+            ..Default::default()
+        };
+        assert!(apply(&config, notification("Signal", UrgencyLevel::Low, false)).is_none());
+        assert!(apply(&config, notification("Signal", UrgencyLevel::Normal, false)).is_some());
+    }
+
+    #[test]
+    fn test_blocklist() {
+        let config = SendNotificationsPluginConfig {
+            app_settings: vec![rule("Facebook")],
+            use_blocklist: true,
+            ..Default::default()
+        };
+        assert!(apply(&config, notification("facebook", UrgencyLevel::Normal, false)).is_none());
+        assert!(apply(&config, notification("Signal", UrgencyLevel::Normal, false)).is_some());
+    }
+
+    #[test]
+    fn test_allowlist() {
+        let config = SendNotificationsPluginConfig {
+            app_settings: vec![rule("Signal")],
+            use_blocklist: false,
+            ..Default::default()
+        };
+        assert!(apply(&config, notification("Signal", UrgencyLevel::Normal, false)).is_some());
+        assert!(apply(&config, notification("Facebook", UrgencyLevel::Normal, false)).is_none());
+    }
+
+    #[test]
+    fn test_persistent_only() {
+        let config = SendNotificationsPluginConfig {
+            persistent_only: true,
+            ..Default::default()
+        };
+        assert!(apply(&config, notification("Maps", UrgencyLevel::Normal, true)).is_some());
+        assert!(apply(&config, notification("Signal", UrgencyLevel::Normal, false)).is_none());
+    }
+
+    #[test]
+    fn test_body_stripped() {
+        let config = SendNotificationsPluginConfig {
+            include_body: false,
+            ..Default::default()
+        };
+        let shown = apply(&config, notification("Signal", UrgencyLevel::Normal, false)).unwrap();
+        assert!(shown.body.is_empty());
+        assert_eq!(shown.title, "Title");
+    }
+}
@@ -0,0 +1,90 @@
+// kdeconnect-service/src/packet_log.rs
+//! Developer packet log.
+//!
+//! Disabled unless the service is started with COSMIC_CONNECT_DEV=1. When
+//! enabled, packets handed to kdeconnect-core and events coming back from it
+//! are kept in a bounded in-memory log for the settings window's inspector.
+
+use std::collections::VecDeque;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Environment variable that enables developer features
+pub const DEV_MODE_ENV: &str = "COSMIC_CONNECT_DEV";
+
+/// Entries kept before the oldest are dropped
+pub const LOG_CAPACITY: usize = 500;
+
+pub fn dev_mode_enabled() -> bool {
+    std::env::var(DEV_MODE_ENV).is_ok_and(|v| !v.is_empty() && v != "0")
+}
+
+#[derive(Debug, Clone)]
+pub struct LoggedPacket {
+    /// Milliseconds since the Unix epoch
+    pub timestamp_ms: i64,
+    /// Sent to the device (true) or received from it (false)
+    pub outbound: bool,
+    /// Empty when the core event doesn't name a device
+    pub device_id: String,
+    pub packet_type: String,
+    pub body: serde_json::Value,
+}
+
+#[derive(Debug, Default)]
+pub struct PacketLog {
+    entries: VecDeque<LoggedPacket>,
+}
+
+impl PacketLog {
+    pub fn record(&mut self, outbound: bool, device_id: &str, packet_type: &str, body: serde_json::Value) {
+        self.push(LoggedPacket {
+            timestamp_ms: now_millis(),
+            outbound,
+            device_id: device_id.to_string(),
+            packet_type: packet_type.to_string(),
+            body,
+        });
+    }
+
+    fn push(&mut self, entry: LoggedPacket) {
+        if self.entries.len() == LOG_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// Entries logged at or after `since_ms`, oldest first
+    pub fn since(&self, since_ms: i64) -> impl Iterator<Item = &LoggedPacket> {
+        self.entries.iter().filter(move |e| e.timestamp_ms >= since_ms)
+    }
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packet_log_bounded_and_filtered() {
+        let mut log = PacketLog::default();
+        for i in 0..(LOG_CAPACITY as i64 + 10) {
+            log.push(LoggedPacket {
+                timestamp_ms: i,
+                outbound: true,
+                device_id: "phone".to_string(),
+                packet_type: "kdeconnect.ping".to_string(),
+                body: serde_json::json!({}),
+            });
+        }
+
+        assert_eq!(log.since(0).count(), LOG_CAPACITY);
+        assert_eq!(log.since(0).next().unwrap().timestamp_ms, 10);
+        assert_eq!(log.since(LOG_CAPACITY as i64 + 5).count(), 5);
+    }
+}
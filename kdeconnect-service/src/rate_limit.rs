@@ -0,0 +1,74 @@
+// kdeconnect-service/src/rate_limit.rs
+//! Sliding-window rate limiting for outbound sends.
+//!
+//! Guards against a stuck client retry loop flooding a carrier with SMS.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Sends allowed to one recipient within `SMS_RATE_WINDOW`
+pub const SMS_MAX_PER_WINDOW: usize = 5;
+
+/// Window over which SMS sends to a recipient are counted
+pub const SMS_RATE_WINDOW: Duration = Duration::from_secs(10);
+
+/// Per-key sliding window limiter
+#[derive(Debug)]
+pub struct RateLimiter {
+    sends: HashMap<String, VecDeque<Instant>>,
+    max_per_window: usize,
+    window: Duration,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(SMS_MAX_PER_WINDOW, SMS_RATE_WINDOW)
+    }
+}
+
+impl RateLimiter {
+    pub fn new(max_per_window: usize, window: Duration) -> Self {
+        Self {
+            sends: HashMap::new(),
+            max_per_window,
+            window,
+        }
+    }
+
+    /// Record a send for `key` if allowed. Otherwise returns how long to
+    /// wait before the next send would be accepted.
+    pub fn check(&mut self, key: &str, now: Instant) -> Result<(), Duration> {
+        let sends = self.sends.entry(key.to_string()).or_default();
+
+        while sends.front().is_some_and(|t| now.duration_since(*t) >= self.window) {
+            sends.pop_front();
+        }
+
+        if sends.len() >= self.max_per_window {
+            let oldest = sends.front().copied().unwrap_or(now);
+            return Err(self.window.saturating_sub(now.duration_since(oldest)));
+        }
+
+        sends.push_back(now);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limit_resets_after_window() {
+        let mut limiter = RateLimiter::new(2, Duration::from_secs(10));
+        let start = Instant::now();
+
+        assert!(limiter.check("5551234567", start).is_ok());
+        assert!(limiter.check("5551234567", start).is_ok());
+        assert!(limiter.check("5551234567", start + Duration::from_secs(1)).is_err());
+        // Other recipients are counted separately
+        assert!(limiter.check("5559876543", start).is_ok());
+
+        assert!(limiter.check("5551234567", start + Duration::from_secs(10)).is_ok());
+    }
+}
@@ -0,0 +1,50 @@
+// kdeconnect-service/src/raw_packet.rs
+//! Building packets from caller-supplied type names and JSON bodies.
+//!
+//! Used by the SendRawPacket D-Bus method so plugins can be prototyped
+//! without a typed method for each packet. Only packet types known to
+//! kdeconnect-core can be sent.
+
+use kdeconnect_core::{PacketType, ProtocolPacket};
+use serde_json::Value;
+
+/// Parse a packet body, which must be a JSON object
+pub fn parse_body(body_json: &str) -> Result<Value, String> {
+    let body = if body_json.trim().is_empty() {
+        Value::Object(Default::default())
+    } else {
+        serde_json::from_str::<Value>(body_json)
+            .map_err(|e| format!("Packet body is not valid JSON: {}", e))?
+    };
+    if !body.is_object() {
+        return Err("Packet body must be a JSON object".to_string());
+    }
+    Ok(body)
+}
+
+/// Look up a packet type by its wire name, e.g. `kdeconnect.ping`
+pub fn parse_type(packet_type: &str) -> Result<PacketType, String> {
+    serde_json::from_value(Value::String(packet_type.trim().to_string()))
+        .map_err(|_| format!("Unknown packet type: {}", packet_type))
+}
+
+/// Build a packet from a wire type name and a JSON object body
+pub fn build(packet_type: &str, body_json: &str) -> Result<ProtocolPacket, String> {
+    let body = parse_body(body_json)?;
+    Ok(ProtocolPacket::new(parse_type(packet_type)?, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_packet_body_validation() {
+        assert_eq!(parse_body(r#"{"message": "hi"}"#).unwrap()["message"], "hi");
+        assert!(parse_body("").unwrap().as_object().unwrap().is_empty());
+
+        assert!(parse_body(r#"{"message": "#).unwrap_err().starts_with("Packet body is not valid JSON"));
+        assert_eq!(parse_body("[1, 2]").unwrap_err(), "Packet body must be a JSON object");
+        assert!(parse_type("kdeconnect.nonexistent").is_err());
+    }
+}
@@ -0,0 +1,156 @@
+// kdeconnect-service/src/send_queue.rs
+//! Per-device outbound queue for packets sent while a device is unreachable.
+//!
+//! Packets are buffered in order and flushed when the device reconnects.
+//! Each device queue is capped and entries expire after a TTL so a phone
+//! that never comes back doesn't receive a stale burst days later.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Maximum packets buffered per device; the oldest is dropped beyond this
+pub const MAX_QUEUED_PER_DEVICE: usize = 50;
+
+/// How long a queued packet stays deliverable
+pub const QUEUE_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// What a queued packet is, so dropped entries can be reported meaningfully
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueuedKind {
+    Ping,
+    Clipboard,
+    Text,
+    Sms { phone_number: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct QueuedPacket<T> {
+    pub kind: QueuedKind,
+    pub packet: T,
+    pub queued_at: Instant,
+}
+
+/// Outbound buffer keyed by device id
+#[derive(Debug)]
+pub struct SendQueue<T> {
+    queues: HashMap<String, VecDeque<QueuedPacket<T>>>,
+    max_per_device: usize,
+    ttl: Duration,
+}
+
+impl<T> Default for SendQueue<T> {
+    fn default() -> Self {
+        Self::new(MAX_QUEUED_PER_DEVICE, QUEUE_TTL)
+    }
+}
+
+impl<T> SendQueue<T> {
+    pub fn new(max_per_device: usize, ttl: Duration) -> Self {
+        Self {
+            queues: HashMap::new(),
+            max_per_device,
+            ttl,
+        }
+    }
+
+    /// Buffer a packet for a device. Returns the entry dropped to make room,
+    /// if the device queue was already full.
+    pub fn push(&mut self, device_id: &str, kind: QueuedKind, packet: T, now: Instant) -> Option<QueuedPacket<T>> {
+        let queue = self.queues.entry(device_id.to_string()).or_default();
+
+        let dropped = if queue.len() >= self.max_per_device {
+            queue.pop_front()
+        } else {
+            None
+        };
+
+        queue.push_back(QueuedPacket { kind, packet, queued_at: now });
+        dropped
+    }
+
+    /// Remove everything queued for a device, split into packets still
+    /// deliverable (in send order) and ones that expired.
+    pub fn drain(&mut self, device_id: &str, now: Instant) -> (Vec<T>, Vec<QueuedPacket<T>>) {
+        let Some(queue) = self.queues.remove(device_id) else {
+            return (Vec::new(), Vec::new());
+        };
+
+        let mut ready = Vec::new();
+        let mut expired = Vec::new();
+        for entry in queue {
+            if now.duration_since(entry.queued_at) > self.ttl {
+                expired.push(entry);
+            } else {
+                ready.push(entry.packet);
+            }
+        }
+
+        (ready, expired)
+    }
+
+    /// Drop expired entries across all devices, returning them with their device id
+    pub fn expire(&mut self, now: Instant) -> Vec<(String, QueuedPacket<T>)> {
+        let mut expired = Vec::new();
+
+        for (device_id, queue) in self.queues.iter_mut() {
+            while queue.front().is_some_and(|e| now.duration_since(e.queued_at) > self.ttl) {
+                if let Some(entry) = queue.pop_front() {
+                    expired.push((device_id.clone(), entry));
+                }
+            }
+        }
+
+        self.queues.retain(|_, q| !q.is_empty());
+        expired
+    }
+
+    /// Number of packets waiting for a device
+    pub fn depth(&self, device_id: &str) -> usize {
+        self.queues.get(device_id).map_or(0, |q| q.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drain_preserves_order() {
+        let mut queue = SendQueue::default();
+        let now = Instant::now();
+        queue.push("phone", QueuedKind::Ping, 1, now);
+        queue.push("phone", QueuedKind::Clipboard, 2, now);
+        queue.push("phone", QueuedKind::Ping, 3, now);
+
+        assert_eq!(queue.depth("phone"), 3);
+        let (ready, expired) = queue.drain("phone", now);
+        assert_eq!(ready, vec![1, 2, 3]);
+        assert!(expired.is_empty());
+        assert_eq!(queue.depth("phone"), 0);
+    }
+
+    #[test]
+    fn test_cap_drops_oldest() {
+        let mut queue = SendQueue::new(2, QUEUE_TTL);
+        let now = Instant::now();
+        assert!(queue.push("phone", QueuedKind::Ping, 1, now).is_none());
+        assert!(queue.push("phone", QueuedKind::Ping, 2, now).is_none());
+        let dropped = queue.push("phone", QueuedKind::Ping, 3, now);
+
+        assert_eq!(dropped.map(|e| e.packet), Some(1));
+        assert_eq!(queue.drain("phone", now).0, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_expired_entries_are_not_flushed() {
+        let mut queue = SendQueue::new(10, Duration::from_secs(60));
+        let start = Instant::now();
+        queue.push("phone", QueuedKind::Sms { phone_number: "5551234567".into() }, 1, start);
+        queue.push("phone", QueuedKind::Ping, 2, start + Duration::from_secs(90));
+
+        let (ready, expired) = queue.drain("phone", start + Duration::from_secs(100));
+        assert_eq!(ready, vec![2]);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].kind, QueuedKind::Sms { phone_number: "5551234567".into() });
+    }
+}
@@ -0,0 +1,181 @@
+// kdeconnect-service/src/telephony.rs
+//! Incoming calls reported by a phone's telephony plugin.
+//!
+//! A `kdeconnect.telephony` packet announces a call (`ringing`, `talking`,
+//! `missedCall`), and the same event with `isCancel` set when it ends. The
+//! desktop can ask the phone to silence the ringer; the protocol has no
+//! packet to reject a call.
+//!
+//! Media pausing follows the device's PauseMusicPluginConfig: playing
+//! players are paused (and the output muted, if configured) when the call
+//! rings or is answered, and resumed once it ends. See `media_control`.
+
+use std::collections::HashMap;
+use zbus::Connection;
+
+use crate::media_control::{MprisBridge, SystemVolume};
+use cosmic_connect_common::plugin_config::PauseMusicPluginConfig;
+
+/// Packet asking the phone to silence its ringer
+pub const MUTE_REQUEST: &str = "kdeconnect.telephony.request_mute";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallEvent {
+    Ringing,
+    Talking,
+    MissedCall,
+}
+
+/// A call event from a device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncomingCall {
+    pub event: CallEvent,
+    pub contact_name: String,
+    pub phone_number: String,
+    /// The call this event describes has ended
+    pub cancelled: bool,
+}
+
+impl IncomingCall {
+    /// Parse the body of a `kdeconnect.telephony` packet. Returns None for
+    /// other events, such as the legacy `sms` one.
+    pub fn from_packet_body(body: &serde_json::Value) -> Option<Self> {
+        let event = match body.get("event")?.as_str()? {
+            "ringing" => CallEvent::Ringing,
+            "talking" => CallEvent::Talking,
+            "missedCall" => CallEvent::MissedCall,
+            _ => return None,
+        };
+        let text = |key: &str| body.get(key).and_then(serde_json::Value::as_str).unwrap_or_default().trim().to_string();
+
+        Some(Self {
+            event,
+            contact_name: text("contactName"),
+            phone_number: text("phoneNumber"),
+            cancelled: body.get("isCancel").and_then(serde_json::Value::as_bool).unwrap_or(false),
+        })
+    }
+
+    /// Who is calling, as shown in notifications
+    pub fn caller(&self) -> &str {
+        if !self.contact_name.is_empty() {
+            &self.contact_name
+        } else if !self.phone_number.is_empty() {
+            &self.phone_number
+        } else {
+            "Unknown caller"
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaAction {
+    Pause,
+    /// The call is over: resume what was paused
+    Resume,
+    /// The call is over but media stays paused
+    Release,
+}
+
+/// What to do with desktop media for a call event, per the device's
+/// pause-music settings. Ringing pauses unless the settings say to pause
+/// only once the call is answered; a missed call ends the ringing.
+pub fn media_action(config: &PauseMusicPluginConfig, call: &IncomingCall) -> Option<MediaAction> {
+    if !config.pause_media && !config.mute_system_sound {
+        return None;
+    }
+    if call.cancelled || call.event == CallEvent::MissedCall {
+        return Some(if config.resume_after_call { MediaAction::Resume } else { MediaAction::Release });
+    }
+    match call.event {
+        CallEvent::Ringing => {
+            (config.pause_on_ringing && !config.pause_only_on_talking).then_some(MediaAction::Pause)
+        }
+        CallEvent::Talking => Some(MediaAction::Pause),
+        CallEvent::MissedCall => None,
+    }
+}
+
+/// Calls in progress and the media paused for them
+#[derive(Debug, Default)]
+pub struct CallState {
+    /// Ringing notification shown per device, to close when the call ends
+    pub notifications: HashMap<String, u32>,
+    players: MprisBridge,
+    volume: SystemVolume,
+}
+
+impl CallState {
+    /// Pause, resume or let go of desktop media
+    pub async fn apply_media(&mut self, connection: &Connection, config: &PauseMusicPluginConfig, action: MediaAction) {
+        match action {
+            MediaAction::Pause => {
+                if config.pause_media {
+                    self.players.pause_playing(connection).await;
+                }
+                if config.mute_system_sound {
+                    self.volume.mute().await;
+                }
+            }
+            MediaAction::Resume => {
+                self.players.resume(connection).await;
+                self.volume.restore().await;
+            }
+            MediaAction::Release => {
+                self.players.forget();
+                self.volume.forget();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_call_event_parsing() {
+        let ringing = IncomingCall::from_packet_body(&json!({
+            "event": "ringing", "contactName": "Alex", "phoneNumber": "+15551234567"
+        })).unwrap();
+        assert_eq!(ringing.event, CallEvent::Ringing);
+        assert_eq!(ringing.caller(), "Alex");
+        assert!(IncomingCall::from_packet_body(&json!({ "event": "sms" })).is_none());
+
+        let ended = IncomingCall::from_packet_body(&json!({ "event": "talking", "isCancel": true })).unwrap();
+        assert!(ended.cancelled);
+        assert_eq!(ended.caller(), "Unknown caller");
+    }
+
+    #[test]
+    fn test_pause_on_ringing_vs_only_on_talking() {
+        let call = |event, cancelled| IncomingCall {
+            event,
+            contact_name: String::new(),
+            phone_number: String::new(),
+            cancelled,
+        };
+        let ringing = call(CallEvent::Ringing, false);
+        let talking = call(CallEvent::Talking, false);
+
+        let on_ringing = PauseMusicPluginConfig::default();
+        assert_eq!(media_action(&on_ringing, &ringing), Some(MediaAction::Pause));
+        assert_eq!(media_action(&on_ringing, &talking), Some(MediaAction::Pause));
+
+        let only_talking = PauseMusicPluginConfig { pause_only_on_talking: true, ..Default::default() };
+        assert_eq!(media_action(&only_talking, &ringing), None);
+        assert_eq!(media_action(&only_talking, &talking), Some(MediaAction::Pause));
+
+        // Hanging up, or letting it ring out, ends the call
+        assert_eq!(media_action(&on_ringing, &call(CallEvent::Talking, true)), Some(MediaAction::Resume));
+        assert_eq!(media_action(&on_ringing, &call(CallEvent::MissedCall, false)), Some(MediaAction::Resume));
+        let no_resume = PauseMusicPluginConfig { resume_after_call: false, ..Default::default() };
+        assert_eq!(media_action(&no_resume, &call(CallEvent::Ringing, true)), Some(MediaAction::Release));
+
+        let off = PauseMusicPluginConfig { pause_media: false, mute_system_sound: false, ..Default::default() };
+        assert_eq!(media_action(&off, &ringing), None);
+        let mute_only = PauseMusicPluginConfig { pause_media: false, mute_system_sound: true, ..Default::default() };
+        assert_eq!(media_action(&mute_only, &ringing), Some(MediaAction::Pause));
+    }
+}
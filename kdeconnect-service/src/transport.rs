@@ -0,0 +1,88 @@
+// kdeconnect-service/src/transport.rs
+//! Picking the link a packet goes out on.
+//!
+//! A device can be reachable over LAN and Bluetooth at once. Sends prefer
+//! LAN and fall back to the next link when one refuses the packet, so a
+//! link dropping mid-send doesn't fail the send while another is up.
+
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    Lan,
+    Bluetooth,
+}
+
+impl Transport {
+    /// Best first
+    pub const PREFERENCE: [Transport; 2] = [Transport::Lan, Transport::Bluetooth];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Transport::Lan => "lan",
+            Transport::Bluetooth => "bluetooth",
+        }
+    }
+
+    /// Parse a device's `connection_type`
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::PREFERENCE.into_iter().find(|t| t.as_str() == s)
+    }
+}
+
+impl fmt::Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The device's active links in the order sends should try them
+pub fn by_preference(active: &[Transport]) -> Vec<Transport> {
+    Transport::PREFERENCE.into_iter().filter(|t| active.contains(t)).collect()
+}
+
+/// Offer a packet to each active link, best first, until one takes it.
+/// Returns the link used, or the last error (`None` if there was no link).
+pub fn send_with_failover<E: fmt::Display>(
+    active: &[Transport],
+    mut send: impl FnMut(Transport) -> Result<(), E>,
+) -> Result<Transport, Option<E>> {
+    let mut last_error = None;
+    for transport in by_preference(active) {
+        match send(transport) {
+            Ok(()) => return Ok(transport),
+            Err(e) => {
+                tracing::warn!("Send over {} failed: {}, trying the next link", transport, e);
+                last_error = Some(e);
+            }
+        }
+    }
+    Err(last_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_send_prefers_lan_and_fails_over() {
+        let both = [Transport::Bluetooth, Transport::Lan];
+        assert_eq!(by_preference(&both), vec![Transport::Lan, Transport::Bluetooth]);
+        assert_eq!(send_with_failover(&both, |_| Ok::<_, String>(())), Ok(Transport::Lan));
+
+        // LAN drops mid-send: Bluetooth takes the packet
+        let mut tried = Vec::new();
+        let used = send_with_failover(&both, |t| {
+            tried.push(t);
+            if t == Transport::Lan { Err("link closed".to_string()) } else { Ok(()) }
+        });
+        assert_eq!(used, Ok(Transport::Bluetooth));
+        assert_eq!(tried, vec![Transport::Lan, Transport::Bluetooth]);
+
+        assert_eq!(
+            send_with_failover(&[Transport::Lan], |_| Err("link closed".to_string())),
+            Err(Some("link closed".to_string()))
+        );
+        assert_eq!(send_with_failover(&[], |_| Ok::<_, String>(())), Err(None));
+    }
+}